@@ -171,10 +171,12 @@ fn demo_skill_installation() {
     println!("  已注册工具: {:?}\n", agent.list_tools());
 
     // 安装内置 Skills
-    agent.add_skill(Box::new(CalculatorSkill));
-    agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/tmp")));
-    agent.add_skill(Box::new(WeatherSkill));
-    agent.add_skill(Box::new(TextProcessingSkill));
+    agent.add_skill(Box::new(CalculatorSkill)).unwrap();
+    agent
+        .add_skill(Box::new(FileSystemSkill::with_base_dir("/tmp")))
+        .unwrap();
+    agent.add_skill(Box::new(WeatherSkill)).unwrap();
+    agent.add_skill(Box::new(TextProcessingSkill)).unwrap();
 
     println!("\n安装后：");
     println!("  已安装 Skill 数量: {}", agent.skill_count());
@@ -222,7 +224,7 @@ async fn demo_agent_with_skills() -> echo_agent::error::Result<()> {
             .system_prompt(system_prompt)
             .enable_tools()
             .build()?;
-        agent.add_skill(Box::new(CalculatorSkill));
+        agent.add_skill(Box::new(CalculatorSkill))?;
 
         let task = "计算: (15 * 8 + 36 / 4) - (100 / 5 * 3)，分步给出每一步的结果";
         println!("任务: {}", task);
@@ -241,7 +243,7 @@ async fn demo_agent_with_skills() -> echo_agent::error::Result<()> {
             .system_prompt(system_prompt)
             .enable_tools()
             .build()?;
-        agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/tmp")));
+        agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/tmp")))?;
 
         let task = "在 /tmp/skills_demo.txt 写入内容 'Hello from echo-agent Skills!'，然后读取它并确认内容正确";
         println!("任务: {}", task);
@@ -263,7 +265,7 @@ async fn demo_agent_with_skills() -> echo_agent::error::Result<()> {
         agent.add_skills(vec![
             Box::new(CalculatorSkill),
             Box::new(FileSystemSkill::with_base_dir("/tmp")),
-        ]);
+        ])?;
 
         let task = "计算 123 * 456 的结果，然后把算式和结果写入 /tmp/calc_result.txt";
         println!("任务: {}", task);
@@ -109,7 +109,7 @@ async fn demo_agent_tasks() -> echo_agent::error::Result<()> {
         .max_iterations(15)
         .build()?;
 
-    agent.add_skill(Box::new(FileSystemSkill::with_base_dir(work_dir)));
+    agent.add_skill(Box::new(FileSystemSkill::with_base_dir(work_dir)))?;
 
     let task = format!(
         "在 {work_dir}/notes.md 写入内容 '# 项目笔记\n- 完成了文件工具的实现'，然后读取确认"
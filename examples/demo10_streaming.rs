@@ -1,8 +1,8 @@
 //! demo10_streaming.rs —— 流式输出综合演示
 
 use echo_agent::agent::{Agent, AgentEvent};
-use echo_agent::llm::stream_chat;
 use echo_agent::llm::types::Message;
+use echo_agent::llm::{ChatRequest, stream_chat};
 use echo_agent::prelude::*;
 use echo_agent::tools::others::math::{AddTool, DivideTool, MultiplyTool, SubtractTool};
 use futures::StreamExt;
@@ -56,12 +56,12 @@ async fn demo_raw_stream() -> echo_agent::error::Result<()> {
         stream_chat(
             client,
             "qwen3-max",
-            messages,
-            Some(0.7),
-            Some(512),
-            None,
-            None,
-            None,
+            ChatRequest {
+                messages,
+                temperature: Some(0.7),
+                max_tokens: Some(512),
+                ..Default::default()
+            },
         )
         .await?,
     );
@@ -71,11 +71,11 @@ async fn demo_raw_stream() -> echo_agent::error::Result<()> {
 
     while let Some(chunk_result) = stream.next().await {
         let chunk = chunk_result?;
-        if let Some(choice) = chunk.choices.first() {
-            if let Some(content) = &choice.delta.content {
-                print!("{}", content);
-                std::io::stdout().flush().ok();
-            }
+        if let Some(choice) = chunk.choices.first()
+            && let Some(content) = &choice.delta.content
+        {
+            print!("{}", content);
+            std::io::stdout().flush().ok();
         }
     }
     println!();
@@ -143,6 +143,9 @@ async fn demo_agent_tool_stream() -> echo_agent::error::Result<()> {
             AgentEvent::ToolCall { name, args } => {
                 println!("\n  🔧 工具调用: {name}({:?})", args);
             }
+            AgentEvent::ToolResultChunk { chunk, .. } => {
+                print!("     │ {chunk}");
+            }
             AgentEvent::ToolResult { name, output } => {
                 println!("  📤 工具结果: [{name}] → {}", truncate(&output, 60));
             }
@@ -152,6 +155,13 @@ async fn demo_agent_tool_stream() -> echo_agent::error::Result<()> {
             AgentEvent::Cancelled => {
                 println!("\n  ⚠️ 执行已取消");
             }
+            AgentEvent::TaskUpdate {
+                task_id,
+                status,
+                progress: (done, total),
+            } => {
+                println!("  📋 任务 [{task_id}] → {status:?} ({done}/{total})");
+            }
         }
     }
 
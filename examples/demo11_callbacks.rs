@@ -239,6 +239,9 @@ async fn demo_multi_callback_stream() -> echo_agent::error::Result<()> {
             AgentEvent::ToolCall { name, args } => {
                 println!("\n  [ToolCall] {name}({})", compact_args(&args));
             }
+            AgentEvent::ToolResultChunk { name, chunk } => {
+                print!("  [ToolResultChunk] [{name}] {chunk}");
+            }
             AgentEvent::ToolResult { name, output } => {
                 println!("  [ToolResult] [{name}] {}", truncate(&output, 60));
             }
@@ -248,6 +251,13 @@ async fn demo_multi_callback_stream() -> echo_agent::error::Result<()> {
             AgentEvent::Cancelled => {
                 println!("\n  [Cancelled] 执行已取消");
             }
+            AgentEvent::TaskUpdate {
+                task_id,
+                status,
+                progress: (done, total),
+            } => {
+                println!("  [TaskUpdate] [{task_id}] {status:?} ({done}/{total})");
+            }
         }
     }
 
@@ -56,11 +56,7 @@ impl Tool for BrokenTool {
         serde_json::json!({ "type": "object", "properties": { "input": { "type": "string" } }, "required": ["input"] })
     }
     async fn execute(&self, _params: ToolParameters) -> echo_agent::error::Result<ToolResult> {
-        Ok(ToolResult {
-            success: false,
-            output: String::new(),
-            error: Some("BrokenTool: 服务不可用".to_string()),
-        })
+        Ok(ToolResult::error("BrokenTool: 服务不可用".to_string()))
     }
 }
 
@@ -99,17 +95,11 @@ impl Tool for FlakyTool {
 
         if remaining > 0 {
             self.fail_remaining.fetch_sub(1, Ordering::Relaxed);
-            Ok(ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some(format!("服务暂时不可用（第 {call_idx} 次尝试）")),
-            })
+            Ok(ToolResult::error(format!(
+                "服务暂时不可用（第 {call_idx} 次尝试）"
+            )))
         } else {
-            Ok(ToolResult {
-                success: true,
-                output: format!("{city}：晴，26°C"),
-                error: None,
-            })
+            Ok(ToolResult::success(format!("{city}：晴，26°C")))
         }
     }
 }
@@ -105,7 +105,7 @@ async fn demo_agent_with_semantic_memory() -> echo_agent::error::Result<()> {
     let store = Arc::new(EmbeddingStore::new(inner as Arc<dyn Store>, embedder));
 
     // 预填充记忆
-    let ns = vec!["memory_agent".to_string(), "memories".to_string()];
+    let ns = ["memory_agent".to_string(), "memories".to_string()];
     let ns_ref: Vec<&str> = ns.iter().map(String::as_str).collect();
 
     store
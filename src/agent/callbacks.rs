@@ -0,0 +1,199 @@
+//! 回调投递：同步逐个 await，或经有界队列由独立 task 异步分发
+//!
+//! [`CallbackMode::Sync`] 下每个事件依次 await 每个 [`AgentCallback`]，回调完成后 Agent 才继续；
+//! [`CallbackMode::AsyncBuffered`] 下事件进入有界队列，由后台 task 按入队顺序分发，
+//! Agent 主循环只在队列满时等待。每次执行结束前会 flush 队列，保证返回时回调已收到全部事件。
+
+use crate::agent::react_agent::StepType;
+use crate::agent::{AgentCallback, BudgetKind};
+use crate::error::ReactError;
+use crate::llm::types::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// `AsyncBuffered` 模式下事件队列的容量
+const CALLBACK_QUEUE_CAPACITY: usize = 1024;
+
+/// 回调投递方式，见 `AgentConfig::callback_mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackMode {
+    /// 每个事件依次 await 每个回调（默认）
+    #[default]
+    Sync,
+    /// 事件进入有界队列，由后台 task 按顺序异步分发；执行结束前 flush
+    AsyncBuffered,
+}
+
+/// 一个回调事件的自有副本
+pub(crate) enum CallbackEvent {
+    ThinkStart(Vec<Message>),
+    ThinkEnd(Vec<StepType>),
+    ToolStart {
+        tool: String,
+        args: Value,
+    },
+    ToolEnd {
+        tool: String,
+        result: String,
+    },
+    ToolWarning {
+        tool: String,
+        warnings: Vec<String>,
+    },
+    ToolError {
+        tool: String,
+        err: ReactError,
+    },
+    FinalAnswer(String),
+    Iteration(usize),
+    BudgetWarning {
+        kind: BudgetKind,
+        used: usize,
+        limit: usize,
+    },
+}
+
+impl CallbackEvent {
+    async fn deliver(&self, agent: &str, callback: &dyn AgentCallback) {
+        match self {
+            Self::ThinkStart(messages) => callback.on_think_start(agent, messages).await,
+            Self::ThinkEnd(steps) => callback.on_think_end(agent, steps).await,
+            Self::ToolStart { tool, args } => callback.on_tool_start(agent, tool, args).await,
+            Self::ToolEnd { tool, result } => callback.on_tool_end(agent, tool, result).await,
+            Self::ToolWarning { tool, warnings } => {
+                callback.on_tool_warning(agent, tool, warnings).await
+            }
+            Self::ToolError { tool, err } => callback.on_tool_error(agent, tool, err).await,
+            Self::FinalAnswer(answer) => callback.on_final_answer(agent, answer).await,
+            Self::Iteration(iteration) => callback.on_iteration(agent, *iteration).await,
+            Self::BudgetWarning { kind, used, limit } => {
+                callback
+                    .on_budget_warning(agent, *kind, *used, *limit)
+                    .await
+            }
+        }
+    }
+
+    async fn deliver_all(&self, agent: &str, callbacks: &[Arc<dyn AgentCallback>]) {
+        for callback in callbacks {
+            self.deliver(agent, callback.as_ref()).await;
+        }
+    }
+}
+
+type CallbackList = Arc<[Arc<dyn AgentCallback>]>;
+
+enum Queued {
+    Event {
+        agent: String,
+        callbacks: CallbackList,
+        event: CallbackEvent,
+    },
+    /// 之前入队的事件都已分发后应答
+    Flush(oneshot::Sender<()>),
+}
+
+/// `AsyncBuffered` 模式的后台分发队列，首次使用时启动
+#[derive(Default)]
+pub(crate) struct CallbackQueue {
+    sender: OnceLock<mpsc::Sender<Queued>>,
+}
+
+impl CallbackQueue {
+    fn sender(&self) -> mpsc::Sender<Queued> {
+        self.sender
+            .get_or_init(|| {
+                let (tx, mut rx) = mpsc::channel(CALLBACK_QUEUE_CAPACITY);
+                // Agent 释放后发送端关闭，task 分发完剩余事件后退出
+                tokio::spawn(async move {
+                    while let Some(item) = rx.recv().await {
+                        match item {
+                            Queued::Event {
+                                agent,
+                                callbacks,
+                                event,
+                            } => event.deliver_all(&agent, &callbacks).await,
+                            Queued::Flush(ack) => {
+                                let _ = ack.send(());
+                            }
+                        }
+                    }
+                });
+                tx
+            })
+            .clone()
+    }
+}
+
+/// 一次执行使用的回调投递端，持有当时已注册回调的快照
+#[derive(Clone)]
+pub(crate) struct CallbackSink {
+    agent: String,
+    callbacks: CallbackList,
+    queue: Option<mpsc::Sender<Queued>>,
+}
+
+impl CallbackSink {
+    pub(crate) fn new(
+        agent: &str,
+        callbacks: &[Arc<dyn AgentCallback>],
+        mode: CallbackMode,
+        queue: &CallbackQueue,
+    ) -> Self {
+        let queue =
+            (mode == CallbackMode::AsyncBuffered && !callbacks.is_empty()).then(|| queue.sender());
+        Self {
+            agent: agent.to_string(),
+            callbacks: callbacks.into(),
+            queue,
+        }
+    }
+
+    /// 投递一个事件；没有回调时不构造事件
+    pub(crate) async fn emit(&self, event: impl FnOnce() -> CallbackEvent) {
+        if self.callbacks.is_empty() {
+            return;
+        }
+        let event = event();
+        match &self.queue {
+            None => event.deliver_all(&self.agent, &self.callbacks).await,
+            Some(queue) => {
+                let item = Queued::Event {
+                    agent: self.agent.clone(),
+                    callbacks: self.callbacks.clone(),
+                    event,
+                };
+                if queue.send(item).await.is_err() {
+                    warn!(agent = %self.agent, "⚠️ 回调分发队列已关闭，事件被丢弃");
+                }
+            }
+        }
+    }
+
+    /// 投递 ThinkStart：同步模式下直接借用消息，只有入队时才克隆
+    pub(crate) async fn think_start(&self, messages: &[Message]) {
+        if self.queue.is_some() {
+            self.emit(|| CallbackEvent::ThinkStart(messages.to_vec()))
+                .await;
+            return;
+        }
+        for callback in self.callbacks.iter() {
+            callback.on_think_start(&self.agent, messages).await;
+        }
+    }
+
+    /// 等待队列中已有的事件全部分发完毕；同步模式下立即返回
+    pub(crate) async fn flush(&self) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+        let (ack, done) = oneshot::channel();
+        if queue.send(Queued::Flush(ack)).await.is_ok() {
+            let _ = done.await;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! 信息来源追踪与引用标注
+//!
+//! 工具结果携带 [`Source`] 时，来源按本次执行内的首次出现顺序编号（同一来源复用编号），
+//! 以来源列表（标题随 locale，如 `[来源]`）附在工具输出末尾写入上下文，并在 system prompt 中引导 LLM
+//! 在答案里用 `[编号]` 标注引用。
+
+use crate::agent::{Locale, LocaleKey};
+use crate::tools::{Source, SourceKind};
+
+/// 把 `sources` 并入本次执行的来源列表（按种类与标识去重），返回各自的引用编号（从 1 计数）
+pub(crate) fn register_sources(all: &mut Vec<Source>, sources: Vec<Source>) -> Vec<usize> {
+    sources
+        .into_iter()
+        .map(|source| {
+            let existing = all
+                .iter()
+                .position(|s| s.kind == source.kind && s.reference == source.reference);
+            match existing {
+                Some(index) => index + 1,
+                None => {
+                    all.push(source);
+                    all.len()
+                }
+            }
+        })
+        .collect()
+}
+
+/// 在工具输出末尾附加带编号的来源列表，标题与来源类型按 `locale` 本地化
+pub(crate) fn append_sources(
+    output: &str,
+    all: &[Source],
+    indices: &[usize],
+    locale: &Locale,
+) -> String {
+    let mut text = format!("{output}\n\n{}", locale.text(LocaleKey::SourcesHeading));
+    for &index in indices {
+        let source = &all[index - 1];
+        let kind = locale.text(match source.kind {
+            SourceKind::Url => LocaleKey::SourceKindUrl,
+            SourceKind::File => LocaleKey::SourceKindFile,
+            SourceKind::Other => LocaleKey::SourceKindOther,
+        });
+        text.push_str(&format!("\n[{index}] {kind} {}", source.reference));
+        if let Some(snippet) = &source.snippet {
+            text.push_str(&format!(" —— {snippet}"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_sources_dedupes_and_numbers() {
+        let mut all = Vec::new();
+        let first = register_sources(
+            &mut all,
+            vec![Source::url("https://a.com"), Source::file("/tmp/b.md")],
+        );
+        let second = register_sources(
+            &mut all,
+            vec![Source::file("/tmp/c.md"), Source::url("https://a.com")],
+        );
+        assert_eq!(first, vec![1, 2]);
+        assert_eq!(second, vec![3, 1]);
+        assert_eq!(all.len(), 3);
+
+        let text = append_sources("正文", &all, &second, &Locale::default());
+        assert_eq!(
+            text,
+            "正文\n\n[来源]\n[3] 文件 /tmp/c.md\n[1] 网页 https://a.com"
+        );
+        let text = append_sources("body", &all, &second, &Locale::en_us());
+        assert_eq!(
+            text,
+            "body\n\n[Sources]\n[3] file /tmp/c.md\n[1] web https://a.com"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! 用户输入语言检测（字符集启发式）
+//!
+//! 按书写系统统计得分：汉字、假名、谚文按字符计，拉丁 / 西里尔字母按单词计，
+//! 使 "帮我 debug 这个 function" 这类夹杂英文术语的中文输入仍判为中文。
+//! 得分过低或没有明显的主要语言时返回 `None`，调用方不做任何注入。
+
+/// 判定所需的最低得分（约等于两个汉字或两个英文单词）
+const MIN_SCORE: usize = 2;
+/// 主要语言得分占比下限
+const MIN_SHARE: f64 = 0.6;
+
+/// 可识别的语言（注入指令中使用的名称）
+const LANGUAGES: [&str; 5] = ["中文", "英文", "日文", "韩文", "俄文"];
+
+/// 检测文本的主要语言，返回 [`LANGUAGES`] 中的名称；不确定时返回 `None`
+pub(crate) fn detect_language(text: &str) -> Option<&'static str> {
+    let (mut han, mut kana, mut hangul, mut latin, mut cyrillic) = (0, 0, 0, 0, 0);
+    let mut prev_latin = false;
+    let mut prev_cyrillic = false;
+    for c in text.chars() {
+        let is_latin = c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c);
+        let is_cyrillic = ('\u{0400}'..='\u{04FF}').contains(&c);
+        match c {
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => hangul += 1,
+            _ if is_latin && !prev_latin => latin += 1,
+            _ if is_cyrillic && !prev_cyrillic => cyrillic += 1,
+            _ => {}
+        }
+        prev_latin = is_latin;
+        prev_cyrillic = is_cyrillic;
+    }
+
+    // 出现假名时，汉字视为日文汉字
+    let (chinese, japanese) = if kana > 0 { (0, kana + han) } else { (han, 0) };
+    let scores = [chinese, latin, japanese, hangul, cyrillic];
+    let total: usize = scores.iter().sum();
+    let (index, &top) = scores.iter().enumerate().max_by_key(|(_, s)| **s)?;
+    if top < MIN_SCORE || (top as f64) < total as f64 * MIN_SHARE {
+        return None;
+    }
+    Some(LANGUAGES[index])
+}
+
+/// 回复语言指令（作为独立的 system 片段注入，多轮对话中整体替换）
+pub(crate) fn language_instruction(language: &str) -> String {
+    format!("请用{language}回复。")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language_by_majority() {
+        assert_eq!(
+            detect_language("What is the capital of France?"),
+            Some("英文")
+        );
+        assert_eq!(detect_language("今天北京的天气怎么样？"), Some("中文"));
+        assert_eq!(
+            detect_language("帮我 debug 一下这个 function 的报错"),
+            Some("中文")
+        );
+        assert_eq!(detect_language("東京の天気はどうですか"), Some("日文"));
+        // 太短或无法判断时不注入
+        assert_eq!(detect_language("ok"), None);
+        assert_eq!(detect_language("1 + 1 = ?"), None);
+        assert_eq!(detect_language("你好 hello world"), None);
+    }
+}
@@ -23,11 +23,13 @@
 //! ```
 
 use crate::agent::react_agent::StepType;
-use crate::error::{ReactError, Result};
-use crate::llm::types::Message;
+use crate::error::{AgentError, ReactError, Result};
+use crate::llm::types::{Message, Usage};
+use crate::tasks::{TaskStatus, TaskUpdate};
+use crate::tools::Source;
 use async_trait::async_trait;
-pub use config::{AgentConfig, AgentRole};
-use futures::stream::BoxStream;
+pub use config::{AgentConfig, AgentRole, FewShotExample, ReflectionConfig, ToolResultOrdering};
+use futures::stream::{BoxStream, Stream, StreamExt};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -37,11 +39,25 @@ pub use tokio_util::sync::CancellationToken;
 /// SubAgent 注册表类型别名
 pub(crate) type SubAgentMap = Arc<RwLock<HashMap<String, Arc<AsyncMutex<Box<dyn Agent>>>>>>;
 
+mod callbacks;
+mod citation;
 mod config;
+mod language;
+mod locale;
 mod planning;
 pub mod react_agent;
+mod secrets;
+mod stream;
+mod trace;
+mod untrusted;
 
+pub use callbacks::CallbackMode;
+pub(crate) use callbacks::{CallbackEvent, CallbackQueue, CallbackSink};
+pub use locale::{Locale, LocaleKey};
 pub use react_agent::builder::ReactAgentBuilder;
+pub use secrets::{SecretAction, SecretDetector, SecretMatch, SecretPolicy};
+pub use stream::chunk_by_sentence;
+pub use trace::{ExecutionTrace, SpanKind, TraceSpan};
 
 /// AgentBuilder 是 ReactAgentBuilder 的别名，用于宏和极简 API
 pub type AgentBuilder = ReactAgentBuilder;
@@ -78,12 +94,125 @@ pub enum AgentEvent {
     Token(String),
     /// LLM 决定调用工具
     ToolCall { name: String, args: Value },
+    /// 流式输出工具（[`Tool::streams_output`](crate::tools::Tool::streams_output)）执行中产生的输出片段
+    ///
+    /// 所有片段之后仍会发出携带完整输出的 `ToolResult`。
+    ToolResultChunk { name: String, chunk: String },
     /// 工具执行完毕，返回观测结果
     ToolResult { name: String, output: String },
     /// 最终答案已生成
     FinalAnswer(String),
     /// 执行被取消
     Cancelled,
+    /// 子任务被创建或状态发生变更（由任务工具触发）
+    ///
+    /// `progress` 为变更后的 `(已完成数, 任务总数)`。
+    TaskUpdate {
+        task_id: String,
+        status: TaskStatus,
+        progress: (usize, usize),
+    },
+}
+
+impl From<TaskUpdate> for AgentEvent {
+    fn from(update: TaskUpdate) -> Self {
+        Self::TaskUpdate {
+            task_id: update.task_id,
+            status: update.status,
+            progress: update.progress,
+        }
+    }
+}
+
+/// 一次工具调用的记录
+///
+/// 标记为 `#[non_exhaustive]`，后续新增字段不会破坏读取它的代码。
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ToolCallRecord {
+    /// 工具名
+    pub name: String,
+    /// 调用参数
+    pub args: Value,
+    /// 工具返回的观测值（失败时为回传给 LLM 的错误信息）
+    pub output: String,
+    /// 工具结果的结构化数据（见 `ToolResult.data`）；流式聚合时为空
+    pub data: Option<Value>,
+}
+
+/// 流式与非流式统一的执行结果
+///
+/// 非流式通过 [`ReactAgent::execute_rich`](react_agent::ReactAgent::execute_rich) 获取；
+/// 流式可用 [`ExecutionResult::from_stream`] 将消费完的事件流聚合为同一结构。
+/// 标记为 `#[non_exhaustive]`，后续新增字段不会破坏读取它的代码。
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ExecutionResult {
+    /// 最终答案（已应用输出后处理器）
+    pub final_answer: String,
+    /// 按执行顺序记录的全部工具调用
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// LLM 推理轮数
+    pub iterations: usize,
+    /// 各轮 token 用量之和；服务端未返回用量（或流式执行）时为 None
+    pub usage: Option<Usage>,
+    /// 工具结果携带的全部信息来源（去重），下标 + 1 即答案中的引用编号；流式聚合时为空
+    pub sources: Vec<Source>,
+}
+
+impl ExecutionResult {
+    /// 消费事件流并聚合为执行结果
+    ///
+    /// 事件流不携带轮次与用量信息：轮数按「工具结果之后再次出现 Token / ToolCall」推断，
+    /// `usage` 恒为 None。流中途出错、被取消或没有产出最终答案时返回错误。
+    pub async fn from_stream<S>(mut stream: S) -> Result<Self>
+    where
+        S: Stream<Item = Result<AgentEvent>> + Unpin,
+    {
+        let mut result = Self::default();
+        let mut pending: Vec<(String, Value)> = Vec::new();
+        let mut after_tool_result = false;
+        let mut final_answer = None;
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            if after_tool_result
+                && matches!(event, AgentEvent::Token(_) | AgentEvent::ToolCall { .. })
+            {
+                result.iterations += 1;
+                after_tool_result = false;
+            }
+            match event {
+                AgentEvent::Token(_)
+                | AgentEvent::ToolResultChunk { .. }
+                | AgentEvent::TaskUpdate { .. } => {}
+                AgentEvent::ToolCall { name, args } => pending.push((name, args)),
+                AgentEvent::ToolResult { name, output } => {
+                    let args = pending
+                        .iter()
+                        .position(|(n, _)| *n == name)
+                        .map(|i| pending.remove(i).1)
+                        .unwrap_or(Value::Null);
+                    result.tool_calls.push(ToolCallRecord {
+                        name,
+                        args,
+                        output,
+                        data: None,
+                    });
+                    after_tool_result = true;
+                }
+                AgentEvent::FinalAnswer(answer) => {
+                    final_answer = Some(answer);
+                    break;
+                }
+                AgentEvent::Cancelled => return Err(AgentError::Interrupted.into()),
+            }
+        }
+
+        result.final_answer = final_answer.ok_or(AgentError::NoResponse)?;
+        result.iterations += 1;
+        Ok(result)
+    }
 }
 
 /// Agent 的统一执行接口
@@ -217,6 +346,17 @@ pub trait Agent: Send + Sync {
     fn reset(&mut self) {}
 }
 
+/// 软预算类型，见 [`AgentCallback::on_budget_warning`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetKind {
+    /// 对话迭代轮次（对应 `AgentConfig::warn_at_iteration`）
+    Iterations,
+    /// 工具调用次数（对应 `AgentConfig::warn_at_tool_calls`）
+    ToolCalls,
+    /// 累计 token 用量（对应 `AgentConfig::warn_at_tokens`）
+    Tokens,
+}
+
 /// Agent 生命周期回调接口
 ///
 /// 实现此 trait 可观测 Agent 执行的每个阶段，用于日志、监控、UI 更新等。
@@ -258,10 +398,21 @@ pub trait AgentCallback: Send + Sync {
     async fn on_tool_start(&self, _agent: &str, _tool: &str, _args: &Value) {}
     /// 工具执行成功后触发
     async fn on_tool_end(&self, _agent: &str, _tool: &str, _result: &str) {}
+    /// 工具执行成功但带有警告时触发（在 `on_tool_end` 之后），见 [`ToolResult::warnings`](crate::tools::ToolResult::warnings)
+    async fn on_tool_warning(&self, _agent: &str, _tool: &str, _warnings: &[String]) {}
     /// 工具执行失败后触发
     async fn on_tool_error(&self, _agent: &str, _tool: &str, _err: &ReactError) {}
     /// 最终答案生成后触发
     async fn on_final_answer(&self, _agent: &str, _answer: &str) {}
     /// 每轮迭代开始前触发，`iteration` 从 0 计数
     async fn on_iteration(&self, _agent: &str, _iteration: usize) {}
+    /// 用量达到软预算时触发（每次执行每类预算只触发一次，不中止执行）
+    async fn on_budget_warning(
+        &self,
+        _agent: &str,
+        _kind: BudgetKind,
+        _used: usize,
+        _limit: usize,
+    ) {
+    }
 }
@@ -1,21 +1,83 @@
+use crate::agent::AgentEvent;
 use crate::agent::config::AgentRole;
-use crate::agent::react_agent::{ReactAgent, StepType};
+use crate::agent::react_agent::{ReactAgent, StepType, TOOL_PLAN};
 use crate::error::{AgentError, ReactError};
+use crate::llm::ToolChoice;
 use crate::llm::types::Message;
-use crate::tasks::{TaskManager, TaskStatus};
+use crate::tasks::{TaskManager, TaskStatus, TaskStatusChange, TaskUpdate};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
+/// 流式执行期间的任务进度订阅；drop 时（包括流被提前丢弃）从 [`TaskManager`] 移除发送端
+pub(crate) struct TaskUpdateSubscription {
+    task_manager: Arc<RwLock<TaskManager>>,
+    updates: mpsc::UnboundedReceiver<TaskUpdate>,
+}
+
+impl TaskUpdateSubscription {
+    /// 取出目前已产生的全部进度，转换为 `AgentEvent::TaskUpdate`
+    pub(crate) fn drain(&mut self) -> Vec<AgentEvent> {
+        let mut events = Vec::new();
+        while let Ok(update) = self.updates.try_recv() {
+            events.push(update.into());
+        }
+        events
+    }
+}
+
+impl Drop for TaskUpdateSubscription {
+    fn drop(&mut self) {
+        if let Ok(mut manager) = self.task_manager.write() {
+            manager.set_update_sender(None);
+        }
+    }
+}
+
 impl ReactAgent {
+    /// 订阅任务进度，供流式执行在工具调用之间转发 `AgentEvent::TaskUpdate`
+    pub(crate) fn subscribe_task_updates(&self) -> TaskUpdateSubscription {
+        let (update_tx, updates) = mpsc::unbounded_channel();
+        if let Ok(mut manager) = self.task_manager.write() {
+            manager.set_update_sender(Some(update_tx));
+        }
+        TaskUpdateSubscription {
+            task_manager: self.task_manager.clone(),
+            updates,
+        }
+    }
+
+    /// 查询任务的状态变更时间线（按时间顺序），任务不存在时返回 `None`
+    pub fn task_timeline(&self, task_id: &str) -> Option<Vec<TaskStatusChange>> {
+        self.task_manager
+            .read()
+            .ok()?
+            .get_task_timeline(task_id)
+            .map(<[TaskStatusChange]>::to_vec)
+    }
+
+    /// 设置单个任务状态历史的最大条数（默认 50，至少保留 1 条）
+    pub fn set_task_history_limit(&self, limit: usize) {
+        if let Ok(mut manager) = self.task_manager.write() {
+            manager.set_history_limit(limit);
+        }
+    }
+
+    /// 规划模式的公共准备（`execute_with_planning` 与流式执行共用）：清空上一次的任务，
+    /// 并强制首轮先调用 plan 工具，避免模型跳过规划直接作答
+    pub(crate) fn begin_planning(&mut self) -> crate::error::Result<()> {
+        self.task_manager
+            .write()
+            .map_err(|e| ReactError::Other(format!("task_manager lock poisoned: {}", e)))?
+            .clear();
+        self.set_next_tool_choice(ToolChoice::function(TOOL_PLAN));
+        Ok(())
+    }
+
     pub async fn execute_with_planning(&mut self, task: &str) -> crate::error::Result<String> {
         let agent = self.config.agent_name.clone();
 
-        // 重置消息历史和任务管理器，确保每次规划都是干净的 session
         self.reset_messages();
-        *self
-            .task_manager
-            .write()
-            .map_err(|e| ReactError::Other(format!("task_manager lock poisoned: {}", e)))? =
-            TaskManager::default();
 
         info!(agent = %agent, "🎯 启动任务规划模式");
         info!(agent = %agent, task = %task, "📋 用户任务");
@@ -28,6 +90,9 @@ impl ReactAgent {
             );
             return self.run_direct(task).await;
         }
+        // 重置任务管理器，确保每次规划都是干净的 session
+        self.begin_planning()?;
+        self.inject_cross_session_summary().await;
 
         // ── 第一阶段：让 Agent 制定计划 ──────────────────────
         info!(agent = %agent, phase = "planning", "📐 阶段1: 制定计划");
@@ -65,13 +130,14 @@ impl ReactAgent {
                     if function_name == "create_task" {
                         created_task_this_round = true;
                     }
-                    let result = self.execute_tool(&function_name, &arguments).await?;
+                    let result = self
+                        .execute_tool(&tool_call_id, &function_name, &arguments)
+                        .await?;
                     if function_name == "final_answer" {
                         info!(agent = %agent, "🏁 规划阶段已生成最终答案");
                         return Ok(result);
                     }
-                    self.context
-                        .push(Message::tool_result(tool_call_id, function_name, result));
+                    self.push_tool_result(tool_call_id, &function_name, &result);
                 }
             }
 
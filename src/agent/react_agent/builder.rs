@@ -316,6 +316,11 @@ impl ReactAgentBuilder {
             agent.set_llm_config(llm_config);
         }
 
+        // 注入自定义 LLM 客户端
+        if let Some(llm_client) = self.llm_client {
+            agent.set_llm_client(llm_client);
+        }
+
         // 注册自定义工具
         for tool in self.tools {
             agent.add_tool(tool);
@@ -2,19 +2,26 @@
 //!
 //! 包含所有"配置型"方法：
 //! - 工具注册（`add_tool` / `add_tools` / `add_need_appeal_tool`）
-//! - Skill 安装（`add_skill` / `add_skills` / `load_skills_from_dir`）
+//! - Skill 安装（`add_skill` / `add_skills` / `load_skills_from_dir` / `watch_skills_dir`）
 //! - MCP 连接（`connect_mcp` / `load_mcp_from_file`）
-//! - SubAgent 注册、压缩器、回调等
-
-use super::ReactAgent;
-use crate::agent::Agent;
-use crate::compression::{ContextCompressor, ForceCompressStats};
-use crate::error::Result;
+//! - SubAgent 注册、共享黑板、压缩器、回调等
+
+use super::{ContextBreakdown, ReactAgent, SKILL_FRAGMENT_PRIORITY, skill_fragment};
+use crate::agent::{Agent, ExecutionTrace};
+use crate::compression::{
+    ContextCompressor, ContextManager, ForceCompressStats, estimate_text_tokens,
+};
+use crate::error::{AgentError, ReactError, Result};
+use crate::llm::ToolChoice;
 use crate::mcp::config_loader::McpServerEntry;
 use crate::mcp::{McpClient, McpConfigFile, McpServerConfig};
-use crate::skills::external::{LoadSkillResourceTool, SkillLoader};
+use crate::memory::Blackboard;
+use crate::skills::external::{LoadSkillResourceTool, SKILL_FILE, SkillLoader, SkillMeta};
 use crate::skills::{Skill, SkillInfo};
-use crate::tools::Tool;
+use crate::tools::builtin::blackboard::{
+    BlackboardReadTool, BlackboardWaitTool, BlackboardWriteTool,
+};
+use crate::tools::{Tool, ToolInterceptor};
 use std::sync::Arc;
 use tokio::sync::Mutex as AsyncMutex;
 use tracing::{info, warn};
@@ -68,6 +75,77 @@ impl ReactAgent {
             .ok();
     }
 
+    /// 让每次工具调用（含之后注册的工具）都经过审批 guard，由当前 provider 决定是否执行
+    ///
+    /// 用于逐步调试等需要拦截所有调用的场景；与 `enable_human_in_loop` 无关。
+    pub fn require_approval_for_all_tools(&mut self) {
+        self.human_in_loop
+            .write()
+            .map_err(|e| {
+                warn!("human_in_loop lock poisoned: {}", e);
+            })
+            .map(|mut guard| guard.mark_all_need_approval())
+            .ok();
+    }
+
+    /// 运行时覆盖发给 LLM 的工具描述（工具名与参数 schema 不变）
+    ///
+    /// 工具未注册时打印警告并返回 `false`。
+    pub fn override_tool_description(
+        &mut self,
+        tool_name: &str,
+        description: impl Into<String>,
+    ) -> bool {
+        self.tool_manager
+            .override_tool_description(tool_name, description)
+    }
+
+    /// 注册工具别名，LLM 以别名调用时落到真实工具，详见 [`ToolManager::register_alias`](crate::tools::ToolManager::register_alias)
+    pub fn register_tool_alias(&mut self, alias: impl Into<String>, real_name: impl Into<String>) {
+        self.tool_manager.register_alias(alias, real_name);
+    }
+
+    /// 注册工具降级链，主工具失败时自动依次尝试备用工具，详见 [`ToolManager::register_fallback_chain`](crate::tools::ToolManager::register_fallback_chain)
+    pub fn register_fallback_chain(
+        &mut self,
+        primary: impl Into<String>,
+        fallbacks: impl IntoIterator<Item = impl Into<String>>,
+    ) {
+        self.tool_manager
+            .register_fallback_chain(primary, fallbacks);
+    }
+
+    /// 注册工具拦截器，工具执行前按注册顺序调用，详见 [`ToolManager::add_interceptor`](crate::tools::ToolManager::add_interceptor)
+    pub fn add_tool_interceptor(&mut self, interceptor: Box<dyn ToolInterceptor>) {
+        self.tool_manager.add_interceptor(interceptor);
+    }
+
+    /// 注册工具软超时钩子，工具执行超过软超时仍未完成时调用，详见 [`ToolManager::on_soft_timeout`](crate::tools::ToolManager::on_soft_timeout)
+    pub fn on_tool_soft_timeout(
+        &mut self,
+        hook: impl Fn(&str, std::time::Duration) + Send + Sync + 'static,
+    ) {
+        self.tool_manager.on_soft_timeout(hook);
+    }
+
+    /// 运行时调整工具并发度，`0` 表示暂停工具执行，详见 [`ToolManager::set_max_concurrency`](crate::tools::ToolManager::set_max_concurrency)
+    pub fn set_tool_concurrency(&self, n: usize) {
+        self.tool_manager.set_max_concurrency(n);
+    }
+
+    /// 强制下一次 LLM 请求只能调用指定工具（单次生效，之后恢复 `AgentConfig::tool_choice`）
+    pub fn force_tool(&mut self, tool_name: &str) {
+        if self.tool_manager.get_tool(tool_name).is_none() {
+            warn!(agent = %self.config.agent_name, tool = %tool_name, "⚠️ 强制调用的工具未注册");
+        }
+        self.next_tool_choice = Some(ToolChoice::function(tool_name));
+    }
+
+    /// 设置仅对下一次 LLM 请求生效的工具选择策略
+    pub fn set_next_tool_choice(&mut self, choice: ToolChoice) {
+        self.next_tool_choice = Some(choice);
+    }
+
     // ── 上下文压缩 ────────────────────────────────────────────────────────────
 
     /// 设置上下文压缩器。
@@ -93,11 +171,68 @@ impl ReactAgent {
         self.context.set_compressor(compressor);
     }
 
+    /// 最近一次 LLM 响应返回的 `system_fingerprint`，可用于判断后端是否变化
+    pub fn system_fingerprint(&self) -> Option<&str> {
+        self.system_fingerprint.as_deref()
+    }
+
+    /// 最近一次执行的结构化链路（task → iteration → llm_call / tool_call）
+    ///
+    /// 执行尚未结束（如流式消费中途）时返回当前快照。
+    pub fn last_trace(&self) -> Option<ExecutionTrace> {
+        self.trace
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|t| t.snapshot())
+    }
+
+    /// 把最近一次执行的链路回放为 Mermaid 序列图，见 [`ExecutionTrace::to_mermaid`]
+    ///
+    /// 尚未执行过时只返回 `sequenceDiagram` 头。
+    pub fn trace_as_mermaid(&self) -> String {
+        self.last_trace()
+            .map(|t| t.to_mermaid())
+            .unwrap_or_else(|| "sequenceDiagram\n".to_string())
+    }
+
     /// 返回当前上下文的（消息条数，估算 token 数）
     pub fn context_stats(&self) -> (usize, usize) {
         (self.context.messages().len(), self.context.token_estimate())
     }
 
+    /// 分项估算每次请求的 token 占用：system prompt、工具 schema 与对话历史
+    ///
+    /// 用于发现"工具太多 / system prompt 太长挤占历史"的问题。估算方式与
+    /// [`context_stats`](Self::context_stats) 一致（字节数 / 4），工具 schema 按序列化后的 JSON 估算。
+    pub fn context_breakdown(&self) -> ContextBreakdown {
+        let (mut system_tokens, mut history_tokens) = (0, 0);
+        for message in self.context.messages() {
+            let tokens = ContextManager::estimate_tokens(std::slice::from_ref(message));
+            if message.role == "system" {
+                system_tokens += tokens;
+            } else {
+                history_tokens += tokens;
+            }
+        }
+
+        let definitions = self.tool_manager.get_tool_definitions();
+        let tools_schema_tokens = if definitions.is_empty() {
+            0
+        } else {
+            serde_json::to_string(&definitions)
+                .map(|json| estimate_text_tokens(&json))
+                .unwrap_or(0)
+        };
+
+        ContextBreakdown {
+            system_tokens,
+            tools_schema_tokens,
+            history_tokens,
+            total: system_tokens + tools_schema_tokens + history_tokens,
+        }
+    }
+
     /// 使用指定压缩器强制压缩上下文（不影响已安装的默认压缩器）
     pub async fn force_compress_with(
         &mut self,
@@ -111,6 +246,17 @@ impl ReactAgent {
         self.tool_manager.list_tools()
     }
 
+    /// 导出所有已注册工具的 OpenAI tools 数组 JSON，按工具名排序
+    ///
+    /// 每个元素形如 `{"type": "function", "function": {"name", "description", "parameters"}}`，
+    /// 已应用描述覆盖，可直接交给 OpenAI SDK 或用于生成工具文档。包含内置工具、MCP 适配工具与
+    /// 运行时添加的工具，不受工具作用域与 [`Tool::is_available`] 的过滤。
+    pub fn export_tool_schemas(&self) -> serde_json::Value {
+        let mut definitions = self.tool_manager.get_tool_definitions();
+        definitions.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+        serde_json::json!(definitions)
+    }
+
     // ── SubAgent ──────────────────────────────────────────────────────────────
 
     pub fn register_agent(&mut self, agent: Box<dyn Agent>) {
@@ -144,6 +290,19 @@ impl ReactAgent {
         }
     }
 
+    /// 接入共享黑板：注册 `blackboard_write` / `blackboard_read` / `blackboard_wait` 工具，
+    /// 写入者记为本 Agent 的名称
+    ///
+    /// 把同一个 `Arc<Blackboard>` 接入多个 Agent，即可在它们之间直接交换中间结果。
+    pub fn attach_blackboard(&mut self, board: Arc<Blackboard>) {
+        let author = self.config.agent_name.clone();
+        self.add_tools(vec![
+            Box::new(BlackboardWriteTool::new(board.clone(), author)),
+            Box::new(BlackboardReadTool::new(board.clone())),
+            Box::new(BlackboardWaitTool::new(board)),
+        ]);
+    }
+
     // ── 基础配置 ──────────────────────────────────────────────────────────────
 
     pub fn set_model(&mut self, model_name: &str) {
@@ -185,7 +344,7 @@ impl ReactAgent {
         &mut self,
         skills_dir: impl Into<std::path::PathBuf>,
     ) -> Result<Vec<String>> {
-        let loader = Arc::new(tokio::sync::Mutex::new(SkillLoader::new(skills_dir)));
+        let loader = Arc::new(AsyncMutex::new(SkillLoader::new(skills_dir)));
 
         let loaded = {
             let mut l = loader.lock().await;
@@ -215,9 +374,6 @@ impl ReactAgent {
                 continue;
             }
 
-            let prompt_block = meta.to_prompt_block();
-            self.config.system_prompt.push_str(&prompt_block);
-
             {
                 let l = loader.lock().await;
                 for res_ref in meta.startup_resources() {
@@ -231,70 +387,193 @@ impl ReactAgent {
                 }
             }
 
-            if meta.resources.as_ref().is_some_and(|r| !r.is_empty()) {
-                has_resources = true;
+            has_resources |= self.install_external_skill(meta);
+            loaded_names.push(meta.name.clone());
+        }
+
+        if has_resources && self.tool_manager.get_tool("load_skill_resource").is_none() {
+            self.register_skill_resource_tool(loader.clone()).await;
+        }
+        self.skill_loader = Some(loader);
+
+        Ok(loaded_names)
+    }
+
+    /// 监听技能目录，SKILL.md 变更时热重载对应的 Skill
+    ///
+    /// 文件事件只登记待重载的技能目录；重载在 Agent 空闲时（下一次
+    /// `execute` / `chat` 及其流式版本开始前）执行，不会打断正在进行的任务。
+    /// 重载流程与失败处理见 [`reload_skill`](Self::reload_skill)。
+    /// 监听随 Agent 一起释放，重复调用会替换之前的监听。
+    pub fn watch_skills_dir(&mut self, skills_dir: impl Into<std::path::PathBuf>) -> Result<()> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let skills_dir = skills_dir.into();
+        let pending = self.pending_skill_reloads.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    let dirs = event
+                        .paths
+                        .iter()
+                        .filter(|p| p.file_name().is_some_and(|n| n == SKILL_FILE))
+                        .filter_map(|p| p.parent().map(|d| d.to_path_buf()));
+                    pending.lock().unwrap().extend(dirs);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "技能目录监听出错"),
+            })
+            .map_err(|e| ReactError::Other(format!("无法创建技能目录监听: {e}")))?;
+        watcher
+            .watch(&skills_dir, RecursiveMode::Recursive)
+            .map_err(|e| {
+                ReactError::Other(format!("无法监听技能目录 {}: {e}", skills_dir.display()))
+            })?;
+
+        if self.skill_loader.is_none() {
+            self.skill_loader = Some(Arc::new(AsyncMutex::new(SkillLoader::new(&skills_dir))));
+        }
+        self.skill_watcher = Some(watcher);
+        info!(
+            agent = %self.config.agent_name,
+            dir = %skills_dir.display(),
+            "👀 开始监听技能目录"
+        );
+        Ok(())
+    }
+
+    /// 重新加载单个技能目录中的 SKILL.md，返回新的 Skill 名称
+    ///
+    /// 先解析新版本，成功后卸载旧版本（system 片段与 SkillManager 记录），
+    /// 再安装新版本并刷新 `load_skill_resource` 的资源目录。
+    /// 解析失败时保留旧版本、打印警告并返回错误。
+    pub async fn reload_skill(&mut self, skill_dir: impl AsRef<std::path::Path>) -> Result<String> {
+        let skill_dir = skill_dir.as_ref();
+        let loader = match &self.skill_loader {
+            Some(loader) => loader.clone(),
+            None => {
+                let root = skill_dir.parent().unwrap_or(skill_dir);
+                let loader = Arc::new(AsyncMutex::new(SkillLoader::new(root)));
+                self.skill_loader = Some(loader.clone());
+                loader
             }
+        };
 
-            let tool_names = if has_resources {
-                vec!["load_skill_resource".to_string()]
-            } else {
-                vec![]
-            };
-            self.skill_manager.record(SkillInfo {
-                name: meta.name.clone(),
-                description: meta.description.clone(),
-                tool_names,
-                has_prompt_injection: true,
-            });
-
-            tracing::info!(
-                agent = %self.config.agent_name,
-                skill = %meta.name,
-                version = %meta.version.as_deref().unwrap_or("?"),
-                resources = meta.resources.as_ref().map_or(0, |r| r.len()),
-                "🎯 外部 Skill 已加载"
-            );
+        let (old_name, reloaded) = {
+            let mut l = loader.lock().await;
+            let old_name = l.skill_at(skill_dir).map(|s| s.meta.name.clone());
+            match l.reload(skill_dir).await {
+                Ok(skill) => (old_name, skill),
+                Err(e) => {
+                    warn!(
+                        agent = %self.config.agent_name,
+                        dir = %skill_dir.display(),
+                        error = %e,
+                        "⚠️ Skill 重载失败，保留旧版本"
+                    );
+                    return Err(e);
+                }
+            }
+        };
 
-            loaded_names.push(meta.name.clone());
+        let name = reloaded.meta.name.clone();
+        for stale in old_name.iter().chain(std::iter::once(&name)) {
+            if self.skill_manager.remove(stale).is_some() {
+                self.context.remove_system_fragment(&skill_fragment(stale));
+            }
         }
+        self.install_external_skill(&reloaded.meta);
+        self.register_skill_resource_tool(loader).await;
 
-        self.context
-            .update_system(self.config.system_prompt.clone());
+        info!(
+            agent = %self.config.agent_name,
+            skill = %name,
+            "🔄 外部 Skill 已重载"
+        );
+        Ok(name)
+    }
 
-        if has_resources && self.tool_manager.get_tool("load_skill_resource").is_none() {
-            let catalog_desc = {
-                let l = loader.lock().await;
-                l.resource_catalog()
-                    .iter()
-                    .map(|(sname, rref)| {
-                        format!(
-                            "  - {}/{}: {}",
-                            sname,
-                            rref.name,
-                            rref.description.as_deref().unwrap_or("")
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            };
-
-            let tool = LoadSkillResourceTool::new(loader).with_catalog_desc(catalog_desc);
-            self.tool_manager.register(Box::new(tool));
-
-            tracing::info!(
-                agent = %self.config.agent_name,
-                "已注册 load_skill_resource 工具"
-            );
+    /// 应用文件监听登记的待重载技能，返回重载成功的 Skill 名称
+    pub(crate) async fn apply_pending_skill_reloads(&mut self) -> Vec<String> {
+        let dirs = std::mem::take(&mut *self.pending_skill_reloads.lock().unwrap());
+        let mut reloaded = Vec::new();
+        for dir in dirs {
+            // 失败时 reload_skill 已打印警告并保留旧版本
+            if let Ok(name) = self.reload_skill(&dir).await {
+                reloaded.push(name);
+            }
         }
+        reloaded
+    }
 
-        Ok(loaded_names)
+    /// 注入外部 Skill 的 system 片段并记录元数据，返回它是否声明了资源
+    fn install_external_skill(&mut self, meta: &SkillMeta) -> bool {
+        self.context.set_system_fragment(
+            &skill_fragment(&meta.name),
+            SKILL_FRAGMENT_PRIORITY,
+            meta.to_prompt_block(),
+        );
+
+        let has_resources = meta.resources.as_ref().is_some_and(|r| !r.is_empty());
+        let tool_names = if has_resources {
+            vec!["load_skill_resource".to_string()]
+        } else {
+            vec![]
+        };
+        self.skill_manager.record(SkillInfo {
+            name: meta.name.clone(),
+            description: meta.description.clone(),
+            tool_names,
+            has_prompt_injection: true,
+        });
+
+        tracing::info!(
+            agent = %self.config.agent_name,
+            skill = %meta.name,
+            version = %meta.version.as_deref().unwrap_or("?"),
+            resources = meta.resources.as_ref().map_or(0, |r| r.len()),
+            "🎯 外部 Skill 已加载"
+        );
+        has_resources
+    }
+
+    /// 按加载器当前的资源目录（重新）注册 `load_skill_resource`；目录为空时注销该工具
+    async fn register_skill_resource_tool(&mut self, loader: Arc<AsyncMutex<SkillLoader>>) {
+        let catalog_desc = {
+            let l = loader.lock().await;
+            l.resource_catalog()
+                .iter()
+                .map(|(sname, rref)| {
+                    format!(
+                        "  - {}/{}: {}",
+                        sname,
+                        rref.name,
+                        rref.description.as_deref().unwrap_or("")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        self.tool_manager.unregister("load_skill_resource");
+        if catalog_desc.is_empty() {
+            return;
+        }
+        let tool = LoadSkillResourceTool::new(loader).with_catalog_desc(catalog_desc);
+        self.tool_manager.register(Box::new(tool));
+
+        tracing::info!(
+            agent = %self.config.agent_name,
+            "已注册 load_skill_resource 工具"
+        );
     }
 
     /// 为 Agent 安装一个 Skill
     ///
     /// 安装过程：
+    /// 0. 调用 `Skill::validate` 校验依赖，失败时拒绝安装并返回 `AgentError::SkillValidationFailed`
     /// 1. 将 Skill 提供的所有工具注册到 ToolManager
-    /// 2. 若 Skill 有 system_prompt_injection，追加到 system_prompt
+    /// 2. 若 Skill 有 system_prompt_injection，作为 `skill:<name>` system 片段注入
     /// 3. 记录 Skill 元数据到 SkillManager
     ///
     /// # 示例
@@ -303,12 +582,12 @@ impl ReactAgent {
     /// use echo_agent::prelude::*;
     ///
     /// let mut agent = ReactAgent::new(AgentConfig::minimal("qwen3-max", "你是一个助手"));
-    /// agent.add_skill(Box::new(CalculatorSkill));
-    /// agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/workspace")));
+    /// agent.add_skill(Box::new(CalculatorSkill))?;
+    /// agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/workspace")))?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn add_skill(&mut self, skill: Box<dyn Skill>) {
+    pub fn add_skill(&mut self, skill: Box<dyn Skill>) -> Result<()> {
         let name = skill.name().to_string();
 
         if self.skill_manager.is_installed(&name) {
@@ -317,7 +596,21 @@ impl ReactAgent {
                 skill = %name,
                 "⚠️ Skill 已安装，跳过重复注册"
             );
-            return;
+            return Ok(());
+        }
+
+        if let Err(reason) = skill.validate() {
+            warn!(
+                agent = %self.config.agent_name,
+                skill = %name,
+                reason = %reason,
+                "❌ Skill 校验失败，拒绝安装"
+            );
+            return Err(AgentError::SkillValidationFailed {
+                skill: name,
+                reason,
+            }
+            .into());
         }
 
         let tools = skill.tools();
@@ -329,9 +622,11 @@ impl ReactAgent {
 
         let has_injection = skill.system_prompt_injection().is_some();
         if let Some(injection) = skill.system_prompt_injection() {
-            self.config.system_prompt.push_str(&injection);
-            self.context
-                .update_system(self.config.system_prompt.clone());
+            self.context.set_system_fragment(
+                &skill_fragment(&name),
+                SKILL_FRAGMENT_PRIORITY,
+                injection,
+            );
         }
 
         self.skill_manager.record(SkillInfo {
@@ -347,13 +642,15 @@ impl ReactAgent {
             description = %skill.description(),
             "🎯 Skill 已安装"
         );
+        Ok(())
     }
 
-    /// 批量安装多个 Skill
-    pub fn add_skills(&mut self, skills: Vec<Box<dyn Skill>>) {
+    /// 批量安装多个 Skill，遇到校验失败的 Skill 时停止并返回错误（之前的已安装）
+    pub fn add_skills(&mut self, skills: Vec<Box<dyn Skill>>) -> Result<()> {
         for skill in skills {
-            self.add_skill(skill);
+            self.add_skill(skill)?;
         }
+        Ok(())
     }
 
     /// 列出所有已安装的 Skill 元数据
@@ -554,7 +851,7 @@ impl ReactAgent {
 
     /// 运行时更新系统提示词
     ///
-    /// 同时更新配置和上下文中的 system 消息
+    /// 同时更新配置和上下文中的 `base` system 片段，Skill 注入、CoT 等其他片段保持不变
     pub fn set_system_prompt(&mut self, prompt: String) {
         // 更新配置
         self.config.system_prompt = prompt.clone();
@@ -0,0 +1,161 @@
+//! 对话章节标记
+//!
+//! 开启 [`AgentConfig::auto_chapter`](crate::agent::config::AgentConfig::auto_chapter) 后，
+//! 每隔 `chapter_interval` 轮用户输入，或检测到话题切换时，在上下文中插入一条
+//! `role = "chapter"` 的标记消息，内容为该段的主题摘要。标记随 checkpoint 持久化，
+//! 但不会交给压缩器，也会在 [`ContextManager::prepare`](crate::compression::ContextManager::prepare)
+//! 中被过滤，不会进入 LLM 请求。[`export_markdown`](ReactAgent::export_markdown) 据此生成目录。
+//!
+//! 话题切换按关键词是否重合判断（英文按单词、中文按相邻两字），不调用 LLM。
+
+use super::ReactAgent;
+use crate::compression::CHAPTER_ROLE;
+use crate::compression::compressor::relevance::keywords;
+use crate::llm::types::Message;
+use std::collections::HashSet;
+use tracing::debug;
+
+/// 章节标题最大字符数
+const MAX_CHAPTER_TITLE_CHARS: usize = 30;
+/// 当前章节至少有几轮后才检测话题切换（避免"继续""好的"之类的短回复误判）
+const MIN_TURNS_BEFORE_SHIFT: usize = 2;
+/// 新输入至少包含几个关键词才参与话题切换判断
+const MIN_KEYWORDS_FOR_SHIFT: usize = 3;
+
+/// 当前章节的累计状态
+#[derive(Debug, Default)]
+pub(crate) struct ChapterState {
+    /// 当前章节已有的用户轮数
+    turns: usize,
+    /// 当前章节用户输入的关键词
+    keywords: HashSet<String>,
+}
+
+impl ReactAgent {
+    /// 追加用户输入前调用：需要开启新章节时插入标记消息
+    pub(crate) fn mark_chapter(&mut self, input: &str) {
+        if !self.config.auto_chapter {
+            return;
+        }
+        let keywords = keywords(input);
+        let has_chapter = self
+            .context
+            .messages()
+            .iter()
+            .any(|m| m.role == CHAPTER_ROLE);
+        let state = &mut self.chapter_state;
+        let new_chapter = !has_chapter
+            || state.turns >= self.config.chapter_interval.max(1)
+            || (state.turns >= MIN_TURNS_BEFORE_SHIFT
+                && is_topic_shift(&keywords, &state.keywords));
+
+        if new_chapter {
+            let title = chapter_title(input);
+            debug!(agent = %self.config.agent_name, title = %title, "📑 插入章节标记");
+            *state = ChapterState { turns: 1, keywords };
+            self.context.push(Message {
+                role: CHAPTER_ROLE.to_string(),
+                content: Some(title),
+                tool_calls: None,
+                name: None,
+                tool_call_id: None,
+            });
+        } else {
+            state.turns += 1;
+            state.keywords.extend(keywords);
+        }
+    }
+
+    /// 当前对话中的章节标题（按出现顺序）
+    pub fn chapters(&self) -> Vec<String> {
+        self.context
+            .messages()
+            .iter()
+            .filter(|m| m.role == CHAPTER_ROLE)
+            .filter_map(|m| m.content.clone())
+            .collect()
+    }
+
+    /// 把对话导出为 Markdown：有章节标记时开头生成目录，每个章节一个小标题
+    ///
+    /// 只导出用户与助手的文本消息，system / tool 消息与纯工具调用不导出。
+    pub fn export_markdown(&self) -> String {
+        let chapters = self.chapters();
+        let mut out = String::new();
+        if !chapters.is_empty() {
+            out.push_str("# 目录\n\n");
+            for (i, title) in chapters.iter().enumerate() {
+                out.push_str(&format!("{}. [{}](#chapter-{})\n", i + 1, title, i + 1));
+            }
+            out.push('\n');
+        }
+
+        let mut chapter_index = 0;
+        for message in self.context.messages() {
+            let Some(content) = message.content.as_deref().map(str::trim) else {
+                continue;
+            };
+            match message.role.as_str() {
+                CHAPTER_ROLE => {
+                    chapter_index += 1;
+                    out.push_str(&format!(
+                        "<a id=\"chapter-{chapter_index}\"></a>\n\n## {chapter_index}. {content}\n\n"
+                    ));
+                }
+                "user" if !content.is_empty() => {
+                    out.push_str(&format!("**用户**：{content}\n\n"));
+                }
+                "assistant" if !content.is_empty() => {
+                    out.push_str(&format!("**助手**：{content}\n\n"));
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+/// 章节标题：用户输入的第一行，超长时截断
+fn chapter_title(input: &str) -> String {
+    let line = input.trim().lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MAX_CHAPTER_TITLE_CHARS {
+        return line.to_string();
+    }
+    let mut title: String = line.chars().take(MAX_CHAPTER_TITLE_CHARS).collect();
+    title.push('…');
+    title
+}
+
+/// 新输入的关键词与当前章节完全不重合
+///
+/// 中文按两字切分时新输入里大多是新组合，按比例判断容易误判，因此只看有无共同关键词。
+fn is_topic_shift(keywords: &HashSet<String>, chapter: &HashSet<String>) -> bool {
+    keywords.len() >= MIN_KEYWORDS_FOR_SHIFT && keywords.is_disjoint(chapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_shift_by_overlap() {
+        let chapter = keywords("Rust 的所有权和借用规则");
+        assert!(!is_topic_shift(
+            &keywords("所有权转移后原变量还能用吗"),
+            &chapter
+        ));
+        assert!(is_topic_shift(&keywords("推荐一家北京的烤鸭店"), &chapter));
+        // 关键词太少的短回复不判为切换
+        assert!(!is_topic_shift(&keywords("继续"), &chapter));
+    }
+
+    #[test]
+    fn test_chapter_title_truncates() {
+        assert_eq!(chapter_title("  介绍一下 Rust\n详细一点"), "介绍一下 Rust");
+        let long = "很".repeat(40);
+        assert_eq!(
+            chapter_title(&long).chars().count(),
+            MAX_CHAPTER_TITLE_CHARS + 1
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! 多候选回复的选择策略（best-of / 采样）
+//!
+//! [`AgentConfig::n_choices`](crate::agent::config::AgentConfig::n_choices) 大于 1 时，
+//! 每轮推理请求一次返回多个 choices，由 [`ChoiceSelector`] 选出其中一个继续执行。
+//! 内置 [`FirstChoice`]（默认）、[`LongestChoice`]、[`MajorityVote`] 与 [`LlmJudge`]。
+
+use super::ReactAgent;
+use crate::error::Result;
+use crate::llm::LlmClient;
+use crate::llm::types::Message;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// 从多个候选回复中选出一个
+///
+/// # 示例
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use echo_agent::agent::react_agent::ChoiceSelector;
+/// use echo_agent::error::Result;
+/// use echo_agent::llm::types::Message;
+///
+/// /// 优先选择调用了工具的候选
+/// struct PreferToolCalls;
+///
+/// #[async_trait]
+/// impl ChoiceSelector for PreferToolCalls {
+///     async fn select(&self, _context: &[Message], candidates: &[Message]) -> Result<usize> {
+///         Ok(candidates
+///             .iter()
+///             .position(|m| m.tool_calls.as_ref().is_some_and(|c| !c.is_empty()))
+///             .unwrap_or(0))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ChoiceSelector: Send + Sync {
+    /// 返回选中候选的下标；`context` 为本轮发给 LLM 的消息，`candidates` 至少有两个
+    ///
+    /// 返回越界下标时取第一个候选；返回 `Err` 时本轮推理以该错误结束。
+    async fn select(&self, context: &[Message], candidates: &[Message]) -> Result<usize>;
+}
+
+/// 取第一个候选（默认）
+pub struct FirstChoice;
+
+#[async_trait]
+impl ChoiceSelector for FirstChoice {
+    async fn select(&self, _context: &[Message], _candidates: &[Message]) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// 取内容（含工具调用参数）最长的候选，长度相同时取靠前的
+pub struct LongestChoice;
+
+#[async_trait]
+impl ChoiceSelector for LongestChoice {
+    async fn select(&self, _context: &[Message], candidates: &[Message]) -> Result<usize> {
+        let length = |m: &Message| {
+            let content = m.content.as_deref().map_or(0, |c| c.chars().count());
+            let arguments: usize = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|c| c.function.arguments.chars().count())
+                .sum();
+            content + arguments
+        };
+        let mut best = 0;
+        for (i, candidate) in candidates.iter().enumerate() {
+            if length(candidate) > length(&candidates[best]) {
+                best = i;
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// 投票：取出现次数最多的回复，票数相同时取最先出现的
+///
+/// 文本回复按去掉首尾空白后的内容比较；工具调用按工具名与参数 JSON 比较（忽略键顺序与调用 ID）。
+pub struct MajorityVote;
+
+impl MajorityVote {
+    fn ballot(message: &Message) -> String {
+        let calls: Vec<String> = message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| {
+                let arguments = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|_| call.function.arguments.clone());
+                format!("{}({arguments})", call.function.name)
+            })
+            .collect();
+        if calls.is_empty() {
+            message
+                .content
+                .as_deref()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        } else {
+            calls.join(";")
+        }
+    }
+}
+
+#[async_trait]
+impl ChoiceSelector for MajorityVote {
+    async fn select(&self, _context: &[Message], candidates: &[Message]) -> Result<usize> {
+        let mut votes: HashMap<String, (usize, usize)> = HashMap::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            votes.entry(Self::ballot(candidate)).or_insert((0, i)).0 += 1;
+        }
+        Ok(votes
+            .into_values()
+            .max_by(|(a, first_a), (b, first_b)| a.cmp(b).then(first_b.cmp(first_a)))
+            .map_or(0, |(_, first)| first))
+    }
+}
+
+/// 用一个评分 LLM 从候选中选出最好的一个（多一次 LLM 调用）
+pub struct LlmJudge {
+    llm: Arc<dyn LlmClient>,
+}
+
+impl LlmJudge {
+    pub fn new(llm: Arc<dyn LlmClient>) -> Self {
+        Self { llm }
+    }
+
+    /// 候选的文本表示：内容与工具调用
+    fn describe(message: &Message) -> String {
+        let mut parts: Vec<String> = message.content.iter().cloned().collect();
+        for call in message.tool_calls.iter().flatten() {
+            parts.push(format!(
+                "调用工具 {}，参数 {}",
+                call.function.name, call.function.arguments
+            ));
+        }
+        parts.join("\n")
+    }
+}
+
+#[async_trait]
+impl ChoiceSelector for LlmJudge {
+    async fn select(&self, context: &[Message], candidates: &[Message]) -> Result<usize> {
+        let task = context
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .and_then(|m| m.content.as_deref())
+            .unwrap_or_default();
+        let listing: String = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, m)| format!("[{}]\n{}\n\n", i + 1, Self::describe(m)))
+            .collect();
+        let prompt = format!(
+            "以下是针对同一请求的 {} 个候选回复，请选出最好的一个，只回复其编号。\n\n请求：{task}\n\n{listing}",
+            candidates.len()
+        );
+        let reply = self.llm.chat_simple(vec![Message::user(prompt)]).await?;
+        let picked = reply
+            .split(|c: char| !c.is_ascii_digit())
+            .find_map(|s| s.parse::<usize>().ok())
+            .filter(|&n| (1..=candidates.len()).contains(&n));
+        match picked {
+            Some(n) => Ok(n - 1),
+            None => {
+                warn!(reply = %reply, "⚠️ 评分 LLM 未给出有效编号，取第一个候选");
+                Ok(0)
+            }
+        }
+    }
+}
+
+impl ReactAgent {
+    /// 设置多候选回复的选择策略，`n_choices` 大于 1 时生效（默认 [`FirstChoice`]）
+    pub fn set_choice_selector(&mut self, selector: Arc<dyn ChoiceSelector>) {
+        self.choice_selector = selector;
+    }
+
+    /// 从响应的多个候选中选出本轮使用的回复；只有一个候选时直接返回
+    pub(crate) async fn select_choice(
+        &self,
+        context: &[Message],
+        mut candidates: Vec<Message>,
+    ) -> Result<Option<Message>> {
+        if candidates.len() <= 1 {
+            return Ok(candidates.pop());
+        }
+        let index = self.choice_selector.select(context, &candidates).await?;
+        let index = if index < candidates.len() {
+            index
+        } else {
+            warn!(
+                agent = %self.config.agent_name,
+                index,
+                count = candidates.len(),
+                "⚠️ 选择器返回的下标越界，取第一个候选"
+            );
+            0
+        };
+        debug!(agent = %self.config.agent_name, index, count = candidates.len(), "🎯 已从多个候选回复中选出一个");
+        Ok(Some(candidates.swap_remove(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLlmClient;
+
+    fn texts(items: &[&str]) -> Vec<Message> {
+        items
+            .iter()
+            .map(|t| Message::assistant(t.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_builtin_selectors() {
+        let candidates = texts(&["短", "最长的一个回复", "B", " B ", "中等长度"]);
+        assert_eq!(FirstChoice.select(&[], &candidates).await.unwrap(), 0);
+        assert_eq!(LongestChoice.select(&[], &candidates).await.unwrap(), 1);
+        assert_eq!(MajorityVote.select(&[], &candidates).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_llm_judge_parses_number() {
+        let judge = LlmJudge::new(Arc::new(MockLlmClient::new().with_response("最好的是 [3]")));
+        let candidates = texts(&["a", "b", "c"]);
+        let context = [Message::user("选一个".to_string())];
+        assert_eq!(judge.select(&context, &candidates).await.unwrap(), 2);
+
+        let judge = LlmJudge::new(Arc::new(MockLlmClient::new().with_response("都不好")));
+        assert_eq!(judge.select(&context, &candidates).await.unwrap(), 0);
+    }
+}
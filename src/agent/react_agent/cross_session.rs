@@ -0,0 +1,194 @@
+//! 跨会话记忆
+//!
+//! 新会话开始时，从 Checkpointer 读取同一用户最近几个已结束会话，请求 LLM 提炼要点，
+//! 以 system 片段注入（"你之前和该用户讨论过：..."），让 Agent 不必从空白开始。
+//!
+//! Checkpointer 按 session_id 存储，没有独立的用户字段：session_id 为 `<user_id>`
+//! 本身，或以 `<user_id>` 加分隔符（`:` `-` `_` `/`）开头的会话视为属于该用户，
+//! 如 `alice:2024-06-01`、`alice-session-1`。
+
+use super::title::truncate;
+use super::{CROSS_SESSION_FRAGMENT, CROSS_SESSION_FRAGMENT_PRIORITY, ReactAgent};
+use crate::error::Result;
+use crate::llm::types::Message;
+use crate::memory::checkpointer::Checkpointer;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// 摘要最大字符数，超出部分截断
+const MAX_SUMMARY_CHARS: usize = 500;
+/// 每个历史会话提交给 LLM 的最大消息条数（取会话末尾）
+const MAX_SESSION_MESSAGES: usize = 20;
+/// 每条消息提交给 LLM 的最大字符数
+const MAX_MESSAGE_CHARS: usize = 300;
+
+const SUMMARY_PROMPT: &str = "下面是你与同一用户之前几次会话的记录。\
+请提炼对后续对话有用的要点：用户的背景与偏好、进行中的事项、已经得出的结论。\
+用简洁的条目输出，总长度不超过 300 字，只输出要点本身。";
+
+/// 注入片段的前缀
+const SUMMARY_HEADER: &str = "你之前和该用户讨论过：";
+
+/// 跨会话记忆配置
+pub(super) struct CrossSessionMemory {
+    checkpointer: Arc<dyn Checkpointer>,
+    user_id: String,
+    /// 最多读取的历史会话数
+    limit: usize,
+    /// 已为哪个 session_id 注入摘要或确认没有历史会话；同一会话再次开始时沿用已有片段，
+    /// 切换到其他会话后重新生成
+    resolved: Option<Option<String>>,
+}
+
+impl ReactAgent {
+    /// 启用跨会话记忆：新会话开始时注入该用户最近 `sessions` 个已结束会话的摘要
+    ///
+    /// 当前 session_id 对应的会话不计入；没有历史会话时不注入。
+    pub fn with_cross_session_memory(
+        mut self,
+        checkpointer: Arc<dyn Checkpointer>,
+        user_id: impl Into<String>,
+        sessions: usize,
+    ) -> Self {
+        self.set_cross_session_memory(checkpointer, user_id, sessions);
+        self
+    }
+
+    /// 启用跨会话记忆，见 [`with_cross_session_memory`](Self::with_cross_session_memory)
+    pub fn set_cross_session_memory(
+        &mut self,
+        checkpointer: Arc<dyn Checkpointer>,
+        user_id: impl Into<String>,
+        sessions: usize,
+    ) {
+        self.context.remove_system_fragment(CROSS_SESSION_FRAGMENT);
+        self.cross_session = Some(CrossSessionMemory {
+            checkpointer,
+            user_id: user_id.into(),
+            limit: sessions,
+            resolved: None,
+        });
+    }
+
+    /// 新会话开始时调用：生成历史会话摘要并注入 system prompt
+    ///
+    /// 读取或摘要失败只记录警告，下一个新会话会重试。
+    pub(crate) async fn inject_cross_session_summary(&mut self) {
+        let session_id = self.config.session_id.clone();
+        let Some(memory) = self.cross_session.as_ref() else {
+            return;
+        };
+        match &memory.resolved {
+            Some(resolved) if *resolved == session_id => return,
+            // 换了会话：旧摘要可能包含当前会话本身，或遗漏之后结束的会话
+            Some(_) => {
+                self.context.remove_system_fragment(CROSS_SESSION_FRAGMENT);
+            }
+            None => {}
+        }
+        let agent = self.config.agent_name.clone();
+        let user_id = memory.user_id.clone();
+
+        let transcripts = match self.recent_transcripts(memory).await {
+            Ok(transcripts) => transcripts,
+            Err(e) => {
+                warn!(agent = %agent, user_id = %user_id, error = %e, "⚠️ 历史会话读取失败，跳过跨会话记忆");
+                return;
+            }
+        };
+        if transcripts.is_empty() {
+            debug!(agent = %agent, user_id = %user_id, "没有历史会话，不注入跨会话记忆");
+            self.mark_cross_session_resolved();
+            return;
+        }
+
+        let count = transcripts.len();
+        let messages = vec![
+            Message::system(SUMMARY_PROMPT.to_string()),
+            Message::user(transcripts.join("\n\n")),
+        ];
+        match self.request_text(messages, 0.3, 512).await {
+            Ok(raw) if !raw.trim().is_empty() => {
+                let summary = truncate(raw.trim(), MAX_SUMMARY_CHARS);
+                info!(agent = %agent, user_id = %user_id, sessions = count, "🧳 注入跨会话记忆摘要");
+                self.context.set_system_fragment(
+                    CROSS_SESSION_FRAGMENT,
+                    CROSS_SESSION_FRAGMENT_PRIORITY,
+                    format!("{SUMMARY_HEADER}\n{summary}"),
+                );
+                self.mark_cross_session_resolved();
+            }
+            Ok(_) => warn!(agent = %agent, "⚠️ 跨会话摘要为空，跳过注入"),
+            Err(e) => warn!(agent = %agent, error = %e, "⚠️ 跨会话摘要生成失败，跳过注入"),
+        }
+    }
+
+    /// 多轮对话的首条消息同样开启新会话：上下文中还没有对话消息时注入摘要
+    pub(crate) async fn inject_cross_session_summary_for_chat(&mut self) {
+        if self.context.messages().iter().all(|m| m.role == "system") {
+            self.inject_cross_session_summary().await;
+        }
+    }
+
+    fn mark_cross_session_resolved(&mut self) {
+        if let Some(memory) = &mut self.cross_session {
+            memory.resolved = Some(self.config.session_id.clone());
+        }
+    }
+
+    /// 该用户最近的已结束会话，按时间先后转为文本记录（无文本内容的会话跳过）
+    async fn recent_transcripts(&self, memory: &CrossSessionMemory) -> Result<Vec<String>> {
+        let current = self.config.session_id.as_deref();
+        let metas = memory.checkpointer.list_session_meta().await?;
+        let mut transcripts = Vec::new();
+        for meta in metas
+            .iter()
+            .filter(|m| Some(m.id.as_str()) != current && belongs_to(&m.id, &memory.user_id))
+            .take(memory.limit)
+        {
+            let Some(checkpoint) = memory.checkpointer.get(&meta.id).await? else {
+                continue;
+            };
+            let lines: Vec<String> = checkpoint
+                .messages
+                .iter()
+                .filter(|m| m.role == "user" || m.role == "assistant")
+                .filter_map(|m| {
+                    let content = m.content.as_deref()?.trim();
+                    (!content.is_empty())
+                        .then(|| format!("{}: {}", m.role, truncate(content, MAX_MESSAGE_CHARS)))
+                })
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+            let start = lines.len().saturating_sub(MAX_SESSION_MESSAGES);
+            let title = checkpoint.title.as_deref().unwrap_or(&meta.id);
+            transcripts.push(format!("## 会话：{title}\n{}", lines[start..].join("\n")));
+        }
+        // list_session_meta 按最近活跃倒序，摘要按时间先后排列
+        transcripts.reverse();
+        Ok(transcripts)
+    }
+}
+
+/// session_id 是否属于该用户
+fn belongs_to(session_id: &str, user_id: &str) -> bool {
+    session_id
+        .strip_prefix(user_id)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([':', '-', '_', '/']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_belongs_to_user() {
+        assert!(belongs_to("alice", "alice"));
+        assert!(belongs_to("alice:2024-06-01", "alice"));
+        assert!(belongs_to("alice-session-1", "alice"));
+        assert!(!belongs_to("alicia:1", "alice"));
+        assert!(!belongs_to("bob:1", "alice"));
+    }
+}
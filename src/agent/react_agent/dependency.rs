@@ -0,0 +1,168 @@
+//! 同一轮工具调用之间的依赖解析
+//!
+//! LLM 可以在工具参数的字符串里写 `$tool_N.output`，引用本轮第 N 个（从 0 计数）
+//! 工具调用的输出。执行前按引用关系把调用分层：同一层内互不依赖、可并行，
+//! 层与层之间串行，执行下一层前把引用替换为前序调用的实际输出。
+//!
+//! 引用越界、自引用、循环引用或被引用的调用失败时，引用按原样保留并 warn。
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use tracing::warn;
+
+const REF_PREFIX: &str = "$tool_";
+const REF_SUFFIX: &str = ".output";
+
+/// 在字符串中查找所有 `$tool_N.output` 引用，返回 (字节起点, 字节终点, N)
+fn find_references(text: &str) -> Vec<(usize, usize, usize)> {
+    let mut refs = Vec::new();
+    let mut offset = 0;
+    while let Some(pos) = text[offset..].find(REF_PREFIX) {
+        let start = offset + pos;
+        let digits_start = start + REF_PREFIX.len();
+        let digits_len = text[digits_start..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let digits_end = digits_start + digits_len;
+        if digits_len > 0
+            && text[digits_end..].starts_with(REF_SUFFIX)
+            && let Ok(index) = text[digits_start..digits_end].parse()
+        {
+            let end = digits_end + REF_SUFFIX.len();
+            refs.push((start, end, index));
+            offset = end;
+        } else {
+            offset = digits_start;
+        }
+    }
+    refs
+}
+
+fn collect_references(value: &Value, out: &mut BTreeSet<usize>) {
+    match value {
+        Value::String(s) if s.contains(REF_PREFIX) => {
+            out.extend(find_references(s).into_iter().map(|(_, _, i)| i));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_references(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_references(v, out)),
+        _ => {}
+    }
+}
+
+/// 参数中引用到的调用序号
+pub(crate) fn references(args: &Value) -> BTreeSet<usize> {
+    let mut out = BTreeSet::new();
+    collect_references(args, &mut out);
+    out
+}
+
+/// 按依赖关系把 `args_list` 中的调用分层，每层内的序号保持原顺序
+///
+/// 没有任何引用时只有一层（即全部并行），与不解析依赖时的行为一致。
+/// 无效引用（越界 / 自引用）视为不存在；陷入循环的调用放在最后一层按原样执行。
+pub(crate) fn plan_waves(args_list: &[&Value]) -> Vec<Vec<usize>> {
+    let count = args_list.len();
+    let mut deps: Vec<BTreeSet<usize>> = Vec::with_capacity(count);
+    for (i, args) in args_list.iter().enumerate() {
+        let mut refs = references(args);
+        refs.retain(|&dep| {
+            let valid = dep < count && dep != i;
+            if !valid {
+                warn!(
+                    call = i,
+                    reference = dep,
+                    "⚠️ 工具参数引用了不存在的调用，按原样执行"
+                );
+            }
+            valid
+        });
+        deps.push(refs);
+    }
+
+    let mut done = vec![false; count];
+    let mut waves = Vec::new();
+    loop {
+        let wave: Vec<usize> = (0..count)
+            .filter(|&i| !done[i] && deps[i].iter().all(|&d| done[d]))
+            .collect();
+        if wave.is_empty() {
+            break;
+        }
+        wave.iter().for_each(|&i| done[i] = true);
+        waves.push(wave);
+    }
+
+    let cyclic: Vec<usize> = (0..count).filter(|&i| !done[i]).collect();
+    if !cyclic.is_empty() {
+        warn!(calls = ?cyclic, "⚠️ 工具调用之间存在循环引用，按原样执行");
+        waves.push(cyclic);
+    }
+    waves
+}
+
+/// 把参数中的引用替换为对应调用的输出；输出缺失（调用失败或尚未执行）时保留原文
+pub(crate) fn substitute(args: &Value, outputs: &[Option<String>]) -> Value {
+    match args {
+        Value::String(s) if s.contains(REF_PREFIX) => {
+            let mut result = String::with_capacity(s.len());
+            let mut last = 0;
+            for (start, end, index) in find_references(s) {
+                result.push_str(&s[last..start]);
+                match outputs.get(index).and_then(Option::as_deref) {
+                    Some(output) => result.push_str(output),
+                    None => {
+                        warn!(
+                            reference = index,
+                            "⚠️ 被引用的工具调用没有可用输出，引用保持原样"
+                        );
+                        result.push_str(&s[start..end]);
+                    }
+                }
+                last = end;
+            }
+            result.push_str(&s[last..]);
+            Value::String(result)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| substitute(v, outputs)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), substitute(v, outputs)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_waves_orders_dependencies() {
+        let a = json!({"path": "a.txt"});
+        let b = json!({"content": "$tool_0.output"});
+        let c = json!({"x": 1});
+        assert_eq!(plan_waves(&[&a, &b, &c]), vec![vec![0, 2], vec![1]]);
+        assert_eq!(plan_waves(&[&a, &c]), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_plan_waves_cycle_and_invalid_refs() {
+        let a = json!({"v": "$tool_1.output"});
+        let b = json!({"v": "$tool_0.output"});
+        let c = json!({"v": "$tool_9.output $tool_2.output"});
+        assert_eq!(plan_waves(&[&a, &b, &c]), vec![vec![2], vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_substitute_references() {
+        let args = json!({"text": "结果：$tool_0.output", "list": ["$tool_1.output"], "n": 3});
+        let outputs = vec![Some("42".to_string()), None];
+        assert_eq!(
+            substitute(&args, &outputs),
+            json!({"text": "结果：42", "list": ["$tool_1.output"], "n": 3})
+        );
+    }
+}
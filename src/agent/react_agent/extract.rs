@@ -1,11 +1,19 @@
 //! ReactAgent 结构化提取
 //!
-//! 提供一次性 JSON 提取方法，不经过 ReAct 循环。
+//! 提供一次性 JSON 提取方法（不经过 ReAct 循环），以及带 schema 校验与重试的
+//! [`execute_typed`](ReactAgent::execute_typed)。两者都经
+//! [`coerce_json`](crate::llm::json_coerce::coerce_json) 解析 LLM 输出。
 
 use super::ReactAgent;
-use crate::error::{ReactError, Result};
+use crate::agent::Agent;
+use crate::error::{ParseError, ReactError, Result};
+pub(crate) use crate::llm::json_coerce::validate_schema;
+use crate::llm::json_coerce::{CoerceError, CoerceOptions, coerce_json};
 use crate::llm::types::Message;
-use crate::llm::{ResponseFormat, chat};
+use crate::llm::{ChatOptions, ChatRequest, ResponseFormat, chat};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::{debug, warn};
 
 impl ReactAgent {
     /// 一次性结构化 JSON 提取，不走 ReAct 循环。
@@ -43,21 +51,34 @@ impl ReactAgent {
         prompt: &str,
         schema: ResponseFormat,
     ) -> Result<serde_json::Value> {
+        let json_schema = match &schema {
+            ResponseFormat::JsonSchema { json_schema } => Some(json_schema.schema.clone()),
+            _ => None,
+        };
+        // 与 ReAct 循环使用同一份合并后的 system prompt（含 Skill / CoT 等片段）
+        let system_prompt = self
+            .context
+            .merged_system()
+            .unwrap_or_else(|| self.config.system_prompt.clone());
         let messages = vec![
-            Message::system(self.config.system_prompt.clone()),
+            Message::system(system_prompt),
             Message::user(prompt.to_string()),
         ];
 
         let response = chat(
             self.client.clone(),
             &self.config.model_name,
-            messages,
-            Some(0.0),
-            Some(4096),
-            Some(false),
-            None,
-            None,
-            Some(schema),
+            ChatRequest {
+                messages,
+                temperature: Some(0.0),
+                max_tokens: Some(4096),
+                response_format: Some(schema),
+                options: ChatOptions {
+                    seed: self.config.seed,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
         )
         .await?;
 
@@ -68,7 +89,7 @@ impl ReactAgent {
             .and_then(|c| c.message.content)
             .ok_or_else(|| ReactError::Other("LLM 返回空内容".to_string()))?;
 
-        serde_json::from_str(&text)
+        coerce_json(&text, json_schema.as_ref(), self.config.coerce_options())
             .map_err(|e| ReactError::Other(format!("JSON 解析失败: {e}\n原始响应: {text}")))
     }
 
@@ -111,4 +132,166 @@ impl ReactAgent {
         let value = self.extract_json(prompt, schema).await?;
         serde_json::from_value(value).map_err(|e| ReactError::Other(format!("反序列化失败: {e}")))
     }
+
+    /// 走完整 ReAct 流程执行任务，要求最终答案为符合 `schema` 的 JSON 并反序列化为 `T`。
+    ///
+    /// 执行期间临时把 `response_format` 设为 `schema`；最终答案未通过校验（非法 JSON、
+    /// 违反 schema 或无法反序列化）时，把错误反馈给 LLM 在同一对话中重新生成，
+    /// 最多重试 [`AgentConfig::typed_output_retries`](crate::agent::AgentConfig::typed_output_retries) 次。
+    /// 重试耗尽返回 [`ParseError::SchemaViolation`]，其中包含最后一次的原始输出。
+    ///
+    /// # 示例
+    ///
+    /// ```rust,no_run
+    /// use echo_agent::llm::ResponseFormat;
+    /// use serde::Deserialize;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Report { city: String, temperature: f64 }
+    ///
+    /// # async fn run() -> echo_agent::error::Result<()> {
+    /// # use echo_agent::prelude::*;
+    /// # let config = AgentConfig::new("qwen3-max", "reporter", "你是一个天气助手");
+    /// # let mut agent = ReactAgent::new(config);
+    /// let report: Report = agent.execute_typed(
+    ///     "查询北京今天的气温",
+    ///     ResponseFormat::json_schema(
+    ///         "report",
+    ///         json!({ "type": "object",
+    ///                 "properties": { "city": { "type": "string" }, "temperature": { "type": "number" } },
+    ///                 "required": ["city", "temperature"],
+    ///                 "additionalProperties": false }),
+    ///     ),
+    /// ).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_typed<T>(&mut self, task: &str, schema: ResponseFormat) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let json_schema = match &schema {
+            ResponseFormat::JsonSchema { json_schema } => Some(json_schema.schema.clone()),
+            _ => None,
+        };
+        let previous = self.config.response_format.replace(schema);
+        let result = self.run_typed(task, json_schema.as_ref()).await;
+        self.config.response_format = previous;
+        result
+    }
+
+    async fn run_typed<T: DeserializeOwned>(
+        &mut self,
+        task: &str,
+        schema: Option<&Value>,
+    ) -> Result<T> {
+        let mut output = TypedOutput::new(schema, self.config.typed_output_retries)
+            .with_options(self.config.coerce_options());
+        let mut raw = self.execute(task).await?;
+        loop {
+            match output.check(&raw) {
+                TypedCheck::Valid(value) => return Ok(value),
+                TypedCheck::Retry(feedback) => raw = self.chat(&feedback).await?,
+                TypedCheck::Failed(err) => return Err(err),
+            }
+        }
+    }
+
+    /// 开启 [`AgentConfig::auto_extract`](crate::agent::AgentConfig::auto_extract) 且工具声明了 `output_schema` 时，
+    /// 让 LLM 把工具的文本输出抽取为符合 schema 的 JSON；抽取失败只记录警告
+    pub(crate) async fn extract_tool_data(&self, tool_name: &str, output: &str) -> Option<Value> {
+        if !self.config.auto_extract {
+            return None;
+        }
+        let schema = self.tool_manager.get_tool(tool_name)?.output_schema()?;
+        let messages = vec![
+            Message::system(
+                "你是数据抽取器。根据给定的 JSON Schema，从工具输出中抽取信息，只输出 JSON，\
+                 不要解释。输出中没有的字段不要编造。"
+                    .to_string(),
+            ),
+            Message::user(format!(
+                "JSON Schema：\n{schema}\n\n工具 {tool_name} 的输出：\n{output}"
+            )),
+        ];
+        let raw = match self.request_text(messages, 0.0, 4096).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!(agent = %self.config.agent_name, tool = %tool_name, error = %e, "⚠️ 工具输出结构化抽取失败");
+                return None;
+            }
+        };
+        match coerce_json(&raw, Some(&schema), self.config.coerce_options()) {
+            Ok(data) => {
+                debug!(agent = %self.config.agent_name, tool = %tool_name, data = %data, "🧩 工具输出已抽取为结构化数据");
+                Some(data)
+            }
+            Err(e) => {
+                warn!(agent = %self.config.agent_name, tool = %tool_name, error = %e, "⚠️ 工具输出结构化抽取结果无效");
+                None
+            }
+        }
+    }
+}
+
+/// 一次校验的结论
+pub(crate) enum TypedCheck<T> {
+    Valid(T),
+    /// 需要重试，携带反馈给 LLM 的提示
+    Retry(String),
+    Failed(ReactError),
+}
+
+/// 结构化输出的校验与重试计数
+pub(crate) struct TypedOutput<'a> {
+    schema: Option<&'a Value>,
+    max_retries: usize,
+    attempts: usize,
+    options: CoerceOptions,
+}
+
+impl<'a> TypedOutput<'a> {
+    pub(crate) fn new(schema: Option<&'a Value>, max_retries: usize) -> Self {
+        Self {
+            schema,
+            max_retries,
+            attempts: 0,
+            options: CoerceOptions::default(),
+        }
+    }
+
+    /// 设置 JSON 解析选项（是否启用保守修复）
+    pub(crate) fn with_options(mut self, options: CoerceOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// 校验一次原始输出
+    pub(crate) fn check<T: DeserializeOwned>(&mut self, raw: &str) -> TypedCheck<T> {
+        self.attempts += 1;
+        let error = match parse_typed(raw, self.schema, self.options) {
+            Ok(value) => return TypedCheck::Valid(value),
+            Err(error) => error,
+        };
+        if self.attempts > self.max_retries {
+            return TypedCheck::Failed(ReactError::Parse(ParseError::SchemaViolation {
+                attempts: self.attempts,
+                error: error.to_string(),
+                raw: raw.to_string(),
+            }));
+        }
+        TypedCheck::Retry(error.retry_feedback())
+    }
+}
+
+/// 经 [`coerce_json`] 解析并校验 → 反序列化
+fn parse_typed<T: DeserializeOwned>(
+    raw: &str,
+    schema: Option<&Value>,
+    options: CoerceOptions,
+) -> std::result::Result<T, CoerceError> {
+    let value = coerce_json(raw, schema, options)?;
+    serde_json::from_value(value)
+        .map_err(|e| CoerceError::SchemaViolation(format!("无法反序列化为目标类型：{e}")))
 }
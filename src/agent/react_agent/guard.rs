@@ -0,0 +1,40 @@
+//! 临时状态的恢复守卫
+//!
+//! `execute_with_tools`、`execute_variants` 等接口会在执行期间临时改写 Agent 的状态。
+//! 执行可能出错，也可能因调用方丢弃 future（超时、取消）而中途停止，两种情况下都需要恢复原状态，
+//! 所以恢复逻辑放在 [`RestoreGuard`] 的 `Drop` 中，而不是写在 `await` 之后。
+
+use super::ReactAgent;
+use std::ops::{Deref, DerefMut};
+
+/// 持有 Agent 的可变借用，drop 时执行 `restore`；期间通过 `Deref` 照常调用 Agent 的方法
+pub(crate) struct RestoreGuard<'a, F: FnMut(&mut ReactAgent)> {
+    agent: &'a mut ReactAgent,
+    restore: F,
+}
+
+impl<'a, F: FnMut(&mut ReactAgent)> RestoreGuard<'a, F> {
+    pub(crate) fn new(agent: &'a mut ReactAgent, restore: F) -> Self {
+        Self { agent, restore }
+    }
+}
+
+impl<F: FnMut(&mut ReactAgent)> Deref for RestoreGuard<'_, F> {
+    type Target = ReactAgent;
+
+    fn deref(&self) -> &ReactAgent {
+        self.agent
+    }
+}
+
+impl<F: FnMut(&mut ReactAgent)> DerefMut for RestoreGuard<'_, F> {
+    fn deref_mut(&mut self) -> &mut ReactAgent {
+        self.agent
+    }
+}
+
+impl<F: FnMut(&mut ReactAgent)> Drop for RestoreGuard<'_, F> {
+    fn drop(&mut self) {
+        (self.restore)(self.agent);
+    }
+}
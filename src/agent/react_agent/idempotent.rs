@@ -0,0 +1,193 @@
+//! 请求级幂等执行
+//!
+//! 客户端因网络抖动重试同一请求时，[`execute_idempotent`](ReactAgent::execute_idempotent)
+//! 按幂等键返回已完成请求的结果，不重复执行任务（也就不会重复产生副作用）。
+//! 结果缓存在 [`IdempotencyCache`] 中，超过 TTL 后失效；多个 Agent 实例可共享同一个缓存。
+
+use super::ReactAgent;
+use crate::agent::Agent;
+use crate::error::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::info;
+
+/// 默认的结果缓存时长
+pub(crate) const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(10 * 60);
+
+enum Entry {
+    /// 正在执行，发送端随执行结束（成功或失败）而丢弃，等待者据此被唤醒
+    InFlight(watch::Receiver<()>),
+    /// 已成功完成的结果与完成时刻
+    Done(String, Instant),
+}
+
+/// 按幂等键缓存已完成请求的结果
+///
+/// 只缓存成功的结果：执行失败或被取消时移除该键，之后的重试会重新执行。
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl Default for IdempotencyCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDEMPOTENCY_TTL)
+    }
+}
+
+impl IdempotencyCache {
+    /// 创建缓存，结果在完成 `ttl` 后失效
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 键对应的未过期结果
+    pub fn get(&self, key: &str) -> Option<String> {
+        match self.lock().get(key) {
+            Some(Entry::Done(answer, at)) if at.elapsed() < self.ttl => Some(answer.clone()),
+            _ => None,
+        }
+    }
+
+    /// 已缓存（含执行中）的键数量，过期的结果不计入
+    pub fn len(&self) -> usize {
+        let mut entries = self.lock();
+        self.prune(&mut entries);
+        entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn prune(&self, entries: &mut HashMap<String, Entry>) {
+        entries.retain(|_, entry| match entry {
+            Entry::InFlight(_) => true,
+            Entry::Done(_, at) => at.elapsed() < self.ttl,
+        });
+    }
+
+    /// 领取执行权：已有结果时直接返回；同键请求正在执行时等待其结束后重新检查
+    async fn begin(self: &Arc<Self>, key: &str) -> std::result::Result<String, InFlightGuard> {
+        loop {
+            let mut waiting = {
+                let mut entries = self.lock();
+                self.prune(&mut entries);
+                match entries.get(key) {
+                    Some(Entry::Done(answer, _)) => return Ok(answer.clone()),
+                    Some(Entry::InFlight(rx)) => rx.clone(),
+                    None => {
+                        let (tx, rx) = watch::channel(());
+                        entries.insert(key.to_string(), Entry::InFlight(rx));
+                        return Err(InFlightGuard {
+                            cache: self.clone(),
+                            key: key.to_string(),
+                            _done: tx,
+                            finished: false,
+                        });
+                    }
+                }
+            };
+            // 发送端被丢弃即表示首个请求已结束
+            let _ = waiting.changed().await;
+        }
+    }
+}
+
+/// 持有某个键的执行权；未调用 `finish` 就被丢弃（失败、取消）时移除该键
+struct InFlightGuard {
+    cache: Arc<IdempotencyCache>,
+    key: String,
+    _done: watch::Sender<()>,
+    finished: bool,
+}
+
+impl InFlightGuard {
+    fn finish(mut self, answer: &str) {
+        self.cache.lock().insert(
+            self.key.clone(),
+            Entry::Done(answer.to_string(), Instant::now()),
+        );
+        self.finished = true;
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.cache.lock().remove(&self.key);
+        }
+    }
+}
+
+impl ReactAgent {
+    /// 幂等执行：相同 `idempotency_key` 的请求只执行一次
+    ///
+    /// - 已有未过期的结果：直接返回，不调用 LLM、不执行工具
+    /// - 同键请求正在执行：等待其结束；成功则返回其结果，失败则由本请求重新执行
+    /// - 否则照常 [`execute`](Agent::execute)，成功后按键缓存结果
+    ///
+    /// 缓存默认 TTL 为 10 分钟，可通过 [`set_idempotency_cache`](Self::set_idempotency_cache) 调整或在多个 Agent 间共享。
+    pub async fn execute_idempotent(
+        &mut self,
+        task: &str,
+        idempotency_key: &str,
+    ) -> Result<String> {
+        let guard = match self.idempotency.begin(idempotency_key).await {
+            Ok(answer) => {
+                info!(agent = %self.config.agent_name, key = %idempotency_key, "♻️ 幂等键已完成，返回缓存结果");
+                return Ok(answer);
+            }
+            Err(guard) => guard,
+        };
+        let answer = self.execute(task).await?;
+        guard.finish(&answer);
+        Ok(answer)
+    }
+
+    /// 替换幂等结果缓存（如自定义 TTL，或让多个 Agent 实例共享同一个缓存）
+    pub fn set_idempotency_cache(&mut self, cache: Arc<IdempotencyCache>) {
+        self.idempotency = cache;
+    }
+
+    /// 当前使用的幂等结果缓存
+    pub fn idempotency_cache(&self) -> &Arc<IdempotencyCache> {
+        &self.idempotency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache_expires_after_ttl() {
+        let cache = Arc::new(IdempotencyCache::new(Duration::from_millis(20)));
+        let guard = cache.begin("k").await.unwrap_err();
+        assert!(cache.get("k").is_none());
+        guard.finish("done");
+        assert_eq!(cache.get("k").as_deref(), Some("done"));
+        assert_eq!(cache.begin("k").await.ok().as_deref(), Some("done"));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(cache.get("k").is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_releases_key() {
+        let cache = Arc::new(IdempotencyCache::default());
+        drop(cache.begin("k").await.unwrap_err());
+        assert!(cache.is_empty());
+        assert!(cache.begin("k").await.is_err());
+    }
+}
@@ -0,0 +1,291 @@
+//! 运行指标与 Prometheus 导出
+//!
+//! Agent 在整个生命周期内累计工具调用、LLM 调用、token 消耗与上下文压缩次数（不随
+//! `reset` 或新的执行清零），由 [`ReactAgent::metrics_prometheus`] 导出为 Prometheus
+//! 文本格式，可直接作为 `/metrics` 端点的响应体。
+//!
+//! | 指标 | 类型 | 标签 |
+//! |------|------|------|
+//! | `echo_agent_tool_calls_total` | counter | `agent`、`tool`、`status` |
+//! | `echo_agent_tool_call_duration_seconds` | histogram | `agent`、`tool` |
+//! | `echo_agent_llm_calls_total` | counter | `agent`、`status` |
+//! | `echo_agent_llm_call_duration_seconds` | histogram | `agent` |
+//! | `echo_agent_llm_tokens_total` | counter | `agent` |
+//! | `echo_agent_context_compressions_total` | counter | `agent` |
+//!
+//! `status` 取 `success` 或 `error`。
+
+use super::ReactAgent;
+use crate::error::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// 耗时直方图的默认桶上界（秒），与 Prometheus 客户端库的默认值一致
+pub const DEFAULT_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 调用结果，对应 `status` 标签
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Status {
+    Success,
+    Error,
+}
+
+impl Status {
+    pub(crate) fn of<T, E>(result: &std::result::Result<T, E>) -> Self {
+        if result.is_ok() {
+            Self::Success
+        } else {
+            Self::Error
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// 累计直方图：`counts[i]` 为不超过第 i 个桶上界的观测数
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, buckets: &[f64], value: f64) {
+        self.counts.resize(buckets.len(), 0);
+        for (count, _) in self
+            .counts
+            .iter_mut()
+            .zip(buckets)
+            .filter(|(_, bound)| value <= **bound)
+        {
+            *count += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Agent 的累计运行指标
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    buckets: Vec<f64>,
+    tool_calls: BTreeMap<(String, Status), u64>,
+    tool_durations: BTreeMap<String, Histogram>,
+    llm_calls: BTreeMap<Status, u64>,
+    llm_duration: Histogram,
+    tokens: u64,
+}
+
+impl Metrics {
+    pub(crate) fn new(buckets: Vec<f64>) -> Self {
+        Self {
+            buckets,
+            tool_calls: BTreeMap::new(),
+            tool_durations: BTreeMap::new(),
+            llm_calls: BTreeMap::new(),
+            llm_duration: Histogram::default(),
+            tokens: 0,
+        }
+    }
+
+    pub(crate) fn observe_tool_call(&mut self, tool: &str, elapsed: Duration, status: Status) {
+        *self
+            .tool_calls
+            .entry((tool.to_string(), status))
+            .or_default() += 1;
+        self.tool_durations
+            .entry(tool.to_string())
+            .or_default()
+            .observe(&self.buckets, elapsed.as_secs_f64());
+    }
+
+    pub(crate) fn observe_llm_call(&mut self, elapsed: Duration, status: Status) {
+        *self.llm_calls.entry(status).or_default() += 1;
+        self.llm_duration
+            .observe(&self.buckets, elapsed.as_secs_f64());
+    }
+
+    pub(crate) fn add_tokens(&mut self, tokens: usize) {
+        self.tokens += tokens as u64;
+    }
+
+    /// 渲染为 Prometheus 文本格式（exposition format 0.0.4）
+    pub(crate) fn render(&self, agent: &str, compressions: usize) -> String {
+        let agent = escape_label(agent);
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "echo_agent_tool_calls_total",
+            "counter",
+            "Total number of tool calls.",
+        );
+        for ((tool, status), count) in &self.tool_calls {
+            let _ = writeln!(
+                out,
+                "echo_agent_tool_calls_total{{agent=\"{agent}\",tool=\"{}\",status=\"{}\"}} {count}",
+                escape_label(tool),
+                status.label()
+            );
+        }
+
+        header(
+            &mut out,
+            "echo_agent_tool_call_duration_seconds",
+            "histogram",
+            "Tool call duration in seconds.",
+        );
+        for (tool, histogram) in &self.tool_durations {
+            let labels = format!("agent=\"{agent}\",tool=\"{}\"", escape_label(tool));
+            self.write_histogram(
+                &mut out,
+                "echo_agent_tool_call_duration_seconds",
+                &labels,
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "echo_agent_llm_calls_total",
+            "counter",
+            "Total number of LLM calls.",
+        );
+        for (status, count) in &self.llm_calls {
+            let _ = writeln!(
+                out,
+                "echo_agent_llm_calls_total{{agent=\"{agent}\",status=\"{}\"}} {count}",
+                status.label()
+            );
+        }
+
+        header(
+            &mut out,
+            "echo_agent_llm_call_duration_seconds",
+            "histogram",
+            "LLM call duration in seconds, including retries.",
+        );
+        self.write_histogram(
+            &mut out,
+            "echo_agent_llm_call_duration_seconds",
+            &format!("agent=\"{agent}\""),
+            &self.llm_duration,
+        );
+
+        header(
+            &mut out,
+            "echo_agent_llm_tokens_total",
+            "counter",
+            "Total tokens consumed by LLM calls (estimated when the server reports no usage).",
+        );
+        let _ = writeln!(
+            out,
+            "echo_agent_llm_tokens_total{{agent=\"{agent}\"}} {}",
+            self.tokens
+        );
+
+        header(
+            &mut out,
+            "echo_agent_context_compressions_total",
+            "counter",
+            "Total number of context compressions.",
+        );
+        let _ = writeln!(
+            out,
+            "echo_agent_context_compressions_total{{agent=\"{agent}\"}} {compressions}"
+        );
+        out
+    }
+
+    fn write_histogram(&self, out: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+        for (i, bound) in self.buckets.iter().enumerate() {
+            let count = histogram.counts.get(i).copied().unwrap_or(0);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+            histogram.count
+        );
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", histogram.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", histogram.count);
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// 转义标签值中的 `\`、`"` 与换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl ReactAgent {
+    /// 以 Prometheus 文本格式导出本 Agent 的累计运行指标
+    ///
+    /// 包括工具调用次数与耗时直方图、LLM 调用次数与耗时直方图、token 消耗和上下文压缩次数，
+    /// 所有序列都带 `agent` 标签。指标从 Agent 创建起累计，可直接作为 `/metrics` 端点的响应体；
+    /// 耗时直方图的桶由 [`AgentConfig::metrics_buckets`](crate::agent::AgentConfig::metrics_buckets) 配置。
+    pub fn metrics_prometheus(&self) -> String {
+        self.metrics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .render(&self.config.agent_name, self.context.compression_count())
+    }
+
+    /// 记录一次工具调用（含降级链），`result` 为转为观测值之前的结果
+    pub(crate) fn observe_tool_call<T>(&self, tool: &str, elapsed: Duration, result: &Result<T>) {
+        self.metrics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .observe_tool_call(tool, elapsed, Status::of(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut metrics = Metrics::new(vec![0.1, 1.0]);
+        metrics.observe_tool_call("search", Duration::from_millis(50), Status::Success);
+        metrics.observe_tool_call("search", Duration::from_millis(500), Status::Error);
+        metrics.observe_tool_call("search", Duration::from_secs(3), Status::Success);
+
+        let text = metrics.render("agent", 0);
+        for line in [
+            r#"echo_agent_tool_calls_total{agent="agent",tool="search",status="success"} 2"#,
+            r#"echo_agent_tool_calls_total{agent="agent",tool="search",status="error"} 1"#,
+            r#"echo_agent_tool_call_duration_seconds_bucket{agent="agent",tool="search",le="0.1"} 1"#,
+            r#"echo_agent_tool_call_duration_seconds_bucket{agent="agent",tool="search",le="1"} 2"#,
+            r#"echo_agent_tool_call_duration_seconds_bucket{agent="agent",tool="search",le="+Inf"} 3"#,
+            r#"echo_agent_tool_call_duration_seconds_count{agent="agent",tool="search"} 3"#,
+        ] {
+            assert!(text.lines().any(|l| l == line), "缺少 {line}:\n{text}");
+        }
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let mut metrics = Metrics::new(DEFAULT_DURATION_BUCKETS.to_vec());
+        metrics.observe_tool_call("a\"b\\c", Duration::ZERO, Status::Success);
+        let text = metrics.render("line\nbreak", 0);
+        assert!(text.contains(r#"agent="line\nbreak",tool="a\"b\\c""#));
+        assert!(!text.contains("line\nbreak"));
+    }
+}
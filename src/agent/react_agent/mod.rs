@@ -7,20 +7,34 @@
 //! | `mod.rs` | 结构体定义、`new()`、`impl Agent` trait |
 //! | `run.rs` | 执行引擎（`think` / `process_steps` / `run_react_loop`） |
 //! | `capabilities.rs` | 能力配置（工具 / Skill / MCP / SubAgent 注册） |
+//! | `chapter.rs` | 对话章节标记与 Markdown 导出（`export_markdown`） |
+//! | `choice.rs` | 多候选回复（`n_choices`）的选择策略 |
 //! | `extract.rs` | 结构化 JSON 提取（`extract_json` / `extract`） |
-
-pub use crate::agent::config::{AgentConfig, AgentRole};
-use crate::agent::{Agent, AgentEvent, SubAgentMap};
+//! | `guard.rs` | 执行期间临时状态的恢复守卫（出错或取消时同样恢复） |
+//! | `dependency.rs` | 同一轮工具调用间的 `$tool_N.output` 引用解析与分层 |
+//! | `idempotent.rs` | 请求级幂等执行（`execute_idempotent`） |
+//! | `metrics.rs` | 运行指标累计与 Prometheus 导出（`metrics_prometheus`） |
+//! | `step.rs` | 单步执行（`begin` / `step`） |
+//! | `title.rs` | 会话标题生成（`generate_title`） |
+//! | `variants.rs` | 同一任务多配置对比执行（`execute_variants`） |
+
+pub use crate::agent::config::{AgentConfig, AgentRole, ReflectionConfig};
+use crate::agent::trace::TraceRecorder;
+use crate::agent::{
+    Agent, AgentEvent, CallbackQueue, ExecutionResult, LocaleKey, SubAgentMap, ToolCallRecord,
+};
 use crate::compression::ContextManager;
-use crate::error::{LlmError, ReactError, Result};
+use crate::error::Result;
 use crate::human_loop::{HumanApprovalManager, HumanLoopProvider};
 use crate::llm::config::LlmConfig;
+use crate::llm::types::Usage;
+use crate::llm::{LlmClient, ToolChoice};
 use crate::mcp::McpManager;
 use crate::memory::checkpointer::{Checkpointer, FileCheckpointer};
 use crate::memory::store::{FileStore, Store};
 use crate::skills::SkillManager;
+use crate::skills::external::SkillLoader;
 use crate::tasks::TaskManager;
-use crate::tools::ToolManager;
 use crate::tools::builtin::agent_dispatch::AgentDispatchTool;
 use crate::tools::builtin::answer::FinalAnswerTool;
 use crate::tools::builtin::human_in_loop::HumanInLoop;
@@ -29,34 +43,84 @@ use crate::tools::builtin::plan::PlanTool;
 use crate::tools::builtin::task::{
     CreateTaskTool, GetExecutionOrderTool, ListTasksTool, UpdateTaskTool, VisualizeDependenciesTool,
 };
+use crate::tools::{SeededRng, Source, ToolManager};
 use async_trait::async_trait;
 use futures::stream::BoxStream;
+use guard::RestoreGuard;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::Mutex as AsyncMutex;
 
 pub mod builder;
 mod capabilities;
+mod chapter;
+mod choice;
+mod cross_session;
+mod dependency;
 mod extract;
+mod guard;
+mod idempotent;
+mod metrics;
 mod run;
+mod step;
 #[cfg(test)]
 mod tests;
+mod title;
+mod variants;
+
+pub use choice::{ChoiceSelector, FirstChoice, LlmJudge, LongestChoice, MajorityVote};
+pub use idempotent::IdempotencyCache;
+pub use metrics::DEFAULT_DURATION_BUCKETS;
+pub use step::StepOutcome;
+pub use variants::VariantConfig;
 // ── 内置工具名常量 ─────────────────────────────────────────────────────────────
 
 pub(crate) const TOOL_FINAL_ANSWER: &str = "final_answer";
 pub(crate) const TOOL_CREATE_TASK: &str = "create_task";
 pub(crate) const TOOL_PLAN: &str = "plan";
 pub(crate) const TOOL_UPDATE_TASK: &str = "update_task";
+pub(crate) const TOOL_AGENT_DISPATCH: &str = "agent_tool";
+
+pub(crate) use crate::llm::is_retryable_llm_error;
+pub(crate) use extract::validate_schema;
+
+/// 最终答案后处理器，见 [`ReactAgent::set_output_processor`]
+pub type OutputProcessor = Box<dyn Fn(String) -> String + Send + Sync>;
+
+/// 每轮迭代前钩子，见 [`ReactAgent::set_pre_iteration_hook`]
+pub type PreIterationHook = Box<dyn Fn(&mut IterationContext) + Send + Sync>;
+
+/// 传给 [`PreIterationHook`] 的本轮信息，修改只对本轮 LLM 请求生效
+#[derive(Debug, Clone)]
+pub struct IterationContext {
+    /// 本次执行中的轮次（从 0 开始）
+    pub iteration: usize,
+    /// 本轮将发送的消息条数
+    pub message_count: usize,
+    /// 本轮消息的估算 token 数
+    pub token_estimate: usize,
+    /// 本轮使用的模型，可修改（默认 `AgentConfig::model_name`）
+    pub model_name: String,
+    /// 本轮追加到 system 消息末尾的临时提示，不写入上下文
+    pub extra_system_prompts: Vec<String>,
+}
 
-/// 判断 LLM 错误是否值得重试（网络/超时/限流/服务端 5xx）
-pub(crate) fn is_retryable_llm_error(err: &ReactError) -> bool {
-    match err {
-        ReactError::Llm(LlmError::NetworkError(_)) => true,
-        ReactError::Llm(LlmError::ApiError { status, .. }) => *status == 429 || *status >= 500,
-        _ => false,
-    }
+/// 上下文 token 占用分解，见 [`ReactAgent::context_breakdown`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContextBreakdown {
+    /// system 消息占用
+    pub system_tokens: usize,
+    /// 随每次请求发送的工具定义（JSON schema）占用
+    pub tools_schema_tokens: usize,
+    /// 对话历史（非 system 消息）占用
+    pub history_tokens: usize,
+    /// 以上三项之和
+    pub total: usize,
 }
 
 // ── ReactAgent 结构体 ─────────────────────────────────────────────────────────
@@ -70,18 +134,105 @@ pub struct ReactAgent {
     client: Arc<Client>,
     /// LLM 配置（可选，不设置时使用环境变量配置）
     llm_config: Option<LlmConfig>,
+    /// 自定义 LLM 客户端（可选）；设置后非流式推理改由它发送请求，常用于注入 `MockLlmClient`
+    llm_client: Option<Arc<dyn LlmClient>>,
     pub(crate) task_manager: Arc<RwLock<TaskManager>>,
     human_in_loop: Arc<RwLock<HumanApprovalManager>>,
     /// 人工介入 Provider：支持命令行、HTTP Webhook、WebSocket 等多种渠道
     approval_provider: Arc<dyn HumanLoopProvider>,
     /// Skill 管理器：记录已安装的所有 Skill 元数据
     skill_manager: SkillManager,
+    /// 外部技能加载器，`load_skills_from_dir` 后保留，供热重载复用
+    skill_loader: Option<Arc<AsyncMutex<SkillLoader>>>,
+    /// 文件监听登记、等待 Agent 空闲时重载的技能目录
+    pending_skill_reloads: Arc<Mutex<BTreeSet<PathBuf>>>,
+    /// 技能目录监听器，见 [`ReactAgent::watch_skills_dir`]
+    skill_watcher: Option<notify::RecommendedWatcher>,
     /// 长期记忆 Store，通过 `remember`/`recall`/`forget` 工具访问
     store: Option<Arc<dyn Store>>,
     /// 短期会话 Checkpointer，按 session_id 持久化对话历史
     checkpointer: Option<Arc<dyn Checkpointer>>,
     /// MCP 连接管理器：持有所有 MCP 服务端的客户端，保证连接生命周期与 Agent 一致
     mcp_manager: McpManager,
+    /// 当前执行累计的工具调用次数，用于软预算检查
+    tool_call_count: usize,
+    /// 当前执行的收尾提示状态，见 `AgentConfig::wrap_up_on_budget`
+    wrap_up_hint: run::WrapUpHint,
+    /// 最终答案后处理器，按注册顺序依次应用
+    output_processors: Vec<OutputProcessor>,
+    /// 每轮 LLM 请求前调用的钩子
+    pre_iteration_hook: Option<PreIterationHook>,
+    /// `CallbackMode::AsyncBuffered` 下的回调分发队列
+    callback_queue: CallbackQueue,
+    /// 自定义 LLM 客户端也按 `config.model_name` 发请求（`execute_variants` 指定模型时）
+    pin_model: bool,
+    /// 本次执行对 LLM 可见的工具子集（`execute_with_tools` 期间生效），`None` 表示全部可见
+    tool_scope: Option<HashSet<String>>,
+    /// 最近一轮因 [`Tool::is_available`](crate::tools::Tool::is_available) 为 `false` 未发给 LLM 的工具，执行时拒绝
+    unavailable_tools: HashSet<String>,
+    /// 仅对下一次 LLM 请求生效的工具选择策略，优先于 `AgentConfig::tool_choice`
+    next_tool_choice: Option<ToolChoice>,
+    /// 最近一次 LLM 响应的 `system_fingerprint`
+    system_fingerprint: Option<String>,
+    /// 当前执行的工具调用记录，供 `execute_rich` 汇总
+    tool_call_records: Vec<ToolCallRecord>,
+    /// 已执行但尚未写入上下文的工具结果来源，按 tool_call_id 暂存
+    pending_sources: Mutex<HashMap<String, Vec<Source>>>,
+    /// 已执行但尚未记录的工具结构化输出，按 tool_call_id 暂存
+    pending_data: Mutex<HashMap<String, serde_json::Value>>,
+    /// 实际执行的参数与 LLM 给出的不同时（脱敏、拦截器改写、审批修改），按 tool_call_id 暂存
+    pending_args: Mutex<HashMap<String, serde_json::Value>>,
+    /// 当前执行引用过的信息来源（去重，下标 + 1 即引用编号）
+    sources: Vec<Source>,
+    /// 当前执行的 LLM 推理轮数
+    iteration_count: usize,
+    /// 当前执行累计的 token 用量
+    usage: Option<Usage>,
+    /// 当前执行累计消耗的 token 数（无 usage 时为估算值），用于 `max_total_tokens`
+    tokens_used: usize,
+    /// 辅助请求（标题、跨会话摘要、工具输出抽取）尚未并入 `tokens_used` 的用量；
+    /// 辅助请求只持有 `&self`，先累加到这里，下一轮记录用量时并入
+    helper_tokens: AtomicUsize,
+    /// 当前执行累计的副作用工具调用次数，用于 `destructive_op_threshold`
+    side_effect_count: usize,
+    /// 当前执行是否已通过副作用操作的批量确认
+    side_effects_confirmed: bool,
+    /// 最近一次执行的结构化链路；共享给流式执行的外层包装，出错结束时由它收尾
+    trace: Arc<Mutex<Option<TraceRecorder>>>,
+    /// 跨会话记忆，见 [`ReactAgent::with_cross_session_memory`]
+    cross_session: Option<cross_session::CrossSessionMemory>,
+    /// 进行中的执行的循环状态（None = 没有进行中的执行），见 [`ReactAgent::step`]
+    step_state: Option<step::StepState>,
+    /// 当前对话章节的累计状态，见 [`AgentConfig::auto_chapter`]
+    chapter_state: chapter::ChapterState,
+    /// `n_choices` 大于 1 时从多个候选回复中选出一个
+    choice_selector: Arc<dyn ChoiceSelector>,
+    /// 按幂等键缓存的已完成请求结果，见 [`execute_idempotent`](Self::execute_idempotent)
+    idempotency: Arc<idempotent::IdempotencyCache>,
+    /// 从创建起累计的运行指标，见 [`ReactAgent::metrics_prometheus`]
+    metrics: Mutex<metrics::Metrics>,
+}
+
+// ── system 片段 ───────────────────────────────────────────────────────────────
+//
+// system prompt 由多个带标签的片段组成，由 ContextManager 按优先级合并：
+// base(0) → skill:<name> → cot → untrusted → citation → language → cross_session
+
+pub(crate) const SKILL_FRAGMENT_PRIORITY: i32 = 10;
+const COT_FRAGMENT: &str = "cot";
+const COT_FRAGMENT_PRIORITY: i32 = 20;
+const UNTRUSTED_FRAGMENT: &str = "untrusted";
+const UNTRUSTED_FRAGMENT_PRIORITY: i32 = 30;
+pub(crate) const CITATION_FRAGMENT: &str = "citation";
+pub(crate) const CITATION_FRAGMENT_PRIORITY: i32 = 35;
+pub(crate) const LANGUAGE_FRAGMENT: &str = "language";
+pub(crate) const LANGUAGE_FRAGMENT_PRIORITY: i32 = 40;
+const CROSS_SESSION_FRAGMENT: &str = "cross_session";
+const CROSS_SESSION_FRAGMENT_PRIORITY: i32 = 50;
+
+/// Skill 注入片段的标签
+pub(crate) fn skill_fragment(skill: &str) -> String {
+    format!("skill:{skill}")
 }
 
 // ── 构造与初始化 ──────────────────────────────────────────────────────────────
@@ -94,26 +245,56 @@ impl ReactAgent {
                 .all(|name| self.tool_manager.get_tool(name).is_some())
     }
 
-    /// 工具调用场景下自动注入的思维链引导语。
-    const COT_INSTRUCTION: &'static str = "在调用工具之前，先用文字简述你的分析思路和执行计划。";
-
     pub fn new(config: AgentConfig) -> Self {
-        let system_prompt = if config.enable_tool && config.enable_cot {
-            format!(
-                "{}\n\n{}",
-                config.system_prompt.trim_end(),
-                Self::COT_INSTRUCTION,
-            )
-        } else {
-            config.system_prompt.clone()
-        };
-
-        let context = ContextManager::builder(config.token_limit)
-            .with_system(system_prompt)
-            .build();
+        let metrics = metrics::Metrics::new(config.metrics_buckets.clone());
+        let mut context = ContextManager::builder(config.token_limit)
+            .with_system(config.system_prompt.clone())
+            .max_single_message_chars(config.max_single_message_chars);
+        if config.enable_tool && config.enable_cot {
+            context = context.system_fragment(
+                COT_FRAGMENT,
+                COT_FRAGMENT_PRIORITY,
+                config.locale.text(LocaleKey::CotInstruction).to_string(),
+            );
+        }
+        if config.sanitize_untrusted_content {
+            context = context.system_fragment(
+                UNTRUSTED_FRAGMENT,
+                UNTRUSTED_FRAGMENT_PRIORITY,
+                config.locale.text(LocaleKey::UntrustedNotice).to_string(),
+            );
+        }
+        let mut examples = Vec::new();
+        for (index, example) in config.examples.iter().enumerate() {
+            match example.to_messages(index) {
+                Some(messages) => examples.extend(messages),
+                None => tracing::warn!(
+                    agent = %config.agent_name,
+                    index,
+                    "⚠️ few-shot 示例的工具调用缺少 name，已忽略"
+                ),
+            }
+        }
+        let context = context.pinned(examples).build();
 
         let mut tool_manager = ToolManager::new_with_config(config.tool_execution.clone());
-        let client = reqwest::Client::new();
+        tool_manager.set_quota(config.tool_quota.clone());
+        for (name, quota) in &config.tool_quotas {
+            tool_manager.set_tool_quota(name.clone(), quota.clone());
+        }
+        for (name, priority) in &config.tool_priorities {
+            tool_manager.set_tool_priority(name.clone(), *priority);
+        }
+        if let Some(ratio) = config.tool_soft_timeout_ratio {
+            tool_manager.set_soft_timeout_ratio(ratio);
+        }
+        if let Some(seed) = config.tool_seed {
+            tool_manager.set_randomness(Arc::new(SeededRng::new(seed)));
+        }
+        let client = config.http.build_client().unwrap_or_else(|e| {
+            tracing::warn!("⚠️ 按 http_config 构造 HTTP 客户端失败，使用默认配置: {e}");
+            reqwest::Client::new()
+        });
 
         tool_manager.register(Box::new(FinalAnswerTool));
 
@@ -183,13 +364,45 @@ impl ReactAgent {
             subagents,
             client: Arc::new(client),
             llm_config: None,
+            llm_client: None,
             task_manager,
             human_in_loop,
             approval_provider,
             skill_manager: SkillManager::new(),
+            skill_loader: None,
+            pending_skill_reloads: Arc::new(Mutex::new(BTreeSet::new())),
+            skill_watcher: None,
             store,
             checkpointer,
             mcp_manager: McpManager::new(),
+            tool_call_count: 0,
+            wrap_up_hint: run::WrapUpHint::default(),
+            output_processors: Vec::new(),
+            pre_iteration_hook: None,
+            callback_queue: CallbackQueue::default(),
+            pin_model: false,
+            tool_scope: None,
+            unavailable_tools: HashSet::new(),
+            next_tool_choice: None,
+            system_fingerprint: None,
+            tool_call_records: Vec::new(),
+            pending_sources: Mutex::new(HashMap::new()),
+            pending_data: Mutex::new(HashMap::new()),
+            pending_args: Mutex::new(HashMap::new()),
+            sources: Vec::new(),
+            iteration_count: 0,
+            usage: None,
+            tokens_used: 0,
+            helper_tokens: AtomicUsize::new(0),
+            side_effect_count: 0,
+            side_effects_confirmed: false,
+            trace: Arc::new(Mutex::new(None)),
+            cross_session: None,
+            step_state: None,
+            chapter_state: chapter::ChapterState::default(),
+            choice_selector: Arc::new(FirstChoice),
+            idempotency: Arc::new(idempotent::IdempotencyCache::default()),
+            metrics: Mutex::new(metrics),
         }
     }
 
@@ -233,6 +446,11 @@ impl ReactAgent {
         self.llm_config.as_ref()
     }
 
+    /// 设置自定义 LLM 客户端，`execute` / `chat` 与流式执行的推理请求都改由它发送
+    pub fn set_llm_client(&mut self, client: Arc<dyn LlmClient>) {
+        self.llm_client = Some(client);
+    }
+
     // ── 访问器 & 设置器 ────────────────────────────────────────────────────────
 
     /// 获取 AgentConfig 的只读引用
@@ -240,6 +458,16 @@ impl ReactAgent {
         &self.config
     }
 
+    /// 克隆当前生效的配置，可序列化保存后用 [`ReactAgent::new`] 重建同样配置的 Agent
+    ///
+    /// 包含运行中的修改（`set_model`、`set_system_prompt`、`set_tool_concurrency` 等）与已注册的回调；
+    /// 压缩器、LLM 客户端、Store、Checkpointer 等运行时组件不属于配置，重建后需重新设置。
+    pub fn effective_config(&self) -> AgentConfig {
+        let mut config = self.config.clone();
+        config.tool_execution.max_concurrency = self.tool_manager.concurrency_ceiling();
+        config
+    }
+
     /// 注入自定义长期记忆 Store（仅替换自动注入通道，不重注册工具）
     pub fn set_store(&mut self, store: Arc<dyn Store>) {
         self.store = Some(store);
@@ -292,11 +520,125 @@ impl ReactAgent {
         self.checkpointer.as_ref()
     }
 
+    /// 注册最终答案后处理器（去除 markdown、追加免责声明、格式校验等）
+    ///
+    /// 多次调用按注册顺序链式应用。答案产生时即经过处理器，写入上下文、checkpoint、
+    /// `on_final_answer` 回调与 `execute` / `chat` 返回值的都是处理后的文本；
+    /// 流式中间 token 不经过处理器。
+    pub fn set_output_processor(&mut self, processor: OutputProcessor) -> &mut Self {
+        self.output_processors.push(processor);
+        self
+    }
+
+    /// 清空所有已注册的输出后处理器
+    pub fn clear_output_processors(&mut self) {
+        self.output_processors.clear();
+    }
+
+    /// 设置每轮迭代前钩子（替换之前的钩子）
+    ///
+    /// 每轮请求 LLM 前调用，可读取本轮的消息数与估算 token，切换本轮使用的模型、
+    /// 追加只对本轮生效的 system 提示。流式与非流式执行均生效。
+    /// 钩子在执行循环中同步调用，不要在其中做重 IO 或阻塞操作。
+    pub fn set_pre_iteration_hook(&mut self, hook: PreIterationHook) -> &mut Self {
+        self.pre_iteration_hook = Some(hook);
+        self
+    }
+
+    /// 移除每轮迭代前钩子
+    pub fn clear_pre_iteration_hook(&mut self) {
+        self.pre_iteration_hook = None;
+    }
+
+    /// 执行任务并返回完整的执行结果：最终答案、工具调用记录、推理轮数与 token 用量
+    ///
+    /// 与 [`Agent::execute`] 语义一致（重置上下文、必要时走规划流程）。
+    pub async fn execute_rich(&mut self, task: &str) -> Result<ExecutionResult> {
+        let final_answer = self.execute(task).await?;
+        Ok(self.execution_result(final_answer))
+    }
+
+    /// 只向 LLM 暴露 `allowed` 中的工具执行任务，其余已注册工具本次不可见
+    ///
+    /// 工具仍保持注册，仅在组装工具定义时过滤；`final_answer` 始终可用。LLM 仍然点名调用
+    /// 范围外的工具时不会执行，而是回传错误观测值。`allowed` 中未注册的工具名会记录警告并忽略。
+    /// 执行结束（包括出错或 future 被丢弃）后恢复全部工具可见。
+    pub async fn execute_with_tools(&mut self, task: &str, allowed: &[&str]) -> Result<String> {
+        let scope = self.tool_scope_for(allowed);
+        let mut agent = RestoreGuard::new(self, |agent| agent.tool_scope = None);
+        agent.tool_scope = Some(scope);
+        agent.execute(task).await
+    }
+
+    /// [`execute_with_tools`](Self::execute_with_tools) 的流式版本，事件与
+    /// [`Agent::execute_stream`] 相同；流结束或被丢弃后恢复全部工具可见
+    pub async fn execute_stream_with_tools(
+        &mut self,
+        task: &str,
+        allowed: &[&str],
+    ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
+        let scope = self.tool_scope_for(allowed);
+        let task = task.to_string();
+        Ok(Box::pin(async_stream::try_stream! {
+            let mut agent = RestoreGuard::new(self, |agent| agent.tool_scope = None);
+            agent.tool_scope = Some(scope);
+            let mut stream = agent.execute_stream(&task).await?;
+            while let Some(event) = futures::StreamExt::next(&mut stream).await {
+                yield event?;
+            }
+        }))
+    }
+
+    /// `allowed` 中已注册的工具（解析别名后）加上 `final_answer`
+    fn tool_scope_for(&self, allowed: &[&str]) -> HashSet<String> {
+        let mut scope = HashSet::from([TOOL_FINAL_ANSWER.to_string()]);
+        for &name in allowed {
+            if self.tool_manager.get_tool(name).is_some() {
+                scope.insert(self.tool_manager.resolve_name(name).to_string());
+            } else {
+                tracing::warn!(agent = %self.config.agent_name, tool = %name, "⚠️ 工具未注册，已忽略");
+            }
+        }
+        scope
+    }
+
+    /// 以当前执行记录构造 [`ExecutionResult`]
+    pub(crate) fn execution_result(&self, final_answer: String) -> ExecutionResult {
+        ExecutionResult {
+            final_answer,
+            tool_calls: self.tool_call_records.clone(),
+            iterations: self.iteration_count,
+            usage: self.usage.clone(),
+            sources: self.sources.clone(),
+        }
+    }
+
+    /// 依次应用所有输出后处理器
+    pub(crate) fn apply_output_processors(&self, answer: String) -> String {
+        self.output_processors
+            .iter()
+            .fold(answer, |acc, processor| processor(acc))
+    }
+
+    /// 为返回给调用方的最终答案拼接 `answer_prefix` / `answer_suffix`（不写入上下文）
+    pub(crate) fn decorate_answer(&self, answer: String) -> String {
+        format!(
+            "{}{answer}{}",
+            self.config.answer_prefix.as_deref().unwrap_or_default(),
+            self.config.answer_suffix.as_deref().unwrap_or_default()
+        )
+    }
+
     /// 获取当前对话历史消息（只读）
     pub fn get_messages(&self) -> &[crate::llm::types::Message] {
         self.context.messages()
     }
 
+    /// 向对话历史末尾追加一条消息（如在两次 [`step`](Self::step) 之间注入用户提示）
+    pub fn add_message(&mut self, message: crate::llm::types::Message) {
+        self.context.push(message);
+    }
+
     /// 获取已注册的工具名称列表
     pub fn tool_names(&self) -> Vec<&str> {
         self.tool_manager.list_tools()
@@ -392,11 +734,16 @@ impl Agent for ReactAgent {
 
     /// 统一执行入口：`enable_task=true` 时自动路由到规划模式，否则直接执行
     async fn execute(&mut self, task: &str) -> Result<String> {
-        if self.has_planning_tools() {
+        self.apply_pending_skill_reloads().await;
+        self.reset_execution_record();
+        self.start_trace(task);
+        let answer = if self.has_planning_tools() {
             self.execute_with_planning(task).await
         } else {
             self.run_direct(task).await
-        }
+        };
+        self.finish_trace(answer.as_ref().err());
+        Ok(self.decorate_answer(answer?))
     }
 
     async fn execute_stream(&mut self, task: &str) -> Result<BoxStream<'_, Result<AgentEvent>>> {
@@ -404,7 +751,12 @@ impl Agent for ReactAgent {
     }
 
     async fn chat(&mut self, message: &str) -> Result<String> {
-        self.run_chat_direct(message).await
+        self.apply_pending_skill_reloads().await;
+        self.reset_execution_record();
+        self.start_trace(message);
+        let answer = self.run_chat_direct(message).await;
+        self.finish_trace(answer.as_ref().err());
+        Ok(self.decorate_answer(answer?))
     }
 
     async fn chat_stream(&mut self, message: &str) -> Result<BoxStream<'_, Result<AgentEvent>>> {
@@ -413,6 +765,7 @@ impl Agent for ReactAgent {
 
     fn reset(&mut self) {
         self.reset_messages();
+        self.clear_remembered_approvals();
     }
 
     fn tool_names(&self) -> Vec<String> {
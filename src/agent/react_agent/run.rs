@@ -5,22 +5,73 @@
 //! - `think`（LLM 推理）
 //! - `process_steps`（工具并发调度）
 //! - `run_direct` / `run_chat_direct` / `run_react_loop`（ReAct 主循环）
+//! - `step_round`（单轮 think + process_steps，主循环与单步调试共用）
 //! - `run_stream_loop`（流式执行公共逻辑）
 
-use super::{ReactAgent, StepType, TOOL_FINAL_ANSWER, is_retryable_llm_error};
-use crate::agent::AgentEvent;
-use crate::error::{AgentError, ReactError, Result, ToolError};
-use crate::human_loop::{HumanLoopRequest, HumanLoopResponse};
-use crate::llm::types::{FunctionCall, Message, ToolCall as LlmToolCall};
-use crate::llm::{chat, stream_chat};
-use crate::tools::ToolParameters;
+use super::dependency::{plan_waves, substitute};
+use super::extract::validate_schema;
+use super::metrics::Status;
+use super::step::{StepOutcome, StepState};
+use super::{
+    CITATION_FRAGMENT, CITATION_FRAGMENT_PRIORITY, IterationContext, LANGUAGE_FRAGMENT,
+    LANGUAGE_FRAGMENT_PRIORITY, ReactAgent, StepType, TOOL_AGENT_DISPATCH, TOOL_FINAL_ANSWER,
+    is_retryable_llm_error,
+};
+use crate::agent::citation::{append_sources, register_sources};
+use crate::agent::language::{detect_language, language_instruction};
+use crate::agent::trace::{SpanKind, TraceRecorder, attributes};
+use crate::agent::untrusted::wrap_untrusted;
+use crate::agent::{
+    AgentEvent, BudgetKind, CallbackEvent, CallbackSink, LocaleKey, SecretAction, ToolCallRecord,
+    ToolResultOrdering,
+};
+use crate::compression::{ContextManager, estimate_text_tokens};
+use crate::error::{AgentError, ParseError, ReactError, Result, ToolError};
+use crate::human_loop::{ApprovalDecision, HumanLoopRequest, HumanLoopResponse};
+use crate::llm::json_coerce::{CoerceError, coerce_tool_arguments};
+use crate::llm::types::{FunctionCall, Message, ToolCall as LlmToolCall, Usage};
+use crate::llm::{ChatOptions, ChatRequest, ChatResponse, ToolChoice, chat, stream_chat};
+use crate::tools::{ContextHint, InterceptAction, ToolChunkSender, ToolParameters, ToolResult};
 use futures::StreamExt;
 use futures::future::join_all;
 use futures::stream::BoxStream;
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::time::Instant;
+use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
+/// 敏感信息检测的结论
+enum SecretVerdict<'a> {
+    /// 继续执行（参数可能已脱敏或被审批者修改）
+    Proceed(Cow<'a, Value>),
+    /// 拒绝执行，附带回传给 LLM 的观测值
+    Refused(String),
+}
+
+/// 工具调用执行前检查的结论
+enum PreparedCall {
+    /// 按给定参数执行工具
+    Run(ToolParameters),
+    /// 不执行工具（重复调用、被拒绝等），内容直接作为观测值
+    Done(String),
+    /// 拦截器已直接给出结果，不执行工具
+    Respond(ToolResult),
+}
+
+/// 单次执行中收尾提示的状态：每次执行最多注入一次
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum WrapUpHint {
+    #[default]
+    Idle,
+    /// 已达到软预算，下一轮请求前注入
+    Pending,
+    Injected,
+}
+
 // ── 流式执行模式 ─────────────────────────────────────────────────────────────
 
 /// 流式执行的模式配置
@@ -48,86 +99,926 @@ impl ReactAgent {
         })
     }
 
+    /// 开启 `remember_approvals` 时记住本次审批决策
+    fn remember_approval(&self, tool_name: &str, args: &Value, decision: ApprovalDecision) {
+        if !self.config.remember_approvals {
+            return;
+        }
+        if let Ok(mut manager) = self.human_in_loop.write() {
+            manager.remember_decision(tool_name, args, decision);
+        }
+    }
+
+    /// 清空记住的审批决策
+    pub(crate) fn clear_remembered_approvals(&self) {
+        self.human_in_loop
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear_decisions();
+    }
+
     /// 重置消息历史，仅保留 system prompt，确保每次执行互不干扰
     pub(crate) fn reset_messages(&mut self) {
-        self.context.clear();
-        self.context
-            .push(Message::system(self.config.system_prompt.clone()));
+        self.context.clear_history();
+        self.chapter_state = Default::default();
+        self.tool_manager.clear_executed_calls();
+    }
+
+    /// 开启 `auto_language` 时，按本轮输入的语言更新 system prompt 中的回复语言指令
+    pub(crate) fn apply_auto_language(&mut self, input: &str) {
+        if !self.config.auto_language {
+            return;
+        }
+        let Some(language) = detect_language(input) else {
+            debug!(agent = %self.config.agent_name, "输入语言不确定，不注入回复语言指令");
+            return;
+        };
+        debug!(agent = %self.config.agent_name, language, "🌐 注入回复语言指令");
+        self.context.set_system_fragment(
+            LANGUAGE_FRAGMENT,
+            LANGUAGE_FRAGMENT_PRIORITY,
+            language_instruction(language),
+        );
+    }
+
+    // ── 软预算 ───────────────────────────────────────────────────────────────────
+
+    /// 每次执行开始时重置软预算计数
+    fn reset_budget(&mut self) {
+        self.tool_call_count = 0;
+        self.wrap_up_hint = WrapUpHint::Idle;
+        if let Some(limit) = self.config.warn_at_iteration
+            && limit >= self.config.max_iterations
+        {
+            warn!(
+                agent = %self.config.agent_name,
+                warn_at = limit,
+                max = self.config.max_iterations,
+                "⚠️ 迭代软预算不低于硬上限，预算警告不会触发"
+            );
+        }
+    }
+
+    /// 每次执行开始时清空上一次的工具调用记录、来源、轮数、用量与副作用计数
+    pub(crate) fn reset_execution_record(&mut self) {
+        self.tool_call_records.clear();
+        self.sources.clear();
+        self.pending_sources
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.pending_data
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.pending_args
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.unavailable_tools.clear();
+        self.iteration_count = 0;
+        self.usage = None;
+        self.tokens_used = 0;
+        *self.helper_tokens.get_mut() = 0;
+        self.side_effect_count = 0;
+        self.side_effects_confirmed = false;
+        self.clear_remembered_approvals();
+    }
+
+    // ── 执行链路 ─────────────────────────────────────────────────────────────────
+
+    /// 开始记录新一次执行的链路，替换上一次的 trace
+    pub(crate) fn start_trace(&mut self, task: &str) {
+        let recorder = TraceRecorder::new(
+            task,
+            attributes([
+                ("agent", self.config.agent_name.as_str().into()),
+                ("model", self.config.model_name.as_str().into()),
+            ]),
+        );
+        *self.trace.lock().unwrap_or_else(|e| e.into_inner()) = Some(recorder);
+    }
+
+    pub(crate) fn finish_trace(&mut self, error: Option<&ReactError>) {
+        finish_trace(&self.trace, error);
+    }
+
+    pub(crate) fn trace_begin_iteration(&mut self) {
+        if let Some(trace) = self
+            .trace
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            trace.begin_iteration();
+        }
+    }
+
+    pub(crate) fn trace_llm_call(
+        &mut self,
+        start: Instant,
+        attempts: usize,
+        outcome: std::result::Result<Option<&Usage>, String>,
+    ) {
+        self.metrics
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .observe_llm_call(start.elapsed(), Status::of(&outcome));
+        if let Some(trace) = self
+            .trace
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            let mut attrs = attributes([("attempts", attempts.into())]);
+            match outcome {
+                Ok(Some(usage)) => {
+                    for (key, tokens) in [
+                        ("prompt_tokens", usage.prompt_tokens),
+                        ("completion_tokens", usage.completion_tokens),
+                    ] {
+                        if let Some(tokens) = tokens {
+                            attrs.insert(key.to_string(), tokens.into());
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    attrs.insert("error".to_string(), error.into());
+                }
+            }
+            trace.record(
+                SpanKind::LlmCall,
+                &self.config.model_name,
+                start,
+                Instant::now(),
+                attrs,
+            );
+        }
+    }
+
+    fn trace_tool_call(
+        &mut self,
+        tool_call_id: &str,
+        name: &str,
+        arguments: &Value,
+        (start, end): (Instant, Instant),
+        result: &Result<String>,
+    ) {
+        if let Some(trace) = self
+            .trace
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            let mut attrs = attributes([("tool_call_id", tool_call_id.into())]);
+            // 分派给 SubAgent 时记录目标 Agent，序列图中作为独立参与方
+            if name == TOOL_AGENT_DISPATCH
+                && let Some(target) = arguments.get("agent_name").and_then(Value::as_str)
+            {
+                attrs.insert("agent".to_string(), target.into());
+            }
+            if let Err(e) = result {
+                attrs.insert("error".to_string(), e.to_string().into());
+            }
+            trace.record(SpanKind::ToolCall, name, start, end, attrs);
+        }
+    }
+
+    /// 执行工具并记录起止时间，供 trace 使用
+    async fn execute_tool_timed(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        input: &Value,
+        chunks: Option<&ToolChunkSender>,
+    ) -> (Result<String>, (Instant, Instant)) {
+        let start = Instant::now();
+        let result = self
+            .execute_tool_feedback(tool_call_id, tool_name, input, chunks)
+            .await;
+        (result, (start, Instant::now()))
+    }
+
+    /// 把工具结果写入上下文：附上该调用携带的来源列表；开启 `sanitize_untrusted_content`
+    /// 时包裹边界标记（`final_answer` 除外）
+    pub(crate) fn push_tool_result(&mut self, tool_call_id: String, name: &str, output: &str) {
+        let sources = self
+            .pending_sources
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&tool_call_id);
+        let output = match sources {
+            Some(sources) => {
+                if self.sources.is_empty() {
+                    self.context.set_system_fragment(
+                        CITATION_FRAGMENT,
+                        CITATION_FRAGMENT_PRIORITY,
+                        self.config
+                            .locale
+                            .text(LocaleKey::CitationNotice)
+                            .to_string(),
+                    );
+                }
+                let indices = register_sources(&mut self.sources, sources);
+                append_sources(output, &self.sources, &indices, &self.config.locale)
+            }
+            None => output.to_string(),
+        };
+        let content = if self.config.sanitize_untrusted_content && !self.is_final_answer(name) {
+            wrap_untrusted(&output)
+        } else {
+            output
+        };
+        self.context.push(Message::tool_result(
+            tool_call_id,
+            name.to_string(),
+            content,
+        ));
+    }
+
+    /// 开启 `merge_tool_results` 时，把本轮从 `start` 起写入的工具结果合并为一条 tool 消息
+    fn merge_round_tool_results(&mut self, start: usize) {
+        if self.config.merge_tool_results && self.context.merge_tool_messages(start) {
+            debug!(agent = %self.config.agent_name, "🧩 本轮工具结果已合并为一条消息");
+        }
+    }
+
+    /// 记录一次工具调用；参数优先取实际执行的参数
+    fn record_tool_result(&mut self, tool_call_id: &str, name: &str, args: &Value, output: &str) {
+        let args = self
+            .pending_args
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tool_call_id)
+            .unwrap_or_else(|| args.clone());
+        let data = self
+            .pending_data
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tool_call_id);
+        self.tool_call_records.push(ToolCallRecord {
+            name: name.to_string(),
+            args,
+            output: output.to_string(),
+            data,
+        });
+    }
+
+    /// 第 `iteration` 轮（从 0 计数）开始时检查迭代软预算
+    pub(crate) async fn check_iteration_budget(&mut self, iteration: usize) {
+        if let Some(limit) = self.config.warn_at_iteration
+            && iteration + 1 == limit
+        {
+            self.emit_budget_warning(BudgetKind::Iterations, limit, limit)
+                .await;
+        }
+    }
+
+    /// 累加工具调用次数，首次达到软预算时发出警告
+    pub(crate) async fn record_tool_calls(&mut self, count: usize) {
+        let before = self.tool_call_count;
+        self.tool_call_count += count;
+        if let Some(limit) = self.config.warn_at_tool_calls
+            && before < limit
+            && self.tool_call_count >= limit
+        {
+            self.emit_budget_warning(BudgetKind::ToolCalls, self.tool_call_count, limit)
+                .await;
+        }
+    }
+
+    /// 每轮请求 LLM 前检查 token 硬预算，累计用量已超过 `max_total_tokens` 时中止
+    ///
+    /// 已完成的工具调用与推理记录保留在上下文中，可通过 [`ReactAgent::get_messages`] 取回。
+    /// 辅助请求（标题、跨会话摘要、工具输出抽取）的用量同样计入。
+    pub(crate) fn check_token_budget(&self) -> Result<()> {
+        let used = self.tokens_used + self.helper_tokens.load(AtomicOrdering::Relaxed);
+        match self.config.max_total_tokens {
+            Some(limit) if used > limit => {
+                warn!(
+                    agent = %self.config.agent_name,
+                    used,
+                    limit,
+                    "🛑 token 用量超过硬预算，中止执行"
+                );
+                Err(AgentError::TokenBudgetExceeded { used, limit }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 累加一轮 LLM 请求的 token 消耗，首次达到 token 软预算时发出警告；
+    /// 服务端未返回 usage 时按请求消息与响应文本估算。
+    ///
+    /// 上一轮以来辅助请求累计的用量（已计入指标）一并并入。
+    async fn record_token_usage(
+        &mut self,
+        usage: Option<&Usage>,
+        messages: &[Message],
+        reply: &str,
+    ) {
+        let before = self.tokens_used;
+        let tokens = usage_tokens(usage, messages, reply);
+        self.tokens_used += tokens + std::mem::take(self.helper_tokens.get_mut());
+        self.metrics
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .add_tokens(tokens);
+        if let Some(limit) = self.config.warn_at_tokens
+            && before < limit
+            && self.tokens_used >= limit
+        {
+            self.emit_budget_warning(BudgetKind::Tokens, self.tokens_used, limit)
+                .await;
+        }
+    }
+
+    async fn emit_budget_warning(&mut self, kind: BudgetKind, used: usize, limit: usize) {
+        let agent = &self.config.agent_name;
+        warn!(agent = %agent, kind = ?kind, used, limit, "⏳ 已达到软预算");
+        self.callback_sink()
+            .emit(|| CallbackEvent::BudgetWarning { kind, used, limit })
+            .await;
+        if self.config.wrap_up_on_budget && self.wrap_up_hint == WrapUpHint::Idle {
+            self.wrap_up_hint = WrapUpHint::Pending;
+        }
+    }
+
+    /// 每轮请求 LLM 前注入待发的收尾提示
+    ///
+    /// 软预算可能在一轮中途（工具调用与结果之间）触发，因此推迟到轮次边界再写入上下文，
+    /// 避免拆开 assistant 的 tool_calls 与对应的 tool 消息。提示以 user 消息发出：
+    /// 部分服务商不接受对话中途出现的 system 消息。
+    fn inject_wrap_up_hint(&mut self) {
+        if self.wrap_up_hint == WrapUpHint::Pending {
+            info!(agent = %self.config.agent_name, "🏁 注入收尾提示，引导尽快给出最终答案");
+            let hint = self.config.locale.text(LocaleKey::WrapUpHint).to_string();
+            self.context.push(Message::user(hint));
+            self.wrap_up_hint = WrapUpHint::Injected;
+        }
+    }
+
+    // ── 副作用操作预算 ─────────────────────────────────────────────────────────────
+
+    /// 副作用操作预算检查：本批调用计入后若累计超过 `destructive_op_threshold`，
+    /// 发起一次批量确认。
+    ///
+    /// 返回被拒绝执行的调用在 `tool_calls` 中的下标（已写入执行记录）及其观测值，
+    /// 由调用方随本轮结果写回上下文；其余调用照常执行，`$tool_N.output` 引用仍按原下标解析。
+    /// 确认通过后本次执行不再询问；拒绝时本批副作用调用不计数，下次仍会询问。
+    pub(crate) async fn guard_side_effects(
+        &mut self,
+        tool_calls: &[(String, String, Value)],
+    ) -> Result<Vec<(usize, String)>> {
+        let side_effect_ops: Vec<(String, Value)> = tool_calls
+            .iter()
+            .filter(|(_, name, _)| self.is_side_effect_tool(name))
+            .map(|(_, name, args)| (name.clone(), args.clone()))
+            .collect();
+        if side_effect_ops.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let total = self.side_effect_count + side_effect_ops.len();
+        if let Some(threshold) = self.config.destructive_op_threshold
+            && !self.side_effects_confirmed
+            && total > threshold
+        {
+            let agent = &self.config.agent_name;
+            warn!(agent = %agent, total, threshold, "⚠️ 副作用操作超过预算，请求批量确认");
+            let mut req = HumanLoopRequest::batch_approval(total, &side_effect_ops);
+            req.prompt = self
+                .config
+                .locale
+                .text(LocaleKey::BatchApprovalPrompt)
+                .replace("{total}", &total.to_string());
+            let locale = &self.config.locale;
+            let reason = match self.approval_provider.request(req).await? {
+                HumanLoopResponse::Approved
+                | HumanLoopResponse::ApprovedWithModification { .. } => {
+                    info!(agent = %agent, total, "✅ 用户确认批量修改操作");
+                    self.side_effects_confirmed = true;
+                    None
+                }
+                HumanLoopResponse::Rejected { reason } => {
+                    Some(locale.rejection_reason(reason.as_deref()))
+                }
+                HumanLoopResponse::Timeout => {
+                    Some(locale.text(LocaleKey::ConfirmationTimedOut).to_string())
+                }
+                HumanLoopResponse::Text(_) => {
+                    Some(locale.text(LocaleKey::ConfirmationFailed).to_string())
+                }
+            };
+            if let Some(reason) = reason {
+                warn!(agent = %agent, total, "❌ 批量修改操作未获确认，本批副作用调用已跳过");
+                return Ok(self.reject_side_effect_calls(tool_calls, total, &reason));
+            }
+        }
+
+        self.side_effect_count = total;
+        Ok(Vec::new())
+    }
+
+    fn is_side_effect_tool(&self, tool_name: &str) -> bool {
+        self.tool_manager
+            .get_tool(tool_name)
+            .is_some_and(|tool| tool.has_side_effects())
+    }
+
+    fn reject_side_effect_calls(
+        &mut self,
+        tool_calls: &[(String, String, Value)],
+        total: usize,
+        reason: &str,
+    ) -> Vec<(usize, String)> {
+        let mut rejected = Vec::new();
+        for (index, (tool_call_id, name, args)) in tool_calls.iter().enumerate() {
+            if !self.is_side_effect_tool(name) {
+                continue;
+            }
+            let output = self
+                .config
+                .locale
+                .text(LocaleKey::SideEffectsRejected)
+                .replace("{total}", &total.to_string())
+                .replace("{tool}", name)
+                .replace("{reason}", reason);
+            self.record_tool_result(tool_call_id, name, args, &output);
+            rejected.push((index, output));
+        }
+        rejected
     }
 
     /// 执行工具，保留工具返回的真实错误信息
-    pub(crate) async fn execute_tool(&self, tool_name: &str, input: &Value) -> Result<String> {
+    ///
+    /// `tool_call_id` 作为副作用工具的幂等键：本周期内已执行过的调用直接返回首次结果，
+    /// 不再触发审批与回调，见 [`ToolManager::execute_tool_call`](crate::tools::ToolManager::execute_tool_call)。
+    pub(crate) async fn execute_tool(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        input: &Value,
+    ) -> Result<String> {
+        self.execute_tool_with(tool_call_id, tool_name, input, None)
+            .await
+    }
+
+    /// 同 [`execute_tool`](Self::execute_tool)；流式输出工具的片段实时发送到 `chunks`
+    async fn execute_tool_with(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        input: &Value,
+        chunks: Option<&ToolChunkSender>,
+    ) -> Result<String> {
+        let params = match self
+            .prepare_tool_call(tool_call_id, tool_name, input)
+            .await?
+        {
+            PreparedCall::Run(params) => params,
+            PreparedCall::Done(observation) => return Ok(observation),
+            PreparedCall::Respond(result) => {
+                return self.finish_tool_call(tool_call_id, tool_name, result).await;
+            }
+        };
+        let result = self
+            .tool_manager
+            .execute_tool_call_with(tool_call_id, tool_name, params, chunks)
+            .await?;
+        self.finish_tool_call(tool_call_id, tool_name, result).await
+    }
+
+    /// 同名工具的多个调用合并为一次批量执行，结果与 `calls`（`(tool_call_id, 参数)`）一一对应
+    ///
+    /// 每个调用仍各自经过去重、密钥检查、审批与回调；失败时先走降级链，再按 `tool_error_feedback` 处理，
+    /// 与 [`execute_tool_timed`](Self::execute_tool_timed) 一致。整批共享同一段起止时间。
+    async fn execute_tool_batch_timed(
+        &self,
+        tool_name: &str,
+        calls: &[(&str, &Value)],
+    ) -> Vec<(Result<String>, (Instant, Instant))> {
+        let start = Instant::now();
+        let mut results: Vec<Option<Result<String>>> = Vec::with_capacity(calls.len());
+        let mut runnable = Vec::new();
+        for &(tool_call_id, input) in calls {
+            match self.prepare_tool_call(tool_call_id, tool_name, input).await {
+                Ok(PreparedCall::Run(params)) => {
+                    runnable.push((results.len(), (tool_call_id.to_string(), params)));
+                    results.push(None);
+                }
+                Ok(PreparedCall::Done(observation)) => results.push(Some(Ok(observation))),
+                Ok(PreparedCall::Respond(result)) => results.push(Some(
+                    self.finish_tool_call(tool_call_id, tool_name, result).await,
+                )),
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+
+        let (indices, batch): (Vec<usize>, Vec<_>) = runnable.into_iter().unzip();
+        let outcomes = self
+            .tool_manager
+            .execute_intercepted_batch(tool_name, batch)
+            .await;
+        for (i, outcome) in indices.into_iter().zip(outcomes) {
+            let result = match outcome {
+                Ok(result) => self.finish_tool_call(calls[i].0, tool_name, result).await,
+                Err(e) => Err(e),
+            };
+            results[i] = Some(result);
+        }
+
+        let mut outputs = Vec::with_capacity(results.len());
+        for (result, &(tool_call_id, input)) in results.into_iter().zip(calls) {
+            let result = match result {
+                Some(Ok(output)) => Ok(output),
+                Some(Err(e)) => {
+                    self.run_fallback_chain(tool_call_id, tool_name, input, None, e)
+                        .await
+                }
+                None => Err(ToolError::ExecutionFailed {
+                    tool: tool_name.to_string(),
+                    message: "批量执行未返回该调用的结果".to_string(),
+                }
+                .into()),
+            };
+            self.observe_tool_call(tool_name, start.elapsed(), &result);
+            outputs.push(self.soften_tool_error(tool_name, result));
+        }
+        let end = Instant::now();
+        outputs.into_iter().map(|r| (r, (start, end))).collect()
+    }
+
+    /// 执行前的公共步骤：幂等去重、密钥检查、拦截器、`ToolStart` 回调与人工审批
+    ///
+    /// 返回 [`PreparedCall::Done`] 时无需执行工具，其内容直接作为观测值。
+    /// 别名先解析为真实工具名，之后的审批、沙箱与预览都按真名判断。
+    /// 拦截器在审批之前运行，回调、审批与执行记录看到的都是实际执行的参数。
+    async fn prepare_tool_call(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        input: &Value,
+    ) -> Result<PreparedCall> {
         let agent = &self.config.agent_name;
-        let callbacks = self.config.callbacks.clone();
-        let params: ToolParameters = if let Value::Object(map) = input {
-            map.clone().into_iter().collect()
+        let tool_name = self.tool_manager.resolve_name(tool_name);
+        if let Some(scope) = &self.tool_scope
+            && !scope.contains(tool_name)
+        {
+            warn!(agent = %agent, tool = %tool_name, "🚫 工具不在本次任务允许的范围内，拒绝执行");
+            return Err(ToolError::ExecutionFailed {
+                tool: tool_name.to_string(),
+                message: self
+                    .config
+                    .locale
+                    .text(LocaleKey::ToolOutOfScope)
+                    .to_string(),
+            }
+            .into());
+        }
+        if self.unavailable_tools.contains(tool_name) {
+            warn!(agent = %agent, tool = %tool_name, "🚫 工具在当前状态下不可用，拒绝执行");
+            return Err(ToolError::ExecutionFailed {
+                tool: tool_name.to_string(),
+                message: self
+                    .config
+                    .locale
+                    .text(LocaleKey::ToolUnavailable)
+                    .to_string(),
+            }
+            .into());
+        }
+        if let Some(cached) =
+            self.tool_manager
+                .executed_call(tool_call_id, tool_name, &to_tool_parameters(input))
+        {
+            info!(agent = %agent, tool = %tool_name, tool_call_id, "♻️ 重复的工具调用，返回首次执行结果");
+            return Ok(PreparedCall::Done(cached.output));
+        }
+        let requested = input;
+        let input = match self.guard_secrets(tool_name, input).await? {
+            SecretVerdict::Proceed(args) => args,
+            SecretVerdict::Refused(observation) => return Ok(PreparedCall::Done(observation)),
+        };
+        let callbacks = self.callback_sink();
+        let intercepted = self
+            .tool_manager
+            .intercept(tool_name, to_tool_parameters(&input))
+            .await?;
+        let mut params = match intercepted {
+            InterceptAction::Continue(params) => params,
+            InterceptAction::Respond(result) => {
+                callbacks
+                    .emit(|| CallbackEvent::ToolStart {
+                        tool: tool_name.to_string(),
+                        args: input.as_ref().clone(),
+                    })
+                    .await;
+                return Ok(PreparedCall::Respond(result));
+            }
+        };
+        let input = if params == to_tool_parameters(&input) {
+            input
         } else {
-            HashMap::new()
+            debug!(agent = %agent, tool = %tool_name, "🔀 拦截器改写了工具参数");
+            Cow::Owned(Value::Object(
+                params.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            ))
         };
+        let input = input.as_ref();
 
-        for cb in &callbacks {
-            cb.on_tool_start(agent, tool_name, input).await;
-        }
+        callbacks
+            .emit(|| CallbackEvent::ToolStart {
+                tool: tool_name.to_string(),
+                args: input.clone(),
+            })
+            .await;
 
-        info!(agent = %agent, tool = %tool_name, "🔧 开始执行工具");
+        let sandbox = self
+            .tool_manager
+            .get_tool(tool_name)
+            .and_then(|tool| tool.sandbox());
+        match &sandbox {
+            Some(sandbox) => {
+                info!(agent = %agent, tool = %tool_name, sandbox = %sandbox, "🔧 开始执行工具（沙箱 {sandbox}）")
+            }
+            None => info!(agent = %agent, tool = %tool_name, "🔧 开始执行工具"),
+        }
         debug!(agent = %agent, tool = %tool_name, params = %input, "工具参数详情");
 
         // 获取人工审批状态
         // 保守策略：读取失败时返回错误（安全优先）
-        let needs_approval = {
+        let (needs_approval, remembered) = {
             let approval_manager = self.get_approval_manager()?;
-            approval_manager.needs_approval(tool_name)
+            let remembered = self
+                .config
+                .remember_approvals
+                .then(|| {
+                    approval_manager
+                        .remembered_decision(tool_name, input)
+                        .cloned()
+                })
+                .flatten();
+            (approval_manager.needs_approval(tool_name), remembered)
         };
 
+        if needs_approval && let Some(decision) = remembered {
+            return Ok(match decision {
+                ApprovalDecision::Approved => {
+                    info!(agent = %agent, tool = %tool_name, "✅ 沿用此前的批准决策");
+                    self.note_effective_args(tool_call_id, requested, input);
+                    PreparedCall::Run(params)
+                }
+                ApprovalDecision::Rejected { reason } => {
+                    warn!(agent = %agent, tool = %tool_name, "❌ 沿用此前的拒绝决策");
+                    PreparedCall::Done(
+                        self.config
+                            .locale
+                            .rejection_message(tool_name, reason.as_deref()),
+                    )
+                }
+            });
+        }
+
         if needs_approval {
             warn!(agent = %agent, tool = %tool_name, "⚠️ 工具需要人工审批");
-            let req = HumanLoopRequest::approval(tool_name, input.clone());
+            let mut req = HumanLoopRequest::approval(tool_name, input.clone());
+            if let Some(preview) = self
+                .tool_manager
+                .get_tool(tool_name)
+                .and_then(|tool| tool.preview(&params))
+            {
+                req = req.with_preview(preview);
+            }
+            if let Some(sandbox) = sandbox {
+                req = req.with_sandbox(sandbox);
+            }
+            req.prompt = self.config.locale.approval_prompt(
+                tool_name,
+                req.preview.as_deref(),
+                req.sandbox.as_deref(),
+            );
             match self.approval_provider.request(req).await? {
                 HumanLoopResponse::Approved => {
                     info!(agent = %agent, tool = %tool_name, "✅ 用户批准执行工具");
+                    self.remember_approval(tool_name, input, ApprovalDecision::Approved);
+                }
+                HumanLoopResponse::ApprovedWithModification { args } => {
+                    self.validate_approved_args(tool_name, &args)?;
+                    info!(agent = %agent, tool = %tool_name, args = %args, "✅ 用户批准执行工具（参数已修改）");
+                    params = to_tool_parameters(&args);
+                    self.note_effective_args(tool_call_id, requested, &args);
+                    return Ok(PreparedCall::Run(params));
                 }
                 HumanLoopResponse::Rejected { reason } => {
                     warn!(agent = %agent, tool = %tool_name, reason = ?reason, "❌ 用户拒绝执行工具");
-                    return Ok(format!(
-                        "用户已拒绝执行工具 {}{}",
+                    self.remember_approval(
                         tool_name,
-                        reason.map(|r| format!("，原因：{r}")).unwrap_or_default()
+                        input,
+                        ApprovalDecision::Rejected {
+                            reason: reason.clone(),
+                        },
+                    );
+                    return Ok(PreparedCall::Done(
+                        self.config
+                            .locale
+                            .rejection_message(tool_name, reason.as_deref()),
                     ));
                 }
                 HumanLoopResponse::Timeout => {
                     warn!(agent = %agent, tool = %tool_name, "⏰ 审批超时，工具未执行");
-                    return Ok(format!("工具 {tool_name} 审批超时，已跳过执行"));
+                    return Ok(PreparedCall::Done(
+                        self.config
+                            .locale
+                            .text(LocaleKey::ApprovalTimedOut)
+                            .replace("{tool}", tool_name),
+                    ));
                 }
                 HumanLoopResponse::Text(_) => {
                     warn!(agent = %agent, tool = %tool_name, "⚠️ 审批请求收到意外的 Text 响应，视为拒绝");
-                    return Ok(format!("工具 {tool_name} 审批异常，已跳过执行"));
+                    return Ok(PreparedCall::Done(
+                        self.config
+                            .locale
+                            .text(LocaleKey::ApprovalFailed)
+                            .replace("{tool}", tool_name),
+                    ));
                 }
             }
         }
 
-        let result = self.tool_manager.execute_tool(tool_name, params).await?;
+        self.note_effective_args(tool_call_id, requested, input);
+        Ok(PreparedCall::Run(params))
+    }
+
+    /// 实际执行的参数（脱敏、拦截器改写或审批修改后）与 LLM 给出的不同时暂存，
+    /// 供 [`record_tool_result`](Self::record_tool_result) 写入执行记录
+    fn note_effective_args(&self, tool_call_id: &str, requested: &Value, effective: &Value) {
+        if requested != effective {
+            self.pending_args
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(tool_call_id.to_string(), effective.clone());
+        }
+    }
 
+    /// 执行后的公共步骤：`ToolEnd` / `ToolWarning` / `ToolError` 回调，暂存结构化数据与信息来源
+    ///
+    /// 成功结果带有警告时，警告以单独的段落附在输出之后；失败的结果转为 [`ToolError::ExecutionFailed`]。
+    async fn finish_tool_call(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        mut result: ToolResult,
+    ) -> Result<String> {
+        let agent = &self.config.agent_name;
+        let callbacks = self.callback_sink();
         if result.success {
+            if self.is_final_answer(tool_name) {
+                result.output = self.apply_output_processors(result.output);
+            }
             info!(agent = %agent, tool = %tool_name, "📤 工具执行成功");
             debug!(agent = %agent, tool = %tool_name, output = %result.output, "工具返回详情");
-            for cb in callbacks.iter() {
-                cb.on_tool_end(agent, tool_name, &result.output).await;
+            callbacks
+                .emit(|| CallbackEvent::ToolEnd {
+                    tool: tool_name.to_string(),
+                    result: result.output.clone(),
+                })
+                .await;
+            if !result.warnings.is_empty() {
+                warn!(agent = %agent, tool = %tool_name, warnings = ?result.warnings, "⚠️ 工具执行成功但有警告");
+                callbacks
+                    .emit(|| CallbackEvent::ToolWarning {
+                        tool: tool_name.to_string(),
+                        warnings: result.warnings.clone(),
+                    })
+                    .await;
+            }
+            let data = match result.data.take() {
+                Some(data) => Some(data),
+                None => self.extract_tool_data(tool_name, &result.output).await,
+            };
+            if let Some(data) = data {
+                self.pending_data
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(tool_call_id.to_string(), data);
+            }
+            if !result.sources.is_empty() {
+                self.pending_sources
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(tool_call_id.to_string(), result.sources);
             }
-            Ok(result.output)
+            Ok(self.append_warnings(result.output, &result.warnings))
         } else {
             let error_msg = result
                 .error
                 .clone()
                 .unwrap_or_else(|| result.output.clone());
             warn!(agent = %agent, tool = %tool_name, error = %error_msg, "💥 工具执行失败");
-            let err = ReactError::from(ToolError::ExecutionFailed {
-                tool: tool_name.to_string(),
-                message: error_msg,
-            });
-            for cb in &callbacks {
-                cb.on_tool_error(agent, tool_name, &err).await;
+            let failure = |message: String| {
+                ReactError::from(ToolError::ExecutionFailed {
+                    tool: tool_name.to_string(),
+                    message,
+                })
+            };
+            callbacks
+                .emit(|| CallbackEvent::ToolError {
+                    tool: tool_name.to_string(),
+                    err: failure(error_msg.clone()),
+                })
+                .await;
+            Err(failure(error_msg))
+        }
+    }
+
+    /// 审批者改过的参数同样要满足工具的参数 schema
+    fn validate_approved_args(&self, tool_name: &str, args: &Value) -> Result<()> {
+        if let Some(tool) = self.tool_manager.get_tool(tool_name) {
+            validate_schema(args, &tool.parameters(), "$").map_err(|message| {
+                ReactError::from(ToolError::InvalidParameter {
+                    name: "approved_args".to_string(),
+                    message: format!("审批修改后的参数不符合 schema：{message}"),
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// 按 `secret_policy` 扫描工具参数中的敏感信息
+    ///
+    /// 未配置策略或未命中时原样放行；命中时按策略告警、脱敏，或请求人工审批
+    /// （审批请求中的参数已脱敏，批准后按原参数执行）。
+    async fn guard_secrets<'a>(
+        &self,
+        tool_name: &str,
+        input: &'a Value,
+    ) -> Result<SecretVerdict<'a>> {
+        let Some(policy) = &self.config.secret_policy else {
+            return Ok(SecretVerdict::Proceed(Cow::Borrowed(input)));
+        };
+        let matches = policy.detector.scan(input);
+        if matches.is_empty() {
+            return Ok(SecretVerdict::Proceed(Cow::Borrowed(input)));
+        }
+        let agent = &self.config.agent_name;
+        let locale = &self.config.locale;
+        let hits = matches
+            .iter()
+            .map(|m| {
+                locale
+                    .text(LocaleKey::SecretHit)
+                    .replace("{path}", &m.path)
+                    .replace("{rule}", &m.rule)
+            })
+            .collect::<Vec<_>>()
+            .join(locale.text(LocaleKey::ListSeparator));
+        match policy.action {
+            SecretAction::Warn => {
+                warn!(agent = %agent, tool = %tool_name, hits = %hits, "🔑 工具参数中检测到疑似敏感信息");
+                Ok(SecretVerdict::Proceed(Cow::Borrowed(input)))
+            }
+            SecretAction::Redact => {
+                warn!(agent = %agent, tool = %tool_name, hits = %hits, "🔑 工具参数中检测到疑似敏感信息，已脱敏");
+                Ok(SecretVerdict::Proceed(Cow::Owned(
+                    policy.detector.redact(input),
+                )))
+            }
+            SecretAction::RequireApproval => {
+                warn!(agent = %agent, tool = %tool_name, hits = %hits, "🔑 工具参数中检测到疑似敏感信息，等待人工审批");
+                let preview = self
+                    .config
+                    .locale
+                    .text(LocaleKey::SecretApprovalPreview)
+                    .replace("{hits}", &hits);
+                let mut req = HumanLoopRequest::approval(tool_name, policy.detector.redact(input))
+                    .with_preview(preview);
+                req.prompt =
+                    self.config
+                        .locale
+                        .approval_prompt(tool_name, req.preview.as_deref(), None);
+                match self.approval_provider.request(req).await? {
+                    HumanLoopResponse::Approved => {
+                        info!(agent = %agent, tool = %tool_name, "✅ 用户批准携带敏感信息执行工具");
+                        Ok(SecretVerdict::Proceed(Cow::Borrowed(input)))
+                    }
+                    HumanLoopResponse::ApprovedWithModification { args } => {
+                        self.validate_approved_args(tool_name, &args)?;
+                        info!(agent = %agent, tool = %tool_name, "✅ 用户批准执行工具（参数已修改）");
+                        Ok(SecretVerdict::Proceed(Cow::Owned(args)))
+                    }
+                    response => {
+                        warn!(agent = %agent, tool = %tool_name, response = ?response, "❌ 携带敏感信息的工具调用未获批准");
+                        Ok(SecretVerdict::Refused(
+                            locale
+                                .text(LocaleKey::SecretRefused)
+                                .replace("{tool}", tool_name)
+                                .replace("{hits}", &hits),
+                        ))
+                    }
+                }
             }
-            Err(err)
         }
     }
 
@@ -138,53 +1029,252 @@ impl ReactAgent {
     /// `final_answer` 工具始终保持原始错误语义，不会被软化。
     pub(crate) async fn execute_tool_feedback(
         &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        input: &Value,
+        chunks: Option<&ToolChunkSender>,
+    ) -> Result<String> {
+        let start = Instant::now();
+        let result = match self
+            .execute_tool_with(tool_call_id, tool_name, input, chunks)
+            .await
+        {
+            Err(e) => {
+                self.run_fallback_chain(tool_call_id, tool_name, input, chunks, e)
+                    .await
+            }
+            ok => ok,
+        };
+        self.observe_tool_call(tool_name, start.elapsed(), &result);
+        self.soften_tool_error(tool_name, result)
+    }
+
+    /// 主工具失败后按降级链依次尝试备用工具，返回第一个成功的结果（附来源说明）；
+    /// 没有可用的备用工具或全部失败时返回主工具的错误
+    ///
+    /// 只尝试调用参数能通过其 schema 校验的备用工具；`final_answer` 不降级。
+    async fn run_fallback_chain(
+        &self,
+        tool_call_id: &str,
         tool_name: &str,
         input: &Value,
+        chunks: Option<&ToolChunkSender>,
+        error: ReactError,
     ) -> Result<String> {
-        match self.execute_tool(tool_name, input).await {
+        if self.is_final_answer(tool_name) {
+            return Err(error);
+        }
+        let agent = &self.config.agent_name;
+        for fallback in self.tool_manager.fallback_chain(tool_name) {
+            let Some(tool) = self.tool_manager.get_tool(fallback) else {
+                warn!(agent = %agent, tool = %tool_name, fallback = %fallback, "⚠️ 备用工具未注册，跳过");
+                continue;
+            };
+            if let Err(reason) = validate_schema(input, &tool.parameters(), "$") {
+                warn!(agent = %agent, tool = %tool_name, fallback = %fallback, reason = %reason, "⚠️ 参数与备用工具 schema 不兼容，跳过");
+                continue;
+            }
+            warn!(agent = %agent, tool = %tool_name, fallback = %fallback, error = %error, "🔁 工具执行失败，改用备用工具");
+            match self
+                .execute_tool_with(tool_call_id, fallback, input, chunks)
+                .await
+            {
+                Ok(output) => {
+                    let notice = self
+                        .config
+                        .locale
+                        .text(LocaleKey::FallbackNotice)
+                        .replace("{tool}", tool_name)
+                        .replace("{fallback}", fallback);
+                    return Ok(format!("{notice}\n{output}"));
+                }
+                Err(e) => {
+                    warn!(agent = %agent, fallback = %fallback, error = %e, "💥 备用工具执行失败");
+                }
+            }
+        }
+        Err(error)
+    }
+
+    /// 把工具结果的警告以单独段落附在输出之后，让 LLM 明确看到「成功但有隐患」
+    fn append_warnings(&self, output: String, warnings: &[String]) -> String {
+        if warnings.is_empty() {
+            return output;
+        }
+        let list: String = warnings.iter().map(|w| format!("\n- {w}")).collect();
+        let notice = self
+            .config
+            .locale
+            .text(LocaleKey::ToolWarnings)
+            .replace("{warnings}", &list);
+        format!("{output}\n\n{notice}")
+    }
+
+    /// 按 `tool_error_feedback` 把工具错误转为回传 LLM 的观测值
+    fn soften_tool_error(&self, tool_name: &str, result: Result<String>) -> Result<String> {
+        match result {
             Ok(result) => Ok(result),
-            Err(e) if self.config.tool_error_feedback && tool_name != TOOL_FINAL_ANSWER => {
+            Err(e) if self.config.tool_error_feedback && !self.is_final_answer(tool_name) => {
                 warn!(
                     agent = %self.config.agent_name,
                     tool = %tool_name,
                     error = %e,
                     "⚠️ 工具错误已转为观测值回传 LLM"
                 );
-                Ok(format!(
-                    "[工具执行失败] {e}\n提示：请根据错误信息调整参数后重试，或换用其他工具。"
-                ))
+                let locale = &self.config.locale;
+                Ok(locale
+                    .text(LocaleKey::ToolErrorFeedback)
+                    .replace("{error}", &e.to_string())
+                    .replace("{hint}", locale.text(e.recovery_hint_key())))
             }
             Err(e) => Err(e),
         }
     }
 
+    /// 是否为 `final_answer`（含其别名）
+    fn is_final_answer(&self, tool_name: &str) -> bool {
+        self.tool_manager.resolve_name(tool_name) == TOOL_FINAL_ANSWER
+    }
+
+    /// 本次执行的回调投递端，持有当前已注册回调的快照
+    pub(crate) fn callback_sink(&self) -> CallbackSink {
+        CallbackSink::new(
+            &self.config.agent_name,
+            &self.config.callbacks,
+            self.config.callback_mode,
+            &self.callback_queue,
+        )
+    }
+
+    /// 取出本轮请求使用的工具选择策略：单次覆盖优先，其次为配置项
+    pub(crate) fn take_tool_choice(&mut self) -> Option<ToolChoice> {
+        self.next_tool_choice
+            .take()
+            .or_else(|| self.config.tool_choice.clone())
+    }
+
+    /// 本轮 LLM 请求携带的工具定义：按可用性过滤后，再限定到 `execute_with_tools` 指定的子集
+    fn visible_tools(&mut self) -> Vec<crate::llm::types::ToolDefinition> {
+        let hint = self.context_hint();
+        let mut tools = self.tool_manager.available_tools(&hint);
+        self.unavailable_tools = self.tool_manager.unavailable_tools(&hint);
+        if let Some(scope) = &self.tool_scope {
+            tools.retain(|def| scope.contains(&def.function.name));
+        }
+        tools
+    }
+
+    /// 汇总当前上下文的状态摘要，供工具判断本轮是否可用
+    pub(crate) fn context_hint(&self) -> ContextHint {
+        const MAX_RECENT_TOOLS: usize = 10;
+        let messages = self.context.messages();
+        let mut recent_tools: Vec<String> = messages
+            .iter()
+            .rev()
+            .filter_map(|m| m.tool_calls.as_deref())
+            .flat_map(|calls| calls.iter().rev().map(|c| c.function.name.clone()))
+            .take(MAX_RECENT_TOOLS)
+            .collect();
+        recent_tools.reverse();
+        ContextHint {
+            message_count: messages.len(),
+            iteration: self.iteration_count,
+            recent_tools,
+        }
+    }
+
+    /// 调用每轮迭代前钩子：返回本轮使用的模型，并把临时 system 提示合并进 `messages`
+    fn apply_pre_iteration_hook(&self, messages: &mut Vec<Message>) -> String {
+        let Some(hook) = &self.pre_iteration_hook else {
+            return self.config.model_name.clone();
+        };
+        let mut iteration = IterationContext {
+            iteration: self.iteration_count,
+            message_count: messages.len(),
+            token_estimate: ContextManager::estimate_tokens(messages),
+            model_name: self.config.model_name.clone(),
+            extra_system_prompts: Vec::new(),
+        };
+        hook(&mut iteration);
+
+        if !iteration.extra_system_prompts.is_empty() {
+            let extra = iteration.extra_system_prompts.join("\n\n");
+            match messages.iter_mut().find(|m| m.role == "system") {
+                Some(system) => {
+                    let content = system.content.get_or_insert_with(String::new);
+                    if !content.is_empty() {
+                        content.push_str("\n\n");
+                    }
+                    content.push_str(&extra);
+                }
+                None => messages.insert(0, Message::system(extra)),
+            }
+        }
+        if iteration.model_name != self.config.model_name {
+            debug!(
+                agent = %self.config.agent_name,
+                model = %iteration.model_name,
+                "🔀 迭代前钩子切换本轮模型"
+            );
+        }
+        iteration.model_name
+    }
+
+    /// 最近一条用户消息，作为压缩时判断历史相关度的当前问题
+    fn current_query(&self) -> Option<String> {
+        // 收尾提示同样是 user 消息，但不是用户的问题
+        let hint = self.config.locale.text(LocaleKey::WrapUpHint);
+        self.context
+            .messages()
+            .iter()
+            .rev()
+            .filter(|m| m.role == "user")
+            .find(|m| m.content.as_deref() != Some(hint))
+            .and_then(|m| m.content.clone())
+    }
+
     /// 调用 LLM 推理，返回本轮的步骤列表。
     ///
     /// 每次调用前先通过 `ContextManager::prepare` 自动压缩超限的历史消息，
     /// 再将压缩后的消息列表传给 LLM；LLM 的响应追加回 context。
     pub(crate) async fn think(&mut self) -> Result<Vec<StepType>> {
         let agent = self.config.agent_name.clone();
-        let callbacks = self.config.callbacks.clone();
-        let mut res = Vec::new();
+        let callbacks = self.callback_sink();
+        self.check_token_budget()?;
+        self.inject_wrap_up_hint();
 
-        debug!(agent = %agent, model = %self.config.model_name, "🧠 LLM 思考中...");
+        let query = self.current_query();
+        let mut messages = self.context.prepare(query.as_deref()).await?;
+        let model_name = self.apply_pre_iteration_hook(&mut messages);
 
-        let messages = self.context.prepare(None).await?;
+        debug!(agent = %agent, model = %model_name, "🧠 LLM 思考中...");
 
-        for cb in &callbacks {
-            cb.on_think_start(&agent, &messages).await;
-        }
+        callbacks.think_start(&messages).await;
 
-        let tools = self.tool_manager.get_openai_tools();
+        let tools = self.visible_tools();
+        let tool_choice = self.take_tool_choice();
         let max_retries = self.config.llm_max_retries;
         let retry_delay = self.config.llm_retry_delay_ms;
         // 在循环外克隆一次，避免重复克隆
         let client = self.client.clone();
-        let model_name = self.config.model_name.clone();
+        let llm_client = self.llm_client.clone();
+        // 自定义客户端只在钩子或变体切换了模型时才指定，否则沿用客户端自身的模型
+        let model_override =
+            (model_name != self.config.model_name || self.pin_model).then(|| model_name.clone());
         let response_format = self.config.response_format.clone();
+        let temperature = self.config.temperature;
+        let options = ChatOptions {
+            seed: self.config.seed,
+            n: (self.config.n_choices > 1).then_some(self.config.n_choices),
+            ..Default::default()
+        };
 
+        self.trace_begin_iteration();
+        let llm_start = Instant::now();
+        let mut attempts = 0;
         let mut response_result: Result<_> = Err(ReactError::Agent(AgentError::NoResponse));
         for attempt in 0..=max_retries {
+            attempts = attempt + 1;
             if attempt > 0 {
                 let delay_ms = retry_delay * (1u64 << (attempt - 1).min(5));
                 warn!(
@@ -196,18 +1286,28 @@ impl ReactAgent {
                 );
                 tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
             }
-            response_result = chat(
-                client.clone(),
-                model_name.as_str(),
-                messages.clone(),
-                Some(0.7),
-                Some(8192u32),
-                Some(false),
-                Some(tools.clone()),
-                None,
-                response_format.clone(),
-            )
-            .await;
+            let request = ChatRequest {
+                messages: messages.clone(),
+                temperature: Some(temperature),
+                max_tokens: Some(8192),
+                tools: Some(tools.clone()),
+                tool_choice: tool_choice.clone(),
+                response_format: response_format.clone(),
+                options: options.clone(),
+            };
+            response_result = match &llm_client {
+                Some(llm) => llm
+                    .chat(ChatRequest {
+                        options: ChatOptions {
+                            model: model_override.clone(),
+                            ..request.options
+                        },
+                        ..request
+                    })
+                    .await
+                    .map(ChatResponse::into_completion),
+                None => chat(client.clone(), model_name.as_str(), request).await,
+            };
             match &response_result {
                 Ok(_) => {
                     if attempt > 0 {
@@ -222,49 +1322,142 @@ impl ReactAgent {
             }
         }
 
-        let message = response_result?
-            .choices
-            .first()
-            .ok_or(ReactError::Agent(AgentError::NoResponse))?
-            .message
-            .clone();
+        self.trace_llm_call(
+            llm_start,
+            attempts,
+            response_result
+                .as_ref()
+                .map(|r| r.usage())
+                .map_err(|e| e.to_string()),
+        );
+        let response = response_result?;
+        self.observe_system_fingerprint(response.system_fingerprint());
+        self.iteration_count += 1;
+        if let Some(usage) = response.usage() {
+            self.usage
+                .get_or_insert_with(Default::default)
+                .accumulate(usage);
+        }
+        let candidates = response.choice_messages().cloned().collect();
+        let message = self
+            .select_choice(&messages, candidates)
+            .await?
+            .ok_or(ReactError::Agent(AgentError::NoResponse))?;
+        let reply = message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| call.function.arguments.as_str())
+            .chain(message.content.as_deref())
+            .collect::<String>();
+        self.record_token_usage(response.usage(), &messages, &reply)
+            .await;
 
-        if let Some(tool_calls) = &message.tool_calls {
-            self.context.push(message.clone());
-            let tool_names: Vec<&str> = tool_calls
-                .iter()
-                .map(|c| c.function.name.as_str())
-                .collect();
-            info!(
-                agent = %agent,
-                tools = ?tool_names,
-                "🧠 LLM 决定调用 {} 个工具",
-                tool_calls.len()
+        let res = self.steps_from_message(message)?;
+
+        callbacks
+            .emit(|| CallbackEvent::ThinkEnd(res.clone()))
+            .await;
+
+        Ok(res)
+    }
+
+    /// 记录响应中的 `system_fingerprint`；固定 seed 时指纹变化意味着后端配置变了，输出不再可复现
+    pub(crate) fn observe_system_fingerprint(&mut self, fingerprint: Option<&str>) {
+        let Some(fingerprint) = fingerprint else {
+            return;
+        };
+        if let Some(previous) = &self.system_fingerprint
+            && previous != fingerprint
+            && self.config.seed.is_some()
+        {
+            warn!(
+                agent = %self.config.agent_name,
+                previous = %previous,
+                current = %fingerprint,
+                "⚠️ system_fingerprint 已变化，相同 seed 的输出可能不再一致"
             );
-            for call in tool_calls {
-                res.push(StepType::Call {
-                    tool_call_id: call.id.clone(),
-                    function_name: call.function.name.clone(),
-                    arguments: serde_json::from_str(&call.function.arguments)?,
-                });
-            }
-        } else if let Some(content) = &message.content {
-            self.context.push(message.clone());
-            debug!(agent = %agent, "🧠 LLM 返回文本响应");
-            res.push(StepType::Thought(content.to_string()));
         }
+        self.system_fingerprint = Some(fingerprint.to_string());
+    }
 
-        for cb in &callbacks {
-            cb.on_think_end(&agent, &res).await;
+    /// 将 LLM 返回的 assistant 消息写入上下文，并转换为执行步骤
+    ///
+    /// 部分模型会在同一条消息里同时返回推理文本与工具调用：此时先产出 `Thought`
+    /// 再产出 `Call`，上下文中完整保留 content 与 tool_calls。
+    pub(crate) fn steps_from_message(&mut self, mut message: Message) -> Result<Vec<StepType>> {
+        let agent = &self.config.agent_name;
+        let mut steps = Vec::new();
+
+        for (index, call) in message.tool_calls.iter_mut().flatten().enumerate() {
+            call.id = tool_call_id_or_generate(&call.id, index as u32);
         }
 
-        Ok(res)
+        match message.tool_calls.as_deref() {
+            Some(tool_calls) if !tool_calls.is_empty() => {
+                if let Some(content) = message.content.as_deref()
+                    && !content.trim().is_empty()
+                {
+                    debug!(agent = %agent, "🧠 LLM 在工具调用前附带了推理文本");
+                    steps.push(StepType::Thought(content.to_string()));
+                }
+                let tool_names: Vec<&str> = tool_calls
+                    .iter()
+                    .map(|c| c.function.name.as_str())
+                    .collect();
+                info!(
+                    agent = %agent,
+                    tools = ?tool_names,
+                    "🧠 LLM 决定调用 {} 个工具",
+                    tool_calls.len()
+                );
+                for call in tool_calls {
+                    steps.push(StepType::Call {
+                        tool_call_id: call.id.clone(),
+                        function_name: call.function.name.clone(),
+                        arguments: self
+                            .coerce_tool_arguments(&call.function.name, &call.function.arguments)
+                            .map_err(|e| {
+                                ReactError::Parse(ParseError::JsonError(format!(
+                                    "工具 {} 的参数{e}",
+                                    call.function.name
+                                )))
+                            })?,
+                    });
+                }
+            }
+            _ => match message.content.take() {
+                Some(content) => {
+                    debug!(agent = %agent, "🧠 LLM 返回文本响应");
+                    // 纯文本响应即最终答案：先经输出后处理器，上下文与回调拿到的都是处理后的文本
+                    let content = if content.trim().is_empty() {
+                        content
+                    } else {
+                        self.apply_output_processors(content)
+                    };
+                    steps.push(StepType::Thought(content.clone()));
+                    message.content = Some(content);
+                }
+                None => return Ok(steps),
+            },
+        }
+
+        self.context.push(message);
+        Ok(steps)
     }
 
     /// 处理一轮思考产生的步骤：
-    /// - 有工具调用 → 并行执行（需要审批的工具强制串行），`final_answer` 时返回答案
+    /// - 有工具调用 → 并行执行（需要审批的工具强制串行），`final_answer` 时返回答案；
+    ///   参数中含 `$tool_N.output` 引用时按依赖分批，被依赖的调用先执行
     /// - 无工具调用 → 纯文本响应视为最终答案，直接返回
     pub(crate) async fn process_steps(&mut self, steps: Vec<StepType>) -> Result<Option<String>> {
+        let start = self.context.messages().len();
+        let result = self.run_steps(steps).await;
+        self.merge_round_tool_results(start);
+        result
+    }
+
+    async fn run_steps(&mut self, steps: Vec<StepType>) -> Result<Option<String>> {
         let agent = self.config.agent_name.clone();
         let mut tool_calls = Vec::new();
         let mut last_thought: Option<String> = None;
@@ -289,15 +1482,34 @@ impl ReactAgent {
             return Ok(last_thought.filter(|s| !s.is_empty()));
         }
 
-        if tool_calls.len() > 1 {
-            let tool_names: Vec<&str> = tool_calls.iter().map(|(_, n, _)| n.as_str()).collect();
+        self.record_tool_calls(tool_calls.len()).await;
+
+        // 未获确认的副作用调用以拒绝信息作为结果，剩余调用照常执行
+        let rejected = self.guard_side_effects(&tool_calls).await?;
+        let mut outputs: Vec<Option<String>> = vec![None; tool_calls.len()];
+        // 结果产生的先后顺序（tool_calls 下标），被拒绝的调用最先产生结果
+        let mut completed: Vec<usize> = rejected.iter().map(|&(index, _)| index).collect();
+        if rejected.len() == tool_calls.len() {
+            fill_rejected(&mut outputs, rejected);
+            self.push_round_results(&tool_calls, outputs, &completed);
+            return Ok(None);
+        }
+
+        let runnable = tool_calls.len() - rejected.len();
+        if runnable > 1 {
+            let tool_names: Vec<&str> = tool_calls
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !completed.contains(i))
+                .map(|(_, (_, n, _))| n.as_str())
+                .collect();
             let max_concurrency = self.tool_manager.max_concurrency();
             info!(
                 agent = %agent,
                 tools = ?tool_names,
                 max_concurrency = ?max_concurrency,
                 "⚡ 并发执行 {} 个工具调用",
-                tool_calls.len()
+                runnable
             );
         }
 
@@ -305,67 +1517,192 @@ impl ReactAgent {
         // 统一错误处理：RwLock 中毒时返回错误
         let has_approval_tools = {
             let approval_manager = self.get_approval_manager()?;
-            tool_calls
-                .iter()
-                .any(|(_, name, _)| approval_manager.needs_approval(name))
+            tool_calls.iter().enumerate().any(|(i, (_, name, _))| {
+                !completed.contains(&i)
+                    && approval_manager.needs_approval(self.tool_manager.resolve_name(name))
+            })
         };
 
+        let waves = runnable_waves(&tool_calls, &completed);
+        let mut final_answer: Option<String> = None;
+
         if has_approval_tools {
             info!(agent = %agent, "⚠️ 检测到需人工审批工具，切换为串行执行");
-            for (tool_call_id, function_name, arguments) in tool_calls {
-                let result = self
-                    .execute_tool_feedback(&function_name, &arguments)
-                    .await?;
-                self.context.push(Message::tool_result(
-                    tool_call_id,
-                    function_name.clone(),
-                    result.clone(),
-                ));
-                if function_name == TOOL_FINAL_ANSWER {
-                    info!(agent = %agent, "🏁 最终答案已生成");
-                    return Ok(Some(result));
+            for index in waves.concat() {
+                let (tool_call_id, function_name, arguments) = &tool_calls[index];
+                let arguments = substitute(arguments, &outputs);
+                let (result, timing) = self
+                    .execute_tool_timed(tool_call_id, function_name, &arguments, None)
+                    .await;
+                self.trace_tool_call(tool_call_id, function_name, &arguments, timing, &result);
+                let result = result?;
+                self.record_tool_result(tool_call_id, function_name, &arguments, &result);
+                outputs[index] = Some(result.clone());
+                completed.push(index);
+                if self.is_final_answer(function_name) {
+                    final_answer = Some(result);
+                    break;
                 }
             }
         } else {
-            let futures: Vec<_> = tool_calls
-                .iter()
-                .map(|(_, name, args)| self.execute_tool_feedback(name, args))
-                .collect();
-            let results = join_all(futures).await;
-
-            let mut final_answer: Option<String> = None;
-            for ((tool_call_id, function_name, _), result) in tool_calls.into_iter().zip(results) {
+            if waves.len() > 1 {
+                info!(agent = %agent, waves = waves.len(), "🔗 工具调用之间存在引用，按依赖分批执行");
+            }
+            let mut results: Vec<_> = tool_calls.iter().map(|_| None).collect();
+            let completion = AtomicUsize::new(0);
+            let mut completion_order: Vec<(usize, usize)> = Vec::with_capacity(tool_calls.len());
+            for wave in &waves {
+                let wave_args: Vec<Value> = wave
+                    .iter()
+                    .map(|&i| substitute(&tool_calls[i].2, &outputs))
+                    .collect();
+                let groups = self.batch_groups(wave.iter().map(|&i| tool_calls[i].1.as_str()));
+                let this = &*self;
+                let completion = &completion;
+                let futures: Vec<_> = groups
+                    .iter()
+                    .map(|group| {
+                        let calls: Vec<(&str, &Value)> = group
+                            .iter()
+                            .map(|&p| (tool_calls[wave[p]].0.as_str(), &wave_args[p]))
+                            .collect();
+                        let name = tool_calls[wave[group[0]]].1.as_str();
+                        async move {
+                            let results = match calls[..] {
+                                [(tool_call_id, args)] => vec![
+                                    this.execute_tool_timed(tool_call_id, name, args, None)
+                                        .await,
+                                ],
+                                _ => this.execute_tool_batch_timed(name, &calls).await,
+                            };
+                            (results, completion.fetch_add(1, AtomicOrdering::Relaxed))
+                        }
+                    })
+                    .collect();
+                let group_results = join_all(futures).await;
+                for (group, (group_result, seq)) in groups.iter().zip(group_results) {
+                    for (&p, (result, timing)) in group.iter().zip(group_result) {
+                        let i = wave[p];
+                        if let Ok(output) = &result {
+                            outputs[i] = Some(output.clone());
+                        }
+                        results[i] = Some((wave_args[p].clone(), result, timing));
+                        completion_order.push((seq, i));
+                    }
+                }
+            }
+            completion_order.sort_by_key(|&(seq, _)| seq);
+            completed.extend(completion_order.into_iter().map(|(_, i)| i));
+
+            for (index, entry) in results.into_iter().enumerate() {
+                let Some((arguments, result, timing)) = entry else {
+                    continue;
+                };
+                let (tool_call_id, function_name, _) = &tool_calls[index];
+                self.trace_tool_call(tool_call_id, function_name, &arguments, timing, &result);
                 let result = result?;
-                self.context.push(Message::tool_result(
-                    tool_call_id,
-                    function_name.clone(),
-                    result.clone(),
-                ));
-                if function_name == TOOL_FINAL_ANSWER {
-                    info!(agent = %agent, "🏁 最终答案已生成");
+                self.record_tool_result(tool_call_id, function_name, &arguments, &result);
+                if self.is_final_answer(function_name) {
                     final_answer = Some(result);
                 }
             }
-            if final_answer.is_some() {
-                return Ok(final_answer);
+        }
+
+        fill_rejected(&mut outputs, rejected);
+        self.push_round_results(&tool_calls, outputs, &completed);
+        if final_answer.is_some() {
+            info!(agent = %agent, "🏁 最终答案已生成");
+        }
+        Ok(final_answer)
+    }
+
+    /// 按 [`AgentConfig::tool_result_ordering`](crate::agent::AgentConfig::tool_result_ordering)
+    /// 把一轮工具结果写回上下文
+    ///
+    /// `outputs` 与 `calls` 按下标对应，`completed` 为结果产生的先后顺序。没有结果的调用
+    /// （串行执行时最终答案之后被跳过的）补一条说明，保证每个 tool_call 都有对应的 tool 消息。
+    fn push_round_results(
+        &mut self,
+        calls: &[(String, String, Value)],
+        mut outputs: Vec<Option<String>>,
+        completed: &[usize],
+    ) {
+        let order: Vec<usize> = match self.config.tool_result_ordering {
+            ToolResultOrdering::AsCalled => (0..calls.len()).collect(),
+            ToolResultOrdering::AsCompleted => {
+                let skipped = (0..calls.len()).filter(|i| !completed.contains(i));
+                completed.iter().copied().chain(skipped).collect()
             }
+        };
+        for i in order {
+            let (tool_call_id, function_name, _) = &calls[i];
+            let output = outputs[i].take().unwrap_or_else(|| {
+                self.config
+                    .locale
+                    .text(LocaleKey::SkippedAfterFinalAnswer)
+                    .to_string()
+            });
+            self.push_tool_result(tool_call_id.clone(), function_name, &output);
         }
+    }
 
-        Ok(None)
+    /// 把一批工具调用（按工具名给出）分组：支持批处理的同名调用归为一组，其余各自成组
+    ///
+    /// 组内为调用在 `names` 中的下标。
+    fn batch_groups<'a>(&self, names: impl Iterator<Item = &'a str>) -> Vec<Vec<usize>> {
+        let agent = &self.config.agent_name;
+        let mut groups: Vec<(&str, bool, Vec<usize>)> = Vec::new();
+        for (p, name) in names.enumerate() {
+            let batchable = self
+                .tool_manager
+                .get_tool(name)
+                .is_some_and(|tool| tool.supports_batch());
+            match groups.iter_mut().find(|(group_name, group_batchable, _)| {
+                batchable && *group_batchable && *group_name == name
+            }) {
+                Some((_, _, group)) => group.push(p),
+                None => groups.push((name, batchable, vec![p])),
+            }
+        }
+        for (name, _, group) in &groups {
+            if group.len() > 1 {
+                info!(agent = %agent, tool = %name, count = group.len(), "📦 合并同名工具调用为一次批量执行");
+            }
+        }
+        groups.into_iter().map(|(_, _, group)| group).collect()
     }
 
     /// 直接执行（无规划）：重置/恢复上下文，然后进入 ReAct 循环
     pub(crate) async fn run_direct(&mut self, task: &str) -> Result<String> {
         let agent = self.config.agent_name.clone();
+        self.prepare_direct_context().await;
+
+        info!(agent = %agent, "🧠 Agent 开始执行任务");
+        debug!(
+            agent = %agent,
+            task = %task,
+            tools = ?self.tool_manager.list_tools(),
+            max_iterations = self.config.max_iterations,
+            "执行详情"
+        );
+
+        self.run_react_loop(task).await
+    }
 
+    /// 直接执行前的上下文准备：有 checkpoint 时恢复会话，否则从空上下文开始新会话
+    pub(crate) async fn prepare_direct_context(&mut self) {
+        let agent = self.config.agent_name.clone();
+        let mut new_session = true;
         if let (Some(cp), Some(tid)) = (&self.checkpointer, &self.config.session_id) {
             match cp.get(tid).await {
                 Ok(Some(checkpoint)) => {
                     info!(agent = %agent, session_id = %tid, checkpoint_id = %checkpoint.checkpoint_id, "🔄 从 Checkpoint 恢复会话");
                     self.context.clear();
+                    self.chapter_state = Default::default();
                     for msg in checkpoint.messages {
                         self.context.push(msg);
                     }
+                    new_session = false;
                 }
                 Ok(None) => {
                     debug!(agent = %agent, session_id = %tid, "新会话，从空上下文开始");
@@ -379,17 +1716,9 @@ impl ReactAgent {
         } else {
             self.reset_messages();
         }
-
-        info!(agent = %agent, "🧠 Agent 开始执行任务");
-        debug!(
-            agent = %agent,
-            task = %task,
-            tools = ?self.tool_manager.list_tools(),
-            max_iterations = self.config.max_iterations,
-            "执行详情"
-        );
-
-        self.run_react_loop(task).await
+        if new_session {
+            self.inject_cross_session_summary().await;
+        }
     }
 
     /// 多轮对话：不重置上下文，直接追加消息后进入 ReAct 循环
@@ -405,14 +1734,32 @@ impl ReactAgent {
             "对话详情"
         );
 
+        self.inject_cross_session_summary_for_chat().await;
         self.run_react_loop(message).await
     }
 
     /// 核心 ReAct 循环（注入记忆 → 追加消息 → think/act 迭代）。
     /// `run_direct` 和 `run_chat_direct` 共享此实现。
     async fn run_react_loop(&mut self, message: &str) -> Result<String> {
+        let callbacks = self.callback_sink();
+        let result = self.react_loop(message, &callbacks).await;
+        // AsyncBuffered 模式下等待回调收完本次执行的全部事件再返回
+        callbacks.flush().await;
+        result
+    }
+
+    async fn react_loop(&mut self, message: &str, callbacks: &CallbackSink) -> Result<String> {
+        self.prepare_react_loop(message).await;
+        loop {
+            if let StepOutcome::FinalAnswer(answer) = self.step_round(callbacks).await? {
+                return Ok(answer);
+            }
+        }
+    }
+
+    /// 进入循环前的准备：注入相关长期记忆、追加用户消息，并重置单步状态
+    pub(crate) async fn prepare_react_loop(&mut self, message: &str) {
         let agent = self.config.agent_name.clone();
-        let callbacks = self.config.callbacks.clone();
 
         if let Some(store) = &self.store {
             let agent_name = self.config.agent_name.clone();
@@ -420,7 +1767,8 @@ impl ReactAgent {
             match store.semantic_search(&ns, message, 5).await {
                 Ok(items) if !items.is_empty() => {
                     debug!(agent = %agent, count = items.len(), "📚 注入相关长期记忆");
-                    let mut lines = vec!["[相关历史记忆]".to_string()];
+                    let locale = &self.config.locale;
+                    let mut lines = vec![locale.text(LocaleKey::MemoryRecallHeading).to_string()];
                     for (i, item) in items.iter().enumerate() {
                         let content_str = item
                             .value
@@ -430,7 +1778,7 @@ impl ReactAgent {
                             .unwrap_or_else(|| item.value.to_string());
                         lines.push(format!("{}. {}", i + 1, content_str));
                     }
-                    lines.push("[以上记忆供参考，请结合当前问题作答]".to_string());
+                    lines.push(locale.text(LocaleKey::MemoryRecallFooter).to_string());
                     self.context.push(Message::user(lines.join("\n")));
                 }
                 Ok(_) => {}
@@ -440,48 +1788,127 @@ impl ReactAgent {
             }
         }
 
+        self.apply_auto_language(message);
+        self.mark_chapter(message);
         self.context.push(Message::user(message.to_string()));
+        self.reset_budget();
+        self.step_state = Some(StepState::default());
+    }
 
-        for iteration in 0..self.config.max_iterations {
-            for cb in &callbacks {
-                cb.on_iteration(&agent, iteration).await;
-            }
+    /// 执行一轮 think + process_steps
+    ///
+    /// 得出最终答案、达到最大迭代次数或出错时结束本次执行（清除单步状态）。
+    pub(crate) async fn step_round(&mut self, callbacks: &CallbackSink) -> Result<StepOutcome> {
+        let agent = self.config.agent_name.clone();
+        let mut state = self
+            .step_state
+            .take()
+            .ok_or_else(|| ReactError::Other("没有进行中的执行，请先调用 begin".to_string()))?;
+
+        let iteration = state.iteration;
+        if iteration >= self.config.max_iterations {
+            warn!(agent = %agent, max = self.config.max_iterations, "达到最大迭代次数");
+            return Err(ReactError::from(AgentError::MaxIterationsExceeded(
+                self.config.max_iterations,
+            )));
+        }
+        state.iteration += 1;
 
-            debug!(agent = %agent, iteration = iteration + 1, "--- 迭代 ---");
+        callbacks.emit(|| CallbackEvent::Iteration(iteration)).await;
+        self.check_iteration_budget(iteration).await;
 
-            let steps = self.think().await?;
-            if steps.is_empty() {
-                warn!(agent = %agent, "LLM 没有响应");
-                return Err(ReactError::from(AgentError::NoResponse));
-            }
+        debug!(agent = %agent, iteration = iteration + 1, "--- 迭代 ---");
 
-            if let Some(answer) = self.process_steps(steps).await? {
-                for cb in &callbacks {
-                    cb.on_final_answer(&agent, &answer).await;
-                }
-                info!(agent = %agent, "🏁 执行完毕");
+        let steps = self.think().await?;
+        if steps.is_empty() {
+            warn!(agent = %agent, "LLM 没有响应");
+            return Err(ReactError::from(AgentError::NoResponse));
+        }
 
-                if let (Some(cp), Some(tid)) = (&self.checkpointer, self.config.session_id.clone())
-                {
-                    let messages = self.context.messages().to_vec();
-                    match cp.put(&tid, messages).await {
-                        Ok(cid) => {
-                            debug!(agent = %agent, session_id = %tid, checkpoint_id = %cid, "🔖 Checkpoint 已保存")
-                        }
-                        Err(e) => {
-                            warn!(agent = %agent, error = %e, "⚠️ Checkpoint 保存失败")
-                        }
-                    }
-                }
+        let thought = steps.iter().rev().find_map(|step| match step {
+            StepType::Thought(content) if !content.is_empty() => Some(content.clone()),
+            _ => None,
+        });
+        let calls: Vec<(String, Value)> = steps
+            .iter()
+            .filter_map(|step| match step {
+                StepType::Call {
+                    function_name,
+                    arguments,
+                    ..
+                } => Some((function_name.clone(), arguments.clone())),
+                StepType::Thought(_) => None,
+            })
+            .collect();
+
+        let Some(answer) = self.process_steps(steps).await? else {
+            self.step_state = Some(state);
+            return Ok(if calls.is_empty() {
+                StepOutcome::Idle
+            } else {
+                StepOutcome::ToolCalls { thought, calls }
+            });
+        };
 
-                return Ok(answer);
+        if self.begin_reflection(
+            &answer,
+            &mut state.previous_answer,
+            &mut state.reflection_rounds,
+            state.iteration,
+        ) {
+            self.step_state = Some(state);
+            return Ok(StepOutcome::Reflecting(answer));
+        }
+
+        callbacks
+            .emit(|| CallbackEvent::FinalAnswer(answer.clone()))
+            .await;
+        info!(agent = %agent, "🏁 执行完毕");
+
+        if let (Some(cp), Some(tid)) = (&self.checkpointer, self.config.session_id.clone()) {
+            let messages = self.context.messages().to_vec();
+            match cp.put(&tid, messages).await {
+                Ok(cid) => {
+                    debug!(agent = %agent, session_id = %tid, checkpoint_id = %cid, "🔖 Checkpoint 已保存")
+                }
+                Err(e) => {
+                    warn!(agent = %agent, error = %e, "⚠️ Checkpoint 保存失败")
+                }
             }
         }
 
-        warn!(agent = %agent, max = self.config.max_iterations, "达到最大迭代次数");
-        Err(ReactError::from(AgentError::MaxIterationsExceeded(
-            self.config.max_iterations,
-        )))
+        Ok(StepOutcome::FinalAnswer(answer))
+    }
+
+    /// 自我反思：答案与上一轮不同且还有余量时，注入反思提示要求 LLM 再检查一轮
+    ///
+    /// `used_iterations` 为已消耗的迭代数；返回 `true` 表示已进入下一轮反思，
+    /// 调用方不应再把 `answer` 当作最终答案返回。
+    fn begin_reflection(
+        &mut self,
+        answer: &str,
+        previous_answer: &mut Option<String>,
+        rounds: &mut usize,
+        used_iterations: usize,
+    ) -> bool {
+        let reflection = self.config.reflection;
+        let stable = previous_answer
+            .as_deref()
+            .is_some_and(|prev| prev.trim() == answer.trim());
+        if !reflection.enabled
+            || stable
+            || *rounds >= reflection.max_rounds
+            || used_iterations >= self.config.max_iterations
+        {
+            return false;
+        }
+        *rounds += 1;
+        let round = *rounds;
+        info!(agent = %self.config.agent_name, round, "🪞 自我反思第 {round} 轮");
+        *previous_answer = Some(answer.to_string());
+        let prompt = self.config.locale.text(LocaleKey::ReflectionPrompt);
+        self.context.push(Message::user(prompt.to_string()));
+        true
     }
 
     // ── 流式执行公共方法 ─────────────────────────────────────────────────────────
@@ -491,37 +1918,10 @@ impl ReactAgent {
     /// 根据模式决定是否重置上下文、是否从 checkpoint 恢复
     pub(crate) async fn prepare_stream_context(&mut self, mode: StreamMode, input: &str) {
         match mode {
-            StreamMode::Execute => {
-                self.reset_messages();
-
-                // 从 checkpoint 恢复（如果存在）
-                if let (Some(cp), Some(tid)) = (&self.checkpointer, &self.config.session_id) {
-                    match cp.get(tid).await {
-                        Ok(Some(checkpoint)) => {
-                            info!(
-                                agent = %self.config.agent_name,
-                                session_id = %tid,
-                                "🔄 从 Checkpoint 恢复会话（流式）"
-                            );
-                            self.context.clear();
-                            for msg in checkpoint.messages {
-                                self.context.push(msg);
-                            }
-                        }
-                        Ok(None) => {}
-                        Err(e) => {
-                            tracing::warn!(
-                                agent = %self.config.agent_name,
-                                error = %e,
-                                "⚠️ Checkpoint 加载失败"
-                            );
-                        }
-                    }
-                }
-            }
-            StreamMode::Chat => {
-                // 多轮对话模式：不重置上下文
-            }
+            // 与 execute 相同：从 checkpoint 恢复，否则开始新会话
+            StreamMode::Execute => self.prepare_direct_context().await,
+            // 多轮对话模式：不重置上下文
+            StreamMode::Chat => self.inject_cross_session_summary_for_chat().await,
         }
 
         // 注入相关长期记忆
@@ -531,7 +1931,8 @@ impl ReactAgent {
             if let Ok(items) = store.semantic_search(&ns, input, 5).await
                 && !items.is_empty()
             {
-                let mut lines = vec!["[相关历史记忆]".to_string()];
+                let locale = &self.config.locale;
+                let mut lines = vec![locale.text(LocaleKey::MemoryRecallHeading).to_string()];
                 for (i, item) in items.iter().enumerate() {
                     let content_str = item
                         .value
@@ -541,12 +1942,14 @@ impl ReactAgent {
                         .unwrap_or_else(|| item.value.to_string());
                     lines.push(format!("{}. {}", i + 1, content_str));
                 }
-                lines.push("[以上记忆供参考，请结合当前问题作答]".to_string());
+                lines.push(locale.text(LocaleKey::MemoryRecallFooter).to_string());
                 self.context.push(Message::user(lines.join("\n")));
             }
         }
 
         // 推送用户消息
+        self.apply_auto_language(input);
+        self.mark_chapter(input);
         self.context.push(Message::user(input.to_string()));
     }
 
@@ -554,63 +1957,60 @@ impl ReactAgent {
     pub(crate) async fn create_llm_stream(
         &mut self,
         messages: Vec<Message>,
+        model_name: String,
     ) -> Result<BoxStream<'static, Result<crate::llm::types::ChatCompletionChunk>>> {
-        let agent = &self.config.agent_name;
         let tools_for_stream: Option<Vec<_>> = if self.config.enable_tool {
-            let tools = self.tool_manager.get_openai_tools();
+            let tools = self.visible_tools();
             if tools.is_empty() { None } else { Some(tools) }
         } else {
             None
         };
+        // 未携带工具定义时不能发送 tool_choice
+        let tool_choice = self
+            .take_tool_choice()
+            .filter(|_| tools_for_stream.is_some());
 
+        let agent = self.config.agent_name.clone();
         let max_retries = self.config.llm_max_retries;
         let retry_delay = self.config.llm_retry_delay_ms;
-        let client = self.client.clone();
-        let model_name = self.config.model_name.clone();
         let response_format = self.config.response_format.clone();
 
         info!(agent = %agent, model = %model_name, "📡 创建 LLM 流式请求");
 
-        let mut stream_result: Result<_> = Err(ReactError::Agent(AgentError::NoResponse));
-        for attempt in 0..=max_retries {
-            if attempt > 0 {
-                let delay_ms = retry_delay * (1u64 << (attempt - 1).min(5));
-                warn!(
-                    agent = %agent,
-                    attempt,
-                    max = max_retries,
-                    delay_ms,
-                    "⚠️ 流式 LLM 请求失败，{delay_ms}ms 后重试（{attempt}/{max_retries}）"
-                );
-                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-            }
-            stream_result = stream_chat(
-                client.clone(),
-                &model_name,
-                messages.clone(),
-                Some(0.7),
-                Some(8192u32),
-                tools_for_stream.clone(),
-                None,
-                response_format.clone(),
-            )
-            .await;
-            match &stream_result {
-                Ok(_) => {
-                    if attempt > 0 {
-                        info!(agent = %agent, attempt, "✅ 流式 LLM 重试成功");
-                    }
-                    break;
-                }
-                Err(e) if attempt < max_retries && is_retryable_llm_error(e) => {
-                    warn!(agent = %agent, error = %e, "流式 LLM 可重试错误");
+        let request = ChatRequest {
+            messages,
+            temperature: Some(self.config.temperature),
+            max_tokens: Some(8192),
+            tools: tools_for_stream,
+            tool_choice,
+            response_format,
+            options: ChatOptions {
+                seed: self.config.seed,
+                ..Default::default()
+            },
+        };
+
+        // 自定义客户端的流借用客户端本身，连接（含重试）放进流内完成，错误随首个元素返回
+        if let Some(llm) = self.llm_client.clone() {
+            let mut request = request;
+            request.options.model =
+                (model_name != self.config.model_name || self.pin_model).then_some(model_name);
+            return Ok(Box::pin(async_stream::try_stream! {
+                let mut stream = retry_llm_request(&agent, max_retries, retry_delay, || {
+                    llm.chat_stream(request.clone())
+                })
+                .await?;
+                while let Some(chunk) = stream.next().await {
+                    yield chunk?.into_completion_chunk();
                 }
-                Err(_) => break,
-            }
+            }));
         }
 
-        // 将 impl Stream 转换为 BoxStream<'static, ...>
-        let stream = stream_result?;
+        let client = self.client.clone();
+        let stream = retry_llm_request(&agent, max_retries, retry_delay, || {
+            stream_chat(client.clone(), &model_name, request.clone())
+        })
+        .await?;
         Ok(Box::pin(stream))
     }
 
@@ -658,8 +2058,27 @@ impl ReactAgent {
         event
     }
 
+    /// 按工具的参数 schema 宽松解析 LLM 给出的参数，详见 [`coerce_tool_arguments`]
+    fn coerce_tool_arguments(
+        &self,
+        tool_name: &str,
+        raw: &str,
+    ) -> std::result::Result<Value, CoerceError> {
+        let schema = self
+            .tool_manager
+            .get_tool(tool_name)
+            .map(|t| t.parameters());
+        coerce_tool_arguments(
+            tool_name,
+            raw,
+            schema.as_ref(),
+            self.config.coerce_options(),
+        )
+    }
+
     /// 将收集的 tool_call_map 转换为结构化的工具调用列表
     pub(crate) fn build_tool_calls_from_map(
+        &self,
         tool_call_map: &HashMap<u32, (String, String, String)>,
     ) -> (Vec<LlmToolCall>, Vec<(String, String, Value)>) {
         let mut sorted_indices: Vec<u32> = tool_call_map.keys().cloned().collect();
@@ -670,8 +2089,10 @@ impl ReactAgent {
 
         for idx in &sorted_indices {
             let (id, name, args_str) = &tool_call_map[idx];
-            let args: Value =
-                serde_json::from_str(args_str).unwrap_or(Value::Object(Default::default()));
+            let id = tool_call_id_or_generate(id, *idx);
+            let args: Value = self
+                .coerce_tool_arguments(name, args_str)
+                .unwrap_or(Value::Object(Default::default()));
 
             msg_tool_calls.push(LlmToolCall {
                 id: id.clone(),
@@ -681,7 +2102,7 @@ impl ReactAgent {
                     arguments: args_str.clone(),
                 },
             });
-            steps.push((id.clone(), name.clone(), args));
+            steps.push((id, name.clone(), args));
         }
 
         (msg_tool_calls, steps)
@@ -712,53 +2133,83 @@ impl ReactAgent {
         mode: StreamMode,
     ) -> Result<futures::stream::BoxStream<'_, Result<AgentEvent>>> {
         let input = input.to_string();
-        let stream = async_stream::try_stream! {
+        let sink = self.callback_sink();
+        let trace = self.trace.clone();
+        let inner = async_stream::try_stream! {
             let agent = self.config.agent_name.clone();
-            let callbacks = self.config.callbacks.clone();
+            let callbacks = self.callback_sink();
 
             // 初始化上下文
+            self.apply_pending_skill_reloads().await;
             self.prepare_stream_context(mode, &input).await;
+            self.reset_budget();
+            self.reset_execution_record();
+            self.start_trace(&input);
+            // 与 execute 一致：规划模式下每次执行从空任务列表开始，首轮强制调用 plan 工具
+            if mode == StreamMode::Execute && self.has_planning_tools() {
+                self.begin_planning()?;
+            }
+            let mut task_updates = self.subscribe_task_updates();
 
             // 根据模式输出不同的日志
             match mode {
                 StreamMode::Execute => info!(agent = %agent, "🌊 Agent 开始流式执行任务"),
                 StreamMode::Chat => info!(agent = %agent, "🌊 Agent 开始流式多轮对话"),
             }
+            let mut previous_answer = None;
+            let mut reflection_rounds = 0;
 
             for iteration in 0..self.config.max_iterations {
-                for cb in &callbacks {
-                    cb.on_iteration(&agent, iteration).await;
-                }
+                callbacks.emit(|| CallbackEvent::Iteration(iteration)).await;
+                self.check_iteration_budget(iteration).await;
 
                 debug!(agent = %agent, iteration = iteration + 1, "--- 流式迭代 ---");
+                self.check_token_budget()?;
+                self.inject_wrap_up_hint();
 
-                let messages = self.context.prepare(None).await?;
+                let query = self.current_query();
+                let mut messages = self.context.prepare(query.as_deref()).await?;
+                let model_name = self.apply_pre_iteration_hook(&mut messages);
 
-                for cb in &callbacks {
-                    cb.on_think_start(&agent, &messages).await;
-                }
+                callbacks.think_start(&messages).await;
 
                 // 创建 LLM 流
-                let llm_stream = self.create_llm_stream(messages.clone()).await?;
+                self.trace_begin_iteration();
+                let llm_start = Instant::now();
+                let llm_stream = self.create_llm_stream(messages.clone(), model_name).await?;
+                self.iteration_count += 1;
                 let mut llm_stream = Box::pin(llm_stream);
 
                 // 收集流式响应
                 let mut content_buffer = String::new();
                 let mut tool_call_map: HashMap<u32, (String, String, String)> = HashMap::new();
+                let mut fingerprint = None;
 
                 while let Some(chunk_result) = llm_stream.next().await {
                     let chunk = chunk_result?;
+                    if let Some(fp) = chunk.system_fingerprint() {
+                        fingerprint = Some(fp.to_string());
+                    }
                     if let Some(event) = Self::process_stream_chunk(&chunk, &mut content_buffer, &mut tool_call_map) {
                         yield event;
                     }
                 }
+                self.trace_llm_call(llm_start, 1, Ok(None));
+                self.observe_system_fingerprint(fingerprint.as_deref());
+                // 流式响应不含 usage，按文本估算
+                let reply: String = tool_call_map
+                    .values()
+                    .map(|(_, _, args)| args.as_str())
+                    .chain([content_buffer.as_str()])
+                    .collect();
+                self.record_token_usage(None, &messages, &reply).await;
 
                 // 判断是否有工具调用
                 let has_tool_calls = !tool_call_map.is_empty();
 
                 if has_tool_calls {
                     // 构建工具调用
-                    let (msg_tool_calls, steps) = Self::build_tool_calls_from_map(&tool_call_map);
+                    let (msg_tool_calls, steps) = self.build_tool_calls_from_map(&tool_call_map);
 
                     // 发出 ToolCall 事件
                     for (_, name, args) in &steps {
@@ -768,43 +2219,90 @@ impl ReactAgent {
                         };
                     }
 
+                    // 推理文本与工具调用并存时，文本已作为 Token 流式输出，这里一并记录
+                    let thought = Some(content_buffer.clone()).filter(|c| !c.trim().is_empty());
+
                     // 触发 on_think_end 回调
                     {
-                        let think_steps: Vec<StepType> = steps.iter().map(|(id, name, args)| {
-                            StepType::Call {
+                        let think_steps: Vec<StepType> = thought
+                            .iter()
+                            .map(|c| StepType::Thought(c.clone()))
+                            .chain(steps.iter().map(|(id, name, args)| StepType::Call {
                                 tool_call_id: id.clone(),
                                 function_name: name.clone(),
                                 arguments: args.clone(),
-                            }
-                        }).collect();
-                        for cb in &callbacks {
-                            cb.on_think_end(&agent, &think_steps).await;
-                        }
+                            }))
+                            .collect();
+                        callbacks.emit(|| CallbackEvent::ThinkEnd(think_steps)).await;
                     }
 
-                    // 将 assistant 消息推送到上下文
-                    self.context.push(Message::assistant_with_tools(msg_tool_calls));
+                    // 将 assistant 消息（含推理文本）推送到上下文
+                    let mut assistant_msg = Message::assistant_with_tools(msg_tool_calls);
+                    assistant_msg.content = thought;
+                    self.context.push(assistant_msg);
+                    let round_start = self.context.messages().len();
+                    self.record_tool_calls(steps.len()).await;
+
+                    // 副作用操作超过预算且未获确认的调用直接回传拒绝信息
+                    let mut rejected = self.guard_side_effects(&steps).await?;
+                    for (index, output) in &rejected {
+                        yield AgentEvent::ToolResult { name: steps[*index].1.clone(), output: output.clone() };
+                    }
 
-                    // 执行工具调用并 yield 事件
+                    // 按依赖顺序执行工具调用并 yield 事件，`$tool_N.output` 引用替换为前序输出
+                    let mut completed: Vec<usize> = rejected.iter().map(|&(index, _)| index).collect();
+                    let order = runnable_waves(&steps, &completed).concat();
+                    let mut outputs: Vec<Option<String>> = vec![None; steps.len()];
                     let mut done = false;
-                    for (tool_call_id, function_name, arguments) in steps {
-                        let result = self.execute_tool_feedback(&function_name, &arguments).await?;
+                    let mut reflecting = false;
+                    for index in order {
+                        let (tool_call_id, function_name, arguments) = steps[index].clone();
+                        let arguments = substitute(&arguments, &outputs);
+                        // 流式输出工具的片段边执行边回传，完整输出仍在结束后写入上下文
+                        let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel();
+                        let (result, timing) = {
+                            let execution = self.execute_tool_timed(&tool_call_id, &function_name, &arguments, Some(&chunk_tx));
+                            let mut execution = std::pin::pin!(execution);
+                            loop {
+                                let next = tokio::select! {
+                                    biased;
+                                    Some(chunk) = chunk_rx.recv() => Err(chunk),
+                                    done = &mut execution => Ok(done),
+                                };
+                                match next {
+                                    Ok(done) => break done,
+                                    Err(chunk) => yield AgentEvent::ToolResultChunk { name: function_name.clone(), chunk },
+                                }
+                            }
+                        };
+                        while let Ok(chunk) = chunk_rx.try_recv() {
+                            yield AgentEvent::ToolResultChunk { name: function_name.clone(), chunk };
+                        }
+                        self.trace_tool_call(&tool_call_id, &function_name, &arguments, timing, &result);
+                        let result = result?;
+                        self.record_tool_result(&tool_call_id, &function_name, &arguments, &result);
 
                         yield AgentEvent::ToolResult {
                             name: function_name.clone(),
                             output: result.clone(),
                         };
+                        for update in task_updates.drain() {
+                            yield update;
+                        }
 
-                        self.context.push(Message::tool_result(
-                            tool_call_id,
-                            function_name.clone(),
-                            result.clone(),
-                        ));
-
-                        if function_name == TOOL_FINAL_ANSWER {
-                            for cb in &callbacks {
-                                cb.on_final_answer(&agent, &result).await;
+                        outputs[index] = Some(result.clone());
+                        completed.push(index);
+                        if self.is_final_answer(&function_name) {
+                            let mut outputs = std::mem::take(&mut outputs);
+                            fill_rejected(&mut outputs, std::mem::take(&mut rejected));
+                            self.push_round_results(&steps, outputs, &completed);
+                            self.merge_round_tool_results(round_start);
+                            if self.begin_reflection(&result, &mut previous_answer, &mut reflection_rounds, iteration + 1) {
+                                reflecting = true;
+                                break;
                             }
+                            callbacks.emit(|| CallbackEvent::FinalAnswer(result.clone())).await;
+                            callbacks.flush().await;
                             info!(agent = %agent, "🏁 流式执行完成");
 
                             // Chat 模式保存 checkpoint
@@ -812,43 +2310,168 @@ impl ReactAgent {
                                 self.save_checkpoint().await;
                             }
 
-                            yield AgentEvent::FinalAnswer(result);
+                            self.finish_trace(None);
+                            yield AgentEvent::FinalAnswer(self.decorate_answer(result));
                             done = true;
                             break;
                         }
                     }
+                    if !done && !reflecting {
+                        fill_rejected(&mut outputs, rejected);
+                        self.push_round_results(&steps, outputs, &completed);
+                    }
+                    self.merge_round_tool_results(round_start);
 
                     if done {
                         return;
                     }
                 } else if !content_buffer.is_empty() {
-                    // 纯文本响应
-                    let think_steps = vec![StepType::Thought(content_buffer.clone())];
-                    for cb in &callbacks {
-                        cb.on_think_end(&agent, &think_steps).await;
-                    }
-                    for cb in &callbacks {
-                        cb.on_final_answer(&agent, &content_buffer).await;
-                    }
+                    // 纯文本响应：先经输出后处理器，再写入上下文与回调
+                    let content_buffer = self.apply_output_processors(content_buffer);
+                    callbacks
+                        .emit(|| CallbackEvent::ThinkEnd(vec![StepType::Thought(content_buffer.clone())]))
+                        .await;
                     self.context.push(Message::assistant(content_buffer.clone()));
+                    if self.begin_reflection(&content_buffer, &mut previous_answer, &mut reflection_rounds, iteration + 1) {
+                        continue;
+                    }
+                    callbacks.emit(|| CallbackEvent::FinalAnswer(content_buffer.clone())).await;
+                    callbacks.flush().await;
 
                     // Chat 模式保存 checkpoint
                     if mode == StreamMode::Chat {
                         self.save_checkpoint().await;
                     }
 
-                    yield AgentEvent::FinalAnswer(content_buffer);
+                    self.finish_trace(None);
+                    yield AgentEvent::FinalAnswer(self.decorate_answer(content_buffer));
                     return;
                 } else {
                     Err(ReactError::Agent(AgentError::NoResponse))?;
                 }
             }
 
-            Err(ReactError::Agent(AgentError::MaxIterationsExceeded(
+            let err = ReactError::Agent(AgentError::MaxIterationsExceeded(
                 self.config.max_iterations,
-            )))?;
+            ));
+            self.finish_trace(Some(&err));
+            Err(err)?;
+        };
+
+        // 出错时流随之结束：链路以该错误收尾，AsyncBuffered 模式下同样等待回调收完已入队的事件
+        let stream = async_stream::stream! {
+            let mut inner = std::pin::pin!(inner);
+            while let Some(item) = inner.next().await {
+                if let Err(e) = &item {
+                    finish_trace(&trace, Some(e));
+                    sink.flush().await;
+                }
+                yield item;
+            }
         };
 
         Ok(Box::pin(stream))
     }
 }
+
+/// 建立流式 LLM 请求，可重试错误（网络 / 限流 / 5xx）按指数退避重试至多 `max_retries` 次
+async fn retry_llm_request<T, F, Fut>(
+    agent: &str,
+    max_retries: usize,
+    retry_delay: u64,
+    mut connect: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match connect().await {
+            Ok(stream) => {
+                if attempt > 0 {
+                    info!(agent = %agent, attempt, "✅ 流式 LLM 重试成功");
+                }
+                return Ok(stream);
+            }
+            Err(e) if attempt < max_retries && is_retryable_llm_error(&e) => {
+                warn!(agent = %agent, error = %e, "流式 LLM 可重试错误");
+                attempt += 1;
+                let delay_ms = retry_delay * (1u64 << (attempt - 1).min(5));
+                warn!(
+                    agent = %agent,
+                    attempt,
+                    max = max_retries,
+                    delay_ms,
+                    "⚠️ 流式 LLM 请求失败，{delay_ms}ms 后重试（{attempt}/{max_retries}）"
+                );
+                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 以 `error`（`None` 表示成功）结束当前链路；已结束的链路不受影响
+fn finish_trace(trace: &Mutex<Option<TraceRecorder>>, error: Option<&ReactError>) {
+    if let Some(trace) = trace.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        trace.finish(error.map(|e| e.to_string()));
+    }
+}
+
+/// 一次 LLM 请求消耗的 token 数：优先取服务端 usage，缺失时按请求消息与响应文本估算
+pub(super) fn usage_tokens(usage: Option<&Usage>, messages: &[Message], reply: &str) -> usize {
+    let reported = usage.and_then(|u| {
+        u.total_tokens
+            .or(match (u.prompt_tokens, u.completion_tokens) {
+                (None, None) => None,
+                (prompt, completion) => Some(prompt.unwrap_or(0) + completion.unwrap_or(0)),
+            })
+    });
+    match reported {
+        Some(tokens) => tokens as usize,
+        None => ContextManager::estimate_tokens(messages) + estimate_text_tokens(reply),
+    }
+}
+
+/// 按 `$tool_N.output` 依赖分批，剔除 `skipped` 中的调用
+///
+/// 分批基于完整的调用列表，下标与 LLM 书写引用时的编号一致；引用被剔除调用的参数保持原样。
+fn runnable_waves(calls: &[(String, String, Value)], skipped: &[usize]) -> Vec<Vec<usize>> {
+    plan_waves(&calls.iter().map(|(_, _, args)| args).collect::<Vec<_>>())
+        .into_iter()
+        .map(|wave| {
+            wave.into_iter()
+                .filter(|i| !skipped.contains(i))
+                .collect::<Vec<_>>()
+        })
+        .filter(|wave| !wave.is_empty())
+        .collect()
+}
+
+/// 把被拒绝调用的观测值填入本轮结果，随其余结果一起写回上下文
+fn fill_rejected(outputs: &mut [Option<String>], rejected: Vec<(usize, String)>) {
+    for (index, output) in rejected {
+        outputs[index] = Some(output);
+    }
+}
+
+/// 服务端未返回工具调用 ID（缺失或为空）时生成 `call_<index>_<uuid>`，否则沿用原值
+///
+/// 生成的 ID 同时写入 assistant 消息的 tool_calls 与对应的 tool 消息，保证服务端能配对工具结果。
+fn tool_call_id_or_generate(id: &str, index: u32) -> String {
+    if !id.is_empty() {
+        return id.to_string();
+    }
+    let generated = format!("call_{index}_{}", uuid::Uuid::new_v4().simple());
+    debug!(tool_call_id = %generated, "🆔 工具调用缺少 ID，已生成兜底 ID");
+    generated
+}
+
+/// 把工具参数 JSON 转成 [`ToolParameters`]，非对象时为空
+fn to_tool_parameters(input: &Value) -> ToolParameters {
+    match input {
+        Value::Object(map) => map.clone().into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
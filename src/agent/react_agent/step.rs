@@ -0,0 +1,84 @@
+//! 单步执行
+//!
+//! 像调试器一样逐轮推进 ReAct 循环：[`begin`](ReactAgent::begin) 准备上下文，
+//! 之后每次 [`step`](ReactAgent::step) 只执行一轮 think + process_steps 并返回本轮结果。
+//! 两次 step 之间可以检查、修改上下文（如 [`get_messages`](ReactAgent::get_messages)、
+//! [`add_message`](ReactAgent::add_message)）后再决定是否继续。`execute` 内部同样逐轮调用
+//! 这一实现，迭代计数、自我反思与结束条件与 `execute` 一致。
+
+use super::ReactAgent;
+use crate::error::{ReactError, Result};
+use serde_json::Value;
+
+/// 单步执行一轮的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutcome {
+    /// 本轮发起了工具调用，观察结果已写入上下文
+    ToolCalls {
+        /// 与工具调用同一响应中的思考文本
+        thought: Option<String>,
+        /// 按调用顺序排列的（工具名, 参数）
+        calls: Vec<(String, Value)>,
+    },
+    /// 给出了答案，但开启了自我反思，下一步将复查该答案
+    Reflecting(String),
+    /// 本轮既没有工具调用也没有答案（如 LLM 返回空文本），执行继续
+    Idle,
+    /// 得出最终答案，本次执行结束
+    FinalAnswer(String),
+}
+
+/// 进行中的执行在两轮之间保留的循环状态
+#[derive(Debug, Default)]
+pub(crate) struct StepState {
+    /// 已执行的轮数
+    pub(crate) iteration: usize,
+    /// 已进行的自我反思轮数
+    pub(crate) reflection_rounds: usize,
+    /// 上一轮反思前的答案，答案不再变化时结束反思
+    pub(crate) previous_answer: Option<String>,
+}
+
+impl ReactAgent {
+    /// 开始一次单步执行：与 `execute` 相同地重置或恢复上下文并追加任务，但不进入循环
+    ///
+    /// 总是直接执行，不进入规划模式。之前未结束的执行会被丢弃。
+    pub async fn begin(&mut self, task: &str) {
+        self.apply_pending_skill_reloads().await;
+        self.reset_execution_record();
+        self.start_trace(task);
+        self.prepare_direct_context().await;
+        self.prepare_react_loop(task).await;
+    }
+
+    /// 执行一轮 think + process_steps，返回本轮发生了什么
+    ///
+    /// 返回 [`StepOutcome::FinalAnswer`] 或错误（包括达到 `max_iterations`）后执行结束，
+    /// 再次调用前需重新 [`begin`](Self::begin)；未开始时返回错误。
+    pub async fn step(&mut self) -> Result<StepOutcome> {
+        if !self.is_stepping() {
+            return Err(ReactError::Other(
+                "没有进行中的执行，请先调用 begin".to_string(),
+            ));
+        }
+        let callbacks = self.callback_sink();
+        let outcome = self.step_round(&callbacks).await;
+        callbacks.flush().await;
+        match outcome {
+            Ok(StepOutcome::FinalAnswer(answer)) => {
+                self.finish_trace(None);
+                Ok(StepOutcome::FinalAnswer(self.decorate_answer(answer)))
+            }
+            Ok(outcome) => Ok(outcome),
+            Err(e) => {
+                self.finish_trace(Some(&e));
+                Err(e)
+            }
+        }
+    }
+
+    /// 是否有进行中、尚未结束的执行
+    pub fn is_stepping(&self) -> bool {
+        self.step_state.is_some()
+    }
+}
@@ -0,0 +1,142 @@
+//! 会话标题生成
+//!
+//! 根据当前对话请求 LLM 生成一个简短标题，用于保存会话（[`Checkpoint::title`]）。
+//! LLM 调用失败或返回空内容时退化为首条用户消息的前若干字符。
+//!
+//! [`Checkpoint::title`]: crate::memory::checkpointer::Checkpoint::title
+
+use super::ReactAgent;
+use super::run::usage_tokens;
+use crate::agent::LocaleKey;
+use crate::error::{ReactError, Result};
+use crate::llm::types::Message;
+use crate::llm::{ChatOptions, ChatRequest, chat};
+use std::sync::atomic::Ordering;
+use tracing::warn;
+
+/// 标题最大字符数（LLM 返回过长或退化为截断时使用）
+const MAX_TITLE_CHARS: usize = 20;
+/// 提交给 LLM 的最大消息条数（取对话开头）
+const MAX_TITLE_MESSAGES: usize = 6;
+/// 每条消息提交给 LLM 的最大字符数
+const MAX_MESSAGE_CHARS: usize = 300;
+
+const TITLE_PROMPT: &str = "根据下面的对话生成一个简短的标题（不超过 15 个字），\
+概括用户的主要意图。只输出标题本身，不要加引号、标点或任何解释。";
+
+impl ReactAgent {
+    /// 为当前对话生成简短标题
+    ///
+    /// 只取用户与助手的文本消息交给 LLM；LLM 失败时退化为首条用户消息的前
+    /// 20 个字符，对话为空时返回 locale 中的默认标题（zh-CN 为“新对话”）。
+    pub async fn generate_title(&self) -> Result<String> {
+        let conversation: Vec<(&str, &str)> = self
+            .context
+            .messages()
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .filter_map(|m| {
+                let content = m.content.as_deref()?.trim();
+                (!content.is_empty()).then_some((m.role.as_str(), content))
+            })
+            .collect();
+        let Some(&(_, first_user)) = conversation.iter().find(|(role, _)| *role == "user") else {
+            return Ok(self.config.locale.text(LocaleKey::DefaultTitle).to_string());
+        };
+
+        let transcript = conversation
+            .iter()
+            .take(MAX_TITLE_MESSAGES)
+            .map(|(role, content)| format!("{role}: {}", truncate(content, MAX_MESSAGE_CHARS)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let messages = vec![
+            Message::system(TITLE_PROMPT.to_string()),
+            Message::user(transcript),
+        ];
+
+        match self.request_text(messages, 0.3, 64).await {
+            Ok(raw) => match clean_title(&raw) {
+                Some(title) => Ok(title),
+                None => Ok(truncate(first_user, MAX_TITLE_CHARS)),
+            },
+            Err(e) => {
+                warn!(agent = %self.config.agent_name, error = %e, "⚠️ 标题生成失败，使用首条用户消息");
+                Ok(truncate(first_user, MAX_TITLE_CHARS))
+            }
+        }
+    }
+
+    /// 发送一次无工具的辅助请求（标题、跨会话摘要、工具输出抽取共用），返回文本内容
+    ///
+    /// 配置了自定义 [`LlmClient`](crate::llm::LlmClient) 时经由它发送，否则使用内置客户端。
+    /// 消耗的 token 计入运行指标与 `max_total_tokens` 预算。
+    pub(super) async fn request_text(
+        &self,
+        messages: Vec<Message>,
+        temperature: f32,
+        max_tokens: u32,
+    ) -> Result<String> {
+        let request = ChatRequest {
+            messages: messages.clone(),
+            temperature: Some(temperature),
+            max_tokens: Some(max_tokens),
+            options: ChatOptions {
+                seed: self.config.seed,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let response = match &self.llm_client {
+            Some(llm) => llm.chat(request).await?.into_completion(),
+            None => chat(self.client.clone(), &self.config.model_name, request).await?,
+        };
+        let text = response
+            .choices
+            .first()
+            .and_then(|c| c.message.content.clone());
+        let tokens = usage_tokens(
+            response.usage(),
+            &messages,
+            text.as_deref().unwrap_or_default(),
+        );
+        self.helper_tokens.fetch_add(tokens, Ordering::Relaxed);
+        self.metrics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .add_tokens(tokens);
+        text.ok_or_else(|| ReactError::Other("LLM 返回空内容".to_string()))
+    }
+}
+
+/// 取首行，去掉首尾空白、引号与句末标点；结果为空时返回 `None`
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .strip_prefix("标题：")
+        .or_else(|| line.strip_prefix("标题:"))
+        .unwrap_or(line);
+    let title = line
+        .trim_matches(|c: char| c.is_whitespace() || "\"'“”‘’「」《》`*#".contains(c))
+        .trim_end_matches(['。', '.', '！', '!', '？', '?']);
+    (!title.is_empty()).then(|| truncate(title, MAX_TITLE_CHARS))
+}
+
+pub(super) fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(clean_title("“北京天气查询”\n"), Some("北京天气查询".into()));
+        assert_eq!(
+            clean_title("标题：Rust 所有权。"),
+            Some("Rust 所有权".into())
+        );
+        assert_eq!(clean_title("  \n \"\" "), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! 多配置对比执行（A/B 测试）
+//!
+//! 调 prompt 或换模型时，用 [`ReactAgent::execute_variants`] 让同一任务在不同配置下各跑一遍，
+//! 返回各自的 [`ExecutionResult`] 供对比。变体共享 Agent 已注册的工具，每个变体从空白上下文开始。
+
+use super::ReactAgent;
+use super::guard::RestoreGuard;
+use crate::agent::ExecutionResult;
+use crate::error::Result;
+use tracing::info;
+
+/// 一个对比变体：未设置的项沿用 Agent 当前配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariantConfig {
+    /// 变体名称，用于日志
+    pub name: String,
+    /// 覆盖模型名
+    pub model: Option<String>,
+    /// 覆盖基础 system prompt（Skill / CoT 等片段保持不变）
+    pub system_prompt: Option<String>,
+    /// 覆盖采样温度
+    pub temperature: Option<f32>,
+}
+
+impl VariantConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+}
+
+impl ReactAgent {
+    /// 对同一任务按每个变体的配置各执行一次，按变体顺序返回各自的结果
+    ///
+    /// 变体依次执行（避免并发请求触发 LLM 限流），每个变体与 [`execute_rich`](Self::execute_rich)
+    /// 一样从空白上下文开始；某个变体失败不影响其余变体。全部跑完（或中途被取消）后恢复原有的模型、
+    /// system prompt、温度与对话历史。
+    pub async fn execute_variants(
+        &mut self,
+        task: &str,
+        variants: Vec<VariantConfig>,
+    ) -> Vec<Result<ExecutionResult>> {
+        let model_name = self.config.model_name.clone();
+        let system_prompt = self.config.system_prompt.clone();
+        let temperature = self.config.temperature;
+        let mut saved = Some((
+            model_name.clone(),
+            system_prompt.clone(),
+            self.context.messages().to_vec(),
+        ));
+        // 执行出错或 future 被丢弃（超时、取消）时同样恢复原配置与对话历史
+        let mut agent = RestoreGuard::new(self, move |agent| {
+            let Some((model_name, system_prompt, history)) = saved.take() else {
+                return;
+            };
+            agent.config.model_name = model_name;
+            agent.pin_model = false;
+            agent.config.system_prompt = system_prompt.clone();
+            agent.context.update_system(system_prompt);
+            agent.config.temperature = temperature;
+            agent.context.clear();
+            agent.context.push_many(history);
+        });
+
+        let mut results = Vec::with_capacity(variants.len());
+        for variant in variants {
+            info!(agent = %agent.config.agent_name, variant = %variant.name, "🧪 执行对比变体");
+            agent.config.model_name = variant.model.clone().unwrap_or_else(|| model_name.clone());
+            agent.pin_model = variant.model.is_some();
+            let prompt = variant
+                .system_prompt
+                .unwrap_or_else(|| system_prompt.clone());
+            agent.config.system_prompt = prompt.clone();
+            agent.context.update_system(prompt);
+            agent.config.temperature = variant.temperature.unwrap_or(temperature);
+
+            results.push(agent.execute_rich(task).await);
+        }
+        results
+    }
+}
@@ -0,0 +1,167 @@
+//! 事件流适配器
+//!
+//! 对 `execute_stream` / `chat_stream` 返回的事件流做二次加工，不改变 Agent 的执行过程。
+
+use super::AgentEvent;
+use crate::error::Result;
+use futures::stream::{BoxStream, Stream, StreamExt};
+
+/// 紧跟句末标点、并入同一句的闭合引号与括号
+const CLOSING_MARKS: &[char] = &['"', '\'', '”', '’', '）', ')', '」', '』', '》', '】'];
+
+/// 把连续的 [`AgentEvent::Token`] 缓冲到句子边界后作为一个 `Token` 事件发出
+///
+/// 句子边界为 `。！？`、换行，以及后面跟空白或闭合引号的 `.!?`（避免在 `3.14` 处切开）；
+/// 紧随其后的标点、闭合引号与空白并入同一句。其他事件原样透传，透传前先发出已缓冲的文本，
+/// 因此 `ToolCall` 等事件与 Token 的相对顺序不变；流结束或出错时同样先发出残留缓冲。
+///
+/// 适合前端按句渲染打字机效果或逐句送入 TTS。
+///
+/// # 示例
+///
+/// ```rust
+/// use echo_agent::agent::{AgentEvent, chunk_by_sentence};
+/// use futures::{StreamExt, stream};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let tokens = ["你", "好。今", "天天气", "不错！", "再见"];
+/// let events = stream::iter(tokens.map(|t| Ok(AgentEvent::Token(t.to_string()))));
+/// let sentences: Vec<String> = chunk_by_sentence(events)
+///     .filter_map(|e| async move {
+///         match e {
+///             Ok(AgentEvent::Token(t)) => Some(t),
+///             _ => None,
+///         }
+///     })
+///     .collect()
+///     .await;
+/// assert_eq!(sentences, ["你好。", "今天天气不错！", "再见"]);
+/// # }
+/// ```
+pub fn chunk_by_sentence<'a, S>(stream: S) -> BoxStream<'a, Result<AgentEvent>>
+where
+    S: Stream<Item = Result<AgentEvent>> + Send + 'a,
+{
+    Box::pin(async_stream::stream! {
+        let mut stream = std::pin::pin!(stream);
+        let mut buffer = String::new();
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(AgentEvent::Token(token)) => {
+                    buffer.push_str(&token);
+                    while let Some(end) = sentence_end(&buffer) {
+                        let rest = buffer.split_off(end);
+                        yield Ok(AgentEvent::Token(std::mem::replace(&mut buffer, rest)));
+                    }
+                }
+                other => {
+                    if !buffer.is_empty() {
+                        yield Ok(AgentEvent::Token(std::mem::take(&mut buffer)));
+                    }
+                    yield other;
+                }
+            }
+        }
+        if !buffer.is_empty() {
+            yield Ok(AgentEvent::Token(buffer));
+        }
+    })
+}
+
+/// 第一个完整句子的结束位置（字节下标）；还没有完整句子时返回 `None`
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let boundary = match c {
+            '。' | '！' | '？' | '\n' => true,
+            // 英文标点要看到下一个字符才能确定是否为句末
+            '.' | '!' | '?' => chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_whitespace() || CLOSING_MARKS.contains(next)),
+            _ => false,
+        };
+        if !boundary {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if !(matches!(next, '。' | '！' | '？' | '!' | '?')
+                || CLOSING_MARKS.contains(&next)
+                || next.is_whitespace())
+            {
+                break;
+            }
+            end = j + next.len_utf8();
+            chars.next();
+        }
+        return Some(end);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use serde_json::json;
+
+    fn token(text: &str) -> Result<AgentEvent> {
+        Ok(AgentEvent::Token(text.to_string()))
+    }
+
+    /// 把事件转成便于断言的字符串
+    async fn collect(events: Vec<Result<AgentEvent>>) -> Vec<String> {
+        chunk_by_sentence(stream::iter(events))
+            .map(|event| match event.unwrap() {
+                AgentEvent::Token(t) => t,
+                AgentEvent::ToolCall { name, .. } => format!("<call {name}>"),
+                AgentEvent::FinalAnswer(a) => format!("<final {a}>"),
+                other => format!("{other:?}"),
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_tokens_merged_into_sentences() {
+        let events = vec![
+            token("我"),
+            token("先查"),
+            token("一下天气"),
+            token("。\n"),
+            token("稍"),
+            token("等"),
+            Ok(AgentEvent::ToolCall {
+                name: "weather".to_string(),
+                args: json!({}),
+            }),
+            token("It is 3.5"),
+            token(" degrees. Cold"),
+            token("!"),
+            token("”你说"),
+            token("呢？！"),
+            token("带好外套"),
+            Ok(AgentEvent::FinalAnswer("带好外套".to_string())),
+        ];
+        assert_eq!(
+            collect(events).await,
+            [
+                "我先查一下天气。\n",
+                "稍等",
+                "<call weather>",
+                "It is 3.5 degrees. ",
+                "Cold!”",
+                "你说呢？！",
+                "带好外套",
+                "<final 带好外套>",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flushes_remaining_buffer_at_end() {
+        let events = vec![token("第一句。第二"), token("句没有结尾")];
+        assert_eq!(collect(events).await, ["第一句。", "第二句没有结尾"]);
+    }
+}
@@ -0,0 +1,307 @@
+//! 结构化执行链路（trace）
+//!
+//! 每次执行生成一棵 span 树：`task` 根 span 下挂 `iteration`，每个 iteration 下挂
+//! `llm_call` 与 `tool_call`。时间以相对 trace 起点的微秒记录，可序列化为 JSON
+//! 导入可观测性平台（字段与 OpenTelemetry span 的 name / kind / start / duration / attributes 一一对应），
+//! 也可回放为 Mermaid 序列图（[`ExecutionTrace::to_mermaid`]）。
+//!
+//! 收集只在阶段边界读取一次 `Instant`，不会显著增加执行开销。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+static NEXT_TRACE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// span 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    /// 整次执行（根 span）
+    Task,
+    /// 一轮 ReAct 迭代
+    Iteration,
+    /// 一次 LLM 请求（含重试）
+    LlmCall,
+    /// 一次工具调用
+    ToolCall,
+}
+
+/// trace 中的单个 span
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceSpan {
+    pub kind: SpanKind,
+    /// span 名称：任务名 / `iteration` / 模型名 / 工具名
+    pub name: String,
+    /// 相对 trace 起点的开始时间（微秒）
+    pub start_us: u64,
+    /// 耗时（微秒）
+    pub duration_us: u64,
+    /// 关键属性（模型、重试次数、token 用量、错误信息等）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TraceSpan>,
+}
+
+impl TraceSpan {
+    fn new(kind: SpanKind, name: impl Into<String>, start_us: u64) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            start_us,
+            duration_us: 0,
+            attributes: BTreeMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// 按类型筛选直接子 span
+    pub fn children_of(&self, kind: SpanKind) -> impl Iterator<Item = &TraceSpan> {
+        self.children.iter().filter(move |s| s.kind == kind)
+    }
+}
+
+/// 一次执行的完整链路
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionTrace {
+    /// 32 位十六进制 trace ID（与 OTel trace_id 格式一致）
+    pub trace_id: String,
+    /// 执行开始时间（Unix 毫秒）
+    pub started_at_ms: u64,
+    /// 执行是否已结束
+    pub finished: bool,
+    /// 根 span（kind = `task`）
+    pub root: TraceSpan,
+}
+
+impl ExecutionTrace {
+    /// 根 span 下的所有 iteration
+    pub fn iterations(&self) -> Vec<&TraceSpan> {
+        self.root.children_of(SpanKind::Iteration).collect()
+    }
+
+    /// 导出为 JSON
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    /// 回放为 Mermaid 序列图：User → Agent → LLM → Tool → Agent → … 的时序
+    ///
+    /// 每个工具、每个被分派的 SubAgent 都是独立的参与方（按首次出现编号）；
+    /// `final_answer` 画为 Agent 回复 User，执行失败时画出错误信息。
+    pub fn to_mermaid(&self) -> String {
+        let attr = |key: &str| self.root.attributes.get(key).and_then(Value::as_str);
+        let mut participants = vec![
+            ("User".to_string(), "User".to_string()),
+            (
+                "Agent".to_string(),
+                format!("{} (Agent)", mermaid_text(attr("agent").unwrap_or("agent"))),
+            ),
+            (
+                "LLM".to_string(),
+                format!("{} (LLM)", mermaid_text(attr("model").unwrap_or("llm"))),
+            ),
+        ];
+        let mut lines = vec![format!(
+            "    User->>Agent: {}",
+            mermaid_text(&self.root.name)
+        )];
+        let mut answered = false;
+
+        for (index, iteration) in self.iterations().into_iter().enumerate() {
+            for span in &iteration.children {
+                let error = span.attributes.get("error").map(|e| match e {
+                    Value::String(s) => mermaid_text(s),
+                    other => mermaid_text(&other.to_string()),
+                });
+                match span.kind {
+                    SpanKind::LlmCall => {
+                        lines.push(format!("    Agent->>LLM: 第 {} 轮推理", index + 1));
+                        let reply = error.map_or("响应".to_string(), |e| format!("失败：{e}"));
+                        lines.push(format!("    LLM-->>Agent: {reply}"));
+                    }
+                    SpanKind::ToolCall if span.name == "final_answer" => {
+                        lines.push("    Agent-->>User: 最终答案".to_string());
+                        answered = true;
+                    }
+                    SpanKind::ToolCall => {
+                        let (label, request) =
+                            match span.attributes.get("agent").and_then(Value::as_str) {
+                                Some(agent) => {
+                                    (format!("{} (Agent)", mermaid_text(agent)), "分派任务")
+                                }
+                                None => (format!("{} (Tool)", mermaid_text(&span.name)), "调用"),
+                            };
+                        let id = match participants.iter().find(|(_, l)| *l == label) {
+                            Some((id, _)) => id.clone(),
+                            None => {
+                                let id = format!("P{}", participants.len() - 2);
+                                participants.push((id.clone(), label));
+                                id
+                            }
+                        };
+                        lines.push(format!("    Agent->>{id}: {request}"));
+                        let reply = error.map_or("结果".to_string(), |e| format!("失败：{e}"));
+                        lines.push(format!("    {id}-->>Agent: {reply}"));
+                    }
+                    SpanKind::Task | SpanKind::Iteration => {}
+                }
+            }
+        }
+
+        if let Some(error) = attr("error") {
+            lines.push(format!(
+                "    Agent-->>User: 执行失败：{}",
+                mermaid_text(error)
+            ));
+        } else if self.finished && !answered {
+            lines.push("    Agent-->>User: 最终答案".to_string());
+        }
+
+        let mut mermaid = String::from("sequenceDiagram\n");
+        for (id, label) in &participants {
+            mermaid.push_str(&format!("    participant {id} as {label}\n"));
+        }
+        for line in lines {
+            mermaid.push_str(&line);
+            mermaid.push('\n');
+        }
+        mermaid
+    }
+}
+
+/// 序列图中单条文本的最大字符数
+const MERMAID_TEXT_MAX_CHARS: usize = 40;
+
+/// 转成可放进 Mermaid 序列图的单行短文本（去掉会破坏语法的字符）
+fn mermaid_text(text: &str) -> String {
+    let flat: String = text
+        .chars()
+        .map(|c| match c {
+            '\n' | '\r' | '\t' => ' ',
+            ';' => '；',
+            '#' => '＃',
+            c => c,
+        })
+        .collect();
+    let flat = flat.trim();
+    if flat.chars().count() > MERMAID_TEXT_MAX_CHARS {
+        let truncated: String = flat.chars().take(MERMAID_TEXT_MAX_CHARS).collect();
+        format!("{truncated}…")
+    } else {
+        flat.to_string()
+    }
+}
+
+/// 执行期间的 trace 收集器
+#[derive(Debug, Clone)]
+pub(crate) struct TraceRecorder {
+    started: Instant,
+    trace: ExecutionTrace,
+    /// 最后一个 iteration 是否尚未结束
+    iteration_open: bool,
+}
+
+impl TraceRecorder {
+    pub(crate) fn new(task: &str, attributes: BTreeMap<String, Value>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let seq = NEXT_TRACE_SEQ.fetch_add(1, Ordering::Relaxed);
+        let mut root = TraceSpan::new(SpanKind::Task, task, 0);
+        root.attributes = attributes;
+        Self {
+            started: Instant::now(),
+            trace: ExecutionTrace {
+                trace_id: format!("{:016x}{:016x}", now.as_nanos() as u64, seq),
+                started_at_ms: now.as_millis() as u64,
+                finished: false,
+                root,
+            },
+            iteration_open: false,
+        }
+    }
+
+    fn offset_us(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.started).as_micros() as u64
+    }
+
+    fn close_iteration(&mut self, end_us: u64) {
+        if self.iteration_open
+            && let Some(iteration) = self.trace.root.children.last_mut()
+        {
+            iteration.duration_us = end_us.saturating_sub(iteration.start_us);
+        }
+        self.iteration_open = false;
+    }
+
+    /// 开始新的一轮迭代，并结束上一轮
+    pub(crate) fn begin_iteration(&mut self) {
+        let now = self.offset_us(Instant::now());
+        self.close_iteration(now);
+        let index = self.trace.root.children.len();
+        let mut span = TraceSpan::new(SpanKind::Iteration, "iteration", now);
+        span.attributes.insert("index".to_string(), index.into());
+        self.trace.root.children.push(span);
+        self.iteration_open = true;
+    }
+
+    /// 在当前迭代下记录一个已完成的 span
+    pub(crate) fn record(
+        &mut self,
+        kind: SpanKind,
+        name: &str,
+        start: Instant,
+        end: Instant,
+        attributes: BTreeMap<String, Value>,
+    ) {
+        if !self.iteration_open {
+            self.begin_iteration();
+        }
+        let mut span = TraceSpan::new(kind, name, self.offset_us(start));
+        span.duration_us = self.offset_us(end).saturating_sub(span.start_us);
+        span.attributes = attributes;
+        if let Some(iteration) = self.trace.root.children.last_mut() {
+            iteration.children.push(span);
+        }
+    }
+
+    /// 结束整条链路，`error` 为执行失败时的错误信息
+    pub(crate) fn finish(&mut self, error: Option<String>) {
+        if self.trace.finished {
+            return;
+        }
+        let end = self.offset_us(Instant::now());
+        self.close_iteration(end);
+        let root = &mut self.trace.root;
+        root.duration_us = end;
+        root.attributes.insert(
+            "status".to_string(),
+            if error.is_some() { "error" } else { "ok" }.into(),
+        );
+        if let Some(error) = error {
+            root.attributes.insert("error".to_string(), error.into());
+        }
+        self.trace.finished = true;
+    }
+
+    /// 当前链路快照；尚未结束时以当前时间补齐进行中 span 的耗时
+    pub(crate) fn snapshot(&self) -> ExecutionTrace {
+        if self.trace.finished {
+            return self.trace.clone();
+        }
+        let mut recorder = self.clone();
+        let end = recorder.offset_us(Instant::now());
+        recorder.close_iteration(end);
+        recorder.trace.root.duration_us = end;
+        recorder.trace
+    }
+}
+
+/// 构造 span 属性表
+pub(crate) fn attributes<const N: usize>(pairs: [(&str, Value); N]) -> BTreeMap<String, Value> {
+    pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
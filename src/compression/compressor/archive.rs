@@ -0,0 +1,90 @@
+//! 摘要存档：将被摘要替换掉的原始消息与摘要配对保存，供审计/复盘
+
+use crate::error::{MemoryError, Result};
+use crate::llm::types::Message;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// 一次摘要压缩的存档记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryArchiveRecord {
+    /// 存档命名空间（如 agent 名或会话 ID）
+    pub namespace: String,
+    /// 压缩发生时间（Unix 秒）
+    pub timestamp: u64,
+    /// LLM 生成的摘要
+    pub summary: String,
+    /// 被摘要替换掉的原始消息
+    pub original: Vec<Message>,
+}
+
+/// 摘要存档接口，实现此 trait 可将存档写入文件、数据库或远端服务
+#[async_trait]
+pub trait SummaryArchiveSink: Send + Sync {
+    async fn archive(&self, record: SummaryArchiveRecord) -> Result<()>;
+}
+
+/// 以 JSON Lines 格式追加写入本地文件的存档 sink（每次压缩一行）
+pub struct FileArchiveSink {
+    path: PathBuf,
+}
+
+impl FileArchiveSink {
+    /// 创建文件存档 sink，支持 `~/` 开头的路径
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: expand_tilde(path.as_ref()),
+        }
+    }
+
+    /// 存档文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl SummaryArchiveSink for FileArchiveSink {
+    async fn archive(&self, record: SummaryArchiveRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| MemoryError::IoError(e.to_string()))?;
+        }
+
+        let mut line = serde_json::to_string(&record)
+            .map_err(|e| MemoryError::SerializationError(e.to_string()))?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| MemoryError::IoError(e.to_string()))?;
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| MemoryError::IoError(e.to_string()))?;
+        // tokio::fs::File 在后台线程写入，需显式 flush 才能保证落盘
+        file.flush()
+            .await
+            .map_err(|e| MemoryError::IoError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+fn expand_tilde(path: &Path) -> PathBuf {
+    let s = path.to_string_lossy();
+    if s.starts_with("~/")
+        && let Some(home) = std::env::var("HOME")
+            .ok()
+            .or_else(|| std::env::var("USERPROFILE").ok())
+    {
+        return PathBuf::from(home).join(&s[2..]);
+    }
+    path.to_path_buf()
+}
@@ -0,0 +1,114 @@
+//! 摘要保真度评估：压缩后让 LLM 比对原始历史的关键点与摘要，给出保真度分数与丢失点
+
+use crate::llm::LlmClient;
+use crate::llm::types::Message;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::warn;
+
+/// 一次保真度评估的结果
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FidelityReport {
+    /// 保真度分数，0～1，越高表示摘要保留的关键信息越完整
+    pub score: f32,
+    /// 原始历史中有、摘要中丢失的关键点
+    #[serde(default)]
+    pub missing: Vec<String>,
+}
+
+/// 保真度低于阈值时的告警回调
+pub type FidelityAlert = Arc<dyn Fn(&FidelityReport) + Send + Sync>;
+
+/// 保真度检查配置，见 [`SummaryCompressor::with_fidelity_check`](super::SummaryCompressor::with_fidelity_check)
+pub(crate) struct FidelityCheck {
+    /// 告警阈值；`None` 表示不评估
+    pub(crate) threshold: Option<f32>,
+    /// 每 N 次压缩评估一次
+    pub(crate) sample_every: usize,
+    pub(crate) on_alert: Option<FidelityAlert>,
+    /// 已发生的压缩次数，用于采样
+    compressions: AtomicUsize,
+}
+
+impl Default for FidelityCheck {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            sample_every: 1,
+            on_alert: None,
+            compressions: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl FidelityCheck {
+    /// 本次压缩是否需要评估：第 1、N+1、2N+1…… 次压缩时评估
+    fn should_evaluate(&self) -> bool {
+        self.threshold.is_some()
+            && self
+                .compressions
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.sample_every.max(1))
+    }
+
+    /// 按采样规则评估摘要，低于阈值时告警；评估失败只记录警告
+    pub(crate) async fn check(&self, llm: &dyn LlmClient, original: &[Message], summary: &str) {
+        if !self.should_evaluate() {
+            return;
+        }
+        let Some(threshold) = self.threshold else {
+            return;
+        };
+        let reply = match llm
+            .chat_simple(vec![Message::user(fidelity_prompt(original, summary))])
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!(error = %e, "⚠️ 摘要保真度评估失败，已跳过");
+                return;
+            }
+        };
+        let Some(report) = parse_report(&reply) else {
+            warn!("⚠️ 摘要保真度评估结果无法解析，已跳过");
+            return;
+        };
+        if report.score < threshold {
+            warn!(
+                score = report.score,
+                threshold,
+                missing = ?report.missing,
+                "⚠️ 摘要保真度低于阈值，可能丢失了关键信息"
+            );
+            if let Some(alert) = &self.on_alert {
+                alert(&report);
+            }
+        }
+    }
+}
+
+fn fidelity_prompt(original: &[Message], summary: &str) -> String {
+    let history = original
+        .iter()
+        .filter_map(|m| m.content.as_ref().map(|c| format!("[{}]: {}", m.role, c)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "先列出下面原始对话中的关键点（用户需求、决策、结论、待办、关键数据），\
+        再逐条检查摘要是否保留了它们。\
+        只输出 JSON：{{\"score\": 0 到 1 之间的保真度分数, \"missing\": [摘要中丢失的关键点]}}。\
+        \n\n原始对话：\n{history}\n\n摘要：\n{summary}"
+    )
+}
+
+/// 从回复中截取首个 `{` 到末个 `}` 解析评估结果，分数截断到 0～1
+fn parse_report(reply: &str) -> Option<FidelityReport> {
+    let json = reply
+        .find('{')
+        .zip(reply.rfind('}'))
+        .and_then(|(start, end)| reply.get(start..=end))?;
+    let mut report: FidelityReport = serde_json::from_str(json).ok()?;
+    report.score = report.score.clamp(0.0, 1.0);
+    Some(report)
+}
@@ -1,4 +1,7 @@
-use crate::compression::{CompressionInput, CompressionOutput, ContextCompressor};
+use crate::compression::{
+    CompressionInput, CompressionOutput, CompressionPlan, CompressionRef, ContextCompressor,
+    PlannedMessage,
+};
 use crate::error::Result;
 use crate::llm::types::Message;
 use async_trait::async_trait;
@@ -52,6 +55,68 @@ impl ContextCompressor for HybridCompressor {
             evicted: all_evicted,
         })
     }
+
+    /// 第一个阶段直接借用输入；后续阶段读取上一阶段结果的副本（只克隆仍保留的原消息），
+    /// 其计划再映射回原始输入的下标。前面阶段生成、又被后续阶段丢弃的消息记为
+    /// [`PlannedMessage::Evicted`]，使 `evicted` 与 [`compress`](ContextCompressor::compress) 一致
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        let Some((first, rest)) = self.stages.split_first() else {
+            return Ok(CompressionPlan::keep_all(input.messages.len()));
+        };
+        let mut plan = first.compress_ref(input).await?;
+        for stage in rest {
+            let (previous, mut dropped): (Vec<_>, Vec<_>) = entries(plan)
+                .into_iter()
+                .partition(|planned| !matches!(planned, PlannedMessage::Evicted(_)));
+            let current: Vec<Message> = previous
+                .iter()
+                .filter_map(|planned| match planned {
+                    PlannedMessage::Original(i) => input.messages.get(*i).cloned(),
+                    PlannedMessage::New(message) => Some(message.clone()),
+                    PlannedMessage::Evicted(_) => None,
+                })
+                .collect();
+            let next = stage
+                .compress_ref(CompressionRef {
+                    messages: &current,
+                    ..input
+                })
+                .await?;
+            let mut slots: Vec<Option<PlannedMessage>> = previous.into_iter().map(Some).collect();
+            let mut kept: Vec<PlannedMessage> = entries(next)
+                .into_iter()
+                .filter_map(|planned| match planned {
+                    PlannedMessage::Original(j) => slots.get_mut(j).and_then(Option::take),
+                    other => Some(other),
+                })
+                .collect();
+            // 未被引用的原消息由 apply 计入 evicted；未被引用的新消息需要显式保留下来
+            dropped.extend(
+                slots
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|planned| match planned {
+                        PlannedMessage::New(message) => Some(PlannedMessage::Evicted(message)),
+                        _ => None,
+                    }),
+            );
+            kept.extend(dropped);
+            plan = CompressionPlan::Indexed(kept);
+        }
+        Ok(plan)
+    }
+}
+
+/// 把计划统一为逐条描述的形式；已生成的输出全部视为新消息
+fn entries(plan: CompressionPlan) -> Vec<PlannedMessage> {
+    match plan {
+        CompressionPlan::Indexed(entries) => entries,
+        CompressionPlan::Owned(output) => output
+            .messages
+            .into_iter()
+            .map(PlannedMessage::New)
+            .collect(),
+    }
 }
 
 impl HybridCompressor {
@@ -1,7 +1,16 @@
+pub mod archive;
+pub mod fidelity;
 pub mod hybrid;
+pub mod relevance;
 pub mod sliding_window;
 pub mod summary;
 
+pub use archive::{FileArchiveSink, SummaryArchiveRecord, SummaryArchiveSink};
+pub use fidelity::{FidelityAlert, FidelityReport};
 pub use hybrid::{HybridCompressor, HybridCompressorBuilder};
+pub use relevance::RelevanceFilterCompressor;
 pub use sliding_window::SlidingWindowCompressor;
-pub use summary::{DefaultSummaryPrompt, FnSummaryPrompt, SummaryCompressor, SummaryPromptBuilder};
+pub use summary::{
+    DefaultSummaryPrompt, FnSummaryPrompt, SummaryCompressor, SummaryPromptBuilder,
+    TopicSegmentation,
+};
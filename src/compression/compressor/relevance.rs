@@ -0,0 +1,209 @@
+use crate::compression::compressor::SlidingWindowCompressor;
+use crate::compression::{
+    CompressionInput, CompressionOutput, CompressionPlan, CompressionRef, ContextCompressor,
+    PlannedMessage,
+};
+use crate::error::Result;
+use crate::llm::types::Message;
+use async_trait::async_trait;
+use std::collections::HashSet;
+
+/// 不参与相关度判断的常见中文两字组合
+const STOP_BIGRAMS: &[&str] = &[
+    "什么", "么是", "是什", "怎么", "如何", "为什", "一下", "可以", "这个", "那个", "一个", "我们",
+    "你们", "请问", "告诉", "帮我", "我想", "有没", "没有", "的是",
+];
+
+/// 相关度过滤：只保留与当前问题相关的早期历史，加上最近 `keep_recent` 条消息。
+///
+/// - 历史按轮次切分：每条 user 消息与其后的 assistant / tool 消息为一段，整段保留或整段裁掉，
+///   不会拆散工具调用与结果的配对
+/// - 一段与 `CompressionInput::current_query` 共有的关键词数达到 `min_overlap`（默认 1）即视为相关；
+///   关键词为英文 / 数字单词与相邻两个汉字
+/// - 最近 `keep_recent` 条消息所在的段总是保留；system 消息始终保留
+/// - 没有 `current_query` 或其中提取不出关键词时，退化为 [`SlidingWindowCompressor`]
+///
+/// 只按相关度裁剪，不保证结果低于 token 上限；需要硬上限时在 [`HybridCompressor`](super::HybridCompressor)
+/// 中串联一个滑动窗口或摘要压缩器。
+pub struct RelevanceFilterCompressor {
+    keep_recent: usize,
+    min_overlap: usize,
+}
+
+impl RelevanceFilterCompressor {
+    pub fn new(keep_recent: usize) -> Self {
+        Self {
+            keep_recent,
+            min_overlap: 1,
+        }
+    }
+
+    /// 一段历史至少与当前问题共有多少个关键词才保留
+    pub fn min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl ContextCompressor for RelevanceFilterCompressor {
+    async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput> {
+        let plan = self.compress_ref(input.borrowed()).await?;
+        Ok(plan.apply(input.messages))
+    }
+
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        let query_keywords = input.current_query.map(keywords).unwrap_or_default();
+        if query_keywords.is_empty() {
+            return SlidingWindowCompressor::new(self.keep_recent)
+                .compress_ref(input)
+                .await;
+        }
+
+        let messages = input.messages;
+        let (system_idx, conv_idx): (Vec<_>, Vec<_>) =
+            (0..messages.len()).partition(|&i| messages[i].role == "system");
+        let recent_from = conv_idx.len().saturating_sub(self.keep_recent);
+
+        let mut kept = system_idx;
+        for (start, segment) in segments(messages, &conv_idx) {
+            let end = start + segment.len();
+            let keep = end > recent_from || {
+                let text: String = segment
+                    .iter()
+                    .filter_map(|&i| messages[i].content.as_deref())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                keywords(&text).intersection(&query_keywords).count() >= self.min_overlap
+            };
+            if keep {
+                kept.extend(segment);
+            }
+        }
+        Ok(CompressionPlan::Indexed(
+            kept.into_iter().map(PlannedMessage::Original).collect(),
+        ))
+    }
+}
+
+/// 按 user 消息把 `conv_idx` 指向的消息切分为轮次段，返回每段在 `conv_idx` 中的起始位置与消息下标
+fn segments(messages: &[Message], conv_idx: &[usize]) -> Vec<(usize, Vec<usize>)> {
+    let mut segments: Vec<(usize, Vec<usize>)> = Vec::new();
+    for (pos, &i) in conv_idx.iter().enumerate() {
+        match segments.last_mut() {
+            Some((_, segment)) if messages[i].role != "user" => segment.push(i),
+            _ => segments.push((pos, vec![i])),
+        }
+    }
+    segments
+}
+
+/// 提取关键词：英文 / 数字按单词（小写，至少 2 个字符），汉字按相邻两字
+pub(crate) fn keywords(text: &str) -> HashSet<String> {
+    let is_han = |c: char| ('\u{4E00}'..='\u{9FFF}').contains(&c);
+    let mut words = HashSet::new();
+    let mut word = String::new();
+    let mut prev_han: Option<char> = None;
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+        } else if word.len() >= 2 {
+            words.insert(std::mem::take(&mut word));
+        } else {
+            word.clear();
+        }
+        if is_han(c) {
+            if let Some(prev) = prev_han {
+                let bigram: String = [prev, c].into_iter().collect();
+                if !STOP_BIGRAMS.contains(&bigram.as_str()) {
+                    words.insert(bigram);
+                }
+            }
+            prev_han = Some(c);
+        } else {
+            prev_han = None;
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keywords_mix_words_and_bigrams() {
+        let words = keywords("Rust 的所有权是什么？");
+        assert!(words.contains("rust"));
+        assert!(words.contains("所有"));
+        assert!(words.contains("有权"));
+        assert!(!words.contains("什么"));
+    }
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|m| m.content.as_deref().unwrap_or_default())
+            .collect()
+    }
+
+    fn history() -> Vec<Message> {
+        vec![
+            Message::system("你是助手".to_string()),
+            Message::user("Rust 的所有权规则是什么".to_string()),
+            Message::assistant("每个值只有一个所有者".to_string()),
+            Message::user("推荐一家北京烤鸭店".to_string()),
+            Message::assistant("可以试试四季民福".to_string()),
+            Message::user("明天天气怎么样".to_string()),
+            Message::assistant("明天晴".to_string()),
+            Message::user("所有权转移后原变量还能用吗".to_string()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_keeps_relevant_history_and_recent_messages() {
+        let output = RelevanceFilterCompressor::new(1)
+            .compress(CompressionInput {
+                messages: history(),
+                token_limit: 10,
+                current_query: Some("所有权转移后原变量还能用吗".to_string()),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            contents(&output.messages),
+            [
+                "你是助手",
+                "Rust 的所有权规则是什么",
+                "每个值只有一个所有者",
+                "所有权转移后原变量还能用吗",
+            ]
+        );
+        assert_eq!(
+            contents(&output.evicted),
+            [
+                "推荐一家北京烤鸭店",
+                "可以试试四季民福",
+                "明天天气怎么样",
+                "明天晴"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_sliding_window_without_query() {
+        let output = RelevanceFilterCompressor::new(2)
+            .compress(CompressionInput {
+                messages: history(),
+                token_limit: 10,
+                current_query: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            contents(&output.messages),
+            ["你是助手", "明天晴", "所有权转移后原变量还能用吗"]
+        );
+        assert_eq!(output.evicted.len(), 5);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::compression::{CompressionInput, CompressionOutput, ContextCompressor};
+use crate::compression::{
+    CompressionInput, CompressionOutput, CompressionPlan, CompressionRef, ContextCompressor,
+    PlannedMessage,
+};
 use crate::error::Result;
 use async_trait::async_trait;
 
@@ -19,25 +22,21 @@ impl SlidingWindowCompressor {
 #[async_trait]
 impl ContextCompressor for SlidingWindowCompressor {
     async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput> {
-        let (system_msgs, conv_msgs): (Vec<_>, Vec<_>) =
-            input.messages.into_iter().partition(|m| m.role == "system");
-
-        if conv_msgs.len() <= self.window_size {
-            let mut messages = system_msgs;
-            messages.extend(conv_msgs);
-            return Ok(CompressionOutput {
-                messages,
-                evicted: vec![],
-            });
-        }
-
-        let split_at = conv_msgs.len() - self.window_size;
-        let evicted = conv_msgs[..split_at].to_vec();
-        let kept = conv_msgs[split_at..].to_vec();
+        let plan = self.compress_ref(input.borrowed()).await?;
+        Ok(plan.apply(input.messages))
+    }
 
-        let mut messages = system_msgs;
-        messages.extend(kept);
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        let (system_idx, conv_idx): (Vec<_>, Vec<_>) =
+            (0..input.messages.len()).partition(|&i| input.messages[i].role == "system");
+        let split_at = conv_idx.len().saturating_sub(self.window_size);
 
-        Ok(CompressionOutput { messages, evicted })
+        Ok(CompressionPlan::Indexed(
+            system_idx
+                .into_iter()
+                .chain(conv_idx.into_iter().skip(split_at))
+                .map(PlannedMessage::Original)
+                .collect(),
+        ))
     }
 }
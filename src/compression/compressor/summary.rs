@@ -1,9 +1,18 @@
-use crate::compression::{CompressionInput, CompressionOutput, ContextCompressor};
+use crate::compression::compressor::archive::{SummaryArchiveRecord, SummaryArchiveSink};
+use crate::compression::compressor::fidelity::{FidelityCheck, FidelityReport};
+use crate::compression::{
+    CompressionInput, CompressionOutput, CompressionPlan, CompressionRef, ContextCompressor,
+    PlannedMessage,
+};
 use crate::error::Result;
 use crate::llm::LlmClient;
 use crate::llm::types::Message;
 use async_trait::async_trait;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::ops::Range;
 use std::sync::Arc;
+use tracing::warn;
 
 const COMPRESSION_PROMPT: &str =
     "你的任务是创建到目前为止对话的详细摘要，密切关注用户的明确请求和你之前的行动。
@@ -109,6 +118,42 @@ where
     }
 }
 
+/// 话题切换标志词：user 消息以这些词开头时视为新话题的起点
+const TOPIC_SHIFT_MARKERS: &[&str] = &[
+    "换个话题",
+    "另外",
+    "还有一个问题",
+    "另一个问题",
+    "顺便问",
+    "接下来",
+    "说回",
+    "by the way",
+    "btw",
+    "another question",
+];
+
+/// 分段摘要的切分方式，见 [`SummaryCompressor::with_topic_segments`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicSegmentation {
+    /// 按 user 消息开头的话题转换词切分，不额外调用 LLM
+    Heuristic,
+    /// 由 LLM 标注各话题段的起始位置（多一次 LLM 调用，切分更准确）
+    Llm,
+}
+
+/// 话题段：消息区间 + 已知的主题名
+struct TopicSegment {
+    range: Range<usize>,
+    topic: Option<String>,
+}
+
+/// LLM 切分结果中的单个话题段
+#[derive(Deserialize)]
+struct SegmentMark {
+    topic: String,
+    start: usize,
+}
+
 /// 摘要压缩：用 LLM 将较早的对话历史压缩成一条摘要 system 消息，保留最近 `keep_recent` 条不变。
 ///
 /// 压缩后的消息结构：
@@ -126,6 +171,12 @@ pub struct SummaryCompressor<P: SummaryPromptBuilder> {
     prompt_builder: P,
     /// 最近多少条对话消息保持原样（不参与摘要）
     keep_recent: usize,
+    /// 可选的存档 sink 及其命名空间：保存被摘要的原始消息
+    archive: Option<(Arc<dyn SummaryArchiveSink>, String)>,
+    /// 分段摘要模式；`None` 时整段摘要
+    segmentation: Option<TopicSegmentation>,
+    /// 压缩后的保真度评估（默认关闭）
+    fidelity: FidelityCheck,
 }
 
 impl<P: SummaryPromptBuilder> SummaryCompressor<P> {
@@ -134,39 +185,271 @@ impl<P: SummaryPromptBuilder> SummaryCompressor<P> {
             llm,
             prompt_builder,
             keep_recent,
+            archive: None,
+            segmentation: None,
+            fidelity: FidelityCheck::default(),
         }
     }
+
+    /// 启用保真度评估：压缩后让 LLM 比对原始历史的关键点与摘要，
+    /// 分数低于 `threshold`（0～1）时记录警告并调用 [`on_fidelity_alert`](Self::on_fidelity_alert) 设置的回调。
+    ///
+    /// 每次评估额外消耗一次 LLM 请求（并发送全部被摘要的原文），可用
+    /// [`fidelity_sample_every`](Self::fidelity_sample_every) 降低频率。评估失败不影响压缩结果。
+    pub fn with_fidelity_check(mut self, threshold: f32) -> Self {
+        self.fidelity.threshold = Some(threshold);
+        self
+    }
+
+    /// 每 `n` 次压缩评估一次保真度（默认 1，即每次都评估）
+    pub fn fidelity_sample_every(mut self, n: usize) -> Self {
+        self.fidelity.sample_every = n.max(1);
+        self
+    }
+
+    /// 设置保真度低于阈值时的告警回调
+    pub fn on_fidelity_alert(
+        mut self,
+        alert: impl Fn(&FidelityReport) + Send + Sync + 'static,
+    ) -> Self {
+        self.fidelity.on_alert = Some(Arc::new(alert));
+        self
+    }
+
+    /// 启用分段摘要：先把待摘要历史切成若干话题段，每段单独摘要，
+    /// 最终摘要形如 `关于X：...；关于Y：...`。
+    ///
+    /// 切分不出多个话题段或分段摘要失败时，降级为使用 `prompt_builder` 的整段摘要。
+    pub fn with_topic_segments(mut self, segmentation: TopicSegmentation) -> Self {
+        self.segmentation = Some(segmentation);
+        self
+    }
+
+    /// 设置存档 sink：每次压缩时把被摘要的原始消息与摘要配对写入 `namespace` 下。
+    ///
+    /// 存档写入失败只记录警告，不影响压缩结果。
+    pub fn with_archive(
+        mut self,
+        sink: Arc<dyn SummaryArchiveSink>,
+        namespace: impl Into<String>,
+    ) -> Self {
+        self.archive = Some((sink, namespace.into()));
+        self
+    }
 }
 
 #[async_trait]
 impl<P: SummaryPromptBuilder + 'static> ContextCompressor for SummaryCompressor<P> {
     async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput> {
-        let (system_msgs, conv_msgs): (Vec<_>, Vec<_>) =
-            input.messages.into_iter().partition(|m| m.role == "system");
-
-        if conv_msgs.len() <= self.keep_recent {
-            let mut messages = system_msgs;
-            messages.extend(conv_msgs);
-            return Ok(CompressionOutput {
-                messages,
-                evicted: vec![],
-            });
+        let plan = self.compress_ref(input.borrowed()).await?;
+        Ok(plan.apply(input.messages))
+    }
+
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        let messages = input.messages;
+        let (system_idx, conv_idx): (Vec<_>, Vec<_>) =
+            (0..messages.len()).partition(|&i| messages[i].role == "system");
+
+        if conv_idx.len() <= self.keep_recent {
+            return Ok(CompressionPlan::Indexed(
+                system_idx
+                    .into_iter()
+                    .chain(conv_idx)
+                    .map(PlannedMessage::Original)
+                    .collect(),
+            ));
         }
 
-        let split_at = conv_msgs.len() - self.keep_recent;
-        let to_summarize = &conv_msgs[..split_at];
-        let to_keep = conv_msgs[split_at..].to_vec();
+        let split_at = conv_idx.len() - self.keep_recent;
+        let summarized = &conv_idx[..split_at];
+        // system 消息通常都在开头，此时待摘要的消息在输入中连续，直接借用
+        let to_summarize: Cow<'_, [Message]> = if summarized.windows(2).all(|w| w[1] == w[0] + 1) {
+            Cow::Borrowed(&messages[summarized[0]..=summarized[split_at - 1]])
+        } else {
+            Cow::Owned(summarized.iter().map(|&i| messages[i].clone()).collect())
+        };
+
+        let summary = match self.segmentation {
+            Some(mode) => match self.summarize_segments(&to_summarize, mode).await {
+                Some(summary) => summary,
+                None => self.summarize_whole(&to_summarize).await?,
+            },
+            None => self.summarize_whole(&to_summarize).await?,
+        };
 
-        let prompt = self.prompt_builder.build(to_summarize);
-        let summary = self.llm.chat_simple(vec![Message::user(prompt)]).await?;
+        self.fidelity
+            .check(self.llm.as_ref(), &to_summarize, &summary)
+            .await;
 
-        let mut messages = system_msgs;
-        messages.push(Message::system(format!("[对话历史摘要]\n{}", summary)));
-        messages.extend(to_keep);
+        let summary_message = Message::system(format!("[对话历史摘要]\n{}", summary));
+        if let Some((sink, namespace)) = &self.archive {
+            let record = SummaryArchiveRecord {
+                namespace: namespace.clone(),
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                summary,
+                original: to_summarize.into_owned(),
+            };
+            if let Err(e) = sink.archive(record).await {
+                warn!(namespace = %namespace, error = %e, "⚠️ 摘要存档写入失败，继续压缩");
+            }
+        }
 
-        Ok(CompressionOutput {
-            messages,
-            evicted: to_summarize.to_vec(),
+        Ok(CompressionPlan::Indexed(
+            system_idx
+                .into_iter()
+                .map(PlannedMessage::Original)
+                .chain([PlannedMessage::New(summary_message)])
+                .chain(
+                    conv_idx[split_at..]
+                        .iter()
+                        .map(|&i| PlannedMessage::Original(i)),
+                )
+                .collect(),
+        ))
+    }
+}
+
+impl<P: SummaryPromptBuilder> SummaryCompressor<P> {
+    async fn summarize_whole(&self, messages: &[Message]) -> Result<String> {
+        let prompt = self.prompt_builder.build(messages);
+        self.llm.chat_simple(vec![Message::user(prompt)]).await
+    }
+
+    /// 分段摘要；返回 `None` 表示应降级为整段摘要
+    async fn summarize_segments(
+        &self,
+        messages: &[Message],
+        mode: TopicSegmentation,
+    ) -> Option<String> {
+        let segments = match mode {
+            TopicSegmentation::Heuristic => split_by_topic_markers(messages),
+            TopicSegmentation::Llm => self.split_by_llm(messages).await?,
+        };
+        if segments.len() < 2 {
+            return None;
+        }
+
+        let mut parts = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let prompt = segment_prompt(&messages[segment.range], segment.topic.as_deref());
+            let reply = match self.llm.chat_simple(vec![Message::user(prompt)]).await {
+                Ok(reply) => reply,
+                Err(e) => {
+                    warn!(error = %e, "⚠️ 话题段摘要失败，降级为整段摘要");
+                    return None;
+                }
+            };
+            let (topic, summary) = match segment.topic {
+                Some(topic) => (topic, reply.trim().to_string()),
+                None => {
+                    let reply = reply.trim();
+                    let (topic, summary) = reply.split_once('\n').unwrap_or(("其他", reply));
+                    (topic.trim().to_string(), summary.trim().to_string())
+                }
+            };
+            parts.push(format!("关于{topic}：{summary}"));
+        }
+        Some(parts.join("；\n"))
+    }
+
+    /// 让 LLM 标注话题段起点；输出无法解析时返回 `None`
+    async fn split_by_llm(&self, messages: &[Message]) -> Option<Vec<TopicSegment>> {
+        let prompt = format!(
+            "下面是一段带编号的对话。请按话题把它切分为连续的若干段，\
+            只输出 JSON 数组，每个元素形如 {{\"topic\": \"不超过 10 字的主题\", \"start\": 该段第一条消息的编号}}，\
+            按 start 升序排列，第一段的 start 为 0。\n\n{}",
+            numbered_history(messages)
+        );
+        let reply = match self.llm.chat_simple(vec![Message::user(prompt)]).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!(error = %e, "⚠️ 话题切分失败，降级为整段摘要");
+                return None;
+            }
+        };
+        let json = reply
+            .find('[')
+            .zip(reply.rfind(']'))
+            .and_then(|(start, end)| reply.get(start..=end))?;
+        let marks: Vec<SegmentMark> = match serde_json::from_str(json) {
+            Ok(marks) => marks,
+            Err(e) => {
+                warn!(error = %e, "⚠️ 话题切分结果无法解析，降级为整段摘要");
+                return None;
+            }
+        };
+
+        let mut segments: Vec<TopicSegment> = Vec::with_capacity(marks.len());
+        for (i, mark) in marks.iter().enumerate() {
+            let start = if i == 0 { 0 } else { mark.start };
+            let end = marks.get(i + 1).map_or(messages.len(), |next| next.start);
+            if start >= end || end > messages.len() {
+                warn!(start, end, "⚠️ 话题切分区间非法，降级为整段摘要");
+                return None;
+            }
+            segments.push(TopicSegment {
+                range: start..end,
+                topic: Some(mark.topic.trim().to_string()),
+            });
+        }
+        Some(segments)
+    }
+}
+
+/// 按话题转换词切分：以转换词开头的 user 消息开启新段
+fn split_by_topic_markers(messages: &[Message]) -> Vec<TopicSegment> {
+    let mut starts = vec![0];
+    for (i, msg) in messages.iter().enumerate().skip(1) {
+        let Some(content) = msg.content.as_deref().filter(|_| msg.role == "user") else {
+            continue;
+        };
+        let head = content.trim_start().to_lowercase();
+        if TOPIC_SHIFT_MARKERS.iter().any(|m| head.starts_with(m)) {
+            starts.push(i);
+        }
+    }
+    starts
+        .iter()
+        .zip(
+            starts
+                .iter()
+                .skip(1)
+                .chain(std::iter::once(&messages.len())),
+        )
+        .map(|(&start, &end)| TopicSegment {
+            range: start..end,
+            topic: None,
         })
+        .collect()
+}
+
+fn history_lines(messages: &[Message]) -> impl Iterator<Item = String> + '_ {
+    messages
+        .iter()
+        .map(|m| format!("[{}]: {}", m.role, m.content.as_deref().unwrap_or_default()))
+}
+
+fn numbered_history(messages: &[Message]) -> String {
+    history_lines(messages)
+        .enumerate()
+        .map(|(i, line)| format!("{i}. {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 单个话题段的摘要提示词；主题未知时要求 LLM 在首行给出主题
+fn segment_prompt(messages: &[Message], topic: Option<&str>) -> String {
+    let history = history_lines(messages).collect::<Vec<_>>().join("\n");
+    match topic {
+        Some(topic) => format!(
+            "以下是关于「{topic}」的一段对话，请用一两句话概括其中的需求、结论与待办，只输出摘要：\n\n{history}"
+        ),
+        None => format!(
+            "请概括以下一段对话。第一行只输出不超过 10 字的主题，\
+            第二行起用一两句话概括其中的需求、结论与待办：\n\n{history}"
+        ),
     }
 }
@@ -5,6 +5,7 @@
 //! 内置压缩策略（均实现 [`ContextCompressor`] trait）：
 //! - [`compressor::SlidingWindowCompressor`]：滑动窗口，丢弃最早的 N 条消息
 //! - [`compressor::SummaryCompressor`]：LLM 摘要，将旧消息压缩为 system 摘要消息
+//! - [`compressor::RelevanceFilterCompressor`]：相关度过滤，只保留与当前问题相关的历史与最近消息
 //! - [`compressor::HybridCompressor`]：多策略串联管道
 
 pub mod compressor;
@@ -20,10 +21,29 @@ pub struct CompressionInput {
     pub messages: Vec<Message>,
     /// Token 上限，超过时触发压缩
     pub token_limit: usize,
-    /// 当前用户问题（保留字段，供扩展使用）
+    /// 当前用户问题，供按相关度裁剪的策略使用（如 [`compressor::RelevanceFilterCompressor`]）
     pub current_query: Option<String>,
 }
 
+impl CompressionInput {
+    /// 借用视图，供 [`ContextCompressor::compress_ref`] 使用
+    pub fn borrowed(&self) -> CompressionRef<'_> {
+        CompressionRef {
+            messages: &self.messages,
+            token_limit: self.token_limit,
+            current_query: self.current_query.as_deref(),
+        }
+    }
+}
+
+/// 压缩管道的借用输入，字段含义同 [`CompressionInput`]
+#[derive(Clone, Copy)]
+pub struct CompressionRef<'a> {
+    pub messages: &'a [Message],
+    pub token_limit: usize,
+    pub current_query: Option<&'a str>,
+}
+
 /// 压缩管道的输出
 pub struct CompressionOutput {
     /// 最终保留、将发送给 LLM 的消息列表
@@ -32,10 +52,78 @@ pub struct CompressionOutput {
     pub evicted: Vec<Message>,
 }
 
+/// 压缩计划中的一条消息
+pub enum PlannedMessage {
+    /// 原样保留输入中该下标的消息
+    Original(usize),
+    /// 压缩器新生成的消息（如摘要）
+    New(Message),
+    /// 压缩过程中生成、但又被裁剪的消息（如混合压缩中被后续阶段丢弃的摘要），
+    /// 不进入压缩结果，只计入 `evicted`
+    Evicted(Message),
+}
+
+/// [`ContextCompressor::compress_ref`] 的结果
+pub enum CompressionPlan {
+    /// 按顺序描述压缩后的消息：保留的消息以下标引用输入，未被引用的输入即被裁剪的消息
+    Indexed(Vec<PlannedMessage>),
+    /// 已生成的完整输出（`compress_ref` 默认实现的结果）
+    Owned(CompressionOutput),
+}
+
+impl CompressionPlan {
+    /// 原样保留全部 `len` 条输入
+    pub fn keep_all(len: usize) -> Self {
+        Self::Indexed((0..len).map(PlannedMessage::Original).collect())
+    }
+
+    /// 对生成计划时借用的那组消息应用计划：保留与裁剪的消息都从 `messages` 中移出，不做克隆
+    ///
+    /// 越界或重复引用的下标会被忽略。
+    pub fn apply(self, messages: Vec<Message>) -> CompressionOutput {
+        match self {
+            Self::Owned(output) => output,
+            Self::Indexed(plan) => {
+                let mut slots: Vec<Option<Message>> = messages.into_iter().map(Some).collect();
+                let mut dropped = Vec::new();
+                let messages = plan
+                    .into_iter()
+                    .filter_map(|planned| match planned {
+                        PlannedMessage::Original(i) => slots.get_mut(i).and_then(Option::take),
+                        PlannedMessage::New(message) => Some(message),
+                        PlannedMessage::Evicted(message) => {
+                            dropped.push(message);
+                            None
+                        }
+                    })
+                    .collect();
+                let mut evicted: Vec<Message> = slots.into_iter().flatten().collect();
+                evicted.extend(dropped);
+                CompressionOutput { messages, evicted }
+            }
+        }
+    }
+}
+
 /// 所有压缩策略的统一接口（async，支持 `dyn` trait object）
 #[async_trait]
 pub trait ContextCompressor: Send + Sync {
     async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput>;
+
+    /// 借用版压缩：只读取输入，返回压缩计划，由调用方按计划移动原消息
+    ///
+    /// [`ContextManager`] 通过它压缩上下文，避免每次复制整个对话历史。默认实现克隆输入后调用
+    /// [`compress`](Self::compress)；内置策略均直接实现为按下标引用，只克隆确实需要读取副本的部分。
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        let output = self
+            .compress(CompressionInput {
+                messages: input.messages.to_vec(),
+                token_limit: input.token_limit,
+                current_query: input.current_query.map(String::from),
+            })
+            .await?;
+        Ok(CompressionPlan::Owned(output))
+    }
 }
 
 /// 允许将 `Box<dyn ContextCompressor>` 直接传给任何接受 `impl ContextCompressor` 的函数，
@@ -45,6 +133,29 @@ impl ContextCompressor for Box<dyn ContextCompressor> {
     async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput> {
         (**self).compress(input).await
     }
+
+    async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+        (**self).compress_ref(input).await
+    }
+}
+
+/// 章节标记消息的角色：只留在上下文中，不交给压缩器，也不发给 LLM
+pub(crate) const CHAPTER_ROLE: &str = "chapter";
+
+/// 基础 system 提示词片段的标签（[`ContextManagerBuilder::with_system`] 使用）
+pub const BASE_SYSTEM_LABEL: &str = "base";
+
+/// 带标签的 system 片段
+///
+/// system prompt 可由多个来源组成（基础提示词、CoT 引导、Skill 注入等），
+/// 各片段单独维护，按 `priority` 升序合并为上下文中的一条 system 消息。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemFragment {
+    /// 片段标签，如 `base`、`cot`、`skill:calculator`
+    pub label: String,
+    /// 合并顺序，数值越小越靠前；相同优先级按添加顺序
+    pub priority: i32,
+    pub content: String,
 }
 
 /// `force_compress()` 返回的压缩统计信息
@@ -75,7 +186,7 @@ pub struct ForceCompressStats {
 ///     .compressor(SlidingWindowCompressor::new(20))
 ///     .build();
 ///
-/// ctx.push(Message::system("你是一个助手".to_string()));
+/// ctx.update_system("你是一个助手".to_string());
 /// ctx.push(Message::user("你好".to_string()));
 ///
 /// // 在每次调用 LLM 前调用 prepare()，自动压缩超限消息
@@ -108,8 +219,16 @@ pub struct ForceCompressStats {
 /// ```
 pub struct ContextManager {
     messages: Vec<Message>,
+    /// 按优先级排好序的 system 片段
+    system_fragments: Vec<SystemFragment>,
     compressor: Option<Box<dyn ContextCompressor>>,
     token_limit: usize,
+    /// 单条非 system 消息 content 的最大字符数（`usize::MAX` 表示不限制）
+    max_single_message_chars: usize,
+    /// 固定消息（如 few-shot 示例）：发给 LLM 时插在 system 消息之后，不参与压缩，也不计入对话历史
+    pinned: Vec<Message>,
+    /// 累计执行压缩的次数（自动与强制）
+    compression_count: usize,
 }
 
 impl ContextManager {
@@ -117,18 +236,56 @@ impl ContextManager {
         ContextManagerBuilder {
             token_limit,
             compressor: None,
-            initial_messages: Vec::new(),
+            system_fragments: Vec::new(),
+            max_single_message_chars: usize::MAX,
+            pinned: Vec::new(),
         }
     }
 
     /// 追加一条消息到上下文缓冲区
+    ///
+    /// 非 system 消息的 content 超过 `max_single_message_chars` 时截断并附加提示，
+    /// 防止失控输出一次性撑爆上下文。`tool_calls` 的参数不截断（截断后不再是合法 JSON）。
     pub fn push(&mut self, message: Message) {
+        let message = self.truncate_oversized(message);
         self.messages.push(message);
     }
 
-    /// 批量追加消息
+    /// 批量追加消息（同样做单条长度预检）
     pub fn push_many(&mut self, messages: impl IntoIterator<Item = Message>) {
-        self.messages.extend(messages);
+        for message in messages {
+            self.push(message);
+        }
+    }
+
+    /// 运行时调整单条消息的最大字符数，只影响之后追加的消息
+    pub fn set_max_single_message_chars(&mut self, max_chars: usize) {
+        self.max_single_message_chars = max_chars;
+    }
+
+    /// 截断超长的非 system 消息，保留前 `max_single_message_chars` 个字符
+    fn truncate_oversized(&self, mut message: Message) -> Message {
+        let limit = self.max_single_message_chars;
+        if message.role == "system" {
+            return message;
+        }
+        let Some(content) = message.content.as_mut() else {
+            return message;
+        };
+        let total = content.chars().count();
+        if total <= limit {
+            return message;
+        }
+        let cut = content
+            .char_indices()
+            .nth(limit)
+            .map_or(content.len(), |(i, _)| i);
+        content.truncate(cut);
+        content.push_str(&format!(
+            "\n…[消息共 {total} 字符，超过单条上限 {limit} 字符，已截断]"
+        ));
+        tracing::warn!(role = %message.role, total, limit, "单条消息过长，已截断");
+        message
     }
 
     /// 返回当前缓冲区中的所有消息（不做压缩）
@@ -141,11 +298,54 @@ impl ContextManager {
         Self::estimate_tokens(&self.messages)
     }
 
-    /// 清空上下文缓冲区（保留已设置的压缩器）
+    /// 累计执行压缩的次数，包括超限自动压缩与 `force_compress*`；`clear` 不会清零
+    pub fn compression_count(&self) -> usize {
+        self.compression_count
+    }
+
+    /// 清空上下文缓冲区（保留已设置的压缩器和 system 片段，但不重新生成 system 消息）
     pub fn clear(&mut self) {
         self.messages.clear();
     }
 
+    /// 把从下标 `start` 开始、位于末尾的连续 tool 消息合并为一条
+    ///
+    /// 合并后按原顺序列出各结果，每段以 `[tool_call_id] 工具名` 开头，消息的
+    /// `tool_call_id` / `name` 取第一条。各段已在追加时做过长度预检，合并不再截断。
+    /// 不足两条或其中混有非 tool 消息时不做改动，返回是否发生了合并。
+    pub fn merge_tool_messages(&mut self, start: usize) -> bool {
+        let tail = self.messages.get(start..).unwrap_or_default();
+        if tail.len() < 2 || tail.iter().any(|m| m.role != "tool") {
+            return false;
+        }
+        let merged: Vec<Message> = self.messages.drain(start..).collect();
+        let content = merged
+            .iter()
+            .map(|m| {
+                format!(
+                    "[{}] {}\n{}",
+                    m.tool_call_id.as_deref().unwrap_or_default(),
+                    m.name.as_deref().unwrap_or_default(),
+                    m.content.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let first = &merged[0];
+        self.messages.push(Message::tool_result(
+            first.tool_call_id.clone().unwrap_or_default(),
+            first.name.clone().unwrap_or_default(),
+            content,
+        ));
+        true
+    }
+
+    /// 清空对话历史，只保留由 system 片段合并出的 system 消息
+    pub fn clear_history(&mut self) {
+        self.messages.clear();
+        self.sync_system();
+    }
+
     /// 动态替换压缩器，不影响已有的消息缓冲区
     pub fn set_compressor(&mut self, compressor: impl ContextCompressor + 'static) {
         self.compressor = Some(Box::new(compressor));
@@ -169,26 +369,16 @@ impl ContextManager {
         let before_count = self.messages.len();
         let before_tokens = self.token_estimate();
 
-        // 分两条路径借用，避免同时持有 `self.compressor` 和 `self.messages` 的可变引用
-        let output = if let Some(compressor) = &self.compressor {
-            let input = CompressionInput {
-                messages: self.messages.clone(),
-                token_limit: self.token_limit,
-                current_query: None,
-            };
-            compressor.compress(input).await?
-        } else {
-            SlidingWindowCompressor::new(fallback_window)
-                .compress(CompressionInput {
-                    messages: self.messages.clone(),
-                    token_limit: self.token_limit,
-                    current_query: None,
-                })
-                .await?
+        let evicted = match &self.compressor {
+            Some(compressor) => {
+                compress_messages(&mut self.messages, compressor, self.token_limit, None).await?
+            }
+            None => {
+                let fallback = SlidingWindowCompressor::new(fallback_window);
+                compress_messages(&mut self.messages, &fallback, self.token_limit, None).await?
+            }
         };
-
-        let evicted = output.evicted.len();
-        self.messages = output.messages;
+        self.compression_count += 1;
         Ok(ForceCompressStats {
             before_count,
             after_count: self.messages.len(),
@@ -208,16 +398,9 @@ impl ContextManager {
         let before_count = self.messages.len();
         let before_tokens = self.token_estimate();
 
-        let output = compressor
-            .compress(CompressionInput {
-                messages: self.messages.clone(),
-                token_limit: self.token_limit,
-                current_query: None,
-            })
-            .await?;
-
-        let evicted = output.evicted.len();
-        self.messages = output.messages;
+        let evicted =
+            compress_messages(&mut self.messages, compressor, self.token_limit, None).await?;
+        self.compression_count += 1;
         Ok(ForceCompressStats {
             before_count,
             after_count: self.messages.len(),
@@ -229,14 +412,101 @@ impl ContextManager {
 
     /// 更新 system 消息内容
     ///
-    /// 通常在 `add_skill()` 注入额外系统提示时调用：
-    /// 找到第一条 role == "system" 的消息并替换其内容；
+    /// 替换基础片段（[`BASE_SYSTEM_LABEL`]）的内容，其他片段（CoT、Skill 等）保持不变；
+    /// 基础片段不存在时以优先级 0 新增。合并后替换第一条 role == "system" 的消息，
     /// 若不存在 system 消息，则在队列头部插入一条。
     pub fn update_system(&mut self, new_system_prompt: String) {
+        if self
+            .system_fragments
+            .iter()
+            .any(|f| f.label == BASE_SYSTEM_LABEL)
+        {
+            self.update_system_fragment(BASE_SYSTEM_LABEL, new_system_prompt);
+        } else {
+            self.set_system_fragment(BASE_SYSTEM_LABEL, 0, new_system_prompt);
+        }
+    }
+
+    /// 更新指定标签的 system 片段，其他片段保持不变
+    ///
+    /// 片段不存在时追加为新片段，优先级排在现有片段之后。
+    pub fn update_system_fragment(&mut self, label: &str, content: String) {
+        if let Some(fragment) = self.system_fragments.iter_mut().find(|f| f.label == label) {
+            fragment.content = content;
+            self.sync_system();
+        } else {
+            let priority = self
+                .system_fragments
+                .last()
+                .map_or(0, |f| f.priority.saturating_add(1));
+            self.set_system_fragment(label, priority, content);
+        }
+    }
+
+    /// 设置 system 片段（同时指定优先级），已存在时替换内容和优先级
+    pub fn set_system_fragment(&mut self, label: &str, priority: i32, content: String) {
+        self.system_fragments.retain(|f| f.label != label);
+        insert_fragment(
+            &mut self.system_fragments,
+            SystemFragment {
+                label: label.to_string(),
+                priority,
+                content,
+            },
+        );
+        self.sync_system();
+    }
+
+    /// 移除指定标签的 system 片段，存在时返回 true
+    pub fn remove_system_fragment(&mut self, label: &str) -> bool {
+        let before = self.system_fragments.len();
+        self.system_fragments.retain(|f| f.label != label);
+        let removed = self.system_fragments.len() != before;
+        if removed {
+            self.sync_system();
+        }
+        removed
+    }
+
+    /// 替换固定消息（如 few-shot 示例）
+    pub fn set_pinned(&mut self, messages: Vec<Message>) {
+        self.pinned = messages;
+    }
+
+    /// 当前的固定消息
+    pub fn pinned(&self) -> &[Message] {
+        &self.pinned
+    }
+
+    /// 按合并顺序返回所有 system 片段，便于追溯 system prompt 的组成
+    pub fn system_fragments(&self) -> &[SystemFragment] {
+        &self.system_fragments
+    }
+
+    /// 按优先级合并后的 system prompt；没有任何片段时返回 `None`
+    pub fn merged_system(&self) -> Option<String> {
+        if self.system_fragments.is_empty() {
+            return None;
+        }
+        Some(
+            self.system_fragments
+                .iter()
+                .map(|f| f.content.trim())
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        )
+    }
+
+    /// 用合并结果覆盖上下文中的 system 消息
+    fn sync_system(&mut self) {
+        let Some(merged) = self.merged_system() else {
+            return;
+        };
         if let Some(msg) = self.messages.iter_mut().find(|m| m.role == "system") {
-            msg.content = Some(new_system_prompt);
+            msg.content = Some(merged);
         } else {
-            self.messages.insert(0, Message::system(new_system_prompt));
+            self.messages.insert(0, Message::system(merged));
         }
     }
 
@@ -245,37 +515,156 @@ impl ContextManager {
     /// 当估算 token 超过 `token_limit` 且已配置压缩器时，自动触发压缩并更新内部缓冲区。
     /// 压缩后的消息会替换原有缓冲区。
     ///
-    /// `current_query` 为保留字段，传 `None` 即可。
+    /// `current_query` 为当前用户问题，透传给压缩器用于按相关度裁剪；不需要时传 `None`。
+    ///
+    /// 固定消息插在开头的 system 消息之后，计入 token 估算但不交给压缩器。
     pub async fn prepare(&mut self, current_query: Option<&str>) -> Result<Vec<Message>> {
+        let pinned_tokens = Self::estimate_tokens(&self.pinned);
         if let Some(compressor) = &self.compressor
-            && Self::estimate_tokens(&self.messages) > self.token_limit
+            && Self::estimate_tokens(&self.messages) + pinned_tokens > self.token_limit
         {
-            let output = compressor
-                .compress(CompressionInput {
-                    messages: self.messages.clone(),
-                    token_limit: self.token_limit,
-                    current_query: current_query.map(String::from),
-                })
-                .await?;
-            self.messages = output.messages;
+            compress_messages(
+                &mut self.messages,
+                compressor,
+                self.token_limit.saturating_sub(pinned_tokens),
+                current_query,
+            )
+            .await?;
+            self.compression_count += 1;
         }
-        Ok(self.messages.clone())
+        // 章节标记等元数据消息只留在上下文中，不发给 LLM
+        let mut messages: Vec<Message> = self
+            .messages
+            .iter()
+            .filter(|m| m.role != CHAPTER_ROLE)
+            .cloned()
+            .collect();
+        let at = messages
+            .iter()
+            .position(|m| m.role != "system")
+            .unwrap_or(messages.len());
+        messages.splice(at..at, self.pinned.iter().cloned());
+        Ok(messages)
     }
 
-    fn estimate_tokens(messages: &[Message]) -> usize {
+    pub(crate) fn estimate_tokens(messages: &[Message]) -> usize {
         messages
             .iter()
             .filter_map(|m| m.content.as_ref())
-            .map(|c| c.len() / 4 + 1)
+            .map(|c| estimate_text_tokens(c))
             .sum()
     }
 }
 
+/// 压缩 `messages` 并返回被裁剪的消息数
+///
+/// 章节标记不交给压缩器（避免混进摘要等 LLM 请求），压缩后放回所在章节第一条保留的消息之前；
+/// 整章都被裁剪时标记随之丢弃。
+async fn compress_messages(
+    messages: &mut Vec<Message>,
+    compressor: &dyn ContextCompressor,
+    token_limit: usize,
+    current_query: Option<&str>,
+) -> Result<usize> {
+    let mut content = Vec::with_capacity(messages.len());
+    // (标记之后第一条消息在 content 中的下标, 标记)
+    let mut markers = Vec::new();
+    for message in std::mem::take(messages) {
+        if message.role == CHAPTER_ROLE {
+            markers.push((content.len(), message));
+        } else {
+            content.push(message);
+        }
+    }
+    let content_len = content.len();
+
+    let plan = compressor
+        .compress_ref(CompressionRef {
+            messages: &content,
+            token_limit,
+            current_query,
+        })
+        .await?;
+    // 每条输出消息对应的原下标（新生成的消息为 None），与 apply 的取舍规则一致
+    let origins: Option<Vec<Option<usize>>> = match &plan {
+        CompressionPlan::Indexed(entries) => {
+            let mut taken = vec![false; content_len];
+            Some(
+                entries
+                    .iter()
+                    .filter_map(|planned| match planned {
+                        PlannedMessage::Original(i) => match taken.get_mut(*i) {
+                            Some(t) if !*t => {
+                                *t = true;
+                                Some(Some(*i))
+                            }
+                            _ => None,
+                        },
+                        PlannedMessage::New(_) => Some(None),
+                        PlannedMessage::Evicted(_) => None,
+                    })
+                    .collect(),
+            )
+        }
+        CompressionPlan::Owned(_) => None,
+    };
+    let output = plan.apply(content);
+    let evicted = output.evicted.len();
+
+    let Some(origins) = origins else {
+        // 无法映射回原下标：只保留最新的章节标记，放在开头的 system 消息之后
+        let mut compressed = output.messages;
+        if let Some((_, marker)) = markers.pop() {
+            let at = compressed
+                .iter()
+                .position(|m| m.role != "system")
+                .unwrap_or(compressed.len());
+            compressed.insert(at, marker);
+        }
+        *messages = compressed;
+        return Ok(evicted);
+    };
+
+    let mut pending = markers.into_iter().peekable();
+    let mut restored = Vec::with_capacity(output.messages.len() + pending.len());
+    for (message, origin) in output.messages.into_iter().zip(origins) {
+        if let Some(i) = origin {
+            let mut current = None;
+            while let Some((_, marker)) = pending.next_if(|(anchor, _)| *anchor <= i) {
+                current = Some(marker);
+            }
+            restored.extend(current);
+        }
+        restored.push(message);
+    }
+    // 位于末尾、之后还没有消息的标记原样保留
+    restored.extend(
+        pending
+            .filter(|(anchor, _)| *anchor >= content_len)
+            .map(|(_, marker)| marker),
+    );
+    *messages = restored;
+    Ok(evicted)
+}
+
+/// 按优先级插入片段，相同优先级排在已有片段之后
+fn insert_fragment(fragments: &mut Vec<SystemFragment>, fragment: SystemFragment) {
+    let index = fragments.partition_point(|f| f.priority <= fragment.priority);
+    fragments.insert(index, fragment);
+}
+
+/// 估算一段文本的 token 数（粗略估算：字节数 / 4）
+pub(crate) fn estimate_text_tokens(text: &str) -> usize {
+    text.len() / 4 + 1
+}
+
 /// `ContextManager` 的构建器
 pub struct ContextManagerBuilder {
     token_limit: usize,
     compressor: Option<Box<dyn ContextCompressor>>,
-    initial_messages: Vec<Message>,
+    system_fragments: Vec<SystemFragment>,
+    max_single_message_chars: usize,
+    pinned: Vec<Message>,
 }
 
 impl ContextManagerBuilder {
@@ -286,18 +675,49 @@ impl ContextManagerBuilder {
         self
     }
 
-    /// 预置一条 system 消息作为初始上下文（通常用于 Agent 的系统提示词）
-    pub fn with_system(mut self, system_prompt: String) -> Self {
-        self.initial_messages.push(Message::system(system_prompt));
+    /// 预置基础 system 片段（标签 [`BASE_SYSTEM_LABEL`]，优先级 0），通常用于 Agent 的系统提示词
+    pub fn with_system(self, system_prompt: String) -> Self {
+        self.system_fragment(BASE_SYSTEM_LABEL, 0, system_prompt)
+    }
+
+    /// 预置一个带标签的 system 片段，同名标签会被替换
+    pub fn system_fragment(mut self, label: &str, priority: i32, content: String) -> Self {
+        self.system_fragments.retain(|f| f.label != label);
+        insert_fragment(
+            &mut self.system_fragments,
+            SystemFragment {
+                label: label.to_string(),
+                priority,
+                content,
+            },
+        );
+        self
+    }
+
+    /// 单条非 system 消息 content 的最大字符数，超过时截断（默认不限制）
+    pub fn max_single_message_chars(mut self, max_chars: usize) -> Self {
+        self.max_single_message_chars = max_chars;
+        self
+    }
+
+    /// 预置固定消息（如 few-shot 示例），见 [`ContextManager::set_pinned`]
+    pub fn pinned(mut self, messages: Vec<Message>) -> Self {
+        self.pinned = messages;
         self
     }
 
     pub fn build(self) -> ContextManager {
-        ContextManager {
-            messages: self.initial_messages,
+        let mut manager = ContextManager {
+            messages: Vec::new(),
+            system_fragments: self.system_fragments,
             compressor: self.compressor,
             token_limit: self.token_limit,
-        }
+            max_single_message_chars: self.max_single_message_chars,
+            pinned: self.pinned,
+            compression_count: 0,
+        };
+        manager.sync_system();
+        manager
     }
 }
 
@@ -337,6 +757,112 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_compression_count_survives_clear() -> Result<()> {
+        let mut ctx = ContextManager::builder(10_000).build();
+        for i in 1..=6 {
+            ctx.push(Message::user(format!("用户消息 {i}")));
+        }
+        ctx.prepare(None).await?;
+        assert_eq!(ctx.compression_count(), 0);
+
+        ctx.force_compress(2).await?;
+        ctx.force_compress_with(&SlidingWindowCompressor::new(1))
+            .await?;
+        ctx.clear();
+        assert_eq!(ctx.compression_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_system_fragments_merge_by_priority() {
+        let mut ctx = ContextManager::builder(4096)
+            .with_system("你是助手".to_string())
+            .system_fragment("cot", 30, "先说明思路。".to_string())
+            .build();
+        ctx.set_system_fragment("skill:calc", 20, "\n\n## 计算器\n用 add 计算。".to_string());
+        ctx.push(Message::user("1+1".to_string()));
+
+        let labels: Vec<_> = ctx
+            .system_fragments()
+            .iter()
+            .map(|f| f.label.as_str())
+            .collect();
+        assert_eq!(labels, ["base", "skill:calc", "cot"]);
+        assert_eq!(ctx.messages().len(), 2);
+        assert_eq!(
+            ctx.messages()[0].content.as_deref(),
+            Some("你是助手\n\n## 计算器\n用 add 计算。\n\n先说明思路。")
+        );
+
+        // 只更新指定标签，其他片段不变；新标签排在最后
+        ctx.update_system("你是数学助手".to_string());
+        ctx.update_system_fragment("language", "请用中文回复。".to_string());
+        assert_eq!(
+            ctx.messages()[0].content.as_deref(),
+            Some("你是数学助手\n\n## 计算器\n用 add 计算。\n\n先说明思路。\n\n请用中文回复。")
+        );
+        assert_eq!(
+            ctx.system_fragments()[1].content,
+            "\n\n## 计算器\n用 add 计算。"
+        );
+
+        assert!(ctx.remove_system_fragment("cot"));
+        assert!(!ctx.remove_system_fragment("cot"));
+        ctx.clear_history();
+        assert_eq!(ctx.messages().len(), 1);
+        assert_eq!(
+            ctx.messages()[0].content.as_deref(),
+            Some("你是数学助手\n\n## 计算器\n用 add 计算。\n\n请用中文回复。")
+        );
+    }
+
+    #[test]
+    fn test_push_truncates_oversized_message() {
+        let mut ctx = ContextManager::builder(4096)
+            .with_system("系".repeat(50))
+            .max_single_message_chars(10)
+            .build();
+        ctx.push(Message::user("一二三四五六七八九十十一".to_string()));
+        ctx.push_many([
+            Message::assistant("短回复".to_string()),
+            Message::system("额外的长 system 消息不截断".to_string()),
+        ]);
+
+        let messages = ctx.messages();
+        assert_eq!(
+            messages[0].content.as_deref(),
+            Some("系".repeat(50).as_str())
+        );
+        assert_eq!(
+            messages[1].content.as_deref(),
+            Some("一二三四五六七八九十\n…[消息共 12 字符，超过单条上限 10 字符，已截断]")
+        );
+        assert_eq!(messages[2].content.as_deref(), Some("短回复"));
+        assert_eq!(
+            messages[3].content.as_deref(),
+            Some("额外的长 system 消息不截断")
+        );
+
+        // tool_calls 的参数不截断，保持合法 JSON
+        let arguments = format!(r#"{{"text": "{}"}}"#, "长".repeat(20));
+        ctx.push(Message::assistant_with_tools(vec![
+            crate::llm::types::ToolCall {
+                id: "call_1".to_string(),
+                call_type: "function".to_string(),
+                function: crate::llm::types::FunctionCall {
+                    name: "echo".to_string(),
+                    arguments: arguments.clone(),
+                },
+            },
+        ]));
+        let pushed = ctx.messages().last().unwrap();
+        assert_eq!(
+            pushed.tool_calls.as_ref().unwrap()[0].function.arguments,
+            arguments
+        );
+    }
+
     #[tokio::test]
     async fn test_summary_compressor_default_prompt() -> Result<()> {
         // ──────────────────────────────────────────────
@@ -490,4 +1016,346 @@ mod tests {
         }
         Ok(())
     }
+
+    struct RecordingSink {
+        records: std::sync::Mutex<Vec<compressor::SummaryArchiveRecord>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl compressor::SummaryArchiveSink for RecordingSink {
+        async fn archive(&self, record: compressor::SummaryArchiveRecord) -> Result<()> {
+            if self.fail {
+                return Err(crate::error::ReactError::Other("sink down".to_string()));
+            }
+            self.records.lock().unwrap().push(record);
+            Ok(())
+        }
+    }
+
+    fn summary_input() -> CompressionInput {
+        let mut messages = vec![Message::system("你是助手。".to_string())];
+        for i in 1..=3 {
+            messages.push(Message::user(format!("问题 {}", i)));
+            messages.push(Message::assistant(format!("回答 {}", i)));
+        }
+        CompressionInput {
+            messages,
+            token_limit: 10,
+            current_query: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summary_compressor_archives_original_messages() -> Result<()> {
+        let llm = Arc::new(crate::testing::MockLlmClient::new().with_response("历史摘要"));
+        let sink = Arc::new(RecordingSink {
+            records: std::sync::Mutex::new(Vec::new()),
+            fail: false,
+        });
+        let compressor = SummaryCompressor::new(llm, DefaultSummaryPrompt, 2)
+            .with_archive(sink.clone(), "agent/session-1");
+
+        let output = compressor.compress(summary_input()).await?;
+        assert_eq!(output.evicted.len(), 4);
+
+        let records = sink.records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].namespace, "agent/session-1");
+        assert_eq!(records[0].summary, "历史摘要");
+        assert!(records[0].timestamp > 0);
+        let originals: Vec<_> = records[0]
+            .original
+            .iter()
+            .map(|m| m.content.clone().unwrap_or_default())
+            .collect();
+        assert_eq!(originals, vec!["问题 1", "回答 1", "问题 2", "回答 2"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_summary_archive_failure_does_not_block_compression() -> Result<()> {
+        let llm = Arc::new(crate::testing::MockLlmClient::new().with_response("历史摘要"));
+        let sink = Arc::new(RecordingSink {
+            records: std::sync::Mutex::new(Vec::new()),
+            fail: true,
+        });
+        let compressor =
+            SummaryCompressor::new(llm, DefaultSummaryPrompt, 2).with_archive(sink, "agent");
+
+        let output = compressor.compress(summary_input()).await?;
+        assert_eq!(output.messages.len(), 4);
+        assert!(
+            output.messages[1]
+                .content
+                .as_deref()
+                .unwrap()
+                .contains("历史摘要")
+        );
+        Ok(())
+    }
+
+    /// 评估分数低于阈值时触发告警回调；按采样间隔跳过后续评估
+    #[tokio::test]
+    async fn test_summary_fidelity_check_alerts_on_low_score() -> Result<()> {
+        let llm = Arc::new(crate::testing::MockLlmClient::new().with_responses([
+            "历史摘要",
+            r#"评估如下：{"score": 0.4, "missing": ["问题 2 的回答"]}"#,
+            "第二次摘要",
+        ]));
+        let alerts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = alerts.clone();
+        let compressor = SummaryCompressor::new(llm.clone(), DefaultSummaryPrompt, 2)
+            .with_fidelity_check(0.7)
+            .fidelity_sample_every(2)
+            .on_fidelity_alert(move |report| sink.lock().unwrap().push(report.clone()));
+
+        let output = compressor.compress(summary_input()).await?;
+        assert_eq!(
+            output.messages[1].content.as_deref(),
+            Some("[对话历史摘要]\n历史摘要")
+        );
+        let alerts_seen = alerts.lock().unwrap().clone();
+        assert_eq!(alerts_seen.len(), 1);
+        assert_eq!(alerts_seen[0].score, 0.4);
+        assert_eq!(alerts_seen[0].missing, ["问题 2 的回答"]);
+        let eval_prompt = llm.all_calls()[1][0].content.clone().unwrap();
+        assert!(eval_prompt.contains("问题 2") && eval_prompt.contains("历史摘要"));
+
+        // 第 2 次压缩不在采样范围内，不发起评估
+        compressor.compress(summary_input()).await?;
+        assert_eq!(llm.call_count(), 3);
+        assert_eq!(alerts.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_archive_sink_appends_json_lines() -> Result<()> {
+        use compressor::SummaryArchiveSink;
+
+        let path =
+            std::env::temp_dir().join(format!("echo_archive_{}.jsonl", uuid::Uuid::new_v4()));
+        let sink = compressor::FileArchiveSink::new(&path);
+        for i in 0..2 {
+            sink.archive(compressor::SummaryArchiveRecord {
+                namespace: "ns".to_string(),
+                timestamp: i,
+                summary: format!("摘要 {}", i),
+                original: vec![Message::user("原文".to_string())],
+            })
+            .await?;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let lines: Vec<_> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let record: compressor::SummaryArchiveRecord = serde_json::from_str(lines[1])?;
+        assert_eq!(record.summary, "摘要 1");
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    fn multi_topic_input() -> CompressionInput {
+        CompressionInput {
+            messages: vec![
+                Message::system("你是助手。".to_string()),
+                Message::user("帮我写一个快速排序".to_string()),
+                Message::assistant("已给出快速排序实现".to_string()),
+                Message::user("换个话题，明天北京天气怎么样？".to_string()),
+                Message::assistant("明天北京晴".to_string()),
+                Message::user("谢谢".to_string()),
+            ],
+            token_limit: 10,
+            current_query: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_segments_produce_structured_summary() {
+        let llm = Arc::new(
+            crate::testing::MockLlmClient::new()
+                .with_responses(["排序\n实现了快速排序", "天气\n查询到明天北京晴"]),
+        );
+        let compressor = SummaryCompressor::new(llm.clone(), DefaultSummaryPrompt, 1)
+            .with_topic_segments(compressor::TopicSegmentation::Heuristic);
+
+        let output = compressor.compress(multi_topic_input()).await.unwrap();
+        assert_eq!(llm.call_count(), 2);
+        assert_eq!(
+            output.messages[1].content.as_deref(),
+            Some("[对话历史摘要]\n关于排序：实现了快速排序；\n关于天气：查询到明天北京晴")
+        );
+        assert_eq!(output.evicted.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_llm_segments_use_labelled_topics() {
+        let llm = Arc::new(crate::testing::MockLlmClient::new().with_responses([
+            r#"[{"topic": "排序", "start": 0}, {"topic": "天气", "start": 2}]"#,
+            "实现了快速排序",
+            "明天北京晴",
+        ]));
+        let compressor = SummaryCompressor::new(llm.clone(), DefaultSummaryPrompt, 1)
+            .with_topic_segments(compressor::TopicSegmentation::Llm);
+
+        let output = compressor.compress(multi_topic_input()).await.unwrap();
+        let summary = output.messages[1].content.as_deref().unwrap();
+        assert!(summary.contains("关于排序：实现了快速排序；\n关于天气：明天北京晴"));
+        // 第二段摘要请求只包含该话题段的消息
+        let calls = llm.all_calls();
+        let second_segment = calls[2][0].content.as_deref().unwrap();
+        assert!(second_segment.contains("天气") && !second_segment.contains("快速排序"));
+    }
+
+    #[tokio::test]
+    async fn test_segmentation_failure_falls_back_to_whole_summary() {
+        let llm =
+            Arc::new(crate::testing::MockLlmClient::new().with_responses(["无法切分", "整段摘要"]));
+        let compressor = SummaryCompressor::new(llm.clone(), DefaultSummaryPrompt, 1)
+            .with_topic_segments(compressor::TopicSegmentation::Llm);
+
+        let output = compressor.compress(multi_topic_input()).await.unwrap();
+        assert_eq!(
+            output.messages[1].content.as_deref(),
+            Some("[对话历史摘要]\n整段摘要")
+        );
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_plan_maps_back_to_original_indices() -> Result<()> {
+        let messages: Vec<Message> = std::iter::once(Message::system("系统".to_string()))
+            .chain((1..=10).map(|i| Message::user(format!("消息 {i}"))))
+            .collect();
+        let compressor = HybridCompressor::builder()
+            .stage(SlidingWindowCompressor::new(6))
+            .stage(SlidingWindowCompressor::new(3))
+            .build();
+
+        let plan = compressor
+            .compress_ref(CompressionRef {
+                messages: &messages,
+                token_limit: 0,
+                current_query: None,
+            })
+            .await?;
+        let CompressionPlan::Indexed(entries) = &plan else {
+            panic!("内置阶段应返回按下标引用的计划");
+        };
+        let indices: Vec<_> = entries
+            .iter()
+            .map(|p| match p {
+                PlannedMessage::Original(i) => *i,
+                PlannedMessage::New(_) | PlannedMessage::Evicted(_) => usize::MAX,
+            })
+            .collect();
+        assert_eq!(indices, [0, 8, 9, 10]);
+
+        let output = plan.apply(messages);
+        assert_eq!(output.messages.len(), 4);
+        assert_eq!(output.evicted.len(), 7);
+        assert_eq!(output.messages[1].content.as_deref(), Some("消息 8"));
+        Ok(())
+    }
+
+    /// 只保留最后 `n` 条消息（不区分角色），用于构造会丢弃摘要的后续阶段
+    struct KeepLast(usize);
+
+    #[async_trait]
+    impl ContextCompressor for KeepLast {
+        async fn compress(&self, input: CompressionInput) -> Result<CompressionOutput> {
+            let plan = self.compress_ref(input.borrowed()).await?;
+            Ok(plan.apply(input.messages))
+        }
+
+        async fn compress_ref(&self, input: CompressionRef<'_>) -> Result<CompressionPlan> {
+            let len = input.messages.len();
+            Ok(CompressionPlan::Indexed(
+                (len.saturating_sub(self.0)..len)
+                    .map(PlannedMessage::Original)
+                    .collect(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_plan_counts_summary_dropped_by_later_stage() -> Result<()> {
+        let messages: Vec<Message> = (1..=6)
+            .map(|i| Message::user(format!("消息 {i}")))
+            .collect();
+        let build = || {
+            let llm = Arc::new(crate::testing::MockLlmClient::new().with_response("摘要"));
+            HybridCompressor::builder()
+                .stage(SummaryCompressor::new(llm, DefaultSummaryPrompt, 2))
+                .stage(KeepLast(2))
+                .build()
+        };
+
+        let owned = build()
+            .compress(CompressionInput {
+                messages: messages.clone(),
+                token_limit: 100,
+                current_query: None,
+            })
+            .await?;
+        let output = build()
+            .compress_ref(CompressionRef {
+                messages: &messages,
+                token_limit: 100,
+                current_query: None,
+            })
+            .await?
+            .apply(messages);
+
+        assert_eq!(output.messages.len(), 2);
+        assert_eq!(output.evicted.len(), owned.evicted.len());
+        assert_eq!(output.evicted.len(), 5);
+        assert!(
+            output
+                .evicted
+                .iter()
+                .any(|m| m.content.as_deref() == Some("[对话历史摘要]\n摘要"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chapter_markers_skip_compressor_and_follow_kept_messages() -> Result<()> {
+        let chapter = |title: &str| Message {
+            role: CHAPTER_ROLE.to_string(),
+            content: Some(title.to_string()),
+            tool_calls: None,
+            name: None,
+            tool_call_id: None,
+        };
+        let llm = Arc::new(crate::testing::MockLlmClient::new().with_response("摘要"));
+        let mut ctx = ContextManager::builder(4096)
+            .with_system("系统".to_string())
+            .compressor(SummaryCompressor::new(llm.clone(), DefaultSummaryPrompt, 2))
+            .build();
+        ctx.push_many([
+            chapter("第一章标题"),
+            Message::user("问题 1".to_string()),
+            Message::assistant("回答 1".to_string()),
+            chapter("第二章标题"),
+            Message::user("问题 2".to_string()),
+            Message::assistant("回答 2".to_string()),
+            Message::user("问题 3".to_string()),
+        ]);
+
+        let stats = ctx.force_compress(10).await?;
+        assert_eq!(stats.evicted, 3);
+        // 摘要请求中不含章节标记
+        let prompt = llm.all_calls()[0][0].content.clone().unwrap();
+        assert!(!prompt.contains("章标题"), "prompt: {prompt}");
+
+        // 第一章整章被摘要后标记丢弃，第二章标记放回其首条保留消息之前
+        let roles: Vec<_> = ctx.messages().iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(
+            roles,
+            ["system", "system", CHAPTER_ROLE, "assistant", "user"]
+        );
+        assert_eq!(ctx.messages()[2].content.as_deref(), Some("第二章标题"));
+        Ok(())
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! 所有公共 API 返回 [`Result<T>`]，底层错误通过 `From` 自动转换为 [`ReactError`]。
 
+use crate::agent::{Locale, LocaleKey};
 use std::fmt;
 
 /// 框架顶层错误，聚合所有子系统错误
@@ -38,6 +39,8 @@ pub enum MemoryError {
     NotFound(String),
     /// 后端不支持该操作
     Unsupported(String),
+    /// 写入的 key 与已有条目冲突
+    Conflict(String),
 }
 
 /// LLM 相关错误
@@ -49,8 +52,10 @@ pub enum LlmError {
     ApiError { status: u16, message: String },
     /// 响应格式无效
     InvalidResponse(String),
-    /// 没有返回内容
+    /// 没有返回内容（响应体为空或 `choices` 为空）
     EmptyResponse,
+    /// 响应 JSON 结构不符合预期，`detail` 含解析错误与截断后的原始响应
+    MalformedResponse { detail: String },
     /// 序列化/反序列化错误
     SerializationError(String),
 }
@@ -83,6 +88,13 @@ pub enum ParseError {
     JsonError(String),
     /// 输出格式不符合预期
     UnexpectedFormat(String),
+    /// 结构化输出在重试耗尽后仍未通过 schema 校验
+    SchemaViolation {
+        attempts: usize,
+        error: String,
+        /// 最后一次的原始输出
+        raw: String,
+    },
 }
 
 /// Agent 执行错误
@@ -100,6 +112,10 @@ pub enum AgentError {
     NoResponse,
     /// Token 数量超出限制
     TokenLimitExceeded,
+    /// 单次执行累计消耗的 token 超过 `max_total_tokens` 硬预算
+    TokenBudgetExceeded { used: usize, limit: usize },
+    /// Skill 安装前校验失败（缺少依赖等）
+    SkillValidationFailed { skill: String, reason: String },
 }
 
 /// MCP 相关错误
@@ -115,6 +131,8 @@ pub enum McpError {
     ToolCallFailed(String),
     /// 传输层已关闭
     TransportClosed,
+    /// 认证失败（token 获取失败，或刷新后仍被服务端拒绝）
+    AuthenticationFailed(String),
 }
 
 /// 配置错误
@@ -132,6 +150,68 @@ pub enum ConfigError {
     NotFindModelError(String),
 }
 
+// ── 恢复建议 ──────────────────────────────────────────────────────────────────
+
+/// 错误信息中表示权限拒绝的关键字
+const PERMISSION_KEYWORDS: &[&str] = &[
+    "permission denied",
+    "not permitted",
+    "forbidden",
+    "权限",
+    "拒绝",
+    "不被允许",
+    "超出允许的目录范围",
+];
+
+/// 错误信息中表示目标不存在的关键字
+const NOT_FOUND_KEYWORDS: &[&str] = &["not found", "no such file", "不存在", "未找到"];
+
+fn hint_from_message(message: &str) -> LocaleKey {
+    let lower = message.to_lowercase();
+    if PERMISSION_KEYWORDS.iter().any(|k| lower.contains(k)) {
+        LocaleKey::HintPermission
+    } else if NOT_FOUND_KEYWORDS.iter().any(|k| lower.contains(k)) {
+        LocaleKey::HintNotFound
+    } else if lower.contains("timeout") || lower.contains("timed out") || lower.contains("超时") {
+        LocaleKey::HintTimeout
+    } else {
+        LocaleKey::HintGeneric
+    }
+}
+
+impl ReactError {
+    /// 根据错误类型给出面向 LLM 的恢复建议（zh-CN），未识别的错误回退到通用建议
+    pub fn recovery_hint(&self) -> &str {
+        Locale::zh_cn_text(self.recovery_hint_key())
+    }
+
+    /// 恢复建议对应的文案条目，Agent 按 `AgentConfig::locale` 取文案
+    pub(crate) fn recovery_hint_key(&self) -> LocaleKey {
+        match self {
+            ReactError::Tool(ToolError::NotFound(_)) => LocaleKey::HintNotFound,
+            ReactError::Tool(ToolError::Timeout(_)) => LocaleKey::HintTimeout,
+            ReactError::Tool(
+                ToolError::MissingParameter(_) | ToolError::InvalidParameter { .. },
+            ) => LocaleKey::HintParameter,
+            ReactError::Tool(ToolError::ExecutionFailed { message, .. }) => {
+                hint_from_message(message)
+            }
+            ReactError::Memory(MemoryError::NotFound(_)) => LocaleKey::HintNotFound,
+            ReactError::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => LocaleKey::HintNotFound,
+                std::io::ErrorKind::PermissionDenied => LocaleKey::HintPermission,
+                std::io::ErrorKind::TimedOut => LocaleKey::HintTimeout,
+                _ => LocaleKey::HintGeneric,
+            },
+            ReactError::Llm(LlmError::NetworkError(_)) => LocaleKey::HintTimeout,
+            ReactError::Mcp(McpError::ToolCallFailed(message)) | ReactError::Other(message) => {
+                hint_from_message(message)
+            }
+            _ => LocaleKey::HintGeneric,
+        }
+    }
+}
+
 // ── Display impls ────────────────────────────────────────────────────────────
 
 impl fmt::Display for ReactError {
@@ -157,6 +237,7 @@ impl fmt::Display for MemoryError {
             MemoryError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             MemoryError::NotFound(id) => write!(f, "Memory '{}' not found", id),
             MemoryError::Unsupported(op) => write!(f, "Unsupported operation: {}", op),
+            MemoryError::Conflict(key) => write!(f, "Key conflict: {}", key),
         }
     }
 }
@@ -169,6 +250,7 @@ impl fmt::Display for McpError {
             McpError::ProtocolError(msg) => write!(f, "Protocol error: {}", msg),
             McpError::ToolCallFailed(msg) => write!(f, "Tool call failed: {}", msg),
             McpError::TransportClosed => write!(f, "MCP transport closed unexpectedly"),
+            McpError::AuthenticationFailed(msg) => write!(f, "Authentication failed: {}", msg),
         }
     }
 }
@@ -182,6 +264,7 @@ impl fmt::Display for LlmError {
             }
             LlmError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
             LlmError::EmptyResponse => write!(f, "Empty response from LLM"),
+            LlmError::MalformedResponse { detail } => write!(f, "Malformed response: {}", detail),
             LlmError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
         }
     }
@@ -211,6 +294,15 @@ impl fmt::Display for ParseError {
             ParseError::InvalidActionInput(msg) => write!(f, "Invalid Action Input: {}", msg),
             ParseError::JsonError(msg) => write!(f, "JSON parse error: {}", msg),
             ParseError::UnexpectedFormat(msg) => write!(f, "Unexpected format: {}", msg),
+            ParseError::SchemaViolation {
+                attempts,
+                error,
+                raw,
+            } => write!(
+                f,
+                "Output violates schema after {} attempts: {}\nLast output: {}",
+                attempts, error, raw
+            ),
         }
     }
 }
@@ -226,6 +318,16 @@ impl fmt::Display for AgentError {
             AgentError::Interrupted => write!(f, "Execution interrupted"),
             AgentError::NoResponse => write!(f, "No response from LLM"),
             AgentError::TokenLimitExceeded => write!(f, "Token limit exceeded"),
+            AgentError::TokenBudgetExceeded { used, limit } => {
+                write!(
+                    f,
+                    "Token budget exceeded: used {} of {} tokens",
+                    used, limit
+                )
+            }
+            AgentError::SkillValidationFailed { skill, reason } => {
+                write!(f, "Skill '{}' validation failed: {}", skill, reason)
+            }
         }
     }
 }
@@ -351,3 +453,54 @@ impl From<MemoryError> for ReactError {
 
 /// 便捷 Result 别名，错误类型固定为 [`ReactError`]
 pub type Result<T> = std::result::Result<T, ReactError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(message: &str) -> ReactError {
+        ToolError::ExecutionFailed {
+            tool: "read_file".to_string(),
+            message: message.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_recovery_hint_by_error_kind() {
+        let not_found = ReactError::from(ToolError::NotFound("serach".to_string()));
+        let timeout = ReactError::from(ToolError::Timeout("web_fetch".to_string()));
+        let permission = failed("路径 '../etc/passwd' 超出允许的目录范围");
+        let missing = ReactError::from(ToolError::MissingParameter("path".to_string()));
+
+        assert_eq!(not_found.recovery_hint_key(), LocaleKey::HintNotFound);
+        assert_eq!(
+            not_found.recovery_hint(),
+            Locale::zh_cn().text(LocaleKey::HintNotFound)
+        );
+        assert_eq!(timeout.recovery_hint_key(), LocaleKey::HintTimeout);
+        assert_eq!(permission.recovery_hint_key(), LocaleKey::HintPermission);
+        assert_eq!(missing.recovery_hint_key(), LocaleKey::HintParameter);
+        assert_eq!(
+            failed("文件不存在: a.txt").recovery_hint_key(),
+            LocaleKey::HintNotFound
+        );
+        assert_eq!(
+            ReactError::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+                .recovery_hint_key(),
+            LocaleKey::HintPermission
+        );
+    }
+
+    #[test]
+    fn test_recovery_hint_falls_back_to_generic() {
+        assert_eq!(
+            failed("unexpected EOF").recovery_hint_key(),
+            LocaleKey::HintGeneric
+        );
+        assert_eq!(
+            ReactError::from(AgentError::NoResponse).recovery_hint_key(),
+            LocaleKey::HintGeneric
+        );
+    }
+}
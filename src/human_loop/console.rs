@@ -6,6 +6,7 @@
 use std::io::Write as _;
 
 use async_trait::async_trait;
+use serde_json::Value;
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 use super::{HumanLoopKind, HumanLoopProvider, HumanLoopRequest, HumanLoopResponse};
@@ -26,31 +27,34 @@ impl HumanLoopProvider for ConsoleHumanLoopProvider {
                 println!("║  ⚠️  工具审批请求                                          ║");
                 println!("╚══════════════════════════════════════════════════════════╝");
                 println!();
-                println!("工具: {}", req.tool_name.as_deref().unwrap_or("unknown"));
-                if let Some(args) = &req.args {
+                match &req.tool_name {
+                    Some(name) => println!("工具: {}", name),
+                    None => println!("{}", req.prompt),
+                }
+                if let Some(preview) = &req.preview {
+                    println!("操作: {}", preview);
+                } else if let Some(args) = &req.args {
                     let args_str = serde_json::to_string_pretty(args).unwrap_or_default();
                     let lines: Vec<&str> = args_str.lines().take(10).collect();
                     println!("参数: {}", lines.join("\n       "));
                 }
+                if let Some(sandbox) = &req.sandbox {
+                    println!("环境: 沙箱 {}", sandbox);
+                }
                 println!();
-                print!("是否批准执行？(y/n): ");
+                print!("是否批准执行？(y/n，或 y {{json}} 修改参数后批准): ");
                 let _ = std::io::stdout().flush();
 
                 let input = read_line().await?;
-                let trimmed = input.trim();
-
-                if trimmed.eq_ignore_ascii_case("y") || trimmed.eq_ignore_ascii_case("yes") {
-                    println!("✅ 已批准");
-                    Ok(HumanLoopResponse::Approved)
-                } else {
-                    let reason = if trimmed.is_empty() {
-                        None
-                    } else {
-                        Some(format!("用户输入: {}", trimmed))
-                    };
-                    println!("❌ 已拒绝");
-                    Ok(HumanLoopResponse::Rejected { reason })
+                let response = parse_approval_input(input.trim());
+                match &response {
+                    HumanLoopResponse::Approved => println!("✅ 已批准"),
+                    HumanLoopResponse::ApprovedWithModification { args } => {
+                        println!("✅ 已批准（修改参数：{args}）")
+                    }
+                    _ => println!("❌ 已拒绝"),
                 }
+                Ok(response)
             }
             HumanLoopKind::Input => {
                 println!();
@@ -69,6 +73,25 @@ impl HumanLoopProvider for ConsoleHumanLoopProvider {
     }
 }
 
+/// 解析审批输入：`y` 批准，`y {json}` 以修改后的参数批准，其余视为拒绝
+fn parse_approval_input(input: &str) -> HumanLoopResponse {
+    let (answer, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    if !answer.eq_ignore_ascii_case("y") && !answer.eq_ignore_ascii_case("yes") {
+        let reason = (!input.is_empty()).then(|| format!("用户输入: {input}"));
+        return HumanLoopResponse::Rejected { reason };
+    }
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return HumanLoopResponse::Approved;
+    }
+    match serde_json::from_str::<Value>(rest) {
+        Ok(args) if args.is_object() => HumanLoopResponse::ApprovedWithModification { args },
+        _ => HumanLoopResponse::Rejected {
+            reason: Some(format!("修改后的参数不是合法的 JSON 对象: {rest}")),
+        },
+    }
+}
+
 async fn read_line() -> Result<String> {
     let stdin = tokio::io::stdin();
     let mut reader = BufReader::new(stdin);
@@ -76,3 +99,35 @@ async fn read_line() -> Result<String> {
     reader.read_line(&mut buf).await?;
     Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_approval_input() {
+        assert!(matches!(
+            parse_approval_input("y"),
+            HumanLoopResponse::Approved
+        ));
+        assert!(matches!(
+            parse_approval_input("YES"),
+            HumanLoopResponse::Approved
+        ));
+        match parse_approval_input(r#"y {"path": "/safe/dir"}"#) {
+            HumanLoopResponse::ApprovedWithModification { args } => {
+                assert_eq!(args, json!({"path": "/safe/dir"}))
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        assert!(matches!(
+            parse_approval_input("y {broken"),
+            HumanLoopResponse::Rejected { .. }
+        ));
+        assert!(matches!(
+            parse_approval_input("n"),
+            HumanLoopResponse::Rejected { .. }
+        ));
+    }
+}
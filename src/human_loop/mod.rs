@@ -54,7 +54,8 @@ pub use console::ConsoleHumanLoopProvider;
 pub use webhook::WebhookHumanLoopProvider;
 pub use websocket::WebSocketHumanLoopProvider;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -441,6 +442,10 @@ pub struct HumanLoopRequest {
     pub tool_name: Option<String>,
     /// 工具参数（仅 Approval 场景）
     pub args: Option<Value>,
+    /// 人类可读的操作预览（仅 Approval 场景，由 `Tool::preview` 提供）
+    pub preview: Option<String>,
+    /// 工具的执行环境（仅 Approval 场景，由 `Tool::sandbox` 提供；None = 宿主机直接执行）
+    pub sandbox: Option<String>,
 }
 
 impl HumanLoopRequest {
@@ -452,6 +457,58 @@ impl HumanLoopRequest {
             prompt: format!("工具 [{}] 需要人工审批", tool_name),
             tool_name: Some(tool_name),
             args: Some(args),
+            preview: None,
+            sandbox: None,
+        }
+    }
+
+    /// 附加操作预览：prompt 改为展示预览文本，原始参数仍保留在 `args` 中
+    pub fn with_preview(mut self, preview: impl Into<String>) -> Self {
+        let preview = preview.into();
+        self.prompt = format!(
+            "工具 [{}] 需要人工审批：{}",
+            self.tool_name.as_deref().unwrap_or("unknown"),
+            preview
+        );
+        self.preview = Some(preview);
+        self
+    }
+
+    /// 标注执行环境：prompt 末尾追加「将在沙箱 X 中执行」
+    pub fn with_sandbox(mut self, sandbox: impl Into<String>) -> Self {
+        let sandbox = sandbox.into();
+        self.prompt = format!("{}（将在沙箱 {} 中执行）", self.prompt, sandbox);
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// 构造批量确认请求：副作用操作累计超过预算时一次性确认
+    ///
+    /// `operations` 为本批待执行的（工具名, 参数），记录在 `args` 中供 UI 展示明细。
+    pub fn batch_approval(total: usize, operations: &[(String, Value)]) -> Self {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for (name, _) in operations {
+            match counts.iter_mut().find(|(n, _)| n == name) {
+                Some((_, c)) => *c += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+        let summary = counts
+            .iter()
+            .map(|(name, c)| format!("{name} ×{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let args = operations
+            .iter()
+            .map(|(name, args)| serde_json::json!({ "tool": name, "args": args }))
+            .collect();
+        Self {
+            kind: HumanLoopKind::Approval,
+            prompt: format!("你即将执行 {total} 个修改操作，确认继续吗？"),
+            tool_name: None,
+            args: Some(Value::Array(args)),
+            preview: Some(format!("本批: {summary}")),
+            sandbox: None,
         }
     }
 
@@ -462,6 +519,8 @@ impl HumanLoopRequest {
             prompt: prompt.into(),
             tool_name: None,
             args: None,
+            preview: None,
+            sandbox: None,
         }
     }
 }
@@ -473,6 +532,8 @@ impl HumanLoopRequest {
 pub enum HumanLoopResponse {
     /// 用户批准
     Approved,
+    /// 用户批准，但要求改用 `args` 作为工具参数（仍需通过工具的参数 schema 校验）
+    ApprovedWithModification { args: Value },
     /// 用户拒绝
     Rejected { reason: Option<String> },
     /// 用户输入的文本
@@ -512,12 +573,18 @@ pub fn default_provider() -> Arc<dyn HumanLoopProvider> {
 /// 执行前会调用注入的 [`HumanLoopProvider`] 请求确认。
 pub struct HumanApprovalManager {
     need_approval_tools: HashSet<String>,
+    /// 所有工具（含之后注册的）都需要审批
+    require_all: bool,
+    /// 记住的审批决策，键为 [`approval_key`]
+    decisions: HashMap<String, ApprovalDecision>,
 }
 
 impl HumanApprovalManager {
     pub fn new() -> Self {
         HumanApprovalManager {
             need_approval_tools: HashSet::new(),
+            require_all: false,
+            decisions: HashMap::new(),
         }
     }
 
@@ -525,8 +592,72 @@ impl HumanApprovalManager {
         self.need_approval_tools.insert(tool_name);
     }
 
+    /// 标记所有工具都需要审批（用于逐步调试等需要拦截每次调用的场景）
+    pub fn mark_all_need_approval(&mut self) {
+        self.require_all = true;
+    }
+
     pub fn needs_approval(&self, tool_name: &str) -> bool {
-        self.need_approval_tools.contains(tool_name)
+        self.require_all || self.need_approval_tools.contains(tool_name)
+    }
+
+    /// 与该调用特征相同的调用之前的审批决策
+    pub fn remembered_decision(&self, tool_name: &str, args: &Value) -> Option<&ApprovalDecision> {
+        self.decisions.get(&approval_key(tool_name, args))
+    }
+
+    /// 记住用户对该调用的审批决策，之后特征相同的调用沿用此决策
+    pub fn remember_decision(&mut self, tool_name: &str, args: &Value, decision: ApprovalDecision) {
+        self.decisions
+            .insert(approval_key(tool_name, args), decision);
+    }
+
+    /// 清空记住的审批决策
+    pub fn clear_decisions(&mut self) {
+        self.decisions.clear();
+    }
+}
+
+/// 路径类参数的键名：取所在目录作为特征
+const PATH_ARG_KEYS: &[&str] = &["path", "file", "filename", "dir", "directory", "cwd"];
+/// 内容类参数的键名：不参与特征比较
+const CONTENT_ARG_KEYS: &[&str] = &["content", "contents", "text", "data", "body"];
+
+/// 审批决策的缓存键：工具名 + 参数关键特征
+///
+/// 路径类参数（见 [`PATH_ARG_KEYS`]，或以 `_path` / `_file` / `_dir` 结尾）取所在目录，
+/// 内容类参数忽略，其余参数须完全相同。因此同一目录下写入不同文件、不同内容共享决策，
+/// 而写入其他目录、执行不同命令仍需各自审批。
+fn approval_key(tool_name: &str, args: &Value) -> String {
+    let Value::Object(map) = args else {
+        return format!("{tool_name}\0{args}");
+    };
+    let features: BTreeMap<&str, Value> = map
+        .iter()
+        .filter(|(key, _)| !CONTENT_ARG_KEYS.contains(&key.to_lowercase().as_str()))
+        .map(|(key, value)| match value.as_str() {
+            Some(path) if is_path_key(key) => (key.as_str(), Value::String(parent_dir(path))),
+            _ => (key.as_str(), value.clone()),
+        })
+        .collect();
+    format!(
+        "{tool_name}\0{}",
+        serde_json::to_string(&features).unwrap_or_default()
+    )
+}
+
+fn is_path_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    PATH_ARG_KEYS.contains(&key.as_str())
+        || ["_path", "_file", "_dir"].iter().any(|s| key.ends_with(s))
+}
+
+/// 路径所在目录；没有目录部分的相对路径视为当前目录
+fn parent_dir(path: &str) -> String {
+    match Path::new(path).parent() {
+        Some(parent) if parent.as_os_str().is_empty() => ".".to_string(),
+        Some(parent) => parent.to_string_lossy().into_owned(),
+        None => path.to_string(),
     }
 }
 
@@ -550,7 +681,7 @@ mod tests {
         };
 
         match approved {
-            ApprovalDecision::Approved => assert!(true),
+            ApprovalDecision::Approved => {}
             _ => panic!("Should be Approved"),
         }
 
@@ -637,6 +768,28 @@ mod tests {
         assert_eq!(request.kind, HumanLoopKind::Approval);
         assert_eq!(request.tool_name, Some("test_tool".to_string()));
         assert!(request.args.is_some());
+        assert!(request.preview.is_none());
+    }
+
+    #[test]
+    fn test_human_loop_request_approval_with_preview() {
+        let request = HumanLoopRequest::approval("write_file", serde_json::json!({"path": "a"}))
+            .with_preview("将向 a 写入 3 字节");
+
+        assert_eq!(
+            request.prompt,
+            "工具 [write_file] 需要人工审批：将向 a 写入 3 字节"
+        );
+        assert_eq!(request.preview.as_deref(), Some("将向 a 写入 3 字节"));
+
+        let request = request.with_sandbox("docker:alpine");
+        assert_eq!(request.sandbox.as_deref(), Some("docker:alpine"));
+        assert!(
+            request
+                .prompt
+                .ends_with("（将在沙箱 docker:alpine 中执行）")
+        );
+        assert!(request.args.is_some());
     }
 
     #[test]
@@ -659,7 +812,7 @@ mod tests {
         let timeout = HumanLoopResponse::Timeout;
 
         match approved {
-            HumanLoopResponse::Approved => assert!(true),
+            HumanLoopResponse::Approved => {}
             _ => panic!("Should be Approved"),
         }
 
@@ -674,7 +827,7 @@ mod tests {
         }
 
         match timeout {
-            HumanLoopResponse::Timeout => assert!(true),
+            HumanLoopResponse::Timeout => {}
             _ => panic!("Should be Timeout"),
         }
     }
@@ -709,6 +862,68 @@ mod tests {
         assert!(!manager.needs_approval("tool4"));
     }
 
+    #[test]
+    fn test_mark_all_need_approval() {
+        let mut manager = HumanApprovalManager::new();
+        assert!(!manager.needs_approval("any_tool"));
+        manager.mark_all_need_approval();
+        assert!(manager.needs_approval("any_tool"));
+    }
+
+    #[test]
+    fn test_remembered_decision_by_directory() {
+        use serde_json::json;
+
+        let mut manager = HumanApprovalManager::new();
+        manager.remember_decision(
+            "write_file",
+            &json!({ "path": "/workspace/a.txt", "content": "1" }),
+            ApprovalDecision::Approved,
+        );
+
+        // 同目录的其他文件、不同内容沿用决策
+        assert_eq!(
+            manager.remembered_decision(
+                "write_file",
+                &json!({ "path": "/workspace/b.txt", "content": "2" })
+            ),
+            Some(&ApprovalDecision::Approved)
+        );
+        // 其他目录、其他工具需重新审批
+        assert!(
+            manager
+                .remembered_decision("write_file", &json!({ "path": "/etc/passwd" }))
+                .is_none()
+        );
+        assert!(
+            manager
+                .remembered_decision("delete_file", &json!({ "path": "/workspace/a.txt" }))
+                .is_none()
+        );
+
+        // 非路径参数须完全相同
+        manager.remember_decision(
+            "shell",
+            &json!({ "command": "ls", "cwd": "/workspace/x" }),
+            ApprovalDecision::Approved,
+        );
+        assert!(
+            manager
+                .remembered_decision(
+                    "shell",
+                    &json!({ "command": "rm -rf .", "cwd": "/workspace/x" })
+                )
+                .is_none()
+        );
+
+        manager.clear_decisions();
+        assert!(
+            manager
+                .remembered_decision("write_file", &json!({ "path": "/workspace/b.txt" }))
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn test_human_loop_manager_new() {
         let manager = HumanLoopManager::new();
@@ -722,12 +937,12 @@ mod tests {
         let input = HumanLoopKind::Input;
 
         match approval {
-            HumanLoopKind::Approval => assert!(true),
+            HumanLoopKind::Approval => {}
             _ => panic!("Should be Approval"),
         }
 
         match input {
-            HumanLoopKind::Input => assert!(true),
+            HumanLoopKind::Input => {}
             _ => panic!("Should be Input"),
         }
     }
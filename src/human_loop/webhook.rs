@@ -72,6 +72,10 @@ struct WebhookPayload<'a> {
     tool_name: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     args: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sandbox: Option<&'a str>,
 }
 
 /// Webhook 统一响应体。
@@ -97,6 +101,8 @@ impl HumanLoopProvider for WebhookHumanLoopProvider {
             prompt: &req.prompt,
             tool_name: req.tool_name.as_deref(),
             args: req.args.as_ref(),
+            preview: req.preview.as_deref(),
+            sandbox: req.sandbox.as_deref(),
         };
 
         let resp = self
@@ -74,6 +74,10 @@ struct ServerMessage<'a> {
     tool_name: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     args: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sandbox: Option<&'a str>,
 }
 
 /// 客户端返回的响应（统一格式）。
@@ -206,6 +210,8 @@ impl HumanLoopProvider for WebSocketHumanLoopProvider {
             prompt: &req.prompt,
             tool_name: req.tool_name.as_deref(),
             args: req.args.as_ref(),
+            preview: req.preview.as_deref(),
+            sandbox: req.sandbox.as_deref(),
         })
         .map_err(|e| ReactError::Other(format!("WS 消息序列化失败: {e}")))?;
 
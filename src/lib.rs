@@ -7,7 +7,7 @@
 //! - **ReAct 执行引擎**: 自动工具调用、多轮推理、流式输出
 //! - **工具系统**: 内置工具 + MCP 协议 + 自定义扩展
 //! - **双层记忆**: 会话持久化 + 长期 KV 存储
-//! - **上下文压缩**: 滑动窗口 / LLM 摘要 / 混合管道
+//! - **上下文压缩**: 滑动窗口 / LLM 摘要 / 相关度过滤 / 混合管道
 //! - **人工介入**: 审批 guard / 文本输入，支持多渠道
 //!
 //! ## 快速开始
@@ -81,18 +81,21 @@ pub mod tools;
 ///
 /// 包含最常用的类型，通过 `use echo_agent::prelude::*` 导入。
 pub mod prelude {
-    pub use crate::agent::react_agent::ReactAgent;
     pub use crate::agent::react_agent::StepType;
+    pub use crate::agent::react_agent::{ContextBreakdown, ReactAgent, StepOutcome, VariantConfig};
     pub use crate::agent::{
-        Agent, AgentBuilder, AgentCallback, AgentConfig, AgentEvent, AgentRole, CancellationToken,
-        ReactAgentBuilder,
+        Agent, AgentBuilder, AgentCallback, AgentConfig, AgentEvent, AgentRole, BudgetKind,
+        CallbackMode, CancellationToken, ExecutionResult, ExecutionTrace, Locale, LocaleKey,
+        ReactAgentBuilder, ReflectionConfig, SecretAction, SecretPolicy, ToolCallRecord,
+        ToolResultOrdering,
     };
     pub use crate::compression::compressor::{
-        DefaultSummaryPrompt, FnSummaryPrompt, HybridCompressor, SlidingWindowCompressor,
-        SummaryCompressor, SummaryPromptBuilder,
+        DefaultSummaryPrompt, FnSummaryPrompt, HybridCompressor, RelevanceFilterCompressor,
+        SlidingWindowCompressor, SummaryCompressor, SummaryPromptBuilder,
     };
     pub use crate::compression::{
-        CompressionInput, CompressionOutput, ContextCompressor, ContextManager, ForceCompressStats,
+        CompressionInput, CompressionOutput, CompressionPlan, CompressionRef, ContextCompressor,
+        ContextManager, ForceCompressStats, PlannedMessage,
     };
     pub use crate::error::Result;
     pub use crate::human_loop::{
@@ -102,15 +105,17 @@ pub mod prelude {
     };
     pub use crate::llm::types::{Message, ToolCall};
     pub use crate::llm::{
-        ChatChunk, ChatRequest, ChatResponse, JsonSchemaSpec, LlmClient, LlmConfig, OpenAiClient,
-        ResponseFormat, ToolDefinition,
+        ChatChunk, ChatOptions, ChatRequest, ChatResponse, JsonSchemaSpec, LlmClient, LlmConfig,
+        OpenAiClient, ResponseFormat, ToolChoice, ToolDefinition,
     };
     pub use crate::mcp::types::McpTool;
     pub use crate::mcp::{McpManager, McpServerConfig, TransportConfig};
     pub use crate::memory::checkpointer::{Checkpointer, FileCheckpointer, InMemoryCheckpointer};
     pub use crate::memory::embedder::{Embedder, HttpEmbedder};
     pub use crate::memory::embedding_store::EmbeddingStore;
-    pub use crate::memory::store::{FileStore, InMemoryStore, Store, StoreItem};
+    pub use crate::memory::store::{
+        FileStore, ImportConflict, ImportOptions, InMemoryStore, Store, StoreItem,
+    };
     pub use crate::skills::{
         Skill, SkillInfo, SkillManager,
         builtin::{CalculatorSkill, FileSystemSkill, ShellSkill, WeatherSkill},
@@ -118,5 +123,8 @@ pub mod prelude {
     };
     pub use crate::testing::{FailingMockAgent, MockAgent, MockEmbedder, MockLlmClient, MockTool};
     pub use crate::tools::builtin::think::ThinkTool;
-    pub use crate::tools::{Tool, ToolExecutionConfig, ToolParameters, ToolResult};
+    pub use crate::tools::{
+        ContextHint, Randomness, SeededRng, Source, SourceKind, Tool, ToolExecutionConfig,
+        ToolParameters, ToolQuota, ToolResult,
+    };
 }
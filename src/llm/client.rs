@@ -1,12 +1,86 @@
 use crate::error::{LlmError, Result};
 use crate::llm::ChatCompletionRequest;
-use crate::llm::types::{ChatCompletionChunk, ChatCompletionResponse};
+use crate::llm::prompt_template::PromptTemplate;
+use crate::llm::types::{
+    ChatCompletionChunk, ChatCompletionResponse, Choice, ChunkChoice, DeltaFunctionCall,
+    DeltaMessage, DeltaToolCall, Message, TextCompletionRequest, TextCompletionResponse,
+};
 use futures::Stream;
 use futures::StreamExt;
 use reqwest::Client;
 use reqwest::header::HeaderMap;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// OpenAI 兼容接口的 Chat Completions 路径
+const CHAT_COMPLETIONS_PATH: &str = "/chat/completions";
+
+/// 文本补全接口路径
+const COMPLETIONS_PATH: &str = "/completions";
+
+/// 错误信息中附带的原始响应片段最大字符数
+const BODY_SNIPPET_CHARS: usize = 200;
+
+/// 规范化 Chat Completions 端点 URL
+///
+/// - 去掉首尾空白与路径尾部多余的 `/`，避免拼出 `//`
+/// - 查询串（如 Azure 的 `?api-version=...`）先拆出，路径处理完再原样拼回
+/// - `complete_path` 为 true 时只补全两种明显不完整的形式：只有域名时补 `/v1/chat/completions`，
+///   末段是版本号（如 `/v1`、`/v4`）时补 `/chat/completions`；其余路径（已是
+///   `/chat/completions` 或其他自定义端点）保持不变
+/// - `complete_path = false` 时仅做空白与尾部斜杠清理
+pub fn normalize_chat_url(base_url: &str, complete_path: bool) -> String {
+    let base_url = base_url.trim();
+    let (url, query) = match base_url.find(['?', '#']) {
+        Some(at) => base_url.split_at(at),
+        None => (base_url, ""),
+    };
+    let url = url.trim_end_matches('/');
+    let suffix = if !complete_path || url.ends_with(CHAT_COMPLETIONS_PATH) {
+        ""
+    } else if ends_with_version_segment(url) {
+        CHAT_COMPLETIONS_PATH
+    } else if is_bare_host(url) {
+        "/v1/chat/completions"
+    } else {
+        ""
+    };
+    format!("{url}{suffix}{query}")
+}
+
+/// 规范化文本补全端点 URL：按 [`normalize_chat_url`] 补全后把 `/chat/completions` 换成 `/completions`
+///
+/// `complete_path` 为 false 时认为 `base_url` 已是完整的补全端点，只做尾部斜杠清理。查询串原样保留。
+pub fn normalize_completion_url(base_url: &str, complete_path: bool) -> String {
+    let url = normalize_chat_url(base_url, complete_path);
+    if !complete_path {
+        return url;
+    }
+    let (path, query) = match url.find(['?', '#']) {
+        Some(at) => url.split_at(at),
+        None => (url.as_str(), ""),
+    };
+    match path.strip_suffix(CHAT_COMPLETIONS_PATH) {
+        Some(prefix) => format!("{prefix}{COMPLETIONS_PATH}{query}"),
+        None => url,
+    }
+}
+
+/// URL 是否只有 scheme://host（可带端口），没有任何路径
+fn is_bare_host(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    !rest.contains('/')
+}
+
+/// 末段路径是否为 `v<数字>` 形式的 API 版本号（scheme://host 本身不算）
+fn ends_with_version_segment(url: &str) -> bool {
+    let path = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let Some((_, last)) = path.rsplit_once('/') else {
+        return false;
+    };
+    last.strip_prefix('v')
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
 
 pub async fn post(
     client: Arc<Client>,
@@ -25,23 +99,16 @@ pub async fn post(
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let status = response.status().as_u16();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(LlmError::ApiError {
-            status,
-            message: error_text,
-        }
-        .into());
+    let status = response.status();
+    if !status.is_success() {
+        return Err(api_error(status.as_u16(), response).await.into());
     }
 
-    let completion_response = response
-        .json::<ChatCompletionResponse>()
+    let body = response
+        .text()
         .await
-        .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+        .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+    let completion_response = parse_completion_body(status.as_u16(), &body)?;
 
     debug!(
         "Post completion response: {}",
@@ -52,9 +119,64 @@ pub async fn post(
     Ok(completion_response)
 }
 
+/// 发送文本补全请求，并用 `template` 把回复解析为 assistant 消息
+///
+/// 返回值与 Chat Completions 的响应结构一致，上层无需区分两种接口；
+/// 解析出工具调用时 `finish_reason` 记为 `tool_calls`。
+pub async fn post_text_completion(
+    client: Arc<Client>,
+    request_body: &TextCompletionRequest,
+    header_map: HeaderMap,
+    url: &str,
+    template: &dyn PromptTemplate,
+) -> Result<ChatCompletionResponse> {
+    debug!(
+        "Post text completion request_body: {}",
+        serde_json::to_string(request_body).unwrap_or_else(|e| format!("<serialize error: {}>", e))
+    );
+    let response = client
+        .post(url)
+        .headers(header_map)
+        .json(request_body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(api_error(status.as_u16(), response).await.into());
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+    let text_response: TextCompletionResponse = parse_json_body(status.as_u16(), &body)?;
+    let Some(choice) = text_response.choices.into_iter().next() else {
+        warn!(body = %body_snippet(&body), "LLM 响应的 choices 为空");
+        return Err(LlmError::EmptyResponse.into());
+    };
+
+    let message = template.parse_reply(&choice.text);
+    let finish_reason = if message.tool_calls.is_some() {
+        Some("tool_calls".to_string())
+    } else {
+        choice.finish_reason
+    };
+    let mut completion = ChatCompletionResponse::default().with_usage(text_response.usage);
+    completion.choices.push(Choice::new(message, finish_reason));
+    Ok(completion)
+}
+
+/// 流式响应中途断开后的最大续写次数
+const MAX_STREAM_RESUMES: usize = 2;
+
 /// 发送带 `stream: true` 的请求，返回解析好的 SSE chunk 流
 ///
 /// 注意：接收 `request_body` 的所有权，避免引用与 async stream 的生命周期冲突。
+///
+/// 连接建立后若中途断流，最多重连 [`MAX_STREAM_RESUMES`] 次：已收到的文本保留，
+/// 重新请求时追加一条 user 消息让模型从断点续写，续写开头与已有内容重复的部分会被去掉。
+/// 已开始输出工具调用时不续写，直接返回错误；建立连接前的失败原样返回，由调用方重试。
 pub async fn stream_post(
     client: Arc<Client>,
     request_body: ChatCompletionRequest,
@@ -71,29 +193,183 @@ pub async fn stream_post(
             .unwrap_or_else(|e| format!("<serialize error: {}>", e))
     );
 
+    let response = send_stream_request(&client, &request_body, &header_map, &url).await?;
+
+    let stream = async_stream::try_stream! {
+        let mut chunks = Box::pin(sse_chunks(response));
+        // 已输出的文本内容，断流后作为续写依据
+        let mut received = String::new();
+        let mut has_tool_calls = false;
+        let mut resumes = 0;
+        // 续写开头暂存区：内容仍是已有文本的片段时先不输出，用于去重
+        let mut pending: Option<String> = None;
+
+        loop {
+            let chunk = match chunks.next().await {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(e)) => {
+                    if has_tool_calls || resumes >= MAX_STREAM_RESUMES {
+                        Err(e)?;
+                        return;
+                    }
+                    resumes += 1;
+                    warn!(
+                        received = received.chars().count(),
+                        attempt = resumes,
+                        error = %e,
+                        "⚠️ 流式响应中途断开，重连续写（{resumes}/{MAX_STREAM_RESUMES}）"
+                    );
+                    let resume_body = continuation_request(&request_body, &received);
+                    let response =
+                        send_stream_request(&client, &resume_body, &header_map, &url).await?;
+                    chunks = Box::pin(sse_chunks(response));
+                    pending = (!received.is_empty()).then(String::new);
+                    continue;
+                }
+                None => {
+                    if let Some(rest) = pending.take() {
+                        let rest = rest[overlap_len(&received, &rest)..].to_string();
+                        if !rest.is_empty() {
+                            yield content_chunk(rest);
+                        }
+                    }
+                    return;
+                }
+            };
+
+            has_tool_calls |= chunk
+                .choices
+                .iter()
+                .any(|c| c.delta.tool_calls.as_ref().is_some_and(|t| !t.is_empty()));
+            let content: String = chunk
+                .choices
+                .iter()
+                .filter_map(|c| c.delta.content.as_deref())
+                .collect();
+
+            if let Some(buffer) = pending.as_mut() {
+                buffer.push_str(&content);
+                let only_content = !has_tool_calls
+                    && chunk.choices.iter().all(|c| c.finish_reason.is_none());
+                if only_content && received.contains(buffer.as_str()) {
+                    continue;
+                }
+                let buffer = pending.take().unwrap_or_default();
+                let rest = buffer[overlap_len(&received, &buffer)..].to_string();
+                received.push_str(&rest);
+                let mut chunk = chunk;
+                for choice in &mut chunk.choices {
+                    choice.delta.content = None;
+                }
+                if let Some(first) = chunk.choices.first_mut() {
+                    first.delta.content = Some(rest);
+                }
+                yield chunk;
+                continue;
+            }
+
+            received.push_str(&content);
+            yield chunk;
+        }
+    };
+
+    Ok(stream)
+}
+
+/// 发送流式请求，非 2xx 状态码转为 [`LlmError::ApiError`]
+async fn send_stream_request(
+    client: &Client,
+    request_body: &ChatCompletionRequest,
+    header_map: &HeaderMap,
+    url: &str,
+) -> Result<reqwest::Response> {
     let response = client
-        .post(&url)
-        .headers(header_map)
-        .json(&request_body)
+        .post(url)
+        .headers(header_map.clone())
+        .json(request_body)
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let status = response.status().as_u16();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(LlmError::ApiError {
-            status,
-            message: error_text,
+    let status = response.status();
+    if !status.is_success() {
+        return Err(api_error(status.as_u16(), response).await.into());
+    }
+    Ok(response)
+}
+
+/// 把非 2xx 响应转为 [`LlmError::ApiError`]，能识别错误体格式时只保留其中的错误信息
+async fn api_error(status: u16, response: reqwest::Response) -> LlmError {
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    LlmError::ApiError {
+        status,
+        message: error_body_message(&body).unwrap_or(body),
+    }
+}
+
+/// 解析非流式响应体
+///
+/// - 空 body 或 `choices` 为空 → [`LlmError::EmptyResponse`]
+/// - 状态码成功但 body 是错误体 → [`LlmError::ApiError`]
+/// - 结构不符合预期 → [`LlmError::MalformedResponse`]，附带截断后的原始响应
+fn parse_completion_body(status: u16, body: &str) -> Result<ChatCompletionResponse> {
+    let response: ChatCompletionResponse = parse_json_body(status, body)?;
+    if response.choices.is_empty() {
+        warn!(body = %body_snippet(body), "LLM 响应的 choices 为空");
+        return Err(LlmError::EmptyResponse.into());
+    }
+    Ok(response)
+}
+
+/// 按 JSON 解析响应体，处理空 body、错误体与结构不符三种情况
+fn parse_json_body<T: serde::de::DeserializeOwned>(status: u16, body: &str) -> Result<T> {
+    if body.trim().is_empty() {
+        return Err(LlmError::EmptyResponse.into());
+    }
+    if let Some(message) = error_body_message(body) {
+        return Err(LlmError::ApiError { status, message }.into());
+    }
+    serde_json::from_str(body).map_err(|e| {
+        LlmError::MalformedResponse {
+            detail: format!("{e}; body: {}", body_snippet(body)),
+        }
+        .into()
+    })
+}
+
+/// 从常见的错误体中提取错误信息
+///
+/// 支持 `{"error": {"message", "type"}}`、`{"error": "..."}` 两种格式，其他格式返回 None。
+fn error_body_message(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    match value.get("error")? {
+        serde_json::Value::String(message) => Some(message.clone()),
+        error => {
+            let message = error.get("message").and_then(|m| m.as_str())?;
+            Some(match error.get("type").and_then(|t| t.as_str()) {
+                Some(kind) => format!("{message} ({kind})"),
+                None => message.to_string(),
+            })
         }
-        .into());
     }
+}
 
+/// 截断原始响应，避免错误信息过长
+fn body_snippet(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// 把响应体解析为 SSE chunk 流；读取字节失败时产出 [`LlmError::NetworkError`]
+fn sse_chunks(response: reqwest::Response) -> impl Stream<Item = Result<ChatCompletionChunk>> {
     let byte_stream = response.bytes_stream();
 
-    let stream = async_stream::try_stream! {
+    async_stream::try_stream! {
         let mut buffer = String::new();
         tokio::pin!(byte_stream);
 
@@ -131,7 +407,377 @@ pub async fn stream_post(
                         yield chunk;
                     }
             }
-    };
+    }
+}
 
-    Ok(stream)
+/// 续写请求：已有内容为空时原样重发，否则追加一条要求从断点继续的 user 消息
+fn continuation_request(
+    request_body: &ChatCompletionRequest,
+    received: &str,
+) -> ChatCompletionRequest {
+    let mut body = request_body.clone();
+    if !received.is_empty() {
+        body.messages.push(Message::user(format!(
+            "继续你刚才的回复（已有：{received}）\n\
+             回复因网络中断被截断，请从断点处直接续写剩余部分，不要重复已有内容，也不要添加任何说明。"
+        )));
+    }
+    body
+}
+
+/// 续写内容开头与已有内容末尾重叠的字节数（取最长重叠）
+fn overlap_len(received: &str, continuation: &str) -> usize {
+    continuation
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(continuation.len()))
+        .rev()
+        .find(|&end| received.ends_with(&continuation[..end]))
+        .unwrap_or(0)
+}
+
+/// 只含文本增量的 chunk
+fn content_chunk(content: String) -> ChatCompletionChunk {
+    ChatCompletionChunk::new(
+        String::new(),
+        vec![ChunkChoice {
+            delta: DeltaMessage {
+                content: Some(content),
+                ..Default::default()
+            },
+            finish_reason: None,
+            index: 0,
+        }],
+    )
+}
+
+/// 把完整响应转为单个 chunk，供不支持增量流式的文本模板模拟流式输出
+pub(crate) fn response_chunk(response: ChatCompletionResponse) -> ChatCompletionChunk {
+    let fingerprint = response.system_fingerprint().map(str::to_string);
+    let choice = response.choices.into_iter().next();
+    let finish_reason = choice.as_ref().and_then(|c| c.finish_reason.clone());
+    let message = choice.map(|c| c.message).unwrap_or_default();
+    let tool_calls = message.tool_calls.map(|calls| {
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| DeltaToolCall {
+                index: index as u32,
+                id: Some(call.id),
+                call_type: Some(call.call_type),
+                function: Some(DeltaFunctionCall {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                }),
+            })
+            .collect()
+    });
+    ChatCompletionChunk::new(
+        String::new(),
+        vec![ChunkChoice {
+            delta: DeltaMessage {
+                role: Some("assistant".to_string()),
+                content: message.content,
+                tool_calls,
+            },
+            finish_reason,
+            index: 0,
+        }],
+    )
+    .with_system_fingerprint(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ReactError;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn sse_event(content: &str) -> String {
+        let chunk = serde_json::json!({
+            "id": "c1",
+            "choices": [{ "index": 0, "delta": { "content": content } }],
+        });
+        format!("data: {chunk}\n\n")
+    }
+
+    /// 读取一个完整的 HTTP 请求（按 Content-Length 读完请求体），返回请求体
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&data);
+            if let Some(pos) = text.find("\r\n\r\n") {
+                let length = text[..pos]
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if data.len() >= pos + 4 + length {
+                    return String::from_utf8_lossy(&data[pos + 4..]).to_string();
+                }
+            }
+        }
+    }
+
+    /// 模拟 SSE 服务端：第一次连接输出部分内容后直接断开，第二次连接返回续写内容
+    #[tokio::test]
+    async fn test_stream_post_resumes_after_mid_stream_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut bodies = Vec::new();
+
+            let (mut socket, _) = listener.accept().await.unwrap();
+            bodies.push(read_request(&mut socket).await);
+            let events = sse_event("你好，") + &sse_event("今天");
+            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+            let chunk = format!("{:x}\r\n{events}\r\n", events.len());
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(chunk.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+            // 不发送结束块直接断开，模拟中途断流
+            drop(socket);
+
+            let (mut socket, _) = listener.accept().await.unwrap();
+            bodies.push(read_request(&mut socket).await);
+            let events = sse_event("今天天气") + &sse_event("晴朗。") + "data: [DONE]\n\n";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{events}",
+                events.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            bodies
+        });
+
+        let request = ChatCompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![Message::user("天气如何？".to_string())],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: Some(true),
+            response_format: None,
+            seed: None,
+            n: None,
+        };
+        let stream = stream_post(
+            Arc::new(Client::new()),
+            request,
+            HeaderMap::new(),
+            format!("http://{addr}/v1/chat/completions"),
+        )
+        .await
+        .unwrap();
+        let chunks: Vec<_> = stream.collect().await;
+        let text: String = chunks
+            .into_iter()
+            .map(|c| c.unwrap())
+            .flat_map(|c| c.choices)
+            .filter_map(|c| c.delta.content)
+            .collect();
+        assert_eq!(text, "你好，今天天气晴朗。");
+
+        let bodies = server.await.unwrap();
+        let resumed: ChatCompletionRequest = serde_json::from_str(&bodies[1]).unwrap();
+        assert_eq!(resumed.messages.len(), 2);
+        let hint = resumed.messages[1].content.as_deref().unwrap();
+        assert!(hint.contains("已有：你好，今天"));
+    }
+
+    /// 服务端收到请求后不响应：按 HttpConfig 的读取超时失败，而不是一直挂起
+    #[tokio::test]
+    async fn test_http_config_read_timeout_applied() {
+        use crate::llm::HttpConfig;
+        use std::time::{Duration, Instant};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request(&mut socket).await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let client = HttpConfig {
+            read_timeout_ms: 300,
+            ..Default::default()
+        }
+        .build_client()
+        .unwrap();
+        let request = ChatCompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![Message::user("hi".to_string())],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+            response_format: None,
+            seed: None,
+            n: None,
+        };
+        let start = Instant::now();
+        let err = post(
+            Arc::new(client),
+            &request,
+            HeaderMap::new(),
+            &format!("http://{addr}/v1/chat/completions"),
+        )
+        .await
+        .unwrap_err();
+        let elapsed = start.elapsed();
+        assert!(err.to_string().contains("timeout"), "{err}");
+        assert!(
+            elapsed >= Duration::from_millis(300) && elapsed < Duration::from_secs(5),
+            "{elapsed:?}"
+        );
+        server.abort();
+    }
+
+    #[test]
+    fn test_overlap_len() {
+        assert_eq!(overlap_len("你好，今天", "今天天气"), "今天".len());
+        assert_eq!(overlap_len("abc", "abc"), 3);
+        assert_eq!(overlap_len("abc", "xyz"), 0);
+    }
+
+    #[test]
+    fn test_normalize_chat_url_completes_missing_path() {
+        let expected = "https://api.x.com/v1/chat/completions";
+        for base in [
+            "https://api.x.com",
+            "https://api.x.com/",
+            "https://api.x.com/v1",
+            "https://api.x.com/v1/",
+            "https://api.x.com/v1/chat/completions",
+            "https://api.x.com/v1/chat/completions/",
+            "  https://api.x.com//  ",
+        ] {
+            assert_eq!(
+                normalize_chat_url(base, true),
+                expected,
+                "base_url = {base:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_chat_url_keeps_existing_endpoint_and_version() {
+        // 不带 /v1 的完整端点不再追加
+        assert_eq!(
+            normalize_chat_url("https://api.deepseek.com/chat/completions", true),
+            "https://api.deepseek.com/chat/completions"
+        );
+        // 非 v1 的版本段保留原样，只补 /chat/completions
+        assert_eq!(
+            normalize_chat_url("https://open.bigmodel.cn/api/paas/v4/", true),
+            "https://open.bigmodel.cn/api/paas/v4/chat/completions"
+        );
+        assert_eq!(
+            normalize_chat_url("https://dashscope.aliyuncs.com/compatible-mode/v1", true),
+            "https://dashscope.aliyuncs.com/compatible-mode/v1/chat/completions"
+        );
+        // 既非版本号也非 /chat/completions 的路径视为自定义端点，保持不变
+        assert_eq!(
+            normalize_chat_url("http://localhost:8000/openai/", true),
+            "http://localhost:8000/openai"
+        );
+        assert_eq!(
+            normalize_chat_url("https://gateway.x.com/llm/invoke", true),
+            "https://gateway.x.com/llm/invoke"
+        );
+    }
+
+    #[test]
+    fn test_normalize_chat_url_keeps_query_string() {
+        let azure = "https://res.openai.azure.com/openai/deployments/gpt4o/chat/completions?api-version=2024-06-01";
+        assert_eq!(normalize_chat_url(azure, true), azure);
+        assert_eq!(
+            normalize_chat_url("https://api.x.com/v1/?key=abc", true),
+            "https://api.x.com/v1/chat/completions?key=abc"
+        );
+        assert_eq!(
+            normalize_completion_url("https://api.x.com/v1?key=abc", true),
+            "https://api.x.com/v1/completions?key=abc"
+        );
+    }
+
+    #[test]
+    fn test_normalize_chat_url_disabled_only_trims() {
+        assert_eq!(
+            normalize_chat_url("https://gateway.x.com/llm/invoke/", false),
+            "https://gateway.x.com/llm/invoke"
+        );
+        assert_eq!(
+            normalize_chat_url("https://api.x.com", false),
+            "https://api.x.com"
+        );
+    }
+
+    #[test]
+    fn test_normalize_completion_url() {
+        assert_eq!(
+            normalize_completion_url("https://api.x.com/v1", true),
+            "https://api.x.com/v1/completions"
+        );
+        assert_eq!(
+            normalize_completion_url("https://api.x.com/v1/chat/completions", true),
+            "https://api.x.com/v1/completions"
+        );
+        assert_eq!(
+            normalize_completion_url("https://gateway.x.com/generate/", false),
+            "https://gateway.x.com/generate"
+        );
+    }
+
+    #[test]
+    fn test_parse_completion_body_empty() {
+        for body in ["", "  \n", r#"{"id": "x", "choices": []}"#] {
+            let err = parse_completion_body(200, body).unwrap_err();
+            assert!(
+                matches!(err, ReactError::Llm(LlmError::EmptyResponse)),
+                "{body:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_completion_body_error_body() {
+        let err = parse_completion_body(
+            200,
+            r#"{"error": {"message": "model not found", "type": "invalid_request_error"}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ReactError::Llm(LlmError::ApiError { status: 200, ref message })
+                if message == "model not found (invalid_request_error)"
+        ));
+
+        assert_eq!(
+            error_body_message(r#"{"error": "quota exceeded"}"#).as_deref(),
+            Some("quota exceeded")
+        );
+        assert_eq!(error_body_message("<html>502</html>"), None);
+    }
+
+    #[test]
+    fn test_parse_completion_body_malformed() {
+        let body = format!(r#"{{"choices": "oops", "padding": "{}"}}"#, "x".repeat(500));
+        let err = parse_completion_body(200, &body).unwrap_err();
+        let ReactError::Llm(LlmError::MalformedResponse { detail }) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(detail.contains(r#"body: {"choices": "oops""#), "{detail}");
+        assert!(detail.ends_with('…'), "{detail}");
+        assert!(detail.chars().count() < 400, "{detail}");
+    }
 }
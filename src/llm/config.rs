@@ -5,26 +5,100 @@
 //! AGENT_MODEL_<ID>_MODEL=gpt-4o
 //! AGENT_MODEL_<ID>_BASEURL=https://api.openai.com/v1/chat/completions
 //! AGENT_MODEL_<ID>_APIKEY=sk-...
+//! AGENT_MODEL_<ID>_AUTOPATH=false   # 可选，关闭 base_url 路径自动补全
+//! AGENT_MODEL_<ID>_ROLEMAP=tool_as_user,merge_system   # 可选，消息角色重映射
+//! AGENT_MODEL_<ID>_TEMPLATE=chatml   # 可选，prompt 模板（openai / chatml / alpaca）
 //! ```
 //! `<ID>` 为自定义标识（如 `GPT4O`、`QWEN`），不区分大小写。
+//!
+//! `BASEURL` 可以只填到域名或版本号（如 `https://api.openai.com/v1`），
+//! 请求时会自动补全 `/chat/completions`，详见 [`normalize_chat_url`]。已经指向其他自定义路径的
+//! `BASEURL` 不会被改写（只去掉尾部斜杠），查询串原样保留。
 
 use crate::error::{ConfigError, ReactError, Result};
+use crate::llm::client::normalize_completion_url;
+use crate::llm::normalize_chat_url;
+use crate::llm::prompt_template::PromptFormat;
+use crate::llm::role_mapping::RoleMapping;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::OnceLock;
+use std::time::Duration;
+
+/// HTTP 传输层超时配置，构造 `reqwest::Client` 时应用
+///
+/// 只约束单次 HTTP 连接与读取；请求失败后的重试由 Agent 层
+/// （`AgentConfig::llm_max_retries`）负责。各项为 0 表示不限制。
+///
+/// `read_timeout_ms` 是相邻两次读取之间的空闲上限而非整个请求的耗时上限，
+/// 流式响应只要持续产出 chunk 就不会超时；默认值留出了模型长时间思考不出 token 的余量。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// 建立 TCP/TLS 连接的超时（毫秒）。默认 10_000
+    pub connect_timeout_ms: u64,
+    /// 读取响应的空闲超时（毫秒）。默认 300_000
+    pub read_timeout_ms: u64,
+    /// 连接池中空闲连接的保留时间（毫秒）。默认 90_000
+    pub pool_idle_timeout_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 10_000,
+            read_timeout_ms: 300_000,
+            pool_idle_timeout_ms: 90_000,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// 按配置构造 HTTP 客户端
+    pub fn build_client(&self) -> Result<reqwest::Client> {
+        let limit = |ms: u64| (ms > 0).then(|| Duration::from_millis(ms));
+        let mut builder =
+            reqwest::Client::builder().pool_idle_timeout(limit(self.pool_idle_timeout_ms));
+        if let Some(timeout) = limit(self.connect_timeout_ms) {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = limit(self.read_timeout_ms) {
+            builder = builder.read_timeout(timeout);
+        }
+        Ok(builder.build()?)
+    }
+}
 
 /// LLM 运行时配置（依赖注入模式）
 ///
-/// 可以直接创建并注入到 Agent，无需环境变量。
+/// 可以直接创建并注入到 Agent，无需环境变量。通过 [`new`](Self::new) / [`openai`](Self::openai)
+/// 等构造函数与 `with_*` 方法创建；标记为 `#[non_exhaustive]`，新增配置项不会破坏已有代码。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct LlmConfig {
-    /// Chat Completions 接口完整 URL
+    /// Chat Completions 接口 URL（可只填到域名或版本号）
     pub base_url: String,
     /// API 密钥
     pub api_key: String,
     /// 模型名称
     pub model: String,
+    /// 是否自动补全 `/v1/chat/completions` 路径（只对只填到域名或版本号的 URL 生效），非标准端点可关闭
+    #[serde(default = "default_auto_complete_path")]
+    pub auto_complete_path: bool,
+    /// 发送请求前的消息角色重映射（默认不转换）
+    #[serde(default)]
+    pub role_mapping: RoleMapping,
+    /// 渲染上下文使用的 prompt 模板（默认 OpenAI 原生 chat 格式）
+    #[serde(default)]
+    pub prompt_format: PromptFormat,
+    /// HTTP 传输层超时
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+fn default_auto_complete_path() -> bool {
+    true
 }
 
 impl LlmConfig {
@@ -38,6 +112,10 @@ impl LlmConfig {
             base_url: base_url.into(),
             api_key: api_key.into(),
             model: model.into(),
+            auto_complete_path: true,
+            role_mapping: RoleMapping::default(),
+            prompt_format: PromptFormat::default(),
+            http: HttpConfig::default(),
         }
     }
 
@@ -50,6 +128,10 @@ impl LlmConfig {
             base_url: config.baseurl,
             api_key: config.apikey,
             model: config.model,
+            auto_complete_path: config.auto_complete_path,
+            role_mapping: config.role_mapping,
+            prompt_format: config.prompt_format,
+            http: HttpConfig::default(),
         })
     }
 
@@ -59,6 +141,10 @@ impl LlmConfig {
             base_url: "https://api.openai.com/v1/chat/completions".to_string(),
             api_key: api_key.into(),
             model: model.into(),
+            auto_complete_path: true,
+            role_mapping: RoleMapping::default(),
+            prompt_format: PromptFormat::default(),
+            http: HttpConfig::default(),
         }
     }
 
@@ -71,12 +157,40 @@ impl LlmConfig {
         Self::new(base_url, api_key, model)
     }
 
+    /// 设置是否自动补全 `/v1/chat/completions` 路径（默认开启）
+    pub fn with_auto_complete_path(mut self, enabled: bool) -> Self {
+        self.auto_complete_path = enabled;
+        self
+    }
+
+    /// 设置该后端需要的消息角色重映射
+    pub fn with_role_mapping(mut self, mapping: RoleMapping) -> Self {
+        self.role_mapping = mapping;
+        self
+    }
+
+    /// 设置该模型使用的 prompt 模板；非 [`PromptFormat::OpenAi`] 时请求发往文本补全接口，
+    /// 请求中的 `tool_choice` 与 `response_format` 不会发送
+    pub fn with_prompt_format(mut self, format: PromptFormat) -> Self {
+        self.prompt_format = format;
+        self
+    }
+
+    /// 设置 HTTP 传输层超时
+    pub fn with_http_config(mut self, http: HttpConfig) -> Self {
+        self.http = http;
+        self
+    }
+
     /// 转换为内部 ModelConfig 格式
     pub(crate) fn to_model_config(&self) -> ModelConfig {
         ModelConfig {
             model: self.model.clone(),
             baseurl: self.base_url.clone(),
             apikey: self.api_key.clone(),
+            auto_complete_path: self.auto_complete_path,
+            role_mapping: self.role_mapping,
+            prompt_format: self.prompt_format,
         }
     }
 }
@@ -84,13 +198,53 @@ impl LlmConfig {
 // ── 环境变量配置（向后兼容）───────────────────────────────────────────────────────
 
 /// 单个模型的连接配置（内部使用）
+///
+/// 标记为 `#[non_exhaustive]`：外部代码请通过 [`new`](Self::new) 创建后再修改字段。
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[non_exhaustive]
 pub struct ModelConfig {
     /// LLM 接口中使用的模型名（如 `qwen3-max`）
     pub model: String,
-    /// Chat Completions 接口完整 URL
+    /// Chat Completions 接口 URL（可只填到域名或版本号）
     pub baseurl: String,
     pub apikey: String,
+    /// 是否自动补全 `/v1/chat/completions` 路径（只对只填到域名或版本号的 URL 生效）
+    #[serde(default = "default_auto_complete_path")]
+    pub auto_complete_path: bool,
+    /// 该后端需要的消息角色重映射
+    #[serde(default)]
+    pub role_mapping: RoleMapping,
+    /// 该模型使用的 prompt 模板
+    #[serde(default)]
+    pub prompt_format: PromptFormat,
+}
+
+impl ModelConfig {
+    /// 创建连接配置，其余选项取默认值（自动补全路径、不重映射角色、OpenAI chat 模板）
+    pub fn new(
+        model: impl Into<String>,
+        baseurl: impl Into<String>,
+        apikey: impl Into<String>,
+    ) -> Self {
+        Self {
+            model: model.into(),
+            baseurl: baseurl.into(),
+            apikey: apikey.into(),
+            auto_complete_path: true,
+            role_mapping: RoleMapping::default(),
+            prompt_format: PromptFormat::default(),
+        }
+    }
+
+    /// 规范化后的 Chat Completions 请求 URL
+    pub fn chat_url(&self) -> String {
+        normalize_chat_url(&self.baseurl, self.auto_complete_path)
+    }
+
+    /// 规范化后的文本补全请求 URL（非 chat 模板使用）
+    pub fn completion_url(&self) -> String {
+        normalize_completion_url(&self.baseurl, self.auto_complete_path)
+    }
 }
 
 /// 全局配置，持有所有已加载的模型配置表（key = model 字段值）
@@ -117,7 +271,7 @@ impl Config {
                 let config_key = parts[1].to_lowercase();
 
                 match config_key.as_str() {
-                    "model" | "baseurl" | "apikey" => {}
+                    "model" | "baseurl" | "apikey" | "autopath" | "rolemap" | "template" => {}
                     _ => {
                         return Err(ReactError::Config(ConfigError::UnMatchConfigError(
                             config_key, key,
@@ -144,6 +298,33 @@ impl Config {
                 .get("apikey")
                 .ok_or_else(|| ConfigError::MissingConfig(model_id.clone(), "apikey".to_string()))?
                 .clone();
+            let auto_complete_path = match config_map.get("autopath") {
+                Some(value) => value.trim().parse::<bool>().map_err(|_| {
+                    ConfigError::EnvParseError(format!(
+                        "AGENT_MODEL_{}_AUTOPATH={value}",
+                        model_id.to_uppercase()
+                    ))
+                })?,
+                None => true,
+            };
+            let role_mapping = match config_map.get("rolemap") {
+                Some(value) => RoleMapping::parse(value).map_err(|e| {
+                    ConfigError::EnvParseError(format!(
+                        "AGENT_MODEL_{}_ROLEMAP={value}: {e}",
+                        model_id.to_uppercase()
+                    ))
+                })?,
+                None => RoleMapping::default(),
+            };
+            let prompt_format = match config_map.get("template") {
+                Some(value) => PromptFormat::parse(value).map_err(|e| {
+                    ConfigError::EnvParseError(format!(
+                        "AGENT_MODEL_{}_TEMPLATE={value}: {e}",
+                        model_id.to_uppercase()
+                    ))
+                })?,
+                None => PromptFormat::default(),
+            };
 
             models.insert(
                 model.to_string(),
@@ -151,6 +332,9 @@ impl Config {
                     model,
                     baseurl,
                     apikey,
+                    auto_complete_path,
+                    role_mapping,
+                    prompt_format,
                 },
             );
         }
@@ -0,0 +1,460 @@
+//! LLM 输出的 JSON 解析管道
+//!
+//! LLM 常把 JSON 裹在 Markdown 代码块里、前后附带解释文字，或留下尾逗号之类的小瑕疵。
+//! [`coerce_json`] 依次尝试：
+//!
+//! 1. 直接解析整段文本
+//! 2. 提取 ```` ```json ```` 代码块或正文中括号配平的 JSON 片段
+//! 3. 保守修复（尾逗号、Python 风格的 `True` / `False` / `None`），可通过
+//!    [`CoerceOptions::strict`] 关闭
+//!
+//! 得到 JSON 后再按 schema 校验；全部失败时返回 [`CoerceError`]，其
+//! [`retry_feedback`](CoerceError::retry_feedback) 可直接反馈给 LLM 重新生成。
+//! `extract_json`、`execute_typed` 与工具参数解析都复用这条管道；工具参数另经
+//! [`coerce_tool_arguments`] 把空串、数组、裸字符串等非对象参数整理为对象。
+//!
+//! ```rust
+//! use echo_agent::llm::json_coerce::{CoerceOptions, coerce_json};
+//! use serde_json::json;
+//!
+//! let raw = "好的，结果如下：\n```json\n{\"city\": \"北京\", \"ok\": True,}\n```\n如需调整请告诉我。";
+//! let value = coerce_json(raw, None, CoerceOptions::default()).unwrap();
+//! assert_eq!(value, json!({ "city": "北京", "ok": true }));
+//!
+//! assert!(coerce_json(raw, None, CoerceOptions::strict()).is_err());
+//! ```
+
+use serde_json::Value;
+use std::fmt;
+use tracing::{debug, warn};
+
+/// 解析选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoerceOptions {
+    /// 直接解析与提取都失败时是否尝试保守修复（默认开启）
+    pub repair: bool,
+}
+
+impl Default for CoerceOptions {
+    fn default() -> Self {
+        Self { repair: true }
+    }
+}
+
+impl CoerceOptions {
+    /// 只做解析与提取，不修改 LLM 输出的任何字符
+    pub fn strict() -> Self {
+        Self { repair: false }
+    }
+}
+
+/// 解析失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoerceError {
+    /// 所有策略都没能得到合法 JSON，携带直接解析时的错误
+    InvalidJson(String),
+    /// JSON 合法但不符合 schema，携带违规路径与原因
+    SchemaViolation(String),
+}
+
+impl fmt::Display for CoerceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoerceError::InvalidJson(e) => write!(f, "不是合法的 JSON：{e}"),
+            CoerceError::SchemaViolation(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for CoerceError {}
+
+impl CoerceError {
+    /// 反馈给 LLM 的重试提示
+    pub fn retry_feedback(&self) -> String {
+        format!(
+            "你的输出未通过 JSON Schema 校验：{self}\n\
+            请修正后重新输出，只输出符合 schema 的 JSON，不要包含任何其他文字。"
+        )
+    }
+}
+
+/// 把 LLM 原始输出解析为 JSON，并按 `schema`（若有）校验，详见[模块文档](self)
+pub fn coerce_json(
+    raw: &str,
+    schema: Option<&Value>,
+    options: CoerceOptions,
+) -> Result<Value, CoerceError> {
+    let value = parse_lenient(raw, options)?;
+    if let Some(schema) = schema {
+        validate_schema(&value, schema, "$").map_err(CoerceError::SchemaViolation)?;
+    }
+    Ok(value)
+}
+
+/// 解析 LLM 给出的工具调用参数（`FunctionCall.arguments`），保证结果是 JSON 对象
+///
+/// 在 [`coerce_json`] 之上对非对象参数做宽松处理：
+/// - 空串、`null` 视为空对象
+/// - 无法解析为 JSON 且不以 `{` / `[` 开头的文本视为裸字符串
+/// - 数组、字符串、数字等非对象值：`schema` 只有一个参数时包装为该参数，否则包装为 `{"value": ...}`
+///
+/// 发生上述处理时以 warn 记录原始参数；以 `{` / `[` 开头却无法解析时仍返回错误。
+///
+/// ```rust
+/// use echo_agent::llm::json_coerce::{CoerceOptions, coerce_tool_arguments};
+/// use serde_json::json;
+///
+/// let schema = json!({ "type": "object", "properties": { "query": { "type": "string" } } });
+/// let args = coerce_tool_arguments("search", "\"rust\"", Some(&schema), CoerceOptions::default());
+/// assert_eq!(args.unwrap(), json!({ "query": "rust" }));
+/// ```
+pub fn coerce_tool_arguments(
+    tool_name: &str,
+    raw: &str,
+    schema: Option<&Value>,
+    options: CoerceOptions,
+) -> Result<Value, CoerceError> {
+    let text = raw.trim();
+    let value = if text.is_empty() {
+        Value::Null
+    } else {
+        match parse_lenient(text, options) {
+            Ok(value) => value,
+            Err(_) if !text.starts_with(['{', '[']) => Value::String(text.to_string()),
+            Err(e) => return Err(e),
+        }
+    };
+    let normalized = match value {
+        Value::Object(_) => return Ok(value),
+        Value::Null => Value::Object(Default::default()),
+        other => {
+            let key = schema
+                .and_then(|s| s.get("properties"))
+                .and_then(Value::as_object)
+                .filter(|properties| properties.len() == 1)
+                .and_then(|properties| properties.keys().next())
+                .map_or("value", String::as_str);
+            serde_json::json!({ key: other })
+        }
+    };
+    warn!(tool = %tool_name, raw = %raw, normalized = %normalized, "⚠️ 工具参数不是 JSON 对象，已宽松处理");
+    Ok(normalized)
+}
+
+fn parse_lenient(raw: &str, options: CoerceOptions) -> Result<Value, CoerceError> {
+    let text = raw.trim();
+    let direct_error = match serde_json::from_str(text) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    let candidates = extract_candidates(text);
+    for candidate in &candidates {
+        if let Ok(value) = serde_json::from_str(candidate) {
+            debug!("🧩 从 LLM 输出中提取到 JSON 片段");
+            return Ok(value);
+        }
+    }
+
+    if options.repair {
+        for candidate in std::iter::once(&text).chain(&candidates) {
+            let repaired = repair(candidate);
+            if repaired != *candidate
+                && let Ok(value) = serde_json::from_str(&repaired)
+            {
+                debug!("🩹 LLM 输出的 JSON 经修复后解析成功");
+                return Ok(value);
+            }
+        }
+    }
+
+    Err(CoerceError::InvalidJson(direct_error.to_string()))
+}
+
+/// 候选 JSON 片段：先是代码块内容，再是正文中括号配平的片段，均按出现顺序
+fn extract_candidates(text: &str) -> Vec<&str> {
+    let mut candidates = fenced_blocks(text);
+    let mut from = 0;
+    while let Some(offset) = text[from..].find(['{', '[']) {
+        let start = from + offset;
+        match balanced_end(&text[start..]) {
+            Some(len) => {
+                candidates.push(&text[start..start + len]);
+                from = start + len;
+            }
+            None => from = start + 1,
+        }
+    }
+    candidates
+}
+
+/// Markdown 代码块（```` ``` ```` 或 ```` ```json ````）的内容
+fn fenced_blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("```") {
+        let after = &rest[open + 3..];
+        // 跳过语言标记所在行
+        let body_start = after.find('\n').map_or(after.len(), |i| i + 1);
+        let body = &after[body_start..];
+        let Some(close) = body.find("```") else {
+            break;
+        };
+        blocks.push(body[..close].trim());
+        rest = &body[close + 3..];
+    }
+    blocks
+}
+
+/// 从开头的 `{` / `[` 扫描到与之配平的闭括号，返回片段字节长度；字符串内的括号不计
+fn balanced_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 保守修复：去掉 `}` / `]` 前的尾逗号，把字符串外的 `True` / `False` / `None` 换成 JSON 字面量
+fn repair(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            out.push(c);
+            i += 1;
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+                i += 1;
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}' | ']')) {
+                    out.push(c);
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric() && *c != '_')
+                    .map_or(chars.len(), |n| i + n);
+                let word: String = chars[i..end].iter().collect();
+                out.push_str(match word.as_str() {
+                    "True" => "true",
+                    "False" => "false",
+                    "None" => "null",
+                    other => other,
+                });
+                i = end;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// 轻量 JSON Schema 校验：支持 `type` / `enum` / `required` / `properties` /
+/// `additionalProperties: false` / `items`，其余关键字忽略
+pub(crate) fn validate_schema(
+    value: &Value,
+    schema: &Value,
+    path: &str,
+) -> std::result::Result<(), String> {
+    if let Some(options) = schema.get("enum").and_then(Value::as_array)
+        && !options.contains(value)
+    {
+        return Err(format!("{path} 的取值不在 enum 范围内"));
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            return Err(format!("{path} 应为 {} 类型", types.join(" | ")));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        return Err(format!("{path} 缺少必填字段 `{key}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, field) in map {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => validate_schema(field, sub, &format!("{path}.{key}"))?,
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        return Err(format!("{path} 包含未定义的字段 `{key}`"));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_schema(item, item_schema, &format!("{path}[{i}]"))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn coerce(raw: &str) -> Result<Value, CoerceError> {
+        coerce_json(raw, None, CoerceOptions::default())
+    }
+
+    #[test]
+    fn test_coerce_common_llm_wrappings() {
+        let expected = json!({ "name": "张三", "tags": ["a", "b"] });
+        let cases = [
+            r#"{"name": "张三", "tags": ["a", "b"]}"#,
+            "```json\n{\"name\": \"张三\", \"tags\": [\"a\", \"b\"]}\n```",
+            "当然可以，结果如下：\n```json\n{\"name\": \"张三\", \"tags\": [\"a\", \"b\"]}\n```\n希望对你有帮助！",
+            "```\n{\"name\": \"张三\", \"tags\": [\"a\", \"b\"]}\n```",
+            "提取结果：{\"name\": \"张三\", \"tags\": [\"a\", \"b\"]}。以上字段均来自原文。",
+            "[注意] 以下为 JSON：\n{\"name\": \"张三\", \"tags\": [\"a\", \"b\"]}",
+        ];
+        for raw in cases {
+            assert_eq!(coerce(raw).unwrap(), expected, "raw: {raw}");
+        }
+        // 字符串里的括号与转义引号不影响配平
+        assert_eq!(
+            coerce(r#"答案是 {"text": "a } b \" ]"} 对吧"#).unwrap(),
+            json!({ "text": "a } b \" ]" })
+        );
+    }
+
+    #[test]
+    fn test_repair_is_conservative_and_switchable() {
+        let raw = "```json\n{\"ok\": True, \"items\": [1, 2,], \"note\": \"True, ]\",}\n```";
+        assert_eq!(
+            coerce(raw).unwrap(),
+            json!({ "ok": true, "items": [1, 2], "note": "True, ]" })
+        );
+        assert!(matches!(
+            coerce_json(raw, None, CoerceOptions::strict()),
+            Err(CoerceError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            coerce("姓名张三，年龄 28"),
+            Err(CoerceError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_schema_violation_feedback() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "age": { "type": "integer" } },
+            "required": ["age"]
+        });
+        let err = coerce_json(
+            "结果：```json\n{\"age\": \"28\"}\n```",
+            Some(&schema),
+            CoerceOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            CoerceError::SchemaViolation("$.age 应为 integer 类型".into())
+        );
+        assert!(err.retry_feedback().contains("$.age"));
+    }
+
+    #[test]
+    fn test_coerce_tool_arguments_non_object() {
+        let options = CoerceOptions::default();
+        let single = json!({ "type": "object", "properties": { "path": { "type": "string" } } });
+        let multi = json!({ "type": "object", "properties": { "a": {}, "b": {} } });
+        let args = |raw: &str, schema: Option<&Value>| {
+            coerce_tool_arguments("tool", raw, schema, options).unwrap()
+        };
+
+        // 空串 / null → 空对象
+        assert_eq!(args("", Some(&single)), json!({}));
+        assert_eq!(args("  null ", None), json!({}));
+        // 裸字符串（带引号或不带）→ 按 schema 的唯一参数包装
+        assert_eq!(
+            args(r#""src/main.rs""#, Some(&single)),
+            json!({ "path": "src/main.rs" })
+        );
+        assert_eq!(
+            args("src/main.rs", Some(&single)),
+            json!({ "path": "src/main.rs" })
+        );
+        // 数组 → 无法推断参数名时包装为 value
+        assert_eq!(args("[1, 2]", Some(&multi)), json!({ "value": [1, 2] }));
+        assert_eq!(args("[1, 2]", None), json!({ "value": [1, 2] }));
+        // 对象原样返回
+        assert_eq!(args(r#"{"a": 1}"#, Some(&multi)), json!({ "a": 1 }));
+        // 看起来是对象却解析失败时仍报错
+        assert!(coerce_tool_arguments("tool", r#"{"a": "#, None, options).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! LLM 请求/响应中间件链
+//!
+//! 类似 tower 的洋葱模型：每个中间件拿到请求后可以改写、记录、重试或拒绝，
+//! 再通过 [`Next::run`] 交给链上的下一环，最内层为真正发出请求的客户端。
+//! 目前只作用于非流式的 [`LlmClient::chat`](crate::llm::LlmClient::chat)。
+//!
+//! ```rust,no_run
+//! use echo_agent::llm::DefaultLlmClient;
+//! use echo_agent::llm::middleware::{LoggingMiddleware, RateLimitMiddleware, RetryMiddleware};
+//! use reqwest::Client;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let client = DefaultLlmClient::new(Arc::new(Client::new()), "qwen3-max")
+//!     .with_middleware(LoggingMiddleware)
+//!     .with_middleware(RateLimitMiddleware::new(10, Duration::from_secs(1)))
+//!     .with_middleware(RetryMiddleware::new(3, Duration::from_millis(500)));
+//! ```
+
+use crate::error::Result;
+use crate::llm::{ChatRequest, ChatResponse, is_retryable_llm_error};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// 链路末端：真正发出请求的函数
+pub(crate) type Endpoint<'a> =
+    dyn Fn(ChatRequest) -> BoxFuture<'a, Result<ChatResponse>> + Send + Sync + 'a;
+
+/// LLM 中间件
+///
+/// 先添加的中间件位于外层，最先看到请求、最后看到响应。
+#[async_trait]
+pub trait LlmMiddleware: Send + Sync {
+    /// 处理一次请求；调用 `next.run(request)` 继续向内传递
+    async fn handle(&self, request: ChatRequest, next: Next<'_>) -> Result<ChatResponse>;
+}
+
+/// 链上剩余的部分，可多次调用（用于重试）
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    chain: &'a [Arc<dyn LlmMiddleware>],
+    endpoint: &'a Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(chain: &'a [Arc<dyn LlmMiddleware>], endpoint: &'a Endpoint<'a>) -> Self {
+        Self { chain, endpoint }
+    }
+
+    /// 将请求交给下一个中间件（或最终的客户端）
+    pub async fn run(self, request: ChatRequest) -> Result<ChatResponse> {
+        match self.chain.split_first() {
+            Some((first, rest)) => {
+                first
+                    .handle(
+                        request,
+                        Next {
+                            chain: rest,
+                            endpoint: self.endpoint,
+                        },
+                    )
+                    .await
+            }
+            None => (self.endpoint)(request).await,
+        }
+    }
+}
+
+// ── 内置中间件 ────────────────────────────────────────────────────────────────
+
+/// 记录请求规模、耗时与结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl LlmMiddleware for LoggingMiddleware {
+    async fn handle(&self, request: ChatRequest, next: Next<'_>) -> Result<ChatResponse> {
+        let messages = request.messages.len();
+        let tools = request.tools.as_ref().map_or(0, Vec::len);
+        let start = Instant::now();
+        debug!(messages, tools, "➡️ LLM 请求");
+        let result = next.run(request).await;
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        match &result {
+            Ok(response) => debug!(
+                elapsed_ms,
+                finish_reason = response.finish_reason.as_deref().unwrap_or("-"),
+                "⬅️ LLM 响应"
+            ),
+            Err(e) => warn!(elapsed_ms, error = %e, "❌ LLM 请求失败"),
+        }
+        result
+    }
+}
+
+/// 对可重试错误（网络 / 429 / 5xx）按指数退避重试
+#[derive(Debug, Clone, Copy)]
+pub struct RetryMiddleware {
+    max_retries: usize,
+    base_delay: Duration,
+}
+
+impl RetryMiddleware {
+    /// `max_retries` 为首次请求之外的最大重试次数，第 n 次重试前等待 `base_delay * 2^(n-1)`
+    pub fn new(max_retries: usize, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for RetryMiddleware {
+    async fn handle(&self, request: ChatRequest, next: Next<'_>) -> Result<ChatResponse> {
+        let mut attempt = 0;
+        loop {
+            match next.run(request.clone()).await {
+                Err(e) if attempt < self.max_retries && is_retryable_llm_error(&e) => {
+                    attempt += 1;
+                    let delay = self.base_delay * (1u32 << (attempt - 1).min(5));
+                    warn!(
+                        error = %e,
+                        attempt,
+                        max = self.max_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        "⚠️ LLM 请求失败，稍后重试"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 滑动窗口限流：任意 `per` 时长内最多放行 `max_requests` 个请求，超出的请求排队等待
+#[derive(Debug)]
+pub struct RateLimitMiddleware {
+    max_requests: usize,
+    per: Duration,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(max_requests: usize, per: Duration) -> Self {
+        Self {
+            max_requests: max_requests.max(1),
+            per,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 占用一个发送名额；窗口已满时返回需要等待的时长
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        while sent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.per)
+        {
+            sent.pop_front();
+        }
+        if sent.len() < self.max_requests {
+            sent.push_back(now);
+            None
+        } else {
+            sent.front()
+                .map(|oldest| self.per - now.duration_since(*oldest))
+        }
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for RateLimitMiddleware {
+    async fn handle(&self, request: ChatRequest, next: Next<'_>) -> Result<ChatResponse> {
+        while let Some(wait) = self.try_acquire() {
+            debug!(wait_ms = wait.as_millis() as u64, "⏳ LLM 请求限流等待");
+            tokio::time::sleep(wait).await;
+        }
+        next.run(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmClient;
+    use crate::llm::types::Message;
+    use crate::testing::MockLlmClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingMiddleware {
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmMiddleware for CountingMiddleware {
+        async fn handle(&self, request: ChatRequest, next: Next<'_>) -> Result<ChatResponse> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            next.run(request).await
+        }
+    }
+
+    async fn run_chain(
+        chain: &[Arc<dyn LlmMiddleware>],
+        mock: &MockLlmClient,
+    ) -> Result<ChatResponse> {
+        let endpoint = |request| mock.chat(request);
+        Next::new(chain, &endpoint)
+            .run(ChatRequest::new(vec![Message::user("hi".to_string())]))
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_middleware_counts_requests() {
+        let counter = Arc::new(CountingMiddleware::default());
+        let chain: Vec<Arc<dyn LlmMiddleware>> = vec![counter.clone(), Arc::new(LoggingMiddleware)];
+        let mock = MockLlmClient::new().with_responses(["a", "b"]);
+
+        assert_eq!(run_chain(&chain, &mock).await.unwrap().content(), Some("a"));
+        assert_eq!(run_chain(&chain, &mock).await.unwrap().content(), Some("b"));
+        assert_eq!(counter.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(mock.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_middleware_retries_inner_chain() {
+        // 重试位于计数中间件外层，每次重试都会重新经过内层
+        let counter = Arc::new(CountingMiddleware::default());
+        let chain: Vec<Arc<dyn LlmMiddleware>> = vec![
+            Arc::new(RetryMiddleware::new(2, Duration::from_millis(1))),
+            counter.clone(),
+        ];
+        let mock = MockLlmClient::new()
+            .with_network_error("连接重置")
+            .with_response("ok");
+
+        let response = run_chain(&chain, &mock).await.unwrap();
+        assert_eq!(response.content(), Some("ok"));
+        assert_eq!(counter.requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let limiter = RateLimitMiddleware::new(2, Duration::from_secs(60));
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_some());
+    }
+}
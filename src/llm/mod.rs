@@ -4,11 +4,14 @@
 //!
 //! # 核心类型
 //!
-//! - [`LlmClient`]: LLM 客户端 trait
-//! - [`OpenAiClient`]: OpenAI 兼容客户端
-//! - [`ChatRequest`]: 聊天请求
-//! - [`ChatResponse`]: 聊天响应
-//! - [`ChatChunk`]: 流式响应块
+//! - [`LlmClient`]：LLM 客户端 trait
+//! - [`OpenAiClient`]：OpenAI 兼容客户端
+//! - [`ChatRequest`]：聊天请求
+//! - [`ChatResponse`]：聊天响应
+//! - [`ChatChunk`]：流式响应块
+//! - [`LlmMiddleware`]：请求/响应中间件，见 [`middleware`] 模块
+//! - [`json_coerce`]：从 LLM 输出中解析 JSON（代码块提取、保守修复、schema 校验）
+//! - [`PromptTemplate`]：把上下文渲染为 ChatML / Alpaca 等文本模板，见 [`prompt_template`] 模块
 //!
 //! # 示例：简单对话
 //!
@@ -58,15 +61,23 @@
 
 mod client;
 pub mod config;
+pub mod json_coerce;
+pub mod middleware;
+pub mod prompt_template;
+pub mod role_mapping;
 pub mod types;
 
-use crate::error::Result;
-pub use crate::llm::config::LlmConfig;
+use crate::error::{LlmError, ReactError, Result};
+pub use crate::llm::client::normalize_chat_url;
+pub use crate::llm::config::{HttpConfig, LlmConfig};
+pub use crate::llm::middleware::LlmMiddleware;
+pub use crate::llm::prompt_template::{PromptFormat, PromptTemplate, RenderedPrompt};
+pub use crate::llm::role_mapping::RoleMapping;
 pub(crate) use crate::llm::types::{
     ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Message,
 };
 pub use crate::llm::types::{
-    JsonSchemaSpec, Message as LlmMessage, ResponseFormat, ToolDefinition,
+    JsonSchemaSpec, Message as LlmMessage, ResponseFormat, ToolChoice, ToolDefinition,
 };
 use async_trait::async_trait;
 use futures::Stream;
@@ -123,6 +134,15 @@ pub trait LlmClient: Send + Sync {
     fn model_name(&self) -> &str;
 }
 
+/// 判断 LLM 错误是否值得重试（网络/超时/限流/服务端 5xx）
+pub(crate) fn is_retryable_llm_error(err: &ReactError) -> bool {
+    match err {
+        ReactError::Llm(LlmError::NetworkError(_)) => true,
+        ReactError::Llm(LlmError::ApiError { status, .. }) => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
 /// 聊天请求参数
 ///
 /// # 示例
@@ -150,10 +170,47 @@ pub struct ChatRequest {
     pub max_tokens: Option<u32>,
     /// 工具定义列表
     pub tools: Option<Vec<ToolDefinition>>,
-    /// 工具选择策略："auto" | "none" | "required"
-    pub tool_choice: Option<String>,
+    /// 工具选择策略，见 [`ToolChoice`]
+    pub tool_choice: Option<ToolChoice>,
     /// 响应格式（JSON Schema 等）
     pub response_format: Option<ResponseFormat>,
+    /// 其余可选参数（seed、模型覆盖、候选数量等），见 [`ChatOptions`]
+    pub options: ChatOptions,
+}
+
+/// [`ChatRequest`] 的扩展参数
+///
+/// 标记为 `#[non_exhaustive]`，新增参数不会破坏已有代码；通过 `Default` 与 `with_*` 方法构造，
+/// 或直接使用 [`ChatRequest`] 上的同名方法。
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ChatOptions {
+    /// 采样随机种子（提升可复现性）
+    pub seed: Option<u64>,
+    /// 本次请求使用的模型（None = 客户端配置的模型）
+    pub model: Option<String>,
+    /// 候选回复数量（None = 1）。大于 1 时按 n 倍计费，仅非流式请求有意义，流式请求忽略
+    pub n: Option<u32>,
+}
+
+impl ChatOptions {
+    /// 设置采样随机种子
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// 指定本次请求使用的模型
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// 设置候选回复数量
+    pub fn with_n(mut self, n: u32) -> Self {
+        self.n = Some(n);
+        self
+    }
 }
 
 impl ChatRequest {
@@ -170,6 +227,43 @@ impl ChatRequest {
         self.tools = Some(tools);
         self
     }
+
+    /// 设置采样随机种子
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    /// 指定本次请求使用的模型
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.options.model = Some(model.into());
+        self
+    }
+
+    /// 设置候选回复数量，见 [`ChatOptions::n`]
+    pub fn with_n(mut self, n: u32) -> Self {
+        self.options.n = Some(n);
+        self
+    }
+
+    /// 转换为发给服务端的请求体
+    ///
+    /// 未携带工具定义时不发送 `tool_choice`（服务端会拒绝）；流式请求不发送 `n`。
+    fn into_completion_request(self, model: &ModelConfig, stream: bool) -> ChatCompletionRequest {
+        let has_tools = self.tools.as_ref().is_some_and(|tools| !tools.is_empty());
+        ChatCompletionRequest {
+            model: self.options.model.unwrap_or_else(|| model.model.clone()),
+            messages: model.role_mapping.apply(self.messages),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            stream: stream.then_some(true),
+            tools: self.tools,
+            tool_choice: self.tool_choice.filter(|_| has_tools),
+            response_format: self.response_format,
+            seed: self.options.seed,
+            n: if stream { None } else { self.options.n },
+        }
+    }
 }
 
 /// 聊天响应
@@ -221,6 +315,16 @@ impl ChatResponse {
             .as_ref()
             .is_some_and(|t| !t.is_empty())
     }
+
+    /// 转换为完整响应；`raw` 中没有 choices 时（如 Mock 客户端）用 `message` 补齐
+    pub(crate) fn into_completion(self) -> ChatCompletionResponse {
+        let mut raw = self.raw;
+        if raw.choices.is_empty() {
+            raw.choices
+                .push(types::Choice::new(self.message, self.finish_reason));
+        }
+        raw
+    }
 }
 
 /// 流式响应块
@@ -232,16 +336,31 @@ pub struct ChatChunk {
     pub finish_reason: Option<String>,
 }
 
+impl ChatChunk {
+    /// 转换为单 choice 的原始流式块，供 Agent 的流式管道统一处理
+    pub(crate) fn into_completion_chunk(self) -> types::ChatCompletionChunk {
+        types::ChatCompletionChunk::new(
+            String::new(),
+            vec![types::ChunkChoice {
+                delta: self.delta,
+                finish_reason: self.finish_reason,
+                index: 0,
+            }],
+        )
+    }
+}
+
 // ── BoxStream 别名 ─────────────────────────────────────────────────────────────
 
 use futures::stream::BoxStream;
 
 // ── 便捷函数（向后兼容）─────────────────────────────────────────────────────────
 
-use crate::llm::client::{post, stream_post};
+use crate::llm::client::{post, post_text_completion, response_chunk, stream_post};
 use crate::llm::config::{Config, ModelConfig};
 use reqwest::Client;
 use reqwest::header::HeaderMap;
+use types::TextCompletionRequest;
 
 /// 组装请求头
 pub fn assemble_req_header(model: &ModelConfig) -> Result<HeaderMap> {
@@ -262,61 +381,115 @@ pub fn assemble_req_header(model: &ModelConfig) -> Result<HeaderMap> {
 }
 
 /// 同步聊天请求（独立函数，使用环境变量配置）
-#[allow(clippy::too_many_arguments)]
+///
+/// `request.options.model` 非空时代替 `model_name` 查找模型配置。
 pub async fn chat(
     client: Arc<Client>,
     model_name: &str,
-    messages: Vec<Message>,
-    temperature: Option<f32>,
-    max_tokens: Option<u32>,
-    stream: Option<bool>,
-    tools: Option<Vec<ToolDefinition>>,
-    tool_choice: Option<String>,
-    response_format: Option<ResponseFormat>,
+    mut request: ChatRequest,
 ) -> Result<ChatCompletionResponse> {
-    let model = Config::get_model(model_name)?;
-    let request_body = ChatCompletionRequest {
-        model: model.model.clone(),
-        messages,
-        temperature,
-        max_tokens,
-        stream,
-        tools,
-        tool_choice,
-        response_format,
-    };
+    let model = Config::get_model(
+        request
+            .options
+            .model
+            .take()
+            .as_deref()
+            .unwrap_or(model_name),
+    )?;
+    let request_body = request.into_completion_request(&model, false);
 
     let header_map = assemble_req_header(&model)?;
-    post(client, &request_body, header_map, model.baseurl.as_str()).await
+    send_request(client, &model, header_map, request_body).await
+}
+
+/// 按模型的 prompt 模板发送非流式请求
+///
+/// chat 模板直接走 Chat Completions；文本模板把 messages 与工具渲染为 prompt 后走文本补全接口，
+/// 此时 `tool_choice` 与 `response_format` 不会发送。
+async fn send_request(
+    client: Arc<Client>,
+    model: &ModelConfig,
+    header_map: HeaderMap,
+    request: ChatCompletionRequest,
+) -> Result<ChatCompletionResponse> {
+    let template = model.prompt_format.template();
+    let rendered = template.render(&request.messages, request.tools.as_deref().unwrap_or(&[]));
+    match rendered {
+        RenderedPrompt::Messages(messages) => {
+            let request = ChatCompletionRequest {
+                messages,
+                ..request
+            };
+            post(client, &request, header_map, &model.chat_url()).await
+        }
+        RenderedPrompt::Text(prompt) => {
+            if request.tool_choice.is_some() || request.response_format.is_some() {
+                tracing::warn!(
+                    format = ?model.prompt_format,
+                    "⚠️ 文本补全模板不支持 tool_choice / response_format，已忽略"
+                );
+            }
+            let request = TextCompletionRequest {
+                model: request.model,
+                prompt,
+                temperature: request.temperature,
+                max_tokens: request.max_tokens,
+                stop: template.stop_sequences(),
+                seed: request.seed,
+            };
+            post_text_completion(
+                client,
+                &request,
+                header_map,
+                &model.completion_url(),
+                template,
+            )
+            .await
+        }
+    }
+}
+
+/// 发送流式请求；文本模板不支持增量输出，整段回复作为单个 chunk 产出
+async fn send_stream_request(
+    client: Arc<Client>,
+    model: &ModelConfig,
+    header_map: HeaderMap,
+    request: ChatCompletionRequest,
+) -> Result<BoxStream<'static, Result<ChatCompletionChunk>>> {
+    if model.prompt_format.is_chat() {
+        let stream = stream_post(client, request, header_map, model.chat_url()).await?;
+        return Ok(Box::pin(stream));
+    }
+    let request = ChatCompletionRequest {
+        stream: None,
+        ..request
+    };
+    let response = send_request(client, model, header_map, request).await?;
+    Ok(Box::pin(futures::stream::iter([Ok(response_chunk(
+        response,
+    ))])))
 }
 
 /// 流式聊天请求（独立函数，使用环境变量配置）
-#[allow(clippy::too_many_arguments)]
+///
+/// `request.options.model` 非空时代替 `model_name` 查找模型配置；`request.options.n` 会被忽略。
 pub async fn stream_chat(
     client: Arc<Client>,
     model_name: &str,
-    messages: Vec<Message>,
-    temperature: Option<f32>,
-    max_tokens: Option<u32>,
-    tools: Option<Vec<ToolDefinition>>,
-    tool_choice: Option<String>,
-    response_format: Option<ResponseFormat>,
+    mut request: ChatRequest,
 ) -> Result<impl Stream<Item = Result<ChatCompletionChunk>> + use<>> {
-    let model = Config::get_model(model_name)?;
-    let request_body = ChatCompletionRequest {
-        model: model.model.clone(),
-        messages,
-        temperature,
-        max_tokens,
-        stream: Some(true),
-        tools,
-        tool_choice,
-        response_format,
-    };
+    let model = Config::get_model(
+        request
+            .options
+            .model
+            .take()
+            .as_deref()
+            .unwrap_or(model_name),
+    )?;
+    let request_body = request.into_completion_request(&model, true);
 
     let header_map = assemble_req_header(&model)?;
-    let url = model.baseurl.clone();
-    stream_post(client, request_body, header_map, url).await
+    send_stream_request(client, &model, header_map, request_body).await
 }
 
 // ── OpenAI 客户端实现 ──────────────────────────────────────────────────────────
@@ -336,7 +509,7 @@ impl OpenAiClient {
         let config = Config::get_model(model_name)?;
         let header_map = assemble_req_header(&config)?;
         Ok(Self {
-            client: Arc::new(Client::new()),
+            client: Arc::new(config::HttpConfig::default().build_client()?),
             config,
             header_map,
         })
@@ -347,7 +520,7 @@ impl OpenAiClient {
         let model_config = config.to_model_config();
         let header_map = assemble_req_header(&model_config)?;
         Ok(Self {
-            client: Arc::new(Client::new()),
+            client: Arc::new(config.http.build_client()?),
             config: model_config,
             header_map,
         })
@@ -368,22 +541,13 @@ impl OpenAiClient {
 #[async_trait]
 impl LlmClient for OpenAiClient {
     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
-        let req = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages: request.messages,
-            temperature: request.temperature,
-            max_tokens: request.max_tokens,
-            stream: None,
-            tools: request.tools,
-            tool_choice: request.tool_choice,
-            response_format: request.response_format,
-        };
+        let req = request.into_completion_request(&self.config, false);
 
-        let raw = post(
+        let raw = send_request(
             self.client.clone(),
-            &req,
+            &self.config,
             self.header_map.clone(),
-            &self.config.baseurl,
+            req,
         )
         .await?;
 
@@ -400,22 +564,13 @@ impl LlmClient for OpenAiClient {
     }
 
     async fn chat_stream(&self, request: ChatRequest) -> Result<BoxStream<'_, Result<ChatChunk>>> {
-        let req = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages: request.messages,
-            temperature: request.temperature,
-            max_tokens: request.max_tokens,
-            stream: Some(true),
-            tools: request.tools,
-            tool_choice: request.tool_choice,
-            response_format: request.response_format,
-        };
+        let req = request.into_completion_request(&self.config, true);
 
-        let stream = stream_post(
+        let stream = send_stream_request(
             self.client.clone(),
-            req,
+            &self.config,
             self.header_map.clone(),
-            self.config.baseurl.clone(),
+            req,
         )
         .await?;
 
@@ -437,9 +592,12 @@ impl LlmClient for OpenAiClient {
 }
 
 /// 基于 [`chat`] 函数的默认 [`LlmClient`] 实现
+///
+/// 可通过 [`with_middleware`](Self::with_middleware) 为非流式请求挂载中间件链。
 pub struct DefaultLlmClient {
     client: Arc<Client>,
     model_name: String,
+    middlewares: Vec<Arc<dyn LlmMiddleware>>,
 }
 
 impl DefaultLlmClient {
@@ -447,25 +605,19 @@ impl DefaultLlmClient {
         Self {
             client,
             model_name: model_name.into(),
+            middlewares: Vec::new(),
         }
     }
-}
 
-#[async_trait]
-impl LlmClient for DefaultLlmClient {
-    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
-        let raw = chat(
-            self.client.clone(),
-            &self.model_name,
-            request.messages,
-            request.temperature,
-            request.max_tokens,
-            None,
-            request.tools,
-            request.tool_choice,
-            request.response_format,
-        )
-        .await?;
+    /// 追加一个中间件；先添加的位于外层，最先处理请求
+    pub fn with_middleware(mut self, middleware: impl LlmMiddleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// 不经过中间件，直接发出请求
+    async fn send(&self, request: ChatRequest) -> Result<ChatResponse> {
+        let raw = chat(self.client.clone(), &self.model_name, request).await?;
 
         let choice = raw
             .choices
@@ -478,19 +630,22 @@ impl LlmClient for DefaultLlmClient {
             raw,
         })
     }
+}
+
+#[async_trait]
+impl LlmClient for DefaultLlmClient {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        if self.middlewares.is_empty() {
+            return self.send(request).await;
+        }
+        let endpoint = |request| Box::pin(self.send(request)) as _;
+        middleware::Next::new(&self.middlewares, &endpoint)
+            .run(request)
+            .await
+    }
 
     async fn chat_stream(&self, request: ChatRequest) -> Result<BoxStream<'_, Result<ChatChunk>>> {
-        let stream = stream_chat(
-            self.client.clone(),
-            &self.model_name,
-            request.messages,
-            request.temperature,
-            request.max_tokens,
-            request.tools,
-            request.tool_choice,
-            request.response_format,
-        )
-        .await?;
+        let stream = stream_chat(self.client.clone(), &self.model_name, request).await?;
 
         Ok(Box::pin(futures::StreamExt::map(stream, |result| {
             result.map(|chunk| {
@@ -504,16 +659,29 @@ impl LlmClient for DefaultLlmClient {
     }
 
     async fn chat_simple(&self, messages: Vec<Message>) -> Result<String> {
+        if !self.middlewares.is_empty() {
+            let response = self
+                .chat(ChatRequest {
+                    messages,
+                    temperature: Some(0.3),
+                    max_tokens: Some(2048),
+                    ..Default::default()
+                })
+                .await?;
+            return response
+                .content()
+                .map(str::to_string)
+                .ok_or_else(|| ReactError::Other("LLM 返回空内容".to_string()));
+        }
         let response = chat(
             self.client.clone(),
             &self.model_name,
-            messages,
-            Some(0.3),
-            Some(2048),
-            Some(false),
-            None,
-            None,
-            None,
+            ChatRequest {
+                messages,
+                temperature: Some(0.3),
+                max_tokens: Some(2048),
+                ..Default::default()
+            },
         )
         .await?;
 
@@ -529,3 +697,61 @@ impl LlmClient for DefaultLlmClient {
         &self.model_name
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_config() -> ModelConfig {
+        ModelConfig::new("test-model", "http://localhost", "sk-test")
+    }
+
+    #[test]
+    fn test_tool_choice_dropped_without_tools() {
+        let request = ChatRequest {
+            messages: vec![Message::user("hi".to_string())],
+            tool_choice: Some(ToolChoice::Required),
+            ..Default::default()
+        };
+        let body = request
+            .clone()
+            .into_completion_request(&model_config(), false);
+        assert!(body.tool_choice.is_none());
+
+        let body = ChatRequest {
+            tools: Some(Vec::new()),
+            ..request.clone()
+        }
+        .into_completion_request(&model_config(), false);
+        assert!(body.tool_choice.is_none());
+
+        let tool: ToolDefinition = serde_json::from_value(serde_json::json!({
+            "type": "function",
+            "function": { "name": "plan", "description": "", "parameters": {} }
+        }))
+        .unwrap();
+        let body = request
+            .with_tools(vec![tool])
+            .into_completion_request(&model_config(), false);
+        assert_eq!(body.tool_choice, Some(ToolChoice::Required));
+    }
+
+    #[test]
+    fn test_options_reach_request_body() {
+        let request = ChatRequest::new(vec![Message::user("hi".to_string())])
+            .with_seed(7)
+            .with_model("other-model")
+            .with_n(3);
+
+        let body = request
+            .clone()
+            .into_completion_request(&model_config(), false);
+        assert_eq!(body.model, "other-model");
+        assert_eq!(body.seed, Some(7));
+        assert_eq!(body.n, Some(3));
+
+        let body = request.into_completion_request(&model_config(), true);
+        assert_eq!(body.seed, Some(7));
+        assert_eq!(body.n, None);
+    }
+}
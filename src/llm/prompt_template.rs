@@ -0,0 +1,400 @@
+//! Prompt 模板：把对话上下文渲染为不同模型需要的输入格式
+//!
+//! Agent 内部始终以 OpenAI messages 结构保存上下文。接入纯 completion 端点或
+//! 使用特殊对话模板（ChatML、Alpaca 等）的模型时，由 [`PromptTemplate`] 在请求
+//! 组装阶段把 messages 渲染为单段文本，再把模型的文本回复解析回 [`Message`]。
+//!
+//! 这类模板不支持原生工具调用，工具通过文本协议约定：工具列表写进 system 段，
+//! 模型以 `<tool_call>{"name": .., "arguments": {..}}</tool_call>` 块发起调用，
+//! 工具结果以 `<tool_response>` 块回传，见 [`parse_tool_calls`]。
+//! 文本补全接口没有对应字段，请求中的 `tool_choice` 与 `response_format` 会被忽略（记录警告）。
+
+use crate::llm::types::{FunctionCall, Message, ToolCall, ToolDefinition};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::warn;
+
+/// 文本协议中工具调用块的起止标记
+pub const TOOL_CALL_OPEN: &str = "<tool_call>";
+pub const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+/// 模板渲染结果
+#[derive(Debug, Clone)]
+pub enum RenderedPrompt {
+    /// 原生 chat 格式，直接作为 messages 发送到 Chat Completions 接口
+    Messages(Vec<Message>),
+    /// 单段文本，发送到 `/completions` 接口
+    Text(String),
+}
+
+/// 把对话上下文渲染为模型输入的模板
+pub trait PromptTemplate: Send + Sync {
+    /// 渲染消息与工具定义
+    fn render(&self, messages: &[Message], tools: &[ToolDefinition]) -> RenderedPrompt;
+
+    /// 文本补全请求的停止序列，避免模型替用户续写下一轮
+    fn stop_sequences(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 把模型的文本回复解析为 assistant 消息（默认按 [`parse_tool_calls`] 的文本协议）
+    fn parse_reply(&self, text: &str) -> Message {
+        parse_tool_calls(text)
+    }
+}
+
+/// 内置模板选择，在 `ModelConfig` / `LlmConfig` 中声明（默认 OpenAI 原生 chat 格式）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptFormat {
+    /// 直接发送 messages，工具走原生 function calling
+    #[default]
+    OpenAi,
+    /// `<|im_start|>role ... <|im_end|>` 对话模板
+    ChatMl,
+    /// `### Instruction:` / `### Response:` 指令模板
+    Alpaca,
+}
+
+impl PromptFormat {
+    /// 对应的模板实现
+    pub fn template(&self) -> &'static dyn PromptTemplate {
+        match self {
+            Self::OpenAi => &OpenAiTemplate,
+            Self::ChatMl => &ChatMlTemplate,
+            Self::Alpaca => &AlpacaTemplate,
+        }
+    }
+
+    /// 是否使用原生 chat 格式（否则走文本补全接口）
+    pub fn is_chat(&self) -> bool {
+        matches!(self, Self::OpenAi)
+    }
+
+    /// 解析模板名（`openai` / `chatml` / `alpaca`），用于环境变量配置
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "openai" | "chat" => Ok(Self::OpenAi),
+            "chatml" => Ok(Self::ChatMl),
+            "alpaca" => Ok(Self::Alpaca),
+            other => Err(format!("未知的 prompt 模板: {other}")),
+        }
+    }
+}
+
+/// OpenAI 原生格式：messages 原样发送
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAiTemplate;
+
+impl PromptTemplate for OpenAiTemplate {
+    fn render(&self, messages: &[Message], _tools: &[ToolDefinition]) -> RenderedPrompt {
+        RenderedPrompt::Messages(messages.to_vec())
+    }
+}
+
+/// ChatML 模板，末尾留出 assistant 轮次的开头
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatMlTemplate;
+
+impl PromptTemplate for ChatMlTemplate {
+    fn render(&self, messages: &[Message], tools: &[ToolDefinition]) -> RenderedPrompt {
+        let mut prompt = String::new();
+        for (role, text) in text_turns(messages, tools) {
+            prompt.push_str(&format!("<|im_start|>{role}\n{text}<|im_end|>\n"));
+        }
+        prompt.push_str("<|im_start|>assistant\n");
+        RenderedPrompt::Text(prompt)
+    }
+
+    fn stop_sequences(&self) -> Vec<String> {
+        vec!["<|im_end|>".to_string(), "<|im_start|>".to_string()]
+    }
+
+    fn parse_reply(&self, text: &str) -> Message {
+        parse_tool_calls(text.trim_end().trim_end_matches("<|im_end|>"))
+    }
+}
+
+/// Alpaca 指令模板：system 内容作为开头说明，每轮对话为一组 Instruction / Response
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AlpacaTemplate;
+
+impl PromptTemplate for AlpacaTemplate {
+    fn render(&self, messages: &[Message], tools: &[ToolDefinition]) -> RenderedPrompt {
+        let mut sections = Vec::new();
+        for (role, text) in text_turns(messages, tools) {
+            sections.push(match role {
+                "system" => text,
+                "assistant" => format!("### Response:\n{text}"),
+                _ => format!("### Instruction:\n{text}"),
+            });
+        }
+        sections.push("### Response:\n".to_string());
+        RenderedPrompt::Text(sections.join("\n\n"))
+    }
+
+    fn stop_sequences(&self) -> Vec<String> {
+        vec!["### Instruction:".to_string()]
+    }
+}
+
+/// 把 messages 归一为 `(role, text)` 轮次：工具说明并入首条 system，
+/// assistant 的工具调用转为 `<tool_call>` 块，连续的工具结果合并为一条 user 轮次
+fn text_turns(messages: &[Message], tools: &[ToolDefinition]) -> Vec<(&'static str, String)> {
+    let mut turns: Vec<(&'static str, String)> = Vec::with_capacity(messages.len() + 1);
+    let mut last_from_tool = false;
+
+    for msg in messages {
+        let content = msg.content.as_deref().unwrap_or_default();
+        match msg.role.as_str() {
+            "tool" => {
+                let block = format!(
+                    "<tool_response name=\"{}\">\n{content}\n</tool_response>",
+                    msg.name.as_deref().unwrap_or("unknown")
+                );
+                match turns.last_mut() {
+                    Some((_, text)) if last_from_tool => {
+                        text.push('\n');
+                        text.push_str(&block);
+                    }
+                    _ => turns.push(("user", block)),
+                }
+                last_from_tool = true;
+                continue;
+            }
+            "assistant" => {
+                let mut parts: Vec<String> = Some(content.trim())
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string)
+                    .into_iter()
+                    .collect();
+                parts.extend(
+                    msg.tool_calls
+                        .iter()
+                        .flatten()
+                        .map(|call| tool_call_block(&call.function)),
+                );
+                turns.push(("assistant", parts.join("\n")));
+            }
+            "system" => turns.push(("system", content.to_string())),
+            _ => turns.push(("user", content.to_string())),
+        }
+        last_from_tool = false;
+    }
+
+    if let Some(instructions) = tool_instructions(tools) {
+        match turns.iter_mut().find(|(role, _)| *role == "system") {
+            Some((_, text)) => {
+                text.push_str("\n\n");
+                text.push_str(&instructions);
+            }
+            None => turns.insert(0, ("system", instructions)),
+        }
+    }
+    turns
+}
+
+/// 工具列表与调用格式说明，写进 system 段
+fn tool_instructions(tools: &[ToolDefinition]) -> Option<String> {
+    if tools.is_empty() {
+        return None;
+    }
+    let specs = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.function.name,
+                "description": tool.function.description,
+                "parameters": tool.function.parameters,
+            })
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "# 工具\n\n可以调用以下工具（parameters 为参数的 JSON Schema）：\n{specs}\n\n\
+         需要调用工具时，输出如下格式的块，每个块对应一次调用：\n\
+         {TOOL_CALL_OPEN}\n{{\"name\": \"<工具名>\", \"arguments\": {{<参数>}}}}\n{TOOL_CALL_CLOSE}"
+    ))
+}
+
+/// 单次工具调用的文本块
+fn tool_call_block(function: &FunctionCall) -> String {
+    let arguments = serde_json::from_str::<Value>(&function.arguments)
+        .unwrap_or_else(|_| Value::String(function.arguments.clone()));
+    let call = json!({ "name": function.name, "arguments": arguments });
+    format!("{TOOL_CALL_OPEN}\n{call}\n{TOOL_CALL_CLOSE}")
+}
+
+/// 按文本协议解析模型回复
+///
+/// 提取所有 `<tool_call>` 块为 `tool_calls`，其余文本作为 content；缺少结束标记时取到文本末尾。
+/// 无法解析的块保留在 content 中。
+///
+/// 文本协议不带调用 ID，这里生成 `call_<序号>_<uuid>`，保证多轮对话中 ID 不重复。
+pub fn parse_tool_calls(text: &str) -> Message {
+    let mut content = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(TOOL_CALL_OPEN) {
+        content.push_str(&rest[..start]);
+        let body_start = start + TOOL_CALL_OPEN.len();
+        let (body, next) = match rest[body_start..].find(TOOL_CALL_CLOSE) {
+            Some(end) => (
+                &rest[body_start..body_start + end],
+                body_start + end + TOOL_CALL_CLOSE.len(),
+            ),
+            None => (&rest[body_start..], rest.len()),
+        };
+        match parse_call_body(body) {
+            Some(function) => calls.push(ToolCall {
+                id: format!("call_{}_{}", calls.len(), uuid::Uuid::new_v4().simple()),
+                call_type: "function".to_string(),
+                function,
+            }),
+            None => {
+                warn!(block = body.trim(), "无法解析的工具调用块，按普通文本保留");
+                content.push_str(&rest[start..next]);
+            }
+        }
+        rest = &rest[next..];
+    }
+    content.push_str(rest);
+
+    let content = content.trim();
+    Message {
+        role: "assistant".to_string(),
+        content: (calls.is_empty() || !content.is_empty()).then(|| content.to_string()),
+        tool_calls: (!calls.is_empty()).then_some(calls),
+        name: None,
+        tool_call_id: None,
+    }
+}
+
+/// 解析 `{"name": .., "arguments": ..}`；arguments 可以是对象或 JSON 字符串
+fn parse_call_body(body: &str) -> Option<FunctionCall> {
+    let value: Value = serde_json::from_str(body.trim()).ok()?;
+    let name = value.get("name")?.as_str()?.to_string();
+    let arguments = match value.get("arguments") {
+        None | Some(Value::Null) => "{}".to_string(),
+        Some(Value::String(raw)) => raw.clone(),
+        Some(args) => args.to_string(),
+    };
+    Some(FunctionCall { name, arguments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::FunctionSpec;
+
+    fn weather_tool() -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".to_string(),
+            function: FunctionSpec {
+                name: "weather".to_string(),
+                description: "查询天气".to_string(),
+                parameters: json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+            },
+        }
+    }
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::system("你是助手".to_string()),
+            Message::user("北京天气？".to_string()),
+            Message::assistant_with_tools(vec![ToolCall {
+                id: "c1".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "weather".to_string(),
+                    arguments: r#"{"city":"北京"}"#.to_string(),
+                },
+            }]),
+            Message::tool_result("c1".to_string(), "weather".to_string(), "晴".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_chatml_renders_messages() {
+        let RenderedPrompt::Text(prompt) = ChatMlTemplate.render(&conversation(), &[]) else {
+            panic!("ChatML 应渲染为文本");
+        };
+        assert_eq!(
+            prompt,
+            "<|im_start|>system\n你是助手<|im_end|>\n\
+             <|im_start|>user\n北京天气？<|im_end|>\n\
+             <|im_start|>assistant\n<tool_call>\n{\"arguments\":{\"city\":\"北京\"},\"name\":\"weather\"}\n</tool_call><|im_end|>\n\
+             <|im_start|>user\n<tool_response name=\"weather\">\n晴\n</tool_response><|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+
+        // 工具说明并入 system 段
+        let RenderedPrompt::Text(with_tools) =
+            ChatMlTemplate.render(&conversation(), &[weather_tool()])
+        else {
+            panic!("ChatML 应渲染为文本");
+        };
+        let system = with_tools.split("<|im_end|>").next().unwrap();
+        assert!(system.starts_with("<|im_start|>system\n你是助手\n\n# 工具"));
+        assert!(system.contains("\"name\":\"weather\""));
+    }
+
+    #[test]
+    fn test_alpaca_and_openai_render() {
+        let RenderedPrompt::Text(prompt) = AlpacaTemplate.render(&conversation()[..2], &[]) else {
+            panic!("Alpaca 应渲染为文本");
+        };
+        assert_eq!(
+            prompt,
+            "你是助手\n\n### Instruction:\n北京天气？\n\n### Response:\n"
+        );
+
+        let RenderedPrompt::Messages(messages) = OpenAiTemplate.render(&conversation(), &[]) else {
+            panic!("OpenAI 模板应原样返回 messages");
+        };
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let reply = "我来查一下。\n<tool_call>\n{\"name\": \"weather\", \"arguments\": {\"city\": \"北京\"}}\n</tool_call>\n<tool_call>{\"name\": \"time\"}";
+        let message = parse_tool_calls(reply);
+        assert_eq!(message.content.as_deref(), Some("我来查一下。"));
+        let calls = message.tool_calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].id.starts_with("call_0_"));
+        assert!(calls[1].id.starts_with("call_1_"));
+        // 每次解析生成新的 ID，多轮之间不会重复
+        let again = parse_tool_calls(reply).tool_calls.unwrap();
+        assert_ne!(again[0].id, calls[0].id);
+        assert_eq!(calls[0].function.arguments, r#"{"city":"北京"}"#);
+        assert_eq!(calls[1].function.name, "time");
+        assert_eq!(calls[1].function.arguments, "{}");
+
+        // 无法解析的块按文本保留
+        let broken = parse_tool_calls("<tool_call>not json</tool_call>");
+        assert!(broken.tool_calls.is_none());
+        assert_eq!(
+            broken.content.as_deref(),
+            Some("<tool_call>not json</tool_call>")
+        );
+
+        assert_eq!(
+            ChatMlTemplate
+                .parse_reply("你好<|im_end|>")
+                .content
+                .as_deref(),
+            Some("你好")
+        );
+    }
+
+    #[test]
+    fn test_parse_prompt_format() {
+        assert_eq!(PromptFormat::parse("ChatML").unwrap(), PromptFormat::ChatMl);
+        assert_eq!(PromptFormat::parse("alpaca").unwrap(), PromptFormat::Alpaca);
+        assert!(PromptFormat::parse("unknown").is_err());
+        assert!(PromptFormat::default().is_chat());
+    }
+}
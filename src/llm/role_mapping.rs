@@ -0,0 +1,227 @@
+//! 消息角色重映射：兼容不支持 `tool` 角色或多条 system 消息的后端
+//!
+//! 在请求组装阶段对消息列表做转换，Agent 内部上下文保持 OpenAI 标准结构不变。
+
+use crate::llm::types::Message;
+use serde::{Deserialize, Serialize};
+
+/// `tool` 角色映射为 `user` 时添加的内容前缀
+pub const TOOL_RESULT_PREFIX: &str = "工具返回：";
+
+/// 消息角色映射策略（默认不做任何转换）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleMapping {
+    /// `tool` 消息转为带 [`TOOL_RESULT_PREFIX`] 前缀的 `user` 消息；
+    /// 对应 assistant 消息中的 `tool_calls` 同步转为文本描述
+    #[serde(default)]
+    pub tool_as_user: bool,
+    /// 将所有 system 消息按顺序合并为开头的一条
+    #[serde(default)]
+    pub merge_system: bool,
+}
+
+impl RoleMapping {
+    /// 不做转换的默认策略
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启用 `tool -> user` 映射
+    pub fn tool_as_user(mut self) -> Self {
+        self.tool_as_user = true;
+        self
+    }
+
+    /// 启用 system 消息合并
+    pub fn merge_system(mut self) -> Self {
+        self.merge_system = true;
+        self
+    }
+
+    /// 是否为不做任何转换的策略
+    pub fn is_identity(&self) -> bool {
+        !self.tool_as_user && !self.merge_system
+    }
+
+    /// 解析逗号分隔的策略名（如 `tool_as_user,merge_system`），用于环境变量配置
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut mapping = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match item.to_lowercase().as_str() {
+                "tool_as_user" | "tool->user" => mapping.tool_as_user = true,
+                "merge_system" => mapping.merge_system = true,
+                other => return Err(format!("未知的角色映射策略: {other}")),
+            }
+        }
+        Ok(mapping)
+    }
+
+    /// 对即将发送的消息列表做转换
+    ///
+    /// 转换后仍保证对话结构合法：不会残留无对应 `tool` 消息的 `tool_calls`，
+    /// 同一批工具结果合并为一条 user 消息，避免出现连续的 user 消息。
+    pub fn apply(&self, messages: Vec<Message>) -> Vec<Message> {
+        if self.is_identity() {
+            return messages;
+        }
+        let mut messages = messages;
+        if self.tool_as_user {
+            messages = Self::map_tool_to_user(messages);
+        }
+        if self.merge_system {
+            messages = Self::merge_system_messages(messages);
+        }
+        messages
+    }
+
+    fn map_tool_to_user(messages: Vec<Message>) -> Vec<Message> {
+        let mut out: Vec<Message> = Vec::with_capacity(messages.len());
+        // 上一条输出是否为工具结果转换来的 user 消息
+        let mut last_from_tool = false;
+
+        for mut msg in messages {
+            match msg.role.as_str() {
+                "tool" => {
+                    let name = msg.name.as_deref().unwrap_or("unknown");
+                    let text = format!(
+                        "{TOOL_RESULT_PREFIX}[{name}] {}",
+                        msg.content.as_deref().unwrap_or_default()
+                    );
+                    match out.last_mut() {
+                        Some(prev) if last_from_tool => {
+                            let content = prev.content.get_or_insert_with(String::new);
+                            content.push_str("\n\n");
+                            content.push_str(&text);
+                        }
+                        _ => out.push(Message::user(text)),
+                    }
+                    last_from_tool = true;
+                    continue;
+                }
+                "assistant" => {
+                    if let Some(calls) = msg.tool_calls.take().filter(|c| !c.is_empty()) {
+                        let mut lines: Vec<String> = msg
+                            .content
+                            .take()
+                            .filter(|c| !c.trim().is_empty())
+                            .into_iter()
+                            .collect();
+                        lines.extend(calls.iter().map(|call| {
+                            format!(
+                                "调用工具 {}({})",
+                                call.function.name, call.function.arguments
+                            )
+                        }));
+                        msg.content = Some(lines.join("\n"));
+                    }
+                }
+                _ => {}
+            }
+            last_from_tool = false;
+            out.push(msg);
+        }
+        out
+    }
+
+    fn merge_system_messages(messages: Vec<Message>) -> Vec<Message> {
+        let (system, rest): (Vec<Message>, Vec<Message>) =
+            messages.into_iter().partition(|m| m.role == "system");
+        if system.len() <= 1 {
+            return system.into_iter().chain(rest).collect();
+        }
+        let merged = system
+            .into_iter()
+            .filter_map(|m| m.content)
+            .filter(|c| !c.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        std::iter::once(Message::system(merged))
+            .chain(rest)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{FunctionCall, ToolCall};
+
+    fn tool_call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: "{}".to_string(),
+            },
+        }
+    }
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::system("你是助手".to_string()),
+            Message::user("查天气".to_string()),
+            Message::assistant_with_tools(vec![
+                tool_call("c1", "weather"),
+                tool_call("c2", "time"),
+            ]),
+            Message::tool_result("c1".to_string(), "weather".to_string(), "晴".to_string()),
+            Message::tool_result("c2".to_string(), "time".to_string(), "12:00".to_string()),
+            Message::assistant("今天晴".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_identity_mapping_keeps_messages() {
+        let mapped = RoleMapping::new().apply(conversation());
+        assert_eq!(mapped.len(), 6);
+        assert_eq!(mapped[3].role, "tool");
+    }
+
+    #[test]
+    fn test_tool_as_user_adds_prefix_and_keeps_structure() {
+        let mapped = RoleMapping::new().tool_as_user().apply(conversation());
+        let roles: Vec<&str> = mapped.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user", "assistant"]);
+
+        // 同一批工具结果合并为一条带前缀的 user 消息
+        let tool_msg = mapped[3].content.as_deref().unwrap();
+        assert!(tool_msg.starts_with(TOOL_RESULT_PREFIX));
+        assert!(tool_msg.contains("[weather] 晴"));
+        assert!(tool_msg.contains("[time] 12:00"));
+
+        // assistant 不再携带 tool_calls，调用信息转为文本
+        assert!(mapped.iter().all(|m| m.tool_calls.is_none()));
+        assert!(
+            mapped[2]
+                .content
+                .as_deref()
+                .unwrap()
+                .contains("调用工具 weather")
+        );
+    }
+
+    #[test]
+    fn test_merge_system_messages() {
+        let messages = vec![
+            Message::system("规则 A".to_string()),
+            Message::user("你好".to_string()),
+            Message::system("规则 B".to_string()),
+        ];
+        let mapped = RoleMapping::new().merge_system().apply(messages);
+        assert_eq!(mapped.len(), 2);
+        assert_eq!(mapped[0].role, "system");
+        assert_eq!(mapped[0].content.as_deref(), Some("规则 A\n\n规则 B"));
+        assert_eq!(mapped[1].role, "user");
+    }
+
+    #[test]
+    fn test_parse_role_mapping() {
+        assert_eq!(
+            RoleMapping::parse("tool_as_user, merge_system").unwrap(),
+            RoleMapping::new().tool_as_user().merge_system()
+        );
+        assert!(RoleMapping::parse("").unwrap().is_identity());
+        assert!(RoleMapping::parse("unknown").is_err());
+    }
+}
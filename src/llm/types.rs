@@ -141,6 +141,63 @@ impl ResponseFormat {
     }
 }
 
+/// 工具选择策略，对应 OpenAI `tool_choice` 字段
+///
+/// - `Auto`：由模型自行决定（序列化为 `"auto"`）
+/// - `None`：禁用工具调用（`"none"`）
+/// - `Required`：必须调用至少一个工具（`"required"`）
+/// - `Function(name)`：强制调用指定工具（`{"type":"function","function":{"name":...}}`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    Auto,
+    None,
+    Required,
+    Function(String),
+}
+
+impl ToolChoice {
+    /// 强制调用指定工具
+    pub fn function(name: impl Into<String>) -> Self {
+        Self::Function(name.into())
+    }
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Auto => serializer.serialize_str("auto"),
+            Self::None => serializer.serialize_str("none"),
+            Self::Required => serializer.serialize_str("required"),
+            Self::Function(name) => serde_json::json!({
+                "type": "function",
+                "function": { "name": name }
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        match &value {
+            serde_json::Value::String(s) => match s.as_str() {
+                "auto" => Ok(Self::Auto),
+                "none" => Ok(Self::None),
+                "required" => Ok(Self::Required),
+                other => Err(D::Error::custom(format!("未知的 tool_choice: {other}"))),
+            },
+            _ => value
+                .pointer("/function/name")
+                .and_then(|v| v.as_str())
+                .map(|name| Self::Function(name.to_string()))
+                .ok_or_else(|| D::Error::custom(format!("无效的 tool_choice: {value}"))),
+        }
+    }
+}
+
 /// OpenAI `/chat/completions` 请求体
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatCompletionRequest {
@@ -148,9 +205,9 @@ pub struct ChatCompletionRequest {
     pub messages: Vec<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ToolDefinition>>,
-    /// 工具选择策略：`"auto"` / `"none"` / 指定工具名
+    /// 工具选择策略，见 [`ToolChoice`]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_choice: Option<String>,
+    pub tool_choice: Option<ToolChoice>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -160,6 +217,44 @@ pub struct ChatCompletionRequest {
     /// 结构化输出格式（None = 默认文本）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<ResponseFormat>,
+    /// 采样随机种子（None 时不发送，不支持的服务端会忽略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// 候选回复数量，大于 1 时响应包含多个 choices（None 时不发送）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+}
+
+/// 文本补全（`/completions`）请求体，供非 chat 格式的 prompt 模板使用
+#[derive(Debug, Serialize, Clone)]
+pub struct TextCompletionRequest {
+    pub model: String,
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// 文本补全响应
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextCompletionResponse {
+    #[serde(default)]
+    pub choices: Vec<TextChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextChoice {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
 /// 发送给 LLM 的工具定义（对应 OpenAI tools 数组元素）
@@ -203,11 +298,37 @@ pub struct ChatCompletionResponse {
     model: Option<String>,
     #[serde(default)]
     usage: Option<Usage>,
+    /// 后端配置指纹，变化意味着即使 seed 相同输出也可能不同
+    #[serde(default)]
+    system_fingerprint: Option<String>,
     #[serde(default)]
     #[serde(flatten)]
     extra: serde_json::Value,
 }
 
+impl ChatCompletionResponse {
+    /// 响应中的 `system_fingerprint`（服务端未返回时为 None）
+    pub fn system_fingerprint(&self) -> Option<&str> {
+        self.system_fingerprint.as_deref()
+    }
+
+    /// 响应中的 token 用量（服务端未返回时为 None）
+    pub fn usage(&self) -> Option<&Usage> {
+        self.usage.as_ref()
+    }
+
+    /// 所有候选回复，按服务端返回的顺序
+    pub fn choice_messages(&self) -> impl Iterator<Item = &Message> {
+        self.choices.iter().map(|c| &c.message)
+    }
+
+    /// 设置 token 用量（供 mock 客户端构造带用量的响应）
+    pub(crate) fn with_usage(mut self, usage: Option<Usage>) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Choice {
     pub message: Message,
@@ -217,14 +338,45 @@ pub struct Choice {
     index: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Choice {
+    pub(crate) fn new(message: Message, finish_reason: Option<String>) -> Self {
+        Self {
+            message,
+            finish_reason,
+            index: Some(0),
+        }
+    }
+
+    pub(crate) fn with_index(mut self, index: u32) -> Self {
+        self.index = Some(index);
+        self
+    }
+}
+
+/// 单次请求的 token 用量
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct Usage {
     #[serde(default)]
-    prompt_tokens: Option<u32>,
+    pub prompt_tokens: Option<u32>,
     #[serde(default)]
-    completion_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
     #[serde(default)]
-    total_tokens: Option<u32>,
+    pub total_tokens: Option<u32>,
+}
+
+impl Usage {
+    /// 累加另一次请求的用量；任一侧缺失的字段按 0 计
+    pub fn accumulate(&mut self, other: &Usage) {
+        fn add(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+            match (a, b) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+            }
+        }
+        self.prompt_tokens = add(self.prompt_tokens, other.prompt_tokens);
+        self.completion_tokens = add(self.completion_tokens, other.completion_tokens);
+        self.total_tokens = add(self.total_tokens, other.total_tokens);
+    }
 }
 
 // ── 流式响应类型 ──────────────────────────────────────────────────────────────
@@ -236,6 +388,30 @@ pub struct ChatCompletionChunk {
     pub id: String,
     #[serde(default)]
     pub choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    system_fingerprint: Option<String>,
+}
+
+impl ChatCompletionChunk {
+    /// 构造只含 choices 的 chunk
+    pub fn new(id: impl Into<String>, choices: Vec<ChunkChoice>) -> Self {
+        Self {
+            id: id.into(),
+            choices,
+            system_fingerprint: None,
+        }
+    }
+
+    /// chunk 中的 `system_fingerprint`（服务端未返回时为 None）
+    pub fn system_fingerprint(&self) -> Option<&str> {
+        self.system_fingerprint.as_deref()
+    }
+
+    /// 设置 `system_fingerprint`
+    pub(crate) fn with_system_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.system_fingerprint = fingerprint;
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -278,3 +454,116 @@ pub struct DeltaFunctionCall {
     #[serde(default)]
     pub arguments: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(choice: Option<ToolChoice>) -> serde_json::Value {
+        let request = ChatCompletionRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message::user("hi".to_string())],
+            tools: None,
+            tool_choice: choice,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+            response_format: None,
+            seed: None,
+            n: None,
+        };
+        serde_json::to_value(&request).unwrap()
+    }
+
+    #[test]
+    fn test_tool_choice_in_request_body() {
+        assert!(request_with(None).get("tool_choice").is_none());
+        assert_eq!(
+            request_with(Some(ToolChoice::Auto))["tool_choice"],
+            serde_json::json!("auto")
+        );
+        assert_eq!(
+            request_with(Some(ToolChoice::None))["tool_choice"],
+            serde_json::json!("none")
+        );
+        assert_eq!(
+            request_with(Some(ToolChoice::function("plan")))["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "plan"}})
+        );
+    }
+
+    #[test]
+    fn test_seed_in_request_body() {
+        let mut request: ChatCompletionRequest =
+            serde_json::from_value(request_with(None)).unwrap();
+        assert!(
+            serde_json::to_value(&request)
+                .unwrap()
+                .get("seed")
+                .is_none()
+        );
+
+        request.seed = Some(42);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["seed"],
+            serde_json::json!(42)
+        );
+    }
+
+    #[test]
+    fn test_n_in_request_body() {
+        assert!(request_with(None).get("n").is_none());
+        let mut request: ChatCompletionRequest =
+            serde_json::from_value(request_with(None)).unwrap();
+        request.n = Some(3);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["n"],
+            serde_json::json!(3)
+        );
+    }
+
+    #[test]
+    fn test_system_fingerprint_parsed() {
+        let response: ChatCompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "x",
+            "choices": [],
+            "system_fingerprint": "fp_abc"
+        }))
+        .unwrap();
+        assert_eq!(response.system_fingerprint(), Some("fp_abc"));
+
+        let response: ChatCompletionResponse =
+            serde_json::from_value(serde_json::json!({"choices": []})).unwrap();
+        assert_eq!(response.system_fingerprint(), None);
+    }
+
+    #[test]
+    fn test_chunk_system_fingerprint_parsed() {
+        let chunk: ChatCompletionChunk = serde_json::from_value(serde_json::json!({
+            "id": "x",
+            "choices": [],
+            "system_fingerprint": "fp_abc"
+        }))
+        .unwrap();
+        assert_eq!(chunk.system_fingerprint(), Some("fp_abc"));
+
+        let chunk: ChatCompletionChunk =
+            serde_json::from_value(serde_json::json!({"choices": []})).unwrap();
+        assert_eq!(chunk.system_fingerprint(), None);
+    }
+
+    #[test]
+    fn test_tool_choice_roundtrip() {
+        for choice in [
+            ToolChoice::Auto,
+            ToolChoice::None,
+            ToolChoice::Required,
+            ToolChoice::function("search"),
+        ] {
+            let json = serde_json::to_string(&choice).unwrap();
+            let back: ToolChoice = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, choice);
+        }
+        assert!(serde_json::from_str::<ToolChoice>("\"sometimes\"").is_err());
+    }
+}
@@ -42,13 +42,44 @@
 //!         Authorization: "Bearer mytoken"
 //! ```
 //!
+//! # 从配置文件加载全部运行参数
+//! ```bash
+//! cargo run -- --config agent.yaml
+//! # 命令行显式参数优先于配置文件
+//! cargo run -- --config agent.yaml --model gpt-4o
+//! ```
+//!
+//! # agent.yaml 配置文件格式
+//! ```yaml
+//! model: qwen3-max
+//! system: "你是一个助手"
+//! tools: [math, files]
+//! compressor: "sliding:20"
+//! token_limit: 8000
+//! max_iter: 20
+//! memory: true
+//! skills_dir: ./skills
+//! mcp: mcp.yaml             # 可选，外部 MCP 配置文件
+//! mcp_servers:              # 可选，内联 MCP 服务端（格式同 mcp.yaml 的 servers）
+//!   - name: filesystem
+//!     stdio:
+//!       command: npx
+//!       args: ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]
+//! ```
+//!
+//! # 逐步调试（每次工具调用前暂停：回车继续 / s 跳过 / q 中止）
+//! ```bash
+//! cargo run -- --tools files --step
+//! ```
+//!
 //! # 单次查询 / 管道模式（stdin 非 TTY 时自动切换）
 //! ```bash
 //! cargo run -- -q "帮我计算 1+1" --tools math
 //! echo "列出当前目录" | cargo run -- --tools files
 //! ```
 
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use echo_agent::agent::react_agent::ReactAgent;
 use echo_agent::agent::{Agent, AgentConfig, AgentEvent};
 use echo_agent::compression::ContextCompressor;
@@ -56,8 +87,10 @@ use echo_agent::compression::compressor::{
     DefaultSummaryPrompt, HybridCompressor, SlidingWindowCompressor, SummaryCompressor,
 };
 use echo_agent::error::ReactError;
+use echo_agent::error::Result as AgentResult;
 use echo_agent::human_loop::{
-    ApprovalDecision, HumanLoopEvent, HumanLoopHandler, HumanLoopManager, dispatch_event,
+    ApprovalDecision, HumanLoopEvent, HumanLoopHandler, HumanLoopKind, HumanLoopManager,
+    HumanLoopProvider, HumanLoopRequest, HumanLoopResponse, default_provider, dispatch_event,
 };
 use echo_agent::llm::DefaultLlmClient;
 use echo_agent::mcp::{McpManager, McpServerConfig, TransportConfig};
@@ -68,9 +101,11 @@ use reqwest::Client;
 use rustyline::DefaultEditor;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::future::Future;
 use std::io::{self, BufRead, IsTerminal, Write};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::io::AsyncBufReadExt as _;
+use tokio_util::sync::CancellationToken;
 
 // ── CLI 参数定义 ──────────────────────────────────────────────────────────────
 
@@ -87,6 +122,10 @@ use tokio::io::AsyncBufReadExt as _;
     version
 )]
 struct Cli {
+    /// 运行参数配置文件路径（YAML 格式），命令行显式参数优先于配置文件
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<String>,
+
     /// 使用的模型名称（需在 .env 中配置对应的 AGENT_MODEL_*_* 环境变量）
     #[arg(short, long, default_value = "qwen3-max", env = "ECHO_MODEL")]
     model: String,
@@ -155,6 +194,10 @@ struct Cli {
     #[arg(long)]
     human_loop: bool,
 
+    /// 逐步调试：每次工具调用前暂停，回车继续 / s 跳过该工具 / q 中止任务（管道模式下忽略）
+    #[arg(long)]
+    step: bool,
+
     /// 上下文压缩策略（summary[:N] / sliding[:N] / hybrid[:N] / none）
     ///
     /// 作为 /compress 命令的默认策略，并在设置 --token-limit 后自动触发。
@@ -191,6 +234,42 @@ struct Cli {
     /// Checkpointer 文件路径（默认 ~/.echo-agent/checkpoints.json）
     #[arg(long, value_name = "PATH")]
     checkpointer_path: Option<String>,
+
+    /// 配置文件中内联声明的 MCP 服务端（仅来自 --config）
+    #[arg(skip)]
+    mcp_servers: Vec<McpServerConfig>,
+}
+
+// ── 运行参数配置文件结构体 ────────────────────────────────────────────────────
+
+/// `--config` 指定的运行参数配置文件，字段与命令行参数一一对应，缺失字段使用 CLI 默认值
+#[derive(Deserialize, Default)]
+struct AgentConfigFile {
+    model: Option<String>,
+    system: Option<String>,
+    /// 启用的内置工具集（如 `[math, files]`）
+    tools: Option<Vec<String>>,
+    skills_dir: Option<String>,
+    /// 外部 MCP 配置文件路径
+    mcp: Option<String>,
+    /// 内联 MCP 服务端列表
+    #[serde(default)]
+    mcp_servers: Vec<McpServerYaml>,
+    log_level: Option<String>,
+    max_iter: Option<usize>,
+    no_stream: Option<bool>,
+    human_loop: Option<bool>,
+    step: Option<bool>,
+    compressor: Option<String>,
+    token_limit: Option<usize>,
+    ctx_stats: Option<bool>,
+    memory: Option<bool>,
+    memory_path: Option<String>,
+    session_id: Option<String>,
+    checkpointer_path: Option<String>,
+    /// 未识别的字段，仅用于告警
+    #[serde(flatten)]
+    unknown: HashMap<String, serde_yaml::Value>,
 }
 
 // ── MCP 配置文件结构体 ────────────────────────────────────────────────────────
@@ -234,7 +313,7 @@ struct McpHttpYaml {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+    let cli = resolve_cli(std::env::args_os())?;
 
     let log_filter = std::env::var("RUST_LOG").unwrap_or_else(|_| cli.log_level.clone());
     tracing_subscriber::fmt()
@@ -255,7 +334,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .collect();
 
     // 是否存在 MCP 配置（影响 enable_tool 默认值）
-    let has_mcp_config = cli.mcp.is_some() || !cli.mcp_stdio.is_empty() || !cli.mcp_http.is_empty();
+    let has_mcp_config = cli.mcp.is_some()
+        || !cli.mcp_stdio.is_empty()
+        || !cli.mcp_http.is_empty()
+        || !cli.mcp_servers.is_empty();
 
     let http = Arc::new(Client::new());
     let mut agent = build_agent(&cli, &enabled_tools, &http, has_mcp_config);
@@ -278,8 +360,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     if let Some(query) = &cli.query {
-        run_single_query(&mut agent, query, cli.no_stream).await?;
+        let step = cli
+            .step
+            .then(|| install_step_mode(&mut agent, default_provider()));
+        run_single_query(&mut agent, query, cli.no_stream, step.as_deref()).await?;
     } else if !io::stdin().is_terminal() {
+        if cli.step {
+            eprintln!("警告: 管道模式下 stdin 不是终端，已忽略 --step");
+        }
         run_pipe_mode(&mut agent, cli.no_stream).await?;
     } else {
         print_banner(&cli, &enabled_tools, &mcp_manager);
@@ -292,6 +380,116 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+// ── 配置文件合并 ──────────────────────────────────────────────────────────────
+
+/// 解析命令行参数；若指定了 `--config`，加载配置文件并与命令行合并
+///
+/// 合并规则：命令行（含环境变量）显式给出的参数优先，其余取配置文件中的值，
+/// 两者都没有时保留 CLI 默认值。
+fn resolve_cli<I, T>(args: I) -> Result<Cli, Box<dyn std::error::Error>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let matches = Cli::command().get_matches_from(args);
+    let mut cli = Cli::from_arg_matches(&matches)?;
+
+    if let Some(path) = cli.config.clone() {
+        let file = load_agent_config_file(&path)?;
+        let is_explicit = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        apply_config_file(&mut cli, file, is_explicit);
+    }
+
+    Ok(cli)
+}
+
+/// 读取并解析运行参数配置文件，未知字段打印警告后忽略
+fn load_agent_config_file(path: &str) -> Result<AgentConfigFile, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let file: AgentConfigFile = serde_yaml::from_str(&content)?;
+
+    let mut unknown: Vec<&String> = file.unknown.keys().collect();
+    unknown.sort();
+    for key in unknown {
+        eprintln!("警告: 配置文件 '{path}' 中存在未知字段 '{key}'，已忽略");
+    }
+
+    Ok(file)
+}
+
+/// 将配置文件中的值填入 `cli`，`is_explicit(id)` 为真的参数保持命令行取值
+fn apply_config_file(cli: &mut Cli, file: AgentConfigFile, is_explicit: impl Fn(&str) -> bool) {
+    fn merge<T>(slot: &mut T, value: Option<T>, explicit: bool) {
+        if let Some(v) = value
+            && !explicit
+        {
+            *slot = v;
+        }
+    }
+
+    merge(&mut cli.model, file.model, is_explicit("model"));
+    merge(
+        &mut cli.system,
+        file.system.map(Some),
+        is_explicit("system"),
+    );
+    merge(
+        &mut cli.tools,
+        file.tools.map(|t| Some(t.join(","))),
+        is_explicit("tools"),
+    );
+    merge(
+        &mut cli.skills_dir,
+        file.skills_dir.map(Some),
+        is_explicit("skills_dir"),
+    );
+    merge(&mut cli.mcp, file.mcp.map(Some), is_explicit("mcp"));
+    merge(&mut cli.log_level, file.log_level, is_explicit("log_level"));
+    merge(&mut cli.max_iter, file.max_iter, is_explicit("max_iter"));
+    merge(&mut cli.no_stream, file.no_stream, is_explicit("no_stream"));
+    merge(
+        &mut cli.human_loop,
+        file.human_loop,
+        is_explicit("human_loop"),
+    );
+    merge(&mut cli.step, file.step, is_explicit("step"));
+    merge(
+        &mut cli.compressor,
+        file.compressor,
+        is_explicit("compressor"),
+    );
+    merge(
+        &mut cli.token_limit,
+        file.token_limit.map(Some),
+        is_explicit("token_limit"),
+    );
+    merge(&mut cli.ctx_stats, file.ctx_stats, is_explicit("ctx_stats"));
+    merge(&mut cli.memory, file.memory, is_explicit("memory"));
+    merge(
+        &mut cli.memory_path,
+        file.memory_path.map(Some),
+        is_explicit("memory_path"),
+    );
+    merge(
+        &mut cli.session_id,
+        file.session_id.map(Some),
+        is_explicit("session_id"),
+    );
+    merge(
+        &mut cli.checkpointer_path,
+        file.checkpointer_path.map(Some),
+        is_explicit("checkpointer_path"),
+    );
+
+    cli.mcp_servers
+        .extend(file.mcp_servers.into_iter().map(mcp_yaml_to_config));
+}
+
 // ── Agent 构建 ────────────────────────────────────────────────────────────────
 
 fn build_agent(cli: &Cli, tools: &[&str], http: &Arc<Client>, has_mcp: bool) -> ReactAgent {
@@ -330,14 +528,18 @@ fn build_agent(cli: &Cli, tools: &[&str], http: &Arc<Client>, has_mcp: bool) ->
     let mut agent = ReactAgent::new(config);
 
     for tool_name in tools {
-        match *tool_name {
+        let installed = match *tool_name {
             "math" => agent.add_skill(Box::new(CalculatorSkill)),
             "weather" => agent.add_skill(Box::new(WeatherSkill)),
             "files" => agent.add_skill(Box::new(FileSystemSkill::new())),
             "shell" => agent.add_skill(Box::new(ShellSkill::new())),
             other => {
-                eprintln!("警告: 未知工具 '{other}'，已跳过（可选: math, weather, files, shell）")
+                eprintln!("警告: 未知工具 '{other}'，已跳过（可选: math, weather, files, shell）");
+                continue;
             }
+        };
+        if let Err(e) = installed {
+            eprintln!("警告: 工具 '{tool_name}' 安装失败，已跳过: {e}");
         }
     }
 
@@ -404,6 +606,9 @@ fn collect_mcp_configs(cli: &Cli) -> Vec<McpServerConfig> {
         }
     }
 
+    // 配置文件内联的服务端
+    configs.extend(cli.mcp_servers.iter().cloned());
+
     // 内联 stdio 服务端
     for spec in &cli.mcp_stdio {
         match parse_mcp_stdio_spec(spec) {
@@ -430,29 +635,22 @@ fn load_mcp_config_file(path: &str) -> Result<Vec<McpServerConfig>, Box<dyn std:
     let content = std::fs::read_to_string(path)?;
     let file: McpConfigFile = serde_yaml::from_str(&content)?;
 
-    let configs = file
-        .servers
-        .into_iter()
-        .map(|def| match def.transport {
-            McpTransportYaml::Stdio(s) => McpServerConfig {
-                name: def.name,
-                transport: TransportConfig::Stdio {
-                    command: s.command,
-                    args: s.args,
-                    env: s.env.into_iter().collect(),
-                },
-            },
-            McpTransportYaml::Http(h) => McpServerConfig {
-                name: def.name,
-                transport: TransportConfig::Http {
-                    base_url: h.url,
-                    headers: h.headers,
-                },
-            },
-        })
-        .collect();
+    Ok(file.servers.into_iter().map(mcp_yaml_to_config).collect())
+}
 
-    Ok(configs)
+/// 将 YAML 中的服务端定义转换为 McpServerConfig
+fn mcp_yaml_to_config(def: McpServerYaml) -> McpServerConfig {
+    match def.transport {
+        McpTransportYaml::Stdio(s) => McpServerConfig {
+            name: def.name,
+            transport: TransportConfig::Stdio {
+                command: s.command,
+                args: s.args,
+                env: s.env.into_iter().collect(),
+            },
+        },
+        McpTransportYaml::Http(h) => McpServerConfig::http_with_headers(def.name, h.url, h.headers),
+    }
 }
 
 /// 解析 stdio 内联格式："名称 命令 [参数...]"
@@ -496,13 +694,7 @@ fn parse_mcp_http_spec(spec: &str) -> Option<McpServerConfig> {
         })
         .collect();
 
-    Some(McpServerConfig {
-        name: name.to_string(),
-        transport: TransportConfig::Http {
-            base_url: url.to_string(),
-            headers,
-        },
-    })
+    Some(McpServerConfig::http_with_headers(name, url, headers))
 }
 
 fn describe_transport(transport: &TransportConfig) -> String {
@@ -586,12 +778,18 @@ async fn run_single_query(
     agent: &mut ReactAgent,
     query: &str,
     no_stream: bool,
+    step: Option<&StepProvider>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if no_stream {
-        println!("{}", agent.execute(query).await?);
+        if let Some(step) = step {
+            step.reset();
+        }
+        if let Some(answer) = run_abortable(step, agent.execute(query)).await {
+            println!("{}", answer?);
+        }
     } else {
         // 单次查询使用默认的 ConsoleHumanLoopProvider，无需 manager
-        stream_execute(agent, query, false, None).await?;
+        stream_execute(agent, query, false, None, step).await?;
         println!();
     }
     Ok(())
@@ -616,7 +814,7 @@ async fn run_pipe_mode(
                 Err(e) => eprintln!("错误: {e:?}"),
             }
         } else {
-            if let Err(e) = stream_execute(agent, &query, false, None).await {
+            if let Err(e) = stream_execute(agent, &query, false, None, None).await {
                 eprintln!("错误: {e:?}");
             }
             println!();
@@ -636,7 +834,12 @@ async fn run_interactive(
     // 创建统一的 HumanLoopManager：用户与 agent 的所有交互（工具审批、输入请求）
     // 均通过下方主循环的同一个 "you > " 提示符完成，与正常对话入口保持一致。
     let manager = Arc::new(HumanLoopManager::new());
-    agent.set_human_loop_provider(manager.clone());
+    let step = if cli.step {
+        Some(install_step_mode(agent, manager.clone()))
+    } else {
+        agent.set_human_loop_provider(manager.clone());
+        None
+    };
 
     let mut rl = DefaultEditor::new()?;
     let history_path = home_dir().map(|h| h.join(".echo_agent_history"));
@@ -695,8 +898,25 @@ async fn run_interactive(
                         continue;
                     }
                     "/ctx" | "/context" => {
-                        let (count, tokens) = agent.context_stats();
-                        println!("上下文: {} 条消息  /  ~{} tokens\n", count, tokens);
+                        let (count, _) = agent.context_stats();
+                        let b = agent.context_breakdown();
+                        let pct = |n: usize| n * 100 / b.total.max(1);
+                        println!("上下文: {} 条消息  /  ~{} tokens", count, b.total);
+                        println!(
+                            "  system:       ~{} tokens ({}%)",
+                            b.system_tokens,
+                            pct(b.system_tokens)
+                        );
+                        println!(
+                            "  tools schema: ~{} tokens ({}%)",
+                            b.tools_schema_tokens,
+                            pct(b.tools_schema_tokens)
+                        );
+                        println!(
+                            "  history:      ~{} tokens ({}%)\n",
+                            b.history_tokens,
+                            pct(b.history_tokens)
+                        );
                         continue;
                     }
                     "/tools" => {
@@ -761,6 +981,9 @@ async fn run_interactive(
                                             &latest.checkpoint_id[..8],
                                             latest.messages.len()
                                         );
+                                        if let Some(ref title) = latest.title {
+                                            println!("会话标题: {title}");
+                                        }
                                     }
                                     println!();
                                 }
@@ -773,6 +996,10 @@ async fn run_interactive(
                         }
                         continue;
                     }
+                    "/save" => {
+                        run_save_cmd(agent, arg).await;
+                        continue;
+                    }
                     c if c.starts_with('/') => {
                         println!("未知命令: {c}（输入 /help 查看帮助）\n");
                         continue;
@@ -792,17 +1019,28 @@ async fn run_interactive(
                         let handler = CliHumanLoopHandler; // 在 async block 内构造，避免生命周期泄漏
                         mgr.serve(&handler).await;
                     });
-                    let result = match agent.execute(&input).await {
-                        Ok(answer) => {
+                    if let Some(step) = &step {
+                        step.reset();
+                    }
+                    let result = match run_abortable(step.as_deref(), agent.execute(&input)).await {
+                        Some(Ok(answer)) => {
                             println!("{}", answer);
                             Ok(())
                         }
-                        Err(e) => Err(e),
+                        Some(Err(e)) => Err(e),
+                        None => Ok(()),
                     };
                     hl_task.abort();
                     result
                 } else {
-                    stream_execute(agent, &input, cli.ctx_stats, Some(&manager)).await
+                    stream_execute(
+                        agent,
+                        &input,
+                        cli.ctx_stats,
+                        Some(&manager),
+                        step.as_deref(),
+                    )
+                    .await
                 };
 
                 println!();
@@ -864,6 +1102,33 @@ fn print_mcp_status(mcp: &McpManager) {
     println!();
 }
 
+// ── /save 命令处理 ────────────────────────────────────────────────────────────
+
+async fn run_save_cmd(agent: &ReactAgent, arg: &str) {
+    let (Some(cp), Some(tid)) = (agent.checkpointer(), agent.config().get_session_id()) else {
+        println!("会话恢复未启用。使用 --session-id <ID> 启动以开启会话保存。\n");
+        return;
+    };
+    let title = if arg.is_empty() {
+        match agent.generate_title().await {
+            Ok(title) => title,
+            Err(e) => {
+                eprintln!("生成标题失败: {e}\n");
+                return;
+            }
+        }
+    } else {
+        arg.to_string()
+    };
+    match cp
+        .put_with_title(tid, agent.get_messages().to_vec(), Some(title.clone()))
+        .await
+    {
+        Ok(id) => println!("会话已保存: {title}（checkpoint {}）\n", &id[..8]),
+        Err(e) => eprintln!("保存会话失败: {e}\n"),
+    }
+}
+
 // ── /memory 命令处理 ──────────────────────────────────────────────────────────
 
 async fn run_memory_cmd(agent: &ReactAgent, arg: &str) {
@@ -1038,8 +1303,9 @@ async fn stream_execute(
     task: &str,
     show_ctx_stats: bool,
     manager: Option<&HumanLoopManager>,
+    step: Option<&StepProvider>,
 ) -> Result<(), ReactError> {
-    let result = stream_run(agent, task, manager).await;
+    let result = stream_run(agent, task, manager, step).await;
     if show_ctx_stats {
         let (count, tokens) = agent.context_stats();
         println!("\n  (上下文: {} 条 / ~{} tokens)", count, tokens);
@@ -1057,15 +1323,26 @@ async fn stream_run(
     agent: &mut ReactAgent,
     task: &str,
     manager: Option<&HumanLoopManager>,
+    step: Option<&StepProvider>,
 ) -> Result<(), ReactError> {
+    if let Some(step) = step {
+        step.reset();
+    }
+    let abort = step.map(StepProvider::abort_token);
     let mut event_stream = agent.execute_stream(task).await?;
     let mut in_token = false;
     let mut iter = 0usize;
 
     loop {
-        // 用 select! 同时等待 agent 流事件和 human-loop 事件
+        // 用 select! 同时等待 agent 流事件和 human-loop 事件；
+        // step 模式下用户中止时丢弃事件流，不再发起后续推理
         let agent_event = if let Some(mgr) = manager {
             tokio::select! {
+                biased;
+                _ = wait_abort(abort.as_ref()) => {
+                    println!("\n  [step] 任务已中止");
+                    return Ok(());
+                }
                 chunk = event_stream.next() => chunk,
                 Some(hl_event) = mgr.recv_event() => {
                     // agent 正在等待用户响应，stream 处于挂起状态
@@ -1081,7 +1358,14 @@ async fn stream_run(
                 }
             }
         } else {
-            event_stream.next().await
+            tokio::select! {
+                biased;
+                _ = wait_abort(abort.as_ref()) => {
+                    println!("\n  [step] 任务已中止");
+                    return Ok(());
+                }
+                chunk = event_stream.next() => chunk,
+            }
         };
 
         match agent_event {
@@ -1103,6 +1387,10 @@ async fn stream_run(
                     }
                     println!("\n  [工具调用] {}({})", name, fmt_args(&args));
                 }
+                AgentEvent::ToolResultChunk { chunk, .. } => {
+                    print!("  │ {}", chunk);
+                    io::stdout().flush().ok();
+                }
                 AgentEvent::ToolResult { name, output } => {
                     println!("  [工具结果] {} → {}", name, truncate_chars(&output, 120));
                     print!("\nagent > ");
@@ -1125,6 +1413,13 @@ async fn stream_run(
                     }
                     println!("\n  [执行已取消]");
                 }
+                AgentEvent::TaskUpdate {
+                    task_id,
+                    status,
+                    progress: (done, total),
+                } => {
+                    println!("  [任务] {} → {:?}（{}/{}）", task_id, status, done, total);
+                }
             },
         }
     }
@@ -1204,6 +1499,123 @@ async fn read_human_loop_input() -> String {
     buf
 }
 
+// ── 逐步调试（--step）────────────────────────────────────────────────────────
+
+/// 暂停点上用户的选择
+#[derive(Debug, PartialEq, Eq)]
+enum StepAction {
+    Continue,
+    Skip,
+    Abort,
+}
+
+/// 解析暂停点输入：空行继续，`s` 跳过，`q` 中止，其余输入视为继续
+fn parse_step_action(input: &str) -> StepAction {
+    match input.trim().to_lowercase().as_str() {
+        "s" | "skip" => StepAction::Skip,
+        "q" | "quit" => StepAction::Abort,
+        _ => StepAction::Continue,
+    }
+}
+
+/// `--step` 调试器：借用工具审批 guard 作为每次工具调用前的暂停点
+///
+/// 只是调试辅助，与安全审批无关：`final_answer` 直接放行，
+/// 非单工具审批的请求（human_in_loop 输入、批量确认）原样转发给内层 provider。
+struct StepProvider {
+    inner: Arc<dyn HumanLoopProvider>,
+    abort: Mutex<CancellationToken>,
+}
+
+impl StepProvider {
+    /// 每个任务开始前换上新的中止令牌，清除上一次的中止状态
+    fn reset(&self) {
+        *self.abort.lock().unwrap() = CancellationToken::new();
+    }
+
+    fn abort_token(&self) -> CancellationToken {
+        self.abort.lock().unwrap().clone()
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.abort.lock().unwrap().is_cancelled()
+    }
+}
+
+/// 等待 step 模式的中止信号；未开启 step 时永不返回
+async fn wait_abort(abort: Option<&CancellationToken>) {
+    match abort {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 运行一次非流式执行；step 模式下用户中止时立即丢弃执行 future 并返回 `None`
+async fn run_abortable<T>(step: Option<&StepProvider>, fut: impl Future<Output = T>) -> Option<T> {
+    let abort = step.map(StepProvider::abort_token);
+    tokio::select! {
+        biased;
+        _ = wait_abort(abort.as_ref()) => {
+            println!("\n  [step] 任务已中止");
+            None
+        }
+        out = fut => Some(out),
+    }
+}
+
+#[async_trait::async_trait]
+impl HumanLoopProvider for StepProvider {
+    async fn request(&self, req: HumanLoopRequest) -> AgentResult<HumanLoopResponse> {
+        let tool_name = match (&req.kind, req.tool_name.as_deref()) {
+            (HumanLoopKind::Approval, Some("final_answer")) => {
+                return Ok(HumanLoopResponse::Approved);
+            }
+            (HumanLoopKind::Approval, Some(name)) => name.to_string(),
+            _ => return self.inner.request(req).await,
+        };
+        if self.is_aborted() {
+            return Ok(HumanLoopResponse::Rejected {
+                reason: Some("用户已中止任务".to_string()),
+            });
+        }
+
+        let args = req.args.as_ref().map(fmt_args).unwrap_or_default();
+        println!("\n  ⏸ [step] 即将执行 {tool_name}({args})");
+        println!("     回车 = 继续    s = 跳过该工具    q = 中止任务");
+        match parse_step_action(&read_human_loop_input().await) {
+            StepAction::Continue => Ok(HumanLoopResponse::Approved),
+            StepAction::Skip => {
+                println!("  [step] 已跳过 {tool_name}");
+                Ok(HumanLoopResponse::Rejected {
+                    reason: Some("调试时被用户跳过".to_string()),
+                })
+            }
+            StepAction::Abort => {
+                self.abort.lock().unwrap().cancel();
+                Ok(HumanLoopResponse::Rejected {
+                    reason: Some("用户已中止任务".to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// 开启 step 模式：所有工具调用都经过审批 guard，由 [`StepProvider`] 暂停等待确认
+///
+/// 仅在 `--step` 时调用；未开启时不安装任何拦截，执行路径与平常完全一致。
+fn install_step_mode(
+    agent: &mut ReactAgent,
+    inner: Arc<dyn HumanLoopProvider>,
+) -> Arc<StepProvider> {
+    let step = Arc::new(StepProvider {
+        inner,
+        abort: Mutex::new(CancellationToken::new()),
+    });
+    agent.require_approval_for_all_tools();
+    agent.set_human_loop_provider(step.clone());
+    step
+}
+
 // ── 辅助函数 ──────────────────────────────────────────────────────────────────
 
 fn print_banner(cli: &Cli, tools: &[&str], mcp: &McpManager) {
@@ -1275,7 +1687,7 @@ fn print_help() {
     println!("    /tools                 列出已注册的工具");
     println!("    /skills                列出已安装的技能");
     println!("    /mcp                   查看已连接的 MCP 服务端和工具");
-    println!("    /ctx                   显示上下文消息数与 token 估算");
+    println!("    /ctx                   显示上下文消息数与 token 分项估算");
     println!("    /reset                 重置对话（清空历史，保留系统提示词）");
     println!("    /clear  /cls           清屏");
     println!("    /quit  /exit           退出程序");
@@ -1293,6 +1705,7 @@ fn print_help() {
     println!();
     println!("  会话恢复命令（需 --session-id 启用）:");
     println!("    /session                显示当前 session_id");
+    println!("    /save [标题]            保存当前会话（未指定标题时自动生成）");
     println!();
     println!("  快捷键:");
     println!("    ↑ / ↓                  浏览历史输入");
@@ -1332,3 +1745,107 @@ fn fmt_args(args: &serde_json::Value) -> String {
 fn home_dir() -> Option<std::path::PathBuf> {
     std::env::var("HOME").ok().map(std::path::PathBuf::from)
 }
+
+// ── 单元测试 ──────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp_config(content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("echo_cli_{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_config_file_builds_expected_agent() {
+        let path = write_temp_config(
+            r#"
+model: file-model
+system: "来自配置文件的提示词"
+tools: [math]
+compressor: none
+max_iter: 5
+token_limit: 4000
+mcp_servers:
+  - name: fs
+    stdio:
+      command: npx
+      args: ["-y", "server-filesystem"]
+unknown_field: 1
+"#,
+        );
+        let cli = resolve_cli(["echo-agent", "--config", path.to_str().unwrap()]).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(cli.model, "file-model");
+        assert_eq!(cli.tools.as_deref(), Some("math"));
+        assert_eq!(cli.compressor, "none");
+        assert_eq!(cli.token_limit, Some(4000));
+        assert_eq!(cli.mcp_servers.len(), 1);
+        assert_eq!(collect_mcp_configs(&cli).len(), 1);
+        // 缺失字段保持 CLI 默认值
+        assert_eq!(cli.log_level, "warn");
+        assert!(!cli.memory);
+
+        let http = Arc::new(Client::new());
+        let agent = build_agent(&cli, &["math"], &http, false);
+        assert_eq!(agent.config().get_model_name(), "file-model");
+        assert!(
+            agent
+                .config()
+                .get_system_prompt()
+                .starts_with("来自配置文件的提示词")
+        );
+        assert_eq!(agent.config().get_max_iterations(), 5);
+        assert_eq!(agent.config().get_token_limit(), 4000);
+        assert!(agent.config().is_tool_enabled());
+        assert!(!agent.list_skills().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_abort_drops_running_execution() {
+        let step = StepProvider {
+            inner: default_provider(),
+            abort: Mutex::new(CancellationToken::new()),
+        };
+        step.abort_token().cancel();
+        let never = std::future::pending::<()>();
+        assert_eq!(run_abortable(Some(&step), never).await, None);
+
+        // 新任务开始前 reset，不受上一次中止影响
+        step.reset();
+        assert!(!step.is_aborted());
+        assert_eq!(run_abortable(Some(&step), async { 1 }).await, Some(1));
+        assert_eq!(run_abortable(None, async { 2 }).await, Some(2));
+    }
+
+    #[test]
+    fn test_parse_step_action() {
+        assert_eq!(parse_step_action("\n"), StepAction::Continue);
+        assert_eq!(parse_step_action(" S \n"), StepAction::Skip);
+        assert_eq!(parse_step_action("q"), StepAction::Abort);
+        assert_eq!(parse_step_action("随便"), StepAction::Continue);
+    }
+
+    #[test]
+    fn test_explicit_cli_args_override_config_file() {
+        let path = write_temp_config("model: file-model\nmax_iter: 5\nmemory: true\n");
+        let cli = resolve_cli([
+            "echo-agent",
+            "--config",
+            path.to_str().unwrap(),
+            "--model",
+            "cli-model",
+            "--max-iter",
+            "7",
+        ])
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(cli.model, "cli-model");
+        assert_eq!(cli.max_iter, 7);
+        assert!(cli.memory);
+    }
+}
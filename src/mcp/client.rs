@@ -1,6 +1,8 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde_json::Value;
+use tokio::sync::{broadcast, mpsc};
 
 use crate::error::{McpError, ReactError, Result};
 use crate::mcp::server_config::{McpServerConfig, TransportConfig};
@@ -10,13 +12,16 @@ use crate::mcp::transport::sse::SseTransport;
 use crate::mcp::transport::stdio::StdioTransport;
 use crate::mcp::types::{
     ClientCapabilities, ClientInfo, ElicitationCapability, InitializeParams, InitializeResult,
-    JsonRpcNotification, JsonRpcRequest, MCP_PROTOCOL_VERSION, McpContent, McpPrompt,
-    McpPromptGetParams, McpPromptGetResult, McpPromptsListResult, McpResource,
+    JsonRpcNotification, JsonRpcRequest, MCP_PROTOCOL_VERSION, McpContent, McpProgress, McpPrompt,
+    McpPromptGetParams, McpPromptGetResult, McpPromptsListResult, McpRequestMeta, McpResource,
     McpResourceReadParams, McpResourceReadResult, McpResourcesListResult, McpTool,
     McpToolCallParams, McpToolCallResult, McpToolsListResult, RootsCapability, SamplingCapability,
     ServerCapabilities,
 };
 
+/// 进度 token 计数器，保证同一进程内的 `progressToken` 唯一
+static NEXT_PROGRESS_TOKEN: AtomicU64 = AtomicU64::new(1);
+
 /// MCP 客户端
 ///
 /// 管理与单个 MCP 服务端的完整生命周期：
@@ -48,15 +53,31 @@ impl McpClient {
             TransportConfig::Stdio { command, args, env } => {
                 Arc::new(StdioTransport::new(&command, &args, &env).await?)
             }
-            TransportConfig::Http { base_url, headers } => {
-                Arc::new(HttpTransport::new(base_url, headers))
+            TransportConfig::Http {
+                base_url,
+                headers,
+                token_provider,
+            } => {
+                let transport = HttpTransport::new(base_url, headers);
+                Arc::new(match token_provider {
+                    Some(provider) => transport.with_token_provider(provider),
+                    None => transport,
+                })
             }
             TransportConfig::Sse { base_url, headers } => {
                 Arc::new(SseTransport::new(base_url, headers).await?)
             }
         };
 
-        tracing::info!("MCP: 正在连接服务端 '{}'", config.name);
+        Self::connect(config.name, transport).await
+    }
+
+    /// 在已建立的传输层上完成握手和能力发现
+    pub(crate) async fn connect(
+        server_name: String,
+        transport: Arc<dyn McpTransport>,
+    ) -> Result<Arc<Self>> {
+        tracing::info!("MCP: 正在连接服务端 '{}'", server_name);
 
         // ── Step 1: initialize 握手 ───────────────────────────────────────────
         let init_params = InitializeParams {
@@ -85,7 +106,7 @@ impl McpClient {
         let negotiated_version = init_result.protocol_version.clone();
         tracing::info!(
             "MCP: 已连接 '{}' (协议版本: {}, 请求版本: {})",
-            config.name,
+            server_name,
             negotiated_version,
             MCP_PROTOCOL_VERSION
         );
@@ -112,25 +133,25 @@ impl McpClient {
 
         // 发现工具
         if server_capabilities.tools.is_some() {
-            tools = Self::fetch_tools(&transport, &config.name).await?;
-            tracing::info!("MCP: 从 '{}' 发现 {} 个工具", config.name, tools.len());
+            tools = Self::fetch_tools(&transport, &server_name).await?;
+            tracing::info!("MCP: 从 '{}' 发现 {} 个工具", server_name, tools.len());
         }
 
         // 发现资源
         if server_capabilities.resources.is_some() {
-            resources = Self::fetch_resources(&transport, &config.name).await?;
-            tracing::info!("MCP: 从 '{}' 发现 {} 个资源", config.name, resources.len());
+            resources = Self::fetch_resources(&transport, &server_name).await?;
+            tracing::info!("MCP: 从 '{}' 发现 {} 个资源", server_name, resources.len());
         }
 
         // 发现提示词
         if server_capabilities.prompts.is_some() {
-            prompts = Self::fetch_prompts(&transport, &config.name).await?;
-            tracing::info!("MCP: 从 '{}' 发现 {} 个提示词", config.name, prompts.len());
+            prompts = Self::fetch_prompts(&transport, &server_name).await?;
+            tracing::info!("MCP: 从 '{}' 发现 {} 个提示词", server_name, prompts.len());
         }
 
         Ok(Arc::new(McpClient {
             transport,
-            server_name: config.name,
+            server_name,
             negotiated_version,
             server_capabilities,
             tools,
@@ -201,14 +222,69 @@ impl McpClient {
     }
 
     /// 调用 MCP 工具
+    ///
+    /// 服务端发送的进度通知只记录日志，需要接收进度请使用
+    /// [`call_tool_with_progress`](Self::call_tool_with_progress)。
     pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<McpToolCallResult> {
+        self.call_tool_with_progress(name, arguments, None).await
+    }
+
+    /// 调用 MCP 工具，并将执行期间的 `notifications/progress` 转发到 `progress_tx`
+    ///
+    /// 传输层支持通知接收时，请求会携带唯一的 `_meta.progressToken`，
+    /// 只有 token 匹配的进度通知才会被转发。服务端不发送进度时行为与普通调用一致。
+    pub async fn call_tool_with_progress(
+        &self,
+        name: &str,
+        arguments: Value,
+        progress_tx: Option<mpsc::UnboundedSender<McpProgress>>,
+    ) -> Result<McpToolCallResult> {
+        // 先订阅再发送请求，避免错过响应前到达的进度通知
+        let mut notifications = self.transport.subscribe_notifications();
+        let progress_token = notifications.as_ref().map(|_| {
+            Value::String(format!(
+                "echo-agent-{}",
+                NEXT_PROGRESS_TOKEN.fetch_add(1, Ordering::Relaxed)
+            ))
+        });
+
         let params = McpToolCallParams {
             name: name.to_string(),
             arguments: Some(arguments),
+            meta: progress_token.clone().map(|token| McpRequestMeta {
+                progress_token: Some(token),
+            }),
         };
 
         let req = JsonRpcRequest::new("tools/call", Some(serde_json::to_value(params)?));
-        let resp = self.transport.send(req).await?;
+        let send = self.transport.send(req);
+        tokio::pin!(send);
+
+        let resp = loop {
+            let Some(rx) = notifications.as_mut() else {
+                break send.await?;
+            };
+            tokio::select! {
+                biased;
+                resp = &mut send => break resp?,
+                notif = rx.recv() => match notif {
+                    Ok(notif) => {
+                        self.handle_progress(name, &notif, &progress_token, &progress_tx)
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::debug!("MCP: '{}' 进度通知积压，跳过 {} 条", name, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => notifications = None,
+                },
+            }
+        };
+
+        // 响应先于部分通知被取出时，补处理已到达的进度
+        if let Some(rx) = notifications.as_mut() {
+            while let Ok(notif) = rx.try_recv() {
+                self.handle_progress(name, &notif, &progress_token, &progress_tx);
+            }
+        }
 
         if let Some(err) = resp.error {
             return Err(ReactError::Mcp(McpError::ToolCallFailed(format!(
@@ -221,6 +297,40 @@ impl McpClient {
         Ok(result)
     }
 
+    /// 处理一条通知：属于本次调用的进度通知会被记录并转发，其余忽略
+    fn handle_progress(
+        &self,
+        tool: &str,
+        notification: &JsonRpcNotification,
+        token: &Option<Value>,
+        progress_tx: &Option<mpsc::UnboundedSender<McpProgress>>,
+    ) {
+        let Some(progress) = McpProgress::from_notification(notification) else {
+            return;
+        };
+        if token.as_ref() != Some(&progress.progress_token) {
+            return;
+        }
+        tracing::info!(
+            "MCP: '{}' 工具 '{}' 进度 {}{}{}",
+            self.server_name,
+            tool,
+            progress.progress,
+            progress
+                .total
+                .map(|t| format!("/{}", t))
+                .unwrap_or_default(),
+            progress
+                .message
+                .as_deref()
+                .map(|m| format!(" - {}", m))
+                .unwrap_or_default()
+        );
+        if let Some(tx) = progress_tx {
+            let _ = tx.send(progress);
+        }
+    }
+
     /// 获取此服务端提供的工具列表
     pub fn tools(&self) -> &[McpTool] {
         &self.tools
@@ -442,3 +552,118 @@ impl McpClient {
             .join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::types::{JsonRpcNotificationReceiver, JsonRpcResponse};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// 模拟服务端：tools/call 执行期间先推送进度通知，再返回最终响应
+    struct ProgressTransport {
+        notification_tx: broadcast::Sender<JsonRpcNotification>,
+    }
+
+    impl ProgressTransport {
+        fn new() -> Self {
+            let (notification_tx, _) = broadcast::channel(16);
+            Self { notification_tx }
+        }
+
+        fn push_progress(&self, token: Value, progress: f64, message: &str) {
+            let params = json!({
+                "progressToken": token,
+                "progress": progress,
+                "total": 2.0,
+                "message": message,
+            });
+            let _ = self.notification_tx.send(JsonRpcNotification::new(
+                "notifications/progress",
+                Some(params),
+            ));
+        }
+    }
+
+    #[async_trait]
+    impl McpTransport for ProgressTransport {
+        async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+            let result = match request.method.as_str() {
+                "initialize" => json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": { "tools": {} },
+                }),
+                "tools/list" => json!({ "tools": [] }),
+                "tools/call" => {
+                    let token = request.params.as_ref().unwrap()["_meta"]["progressToken"].clone();
+                    assert!(token.is_string(), "请求应携带 progressToken");
+                    self.push_progress(token.clone(), 1.0, "step 1");
+                    // 其他请求的进度与无关通知不应被转发
+                    self.push_progress(json!("other-token"), 1.0, "foreign");
+                    let _ = self.notification_tx.send(JsonRpcNotification::new(
+                        "notifications/tools/list_changed",
+                        None,
+                    ));
+                    tokio::task::yield_now().await;
+                    self.push_progress(token, 2.0, "step 2");
+                    json!({ "content": [{ "type": "text", "text": "done" }] })
+                }
+                other => panic!("unexpected method {other}"),
+            };
+            Ok(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(result),
+                error: None,
+            })
+        }
+
+        async fn notify(&self, _notification: JsonRpcNotification) -> Result<()> {
+            Ok(())
+        }
+
+        async fn close(&self) {}
+
+        fn notification_rx(&self) -> Option<Arc<dyn JsonRpcNotificationReceiver>> {
+            None
+        }
+
+        fn subscribe_notifications(&self) -> Option<broadcast::Receiver<JsonRpcNotification>> {
+            Some(self.notification_tx.subscribe())
+        }
+    }
+
+    #[tokio::test]
+    async fn call_tool_forwards_progress_before_final_response() {
+        let client = McpClient::connect("mock".to_string(), Arc::new(ProgressTransport::new()))
+            .await
+            .unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let result = client
+            .call_tool_with_progress("long_task", json!({}), Some(tx))
+            .await
+            .unwrap();
+        assert_eq!(McpClient::content_to_text(&result.content), "done");
+
+        let mut received = Vec::new();
+        while let Ok(p) = rx.try_recv() {
+            received.push(p);
+        }
+        let steps: Vec<_> = received
+            .iter()
+            .map(|p| (p.progress, p.message.as_deref().unwrap()))
+            .collect();
+        assert_eq!(steps, vec![(1.0, "step 1"), (2.0, "step 2")]);
+        assert_eq!(received[0].total, Some(2.0));
+    }
+
+    #[tokio::test]
+    async fn call_tool_without_progress_receiver_still_returns_result() {
+        let client = McpClient::connect("mock".to_string(), Arc::new(ProgressTransport::new()))
+            .await
+            .unwrap();
+        let result = client.call_tool("long_task", json!({})).await.unwrap();
+        assert!(!result.is_error);
+    }
+}
@@ -138,6 +138,7 @@ impl McpServerEntry {
                 _ => TransportConfig::Http {
                     base_url: url.clone(),
                     headers: self.headers.clone(),
+                    token_provider: None,
                 },
             };
             Ok(McpServerConfig {
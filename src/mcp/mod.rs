@@ -25,11 +25,11 @@ use std::sync::Arc;
 
 pub use client::McpClient;
 pub use config_loader::{McpConfigFile, McpServerEntry};
-pub use server_config::{McpServerConfig, TransportConfig};
+pub use server_config::{McpServerConfig, TokenProvider, TransportConfig};
 pub use tool_adapter::McpToolAdapter;
 pub use types::{
-    McpContent, McpPrompt, McpPromptGetResult, McpResource, McpResourceReadResult, McpTool,
-    McpToolCallResult, ServerCapabilities,
+    McpContent, McpProgress, McpPrompt, McpPromptGetResult, McpResource, McpResourceReadResult,
+    McpTool, McpToolCallResult, ServerCapabilities,
 };
 
 use crate::error::Result;
@@ -1,4 +1,12 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+
+use crate::error::Result;
 
 /// MCP 服务端完整配置
 #[derive(Debug, Clone)]
@@ -29,11 +37,16 @@ pub enum TransportConfig {
     /// - 支持 GET SSE 通知流（服务端可选）
     ///
     /// 适用场景：远程 MCP 服务
+    ///
+    /// 标记为 `#[non_exhaustive]`：请通过 [`McpServerConfig::http`] 等构造函数创建。
+    #[non_exhaustive]
     Http {
         /// MCP 服务端端点 URL
         base_url: String,
         /// 自定义请求头（如 Authorization）
         headers: HashMap<String, String>,
+        /// 动态 bearer token；设置后覆盖 `headers` 中的 Authorization
+        token_provider: Option<TokenProvider>,
     },
     /// SSE (Server-Sent Events) 传输：旧版 HTTP+SSE
     ///
@@ -47,6 +60,59 @@ pub enum TransportConfig {
     },
 }
 
+/// token 默认缓存时长
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+type TokenFetch = dyn Fn() -> BoxFuture<'static, Result<String>> + Send + Sync;
+
+/// HTTP 传输的 bearer token 来源
+///
+/// 获取到的 token 缓存 `ttl`（默认 5 分钟），过期后在下一次请求前重新获取；
+/// 服务端返回 401 时立即刷新并重试一次。获取失败或刷新后仍为 401 时返回
+/// [`McpError::AuthenticationFailed`](crate::error::McpError::AuthenticationFailed)。
+#[derive(Clone)]
+pub struct TokenProvider {
+    fetch: Arc<TokenFetch>,
+    ttl: Duration,
+}
+
+impl TokenProvider {
+    /// 用异步函数创建 token 来源，每次调用返回当前有效的 token（不含 `Bearer ` 前缀）
+    pub fn new<F, Fut>(fetch: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        Self {
+            fetch: Arc::new(move || Box::pin(fetch())),
+            ttl: DEFAULT_TOKEN_TTL,
+        }
+    }
+
+    /// 设置 token 缓存时长，应短于 token 的实际有效期
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// 获取一个新 token
+    pub async fn fetch(&self) -> Result<String> {
+        (self.fetch)().await
+    }
+}
+
+impl fmt::Debug for TokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenProvider")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
 impl McpServerConfig {
     /// 创建 stdio 配置（最常用）
     ///
@@ -102,6 +168,7 @@ impl McpServerConfig {
             transport: TransportConfig::Http {
                 base_url: base_url.into(),
                 headers: HashMap::new(),
+                token_provider: None,
             },
         }
     }
@@ -126,6 +193,30 @@ impl McpServerConfig {
             transport: TransportConfig::Http {
                 base_url: base_url.into(),
                 headers,
+                token_provider: None,
+            },
+        }
+    }
+
+    /// 创建 HTTP 配置（bearer token 由 `provider` 动态提供，过期后自动刷新）
+    ///
+    /// # 示例
+    /// ```
+    /// use echo_agent::mcp::{McpServerConfig, TokenProvider};
+    /// let provider = TokenProvider::new(|| async { Ok("fresh-token".to_string()) });
+    /// McpServerConfig::http_with_token_provider("secure-api", "https://api.example.com/mcp", provider);
+    /// ```
+    pub fn http_with_token_provider(
+        name: impl Into<String>,
+        base_url: impl Into<String>,
+        provider: TokenProvider,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            transport: TransportConfig::Http {
+                base_url: base_url.into(),
+                headers: HashMap::new(),
+                token_provider: Some(provider),
             },
         }
     }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use serde_json::Value;
 
+use crate::agent::react_agent::validate_schema;
 use crate::error::Result;
 use crate::mcp::client::McpClient;
 use crate::mcp::types::McpTool;
@@ -11,6 +13,10 @@ use crate::tools::{Tool, ToolParameters, ToolResult};
 ///
 /// 使 MCP 服务端提供的工具可以无缝注册到 `ToolManager`，
 /// 由 ReAct Agent 像使用内置工具一样调用。
+/// 服务端在执行期间发送的进度通知会被记录到日志。
+///
+/// 执行前按缓存的 `inputSchema` 在本地校验参数，不合规时直接返回错误观测值，
+/// 省去一次必然失败的网络往返；服务端未声明 schema 时跳过校验。
 pub struct McpToolAdapter {
     client: Arc<McpClient>,
     tool: McpTool,
@@ -20,6 +26,15 @@ impl McpToolAdapter {
     pub fn new(client: Arc<McpClient>, tool: McpTool) -> Self {
         Self { client, tool }
     }
+
+    /// 服务端声明的参数 schema；缺失（null 或空对象）时返回 `None`
+    fn input_schema(&self) -> Option<&Value> {
+        self.tool
+            .input_schema
+            .as_object()
+            .filter(|schema| !schema.is_empty())
+            .map(|_| &self.tool.input_schema)
+    }
 }
 
 #[async_trait]
@@ -39,6 +54,16 @@ impl Tool for McpToolAdapter {
     async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
         // 将 HashMap<String, Value> 序列化为 JSON Object 传递给 MCP
         let args = serde_json::to_value(&parameters)?;
+
+        if let Some(schema) = self.input_schema()
+            && let Err(message) = validate_schema(&args, schema, "$")
+        {
+            tracing::debug!(tool = %self.tool.name, %message, "MCP: 参数未通过本地 schema 校验");
+            return Ok(ToolResult::error(format!(
+                "参数不符合工具 schema：{message}"
+            )));
+        }
+
         let result = self.client.call_tool(&self.tool.name, args).await?;
 
         let text = McpClient::content_to_text(&result.content);
@@ -50,3 +75,107 @@ impl Tool for McpToolAdapter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::transport::McpTransport;
+    use crate::mcp::types::{
+        JsonRpcNotification, JsonRpcNotificationReceiver, JsonRpcRequest, JsonRpcResponse,
+        MCP_PROTOCOL_VERSION,
+    };
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 记录 tools/call 次数的模拟服务端
+    #[derive(Default)]
+    struct CountingTransport {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl McpTransport for CountingTransport {
+        async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+            let result = match request.method.as_str() {
+                "initialize" => json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": { "tools": {} },
+                }),
+                "tools/list" => json!({ "tools": [] }),
+                "tools/call" => {
+                    self.calls.fetch_add(1, Ordering::SeqCst);
+                    json!({ "content": [{ "type": "text", "text": "ok" }] })
+                }
+                other => panic!("unexpected method {other}"),
+            };
+            Ok(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(result),
+                error: None,
+            })
+        }
+
+        async fn notify(&self, _notification: JsonRpcNotification) -> Result<()> {
+            Ok(())
+        }
+
+        async fn close(&self) {}
+
+        fn notification_rx(&self) -> Option<Arc<dyn JsonRpcNotificationReceiver>> {
+            None
+        }
+    }
+
+    fn params(value: Value) -> ToolParameters {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_arguments_are_rejected_without_request() {
+        let transport = Arc::new(CountingTransport::default());
+        let client = McpClient::connect("mock".to_string(), transport.clone())
+            .await
+            .unwrap();
+        let tool = |schema: Value| McpTool {
+            name: "search".to_string(),
+            description: None,
+            input_schema: schema,
+            output_schema: None,
+            meta: None,
+        };
+        let adapter = McpToolAdapter::new(
+            client.clone(),
+            tool(json!({
+                "type": "object",
+                "properties": { "query": { "type": "string" } },
+                "required": ["query"],
+            })),
+        );
+
+        let result = adapter
+            .execute(params(json!({ "q": "rust" })))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("query"));
+        let result = adapter
+            .execute(params(json!({ "query": 1 })))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 0);
+
+        let result = adapter
+            .execute(params(json!({ "query": "rust" })))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 1);
+
+        // 未声明 schema 时直接转发
+        let adapter = McpToolAdapter::new(client, tool(Value::Null));
+        adapter.execute(params(json!({ "q": 1 }))).await.unwrap();
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+    }
+}
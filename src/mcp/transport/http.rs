@@ -1,11 +1,17 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Serialize;
 use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
 
 use crate::error::{McpError, ReactError, Result};
+use crate::mcp::server_config::TokenProvider;
 use crate::mcp::types::{
     JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, MCP_PROTOCOL_VERSION,
 };
@@ -22,6 +28,52 @@ pub struct HttpTransport {
     endpoint: String,
     headers: HashMap<String, String>,
     next_id: Arc<AtomicU64>,
+    /// 动态 bearer token 来源与缓存
+    auth: Option<TokenAuth>,
+}
+
+/// 带缓存的 bearer token
+struct TokenAuth {
+    provider: TokenProvider,
+    /// 缓存的 token 及获取时间
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+impl TokenAuth {
+    /// 当前有效的 token：缓存未过期时直接返回，否则重新获取
+    async fn token(&self) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, fetched_at)) = cached.as_ref()
+            && fetched_at.elapsed() < self.provider.ttl()
+        {
+            return Ok(token.clone());
+        }
+        let token = fetch_token(&self.provider).await?;
+        *cached = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+
+    /// 服务端拒绝 `stale` 后刷新 token；其他请求已刷新过时直接复用新 token
+    async fn refresh(&self, stale: &str) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, _)) = cached.as_ref()
+            && token != stale
+        {
+            return Ok(token.clone());
+        }
+        let token = fetch_token(&self.provider).await?;
+        *cached = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+}
+
+async fn fetch_token(provider: &TokenProvider) -> Result<String> {
+    debug!("🔑 获取 MCP HTTP token");
+    provider.fetch().await.map_err(|e| {
+        ReactError::Mcp(McpError::AuthenticationFailed(format!(
+            "获取 token 失败: {e}"
+        )))
+    })
 }
 
 impl HttpTransport {
@@ -31,29 +83,85 @@ impl HttpTransport {
             endpoint: endpoint.trim_end_matches('/').to_string(),
             headers,
             next_id: Arc::new(AtomicU64::new(1)),
+            auth: None,
         }
     }
-}
 
-#[async_trait]
-impl McpTransport for HttpTransport {
-    async fn send(&self, mut request: JsonRpcRequest) -> Result<JsonRpcResponse> {
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        request.id = Some(Value::Number(id.into()));
+    /// 使用动态 bearer token：每次请求携带缓存的 token，收到 401 时刷新并重试一次
+    ///
+    /// 设置后忽略 `headers` 中的 Authorization。
+    pub fn with_token_provider(mut self, provider: TokenProvider) -> Self {
+        self.auth = Some(TokenAuth {
+            provider,
+            cached: Mutex::new(None),
+        });
+        self
+    }
 
+    async fn post(
+        &self,
+        body: &impl Serialize,
+        token: Option<&str>,
+    ) -> reqwest::Result<reqwest::Response> {
         let mut builder = self
             .client
             .post(&self.endpoint)
             .header("Content-Type", "application/json")
             .header("MCP-Protocol-Version", MCP_PROTOCOL_VERSION)
-            .json(&request);
+            .json(body);
         for (k, v) in &self.headers {
+            if token.is_some() && k.eq_ignore_ascii_case("authorization") {
+                continue;
+            }
             builder = builder.header(k, v);
         }
+        if let Some(token) = token {
+            builder = builder.bearer_auth(token);
+        }
+        builder.send().await
+    }
 
-        let response = builder.send().await.map_err(|e| {
+    /// 发送请求；使用动态 token 时，401 后刷新 token 重试一次
+    async fn post_with_auth(&self, body: &impl Serialize) -> Result<reqwest::Response> {
+        let connection_failed = |e: reqwest::Error| {
             ReactError::Mcp(McpError::ConnectionFailed(format!("HTTP 请求失败: {}", e)))
-        })?;
+        };
+        let Some(auth) = &self.auth else {
+            return self.post(body, None).await.map_err(connection_failed);
+        };
+
+        let token = auth.token().await?;
+        let response = self
+            .post(body, Some(&token))
+            .await
+            .map_err(connection_failed)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        warn!(endpoint = %self.endpoint, "🔑 MCP 服务端返回 401，刷新 token 后重试");
+        let token = auth.refresh(&token).await?;
+        let response = self
+            .post(body, Some(&token))
+            .await
+            .map_err(connection_failed)?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ReactError::Mcp(McpError::AuthenticationFailed(format!(
+                "刷新 token 后服务端仍返回 401: {body}"
+            ))));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl McpTransport for HttpTransport {
+    async fn send(&self, mut request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        request.id = Some(Value::Number(id.into()));
+
+        let response = self.post_with_auth(&request).await?;
 
         if !response.status().is_success() {
             let status = response.status().as_u16();
@@ -75,17 +183,8 @@ impl McpTransport for HttpTransport {
     }
 
     async fn notify(&self, notification: JsonRpcNotification) -> Result<()> {
-        let mut builder = self
-            .client
-            .post(&self.endpoint)
-            .header("Content-Type", "application/json")
-            .header("MCP-Protocol-Version", MCP_PROTOCOL_VERSION)
-            .json(&notification);
-        for (k, v) in &self.headers {
-            builder = builder.header(k, v);
-        }
         // 通知是 fire-and-forget
-        let _ = builder.send().await;
+        let _ = self.post_with_auth(&notification).await;
         Ok(())
     }
 
@@ -97,3 +196,119 @@ impl McpTransport for HttpTransport {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 读取一个完整的 HTTP 请求，返回请求头部分
+    async fn read_request_head(socket: &mut tokio::net::TcpStream) -> String {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&data);
+            if let Some(pos) = text.find("\r\n\r\n") {
+                let length = text[..pos]
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if data.len() >= pos + 4 + length {
+                    return text[..pos].to_ascii_lowercase();
+                }
+            }
+        }
+    }
+
+    fn counting_provider(calls: Arc<AtomicUsize>) -> TokenProvider {
+        TokenProvider::new(move || {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(if n == 0 { "old" } else { "new" }.to_string()) }
+        })
+    }
+
+    /// 模拟服务端：只接受 `Bearer new`，旧 token 返回 401；刷新后重试成功，之后复用缓存
+    #[tokio::test]
+    async fn test_refreshes_token_after_401() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut auth_headers = Vec::new();
+            for _ in 0..3 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let head = read_request_head(&mut socket).await;
+                let authorized = head.contains("authorization: bearer new");
+                auth_headers.push(
+                    head.lines()
+                        .find(|l| l.starts_with("authorization:"))
+                        .unwrap_or_default()
+                        .to_string(),
+                );
+                let response = if authorized {
+                    let body = r#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 401 Unauthorized\r\nconnection: close\r\ncontent-length: 0\r\n\r\n"
+                        .to_string()
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            auth_headers
+        });
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), "Bearer static".to_string());
+        let transport = HttpTransport::new(format!("http://{addr}/mcp"), headers)
+            .with_token_provider(counting_provider(calls.clone()));
+
+        let response = transport
+            .send(JsonRpcRequest::new("tools/list", None))
+            .await
+            .unwrap();
+        assert!(response.error.is_none());
+        transport
+            .send(JsonRpcRequest::new("tools/list", None))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let auth_headers = server.await.unwrap();
+        assert_eq!(
+            auth_headers,
+            [
+                "authorization: bearer old",
+                "authorization: bearer new",
+                "authorization: bearer new"
+            ]
+        );
+    }
+
+    /// token 获取失败时返回认证错误，不发出请求
+    #[tokio::test]
+    async fn test_token_fetch_failure_is_auth_error() {
+        let provider =
+            TokenProvider::new(|| async { Err(ReactError::Other("凭据已吊销".to_string())) });
+        let transport = HttpTransport::new("http://127.0.0.1:1/mcp".to_string(), HashMap::new())
+            .with_token_provider(provider);
+        let err = transport
+            .send(JsonRpcRequest::new("tools/list", None))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReactError::Mcp(McpError::AuthenticationFailed(ref msg)) if msg.contains("凭据已吊销")),
+            "{err}"
+        );
+    }
+}
@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use crate::error::Result;
 use crate::mcp::types::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 /// MCP 传输层抽象
 ///
@@ -26,4 +27,10 @@ pub trait McpTransport: Send + Sync {
     /// 获取通知接收通道（用于接收服务端推送的通知）
     /// 返回 None 表示该传输层不支持通知接收
     fn notification_rx(&self) -> Option<Arc<dyn crate::mcp::types::JsonRpcNotificationReceiver>>;
+
+    /// 订阅服务端推送的通知（异步接收，如 `notifications/progress`）
+    /// 返回 None 表示该传输层不支持通知接收
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<JsonRpcNotification>> {
+        None
+    }
 }
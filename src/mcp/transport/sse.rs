@@ -407,4 +407,8 @@ impl McpTransport for SseTransport {
             self.notification_tx.subscribe(),
         )))
     }
+
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<JsonRpcNotification>> {
+        Some(self.notification_tx.subscribe())
+    }
 }
@@ -7,10 +7,13 @@ use async_trait::async_trait;
 use serde_json::Value;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{Mutex, oneshot};
+use tokio::sync::{Mutex, broadcast, oneshot};
 
 use crate::error::{McpError, ReactError, Result};
-use crate::mcp::types::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
+use crate::mcp::types::{
+    JsonRpcNotification, JsonRpcNotificationReceiver, JsonRpcRequest, JsonRpcResponse,
+    NotificationReceiver,
+};
 
 use super::McpTransport;
 
@@ -28,6 +31,8 @@ type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
 pub struct StdioTransport {
     stdin: Arc<Mutex<tokio::process::ChildStdin>>,
     pending: PendingMap,
+    /// 服务端通知广播（如 `notifications/progress`）
+    notification_tx: broadcast::Sender<JsonRpcNotification>,
     next_id: Arc<AtomicU64>,
     _child: Arc<Mutex<Child>>,
 }
@@ -66,6 +71,8 @@ impl StdioTransport {
 
         let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
         let pending_clone = pending.clone();
+        let (notification_tx, _) = broadcast::channel(64);
+        let notification_tx_clone = notification_tx.clone();
 
         // 后台 task：持续读取 stdout，将响应路由到对应的 pending channel
         tokio::spawn(async move {
@@ -93,7 +100,7 @@ impl StdioTransport {
                             }
                         };
 
-                        // 有 id → 这是对某个请求的响应
+                        // 有 id → 这是对某个请求的响应；有 method 无 id → 服务端通知
                         if let Some(id) = json.get("id").and_then(|id| id.as_u64()) {
                             match serde_json::from_value::<JsonRpcResponse>(json) {
                                 Ok(response) => {
@@ -107,13 +114,17 @@ impl StdioTransport {
                                     tracing::warn!("MCP stdio: 解析响应失败: {}", e);
                                 }
                             }
+                        } else if json.get("method").is_some() {
+                            match serde_json::from_value::<JsonRpcNotification>(json) {
+                                Ok(notif) => {
+                                    tracing::debug!("MCP stdio: 收到服务端通知: {}", notif.method);
+                                    // 无订阅者时发送失败，直接忽略
+                                    let _ = notification_tx_clone.send(notif);
+                                }
+                                Err(e) => tracing::warn!("MCP stdio: 解析通知失败: {}", e),
+                            }
                         } else {
-                            // 无 id → 服务端主动通知，记录日志后忽略
-                            let method = json
-                                .get("method")
-                                .and_then(|m| m.as_str())
-                                .unwrap_or("unknown");
-                            tracing::debug!("MCP stdio: 收到服务端通知: {}", method);
+                            tracing::debug!("MCP stdio: 收到未知格式数据，已忽略");
                         }
                     }
                     Ok(None) => {
@@ -134,6 +145,7 @@ impl StdioTransport {
         Ok(Self {
             stdin: Arc::new(Mutex::new(stdin)),
             pending,
+            notification_tx,
             next_id: Arc::new(AtomicU64::new(1)),
             _child: Arc::new(Mutex::new(child)),
         })
@@ -196,7 +208,13 @@ impl McpTransport for StdioTransport {
         }
     }
 
-    fn notification_rx(&self) -> Option<Arc<dyn crate::mcp::types::JsonRpcNotificationReceiver>> {
-        None
+    fn notification_rx(&self) -> Option<Arc<dyn JsonRpcNotificationReceiver>> {
+        Some(Arc::new(NotificationReceiver::new(
+            self.notification_tx.subscribe(),
+        )))
+    }
+
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<JsonRpcNotification>> {
+        Some(self.notification_tx.subscribe())
     }
 }
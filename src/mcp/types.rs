@@ -225,6 +225,46 @@ pub struct McpToolCallParams {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Value>,
+    /// 请求元数据（如 `progressToken`）
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none", default)]
+    pub meta: Option<McpRequestMeta>,
+}
+
+/// 请求元数据 `_meta`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct McpRequestMeta {
+    /// 携带该 token 时，服务端可在执行期间发送 `notifications/progress`
+    #[serde(rename = "progressToken", skip_serializing_if = "Option::is_none")]
+    pub progress_token: Option<Value>,
+}
+
+/// 进度通知方法名
+pub const PROGRESS_NOTIFICATION: &str = "notifications/progress";
+
+/// 服务端进度通知（`notifications/progress` 的参数）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct McpProgress {
+    /// 与请求 `_meta.progressToken` 对应
+    #[serde(rename = "progressToken")]
+    pub progress_token: Value,
+    /// 当前进度（单调递增）
+    pub progress: f64,
+    /// 总量（未知时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    /// 进度描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl McpProgress {
+    /// 从通知中解析进度；非进度通知或参数不合法时返回 `None`
+    pub fn from_notification(notification: &JsonRpcNotification) -> Option<Self> {
+        if notification.method != PROGRESS_NOTIFICATION {
+            return None;
+        }
+        serde_json::from_value(notification.params.clone()?).ok()
+    }
 }
 
 /// tools/call 响应结果
@@ -0,0 +1,169 @@
+//! 共享黑板：多个 Agent 之间直接交换中间结果，不经过主 Agent 中转
+//!
+//! [`Blackboard`] 是一个带命名空间的共享 [`Store`]，通过
+//! [`ReactAgent::attach_blackboard`](crate::agent::react_agent::ReactAgent::attach_blackboard)
+//! 注册到参与协作的 Agent 后，Agent 可调用 `blackboard_write` / `blackboard_read` /
+//! `blackboard_wait` 工具读写与等待。
+//!
+//! 每个 key 只追加不覆盖：每次写入产生一个新版本并记录作者，读取默认返回最新版本，
+//! 历史版本始终可查，一个 Agent 的写入不会抹掉另一个 Agent 的结果。
+//!
+//! ```rust
+//! use echo_agent::memory::Blackboard;
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> echo_agent::error::Result<()> {
+//! let board = Blackboard::new();
+//! board.write("researcher", "sources", json!(["a.pdf"])).await?;
+//! board.write("reviewer", "sources", json!(["a.pdf", "b.pdf"])).await?;
+//!
+//! let latest = board.read("sources").await?.unwrap();
+//! assert_eq!((latest.version, latest.author.as_str()), (2, "reviewer"));
+//! assert_eq!(board.history("sources").await?.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{MemoryError, Result};
+use crate::memory::store::{InMemoryStore, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+
+/// 黑板上某个 key 的一个版本
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlackboardEntry {
+    pub key: String,
+    /// 写入者（通常是 Agent 名称）
+    pub author: String,
+    pub value: Value,
+    /// 版本号，从 1 开始递增
+    pub version: u64,
+}
+
+/// 多 Agent 共享黑板，详见[模块文档](self)
+pub struct Blackboard {
+    store: Arc<dyn Store>,
+    namespace: Vec<String>,
+    /// 串行化写入，保证版本号连续、不丢失并发写入
+    write_lock: Mutex<()>,
+    /// 有新写入时唤醒等待者
+    written: Notify,
+}
+
+impl Default for Blackboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blackboard {
+    /// 基于进程内存的黑板，命名空间为 `["blackboard"]`
+    pub fn new() -> Self {
+        Self::with_store(
+            Arc::new(InMemoryStore::new()),
+            vec!["blackboard".to_string()],
+        )
+    }
+
+    /// 基于指定 Store 与命名空间的黑板（如用 `FileStore` 持久化协作记录）
+    pub fn with_store(store: Arc<dyn Store>, namespace: Vec<String>) -> Self {
+        Self {
+            store,
+            namespace,
+            write_lock: Mutex::new(()),
+            written: Notify::new(),
+        }
+    }
+
+    fn ns(&self) -> Vec<&str> {
+        self.namespace.iter().map(String::as_str).collect()
+    }
+
+    /// 追加写入 `key` 的新版本，返回该版本
+    pub async fn write(&self, author: &str, key: &str, value: Value) -> Result<BlackboardEntry> {
+        let _guard = self.write_lock.lock().await;
+        let mut versions = self.history(key).await?;
+        let entry = BlackboardEntry {
+            key: key.to_string(),
+            author: author.to_string(),
+            value,
+            version: versions.len() as u64 + 1,
+        };
+        versions.push(entry.clone());
+        let stored = serde_json::to_value(&versions)
+            .map_err(|e| MemoryError::SerializationError(e.to_string()))?;
+        self.store
+            .put(&self.ns(), key, json!({ "versions": stored }))
+            .await?;
+        self.written.notify_waiters();
+        Ok(entry)
+    }
+
+    /// 读取 `key` 的最新版本
+    pub async fn read(&self, key: &str) -> Result<Option<BlackboardEntry>> {
+        Ok(self.history(key).await?.pop())
+    }
+
+    /// 按版本顺序返回 `key` 的全部历史
+    pub async fn history(&self, key: &str) -> Result<Vec<BlackboardEntry>> {
+        let Some(item) = self.store.get(&self.ns(), key).await? else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_value(item.value["versions"].clone())
+            .map_err(|e| MemoryError::SerializationError(e.to_string()).into())
+    }
+
+    /// 等待 `key` 出现并返回其最新版本；超时仍未出现时返回 `None`
+    pub async fn wait_for(&self, key: &str, timeout: Duration) -> Result<Option<BlackboardEntry>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // 先登记等待再检查，避免检查与等待之间的写入被错过
+            let notified = self.written.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(entry) = self.read(key).await? {
+                return Ok(Some(entry));
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_blackboard_versions_and_wait() {
+        let board = Arc::new(Blackboard::new());
+        let waiter = {
+            let board = board.clone();
+            tokio::spawn(async move { board.wait_for("plan", Duration::from_secs(5)).await })
+        };
+        tokio::task::yield_now().await;
+
+        board.write("a", "plan", json!("v1")).await.unwrap();
+        let seen = waiter.await.unwrap().unwrap().unwrap();
+        assert_eq!((seen.author.as_str(), seen.version), ("a", 1));
+
+        board.write("b", "plan", json!("v2")).await.unwrap();
+        let history = board.history("plan").await.unwrap();
+        assert_eq!(
+            history.iter().map(|e| &e.value).collect::<Vec<_>>(),
+            [&json!("v1"), &json!("v2")]
+        );
+        assert_eq!(board.read("plan").await.unwrap().unwrap().author, "b");
+
+        let missing = board
+            .wait_for("none", Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+    }
+}
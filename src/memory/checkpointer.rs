@@ -46,9 +46,76 @@ pub struct Checkpoint {
     /// 快照唯一 ID（UUID v4）
     pub checkpoint_id: String,
     /// 该时刻的完整消息历史
+    #[serde(default)]
     pub messages: Vec<Message>,
     /// 创建时间（Unix 秒）
+    #[serde(default)]
     pub created_at: u64,
+    /// 会话标题（见 [`ReactAgent::generate_title`](crate::agent::react_agent::ReactAgent::generate_title)）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl Checkpoint {
+    /// 构造新快照；`title` 为 `None` 时沿用 `previous` 的标题
+    fn new(
+        session_id: &str,
+        messages: Vec<Message>,
+        title: Option<String>,
+        previous: Option<&Checkpoint>,
+    ) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            checkpoint_id: new_checkpoint_id(),
+            messages,
+            created_at: now_secs(),
+            title: title.or_else(|| previous.and_then(|cp| cp.title.clone())),
+        }
+    }
+}
+
+// ── SessionMeta ───────────────────────────────────────────────────────────────
+
+/// 会话概要，用于展示会话列表，不含消息内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMeta {
+    /// 会话标识
+    pub id: String,
+    /// 最近一次快照的会话标题
+    pub title: Option<String>,
+    /// 最近一次快照的消息数
+    pub message_count: usize,
+    /// 最近一次快照的创建时间（Unix 秒）
+    pub updated_at: u64,
+}
+
+impl SessionMeta {
+    fn from_checkpoint(checkpoint: &Checkpoint) -> Self {
+        Self {
+            id: checkpoint.session_id.clone(),
+            title: checkpoint.title.clone(),
+            message_count: checkpoint.messages.len(),
+            updated_at: checkpoint.created_at,
+        }
+    }
+}
+
+/// 从已加载的快照聚合会话概要，按最近活跃时间倒序
+fn collect_session_meta(data: &HashMap<String, Vec<Checkpoint>>) -> Vec<SessionMeta> {
+    let mut metas: Vec<SessionMeta> = data
+        .values()
+        .filter_map(|history| history.last().map(SessionMeta::from_checkpoint))
+        .collect();
+    sort_session_meta(&mut metas);
+    metas
+}
+
+fn sort_session_meta(metas: &mut [SessionMeta]) {
+    metas.sort_by(|a, b| {
+        b.updated_at
+            .cmp(&a.updated_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
 }
 
 // ── Checkpointer trait ────────────────────────────────────────────────────────
@@ -61,6 +128,19 @@ pub trait Checkpointer: Send + Sync {
     /// 保存当前会话的消息历史，返回新快照 ID
     async fn put(&self, session_id: &str, messages: Vec<Message>) -> Result<String>;
 
+    /// 保存消息历史并附带会话标题，返回新快照 ID
+    ///
+    /// 默认实现忽略标题直接调用 [`put`](Self::put)；内置实现会保存标题，
+    /// 且之后不带标题的 `put` 沿用该会话最近一次的标题。
+    async fn put_with_title(
+        &self,
+        session_id: &str,
+        messages: Vec<Message>,
+        _title: Option<String>,
+    ) -> Result<String> {
+        self.put(session_id, messages).await
+    }
+
     /// 获取指定会话的最新快照（若不存在返回 `None`）
     async fn get(&self, session_id: &str) -> Result<Option<Checkpoint>>;
 
@@ -72,6 +152,20 @@ pub trait Checkpointer: Send + Sync {
 
     /// 列出所有已存在的 session_id
     async fn list_sessions(&self) -> Result<Vec<String>>;
+
+    /// 列出所有会话的概要（标题、消息数、最后活跃时间），按 `updated_at` 倒序
+    ///
+    /// 默认实现逐个 [`get`](Self::get) 最新快照；内置实现直接从已加载的数据聚合。
+    async fn list_session_meta(&self) -> Result<Vec<SessionMeta>> {
+        let mut metas = Vec::new();
+        for session_id in self.list_sessions().await? {
+            if let Some(checkpoint) = self.get(&session_id).await? {
+                metas.push(SessionMeta::from_checkpoint(&checkpoint));
+            }
+        }
+        sort_session_meta(&mut metas);
+        Ok(metas)
+    }
 }
 
 // ── InMemoryCheckpointer ──────────────────────────────────────────────────────
@@ -98,19 +192,20 @@ impl InMemoryCheckpointer {
 #[async_trait]
 impl Checkpointer for InMemoryCheckpointer {
     async fn put(&self, session_id: &str, messages: Vec<Message>) -> Result<String> {
-        let checkpoint_id = new_checkpoint_id();
-        let checkpoint = Checkpoint {
-            session_id: session_id.to_string(),
-            checkpoint_id: checkpoint_id.clone(),
-            messages,
-            created_at: now_secs(),
-        };
-        self.data
-            .write()
-            .await
-            .entry(session_id.to_string())
-            .or_default()
-            .push(checkpoint);
+        self.put_with_title(session_id, messages, None).await
+    }
+
+    async fn put_with_title(
+        &self,
+        session_id: &str,
+        messages: Vec<Message>,
+        title: Option<String>,
+    ) -> Result<String> {
+        let mut data = self.data.write().await;
+        let history = data.entry(session_id.to_string()).or_default();
+        let checkpoint = Checkpoint::new(session_id, messages, title, history.last());
+        let checkpoint_id = checkpoint.checkpoint_id.clone();
+        history.push(checkpoint);
         Ok(checkpoint_id)
     }
 
@@ -144,6 +239,10 @@ impl Checkpointer for InMemoryCheckpointer {
     async fn list_sessions(&self) -> Result<Vec<String>> {
         Ok(self.data.read().await.keys().cloned().collect())
     }
+
+    async fn list_session_meta(&self) -> Result<Vec<SessionMeta>> {
+        Ok(collect_session_meta(&*self.data.read().await))
+    }
 }
 
 // ── FileCheckpointer ──────────────────────────────────────────────────────────
@@ -206,20 +305,24 @@ impl FileCheckpointer {
 #[async_trait]
 impl Checkpointer for FileCheckpointer {
     async fn put(&self, session_id: &str, messages: Vec<Message>) -> Result<String> {
-        let checkpoint_id = new_checkpoint_id();
-        let checkpoint = Checkpoint {
-            session_id: session_id.to_string(),
-            checkpoint_id: checkpoint_id.clone(),
-            messages,
-            created_at: now_secs(),
+        self.put_with_title(session_id, messages, None).await
+    }
+
+    async fn put_with_title(
+        &self,
+        session_id: &str,
+        messages: Vec<Message>,
+        title: Option<String>,
+    ) -> Result<String> {
+        let checkpoint_id = {
+            let mut data = self.data.write().await;
+            let history = data.entry(session_id.to_string()).or_default();
+            let checkpoint = Checkpoint::new(session_id, messages, title, history.last());
+            let checkpoint_id = checkpoint.checkpoint_id.clone();
+            history.push(checkpoint);
+            checkpoint_id
         };
         info!(session_id = %session_id, checkpoint_id = %checkpoint_id, "🔖 保存 Checkpoint");
-        {
-            let mut data = self.data.write().await;
-            data.entry(session_id.to_string())
-                .or_default()
-                .push(checkpoint);
-        }
         self.flush().await?;
         Ok(checkpoint_id)
     }
@@ -258,6 +361,10 @@ impl Checkpointer for FileCheckpointer {
     async fn list_sessions(&self) -> Result<Vec<String>> {
         Ok(self.data.read().await.keys().cloned().collect())
     }
+
+    async fn list_session_meta(&self) -> Result<Vec<SessionMeta>> {
+        Ok(collect_session_meta(&*self.data.read().await))
+    }
 }
 
 // ── 私有工具函数 ──────────────────────────────────────────────────────────────
@@ -388,6 +495,96 @@ mod tests {
         assert_eq!(cp2.messages[0].content, Some("s2-msg".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_checkpoint_title_carries_over() {
+        let checkpointer = InMemoryCheckpointer::new();
+
+        checkpointer.put("session1", vec![]).await.unwrap();
+        checkpointer
+            .put_with_title("session1", vec![], Some("天气查询".to_string()))
+            .await
+            .unwrap();
+        checkpointer.put("session1", vec![]).await.unwrap();
+
+        let titles: Vec<_> = checkpointer
+            .list("session1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|cp| cp.title)
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                Some("天气查询".to_string()),
+                Some("天气查询".to_string()),
+                None
+            ]
+        );
+
+        // 旧格式（无 title 字段）仍可反序列化
+        let old: Checkpoint = serde_json::from_str(
+            r#"{"session_id":"s","checkpoint_id":"c","messages":[],"created_at":1}"#,
+        )
+        .unwrap();
+        assert!(old.title.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_checkpointer_list_session_meta() {
+        let path =
+            std::env::temp_dir().join(format!("echo_checkpoints_{}.json", uuid::Uuid::new_v4()));
+        // 旧格式快照：缺 title / created_at 字段
+        std::fs::write(
+            &path,
+            r#"{
+                "old": [{"session_id":"old","checkpoint_id":"c0","messages":[]}],
+                "weather": [
+                    {"session_id":"weather","checkpoint_id":"c1","messages":[],"created_at":100,"title":"天气查询"},
+                    {"session_id":"weather","checkpoint_id":"c2","created_at":300,"title":"天气查询",
+                     "messages":[{"role":"user","content":"北京"},{"role":"assistant","content":"晴"}]}
+                ],
+                "news": [{"session_id":"news","checkpoint_id":"c3","messages":[],"created_at":200}]
+            }"#,
+        )
+        .unwrap();
+        let checkpointer = FileCheckpointer::new(&path).unwrap();
+
+        let metas = checkpointer.list_session_meta().await.unwrap();
+        assert_eq!(
+            metas,
+            vec![
+                SessionMeta {
+                    id: "weather".to_string(),
+                    title: Some("天气查询".to_string()),
+                    message_count: 2,
+                    updated_at: 300,
+                },
+                SessionMeta {
+                    id: "news".to_string(),
+                    title: None,
+                    message_count: 0,
+                    updated_at: 200,
+                },
+                SessionMeta {
+                    id: "old".to_string(),
+                    title: None,
+                    message_count: 0,
+                    updated_at: 0,
+                },
+            ]
+        );
+
+        checkpointer
+            .put("fresh", vec![Message::user("hi".to_string())])
+            .await
+            .unwrap();
+        let metas = checkpointer.list_session_meta().await.unwrap();
+        assert_eq!((metas[0].id.as_str(), metas[0].message_count), ("fresh", 1));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_checkpoint_structure() {
         let checkpoint = Checkpoint {
@@ -395,6 +592,7 @@ mod tests {
             checkpoint_id: "cp-123".to_string(),
             messages: vec![Message::user("test".to_string())],
             created_at: 1234567890,
+            title: None,
         };
 
         assert_eq!(checkpoint.session_id, "test-session");
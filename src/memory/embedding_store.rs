@@ -33,7 +33,7 @@
 
 use crate::error::{MemoryError, Result};
 use crate::memory::embedder::Embedder;
-use crate::memory::store::{Store, StoreItem};
+use crate::memory::store::{ImportOptions, Store, StoreItem};
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -224,10 +224,52 @@ impl Store for EmbeddingStore {
         Ok(found)
     }
 
+    /// 透传给内层 Store 比较并替换，写入成功后更新向量索引
+    async fn compare_and_swap(
+        &self,
+        namespace: &[&str],
+        key: &str,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        let text = Self::extract_text(&new);
+        if !self
+            .inner
+            .compare_and_swap(namespace, key, expected, new)
+            .await?
+        {
+            return Ok(false);
+        }
+        match self.embedder.embed(&text).await {
+            Ok(vec) => {
+                self.index
+                    .write()
+                    .await
+                    .insert(&namespace.join("/"), key, vec);
+                if let Err(e) = self.flush_index().await {
+                    warn!("向量索引持久化失败（不影响数据写入）: {e}");
+                }
+            }
+            Err(e) => {
+                warn!(key = %key, error = %e, "⚠️ 嵌入计算失败，该条目不加入向量索引");
+            }
+        }
+        Ok(true)
+    }
+
     async fn list_namespaces(&self, prefix: Option<&[&str]>) -> Result<Vec<Vec<String>>> {
         self.inner.list_namespaces(prefix).await
     }
 
+    async fn list_keys(
+        &self,
+        namespace: &[&str],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize)> {
+        self.inner.list_keys(namespace, offset, limit).await
+    }
+
     fn supports_semantic_search(&self) -> bool {
         true
     }
@@ -276,12 +318,53 @@ impl Store for EmbeddingStore {
         let mut results = Vec::with_capacity(scored.len());
         for (score, key) in scored {
             if let Ok(Some(mut item)) = self.inner.get(namespace, &key).await {
-                item.score = Some(score);
+                // 余弦相似度为负表示完全不相关，截断到 0-1 与关键词检索分数对齐
+                item.score = Some(score.max(0.0));
                 results.push(item);
             }
         }
         Ok(results)
     }
+
+    async fn export_namespace(&self, namespace: &[&str]) -> Result<Vec<StoreItem>> {
+        self.inner.export_namespace(namespace).await
+    }
+
+    /// 透传给内层 Store 导入，再为导入后的条目重建向量索引
+    async fn import_items_with(
+        &self,
+        items: Vec<StoreItem>,
+        options: ImportOptions,
+    ) -> Result<usize> {
+        let targets: Vec<(Vec<String>, String)> = items
+            .iter()
+            .map(|item| (item.namespace.clone(), item.key.clone()))
+            .collect();
+        let imported = self.inner.import_items_with(items, options).await?;
+
+        let mut indexed = 0;
+        for (ns_vec, key) in targets {
+            let ns: Vec<&str> = ns_vec.iter().map(String::as_str).collect();
+            let Ok(Some(item)) = self.inner.get(&ns, &key).await else {
+                continue;
+            };
+            match self.embedder.embed(&Self::extract_text(&item.value)).await {
+                Ok(vec) => {
+                    self.index.write().await.insert(&ns.join("/"), &key, vec);
+                    indexed += 1;
+                }
+                Err(e) => {
+                    warn!(key = %key, error = %e, "⚠️ 嵌入计算失败，该条目不加入向量索引");
+                }
+            }
+        }
+        if indexed > 0
+            && let Err(e) = self.flush_index().await
+        {
+            warn!("向量索引持久化失败（不影响数据写入）: {e}");
+        }
+        Ok(imported)
+    }
 }
 
 // ── 工具函数 ──────────────────────────────────────────────────────────────────
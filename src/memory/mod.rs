@@ -7,6 +7,7 @@
 //! | 短期上下文 | [`compression::ContextManager`] | 单次 `execute()` 内 |
 //! | 短期持久化 | [`Checkpointer`] / [`FileCheckpointer`] | 跨进程恢复同一会话 |
 //! | 长期记忆 | [`Store`] / [`FileStore`] | 跨会话、跨用户共享 |
+//! | 协作黑板 | [`Blackboard`] | 多个 Agent 之间共享中间结果 |
 //!
 //! ## 会话持久化（Checkpointer）
 //!
@@ -42,12 +43,19 @@
 //! # }
 //! ```
 
+pub mod blackboard;
 pub mod checkpointer;
 pub mod embedder;
 pub mod embedding_store;
 pub mod store;
 
-pub use checkpointer::{Checkpoint, Checkpointer, FileCheckpointer, InMemoryCheckpointer};
+pub use blackboard::{Blackboard, BlackboardEntry};
+pub use checkpointer::{
+    Checkpoint, Checkpointer, FileCheckpointer, InMemoryCheckpointer, SessionMeta,
+};
 pub use embedder::{Embedder, HttpEmbedder};
 pub use embedding_store::EmbeddingStore;
-pub use store::{FileStore, InMemoryStore, Store, StoreItem};
+pub use store::{
+    ChangeKind, ChangeListener, FileStore, ImportConflict, ImportOptions, InMemoryStore, Store,
+    StoreItem,
+};
@@ -32,11 +32,12 @@ use crate::error::{MemoryError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 // ── StoreItem ────────────────────────────────────────────────────────────────
 
@@ -53,7 +54,7 @@ pub struct StoreItem {
     pub created_at: u64,
     /// 最后更新时间（Unix 秒）
     pub updated_at: u64,
-    /// 检索相关度分数（仅 `search` 返回时非 None）
+    /// 检索相关度分数，归一化到 0-1，越大越相关（仅 `search` 返回时非 None）
     pub score: Option<f32>,
 }
 
@@ -71,6 +72,29 @@ impl StoreItem {
     }
 }
 
+// ── 导入选项 ──────────────────────────────────────────────────────────────────
+
+/// 导入时遇到已存在 key 的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportConflict {
+    /// 用导入的条目覆盖已有条目（默认）
+    #[default]
+    Overwrite,
+    /// 保留已有条目，跳过导入的条目
+    Skip,
+    /// 任一 key 与已有条目或同批其他条目冲突即整体失败，不写入任何条目
+    Error,
+}
+
+/// [`Store::import_items_with`] 的导入选项
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// key 冲突时的合并策略
+    pub on_conflict: ImportConflict,
+    /// 是否将 `updated_at` 刷新为导入时刻（`created_at` 始终保留原值）
+    pub touch_updated_at: bool,
+}
+
 // ── Store trait ───────────────────────────────────────────────────────────────
 
 /// 长期记忆的统一存储接口
@@ -82,16 +106,90 @@ pub trait Store: Send + Sync {
     /// 按 key 精确获取
     async fn get(&self, namespace: &[&str], key: &str) -> Result<Option<StoreItem>>;
 
-    /// 关键词检索，返回最多 `limit` 条（按相关度排序）
+    /// 关键词检索，返回最多 `limit` 条（按相关度排序，同分按 key 排序）
     async fn search(&self, namespace: &[&str], query: &str, limit: usize)
     -> Result<Vec<StoreItem>>;
 
+    /// 分页关键词检索：跳过排序结果的前 `offset` 条，再返回最多 `limit` 条
+    ///
+    /// 默认实现取前 `offset + limit` 条后截掉前 `offset` 条；`offset` 超出命中数时返回空 vec。
+    async fn search_page(
+        &self,
+        namespace: &[&str],
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<StoreItem>> {
+        let items = self
+            .search(namespace, query, offset.saturating_add(limit))
+            .await?;
+        Ok(items.into_iter().skip(offset).collect())
+    }
+
+    /// 按 key 升序分页列举命名空间下的 key，返回 `(本页 key, 总条数)`
+    ///
+    /// `offset` 超出总数时返回空列表，总条数仍为实际值。
+    async fn list_keys(
+        &self,
+        namespace: &[&str],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize)> {
+        let _ = (namespace, offset, limit);
+        Err(MemoryError::Unsupported("list_keys".to_string()).into())
+    }
+
     /// 删除指定 key，返回是否存在并删除
     async fn delete(&self, namespace: &[&str], key: &str) -> Result<bool>;
 
+    /// 原子比较并替换：当前值等于 `expected` 时写入 `new`，返回是否写入
+    ///
+    /// `expected` 为 `None` 表示期望 key 不存在（仅插入）。并发的读-改-写可以先 `get`
+    /// 再以读到的值为 `expected` 提交，返回 `false` 时重新读取后重试（乐观锁）。
+    async fn compare_and_swap(
+        &self,
+        namespace: &[&str],
+        key: &str,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        let _ = (namespace, key, expected, new);
+        Err(MemoryError::Unsupported("compare_and_swap".to_string()).into())
+    }
+
     /// 列举满足 `prefix` 前缀的所有命名空间
+    ///
+    /// `prefix` 中的 `*` 段是通配符，恰好匹配一层（`["*", "memories"]` 匹配 `alice/memories`，
+    /// 不匹配 `memories` 或 `team/alice/memories`）；含通配符时逐层按前缀匹配，
+    /// 因此 `alice/memories/archive` 也会被匹配。不含通配符时保持按字符串前缀匹配。
     async fn list_namespaces(&self, prefix: Option<&[&str]>) -> Result<Vec<Vec<String>>>;
 
+    /// 跨所有匹配 `pattern` 的命名空间做关键词检索，返回最多 `limit` 条
+    ///
+    /// `pattern` 的匹配规则同 [`list_namespaces`](Store::list_namespaces)。结果按相关度降序，
+    /// 同分按命名空间、key 排序；每条结果的 `namespace` 字段标明其来源。
+    async fn search_across(
+        &self,
+        pattern: &[&str],
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<StoreItem>> {
+        let mut items = Vec::new();
+        for namespace in self.list_namespaces(Some(pattern)).await? {
+            let namespace: Vec<&str> = namespace.iter().map(String::as_str).collect();
+            items.extend(self.search(&namespace, query, limit).await?);
+        }
+        items.sort_by(|a, b| {
+            b.score
+                .unwrap_or(0.0)
+                .total_cmp(&a.score.unwrap_or(0.0))
+                .then_with(|| a.namespace.cmp(&b.namespace))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        items.truncate(limit);
+        Ok(items)
+    }
+
     /// 是否支持语义（向量）搜索。[`EmbeddingStore`](super::EmbeddingStore) 返回 `true`，其余返回 `false`。
     fn supports_semantic_search(&self) -> bool {
         false
@@ -106,6 +204,56 @@ pub trait Store: Send + Sync {
     ) -> Result<Vec<StoreItem>> {
         self.search(namespace, query, limit).await
     }
+
+    /// 带相关度阈值的关键词检索：只返回 `score >= min_score` 的条目，不足 `limit` 条时不凑数
+    ///
+    /// 全部低于阈值时返回空 vec；未打分的条目保留。
+    async fn search_with_threshold(
+        &self,
+        namespace: &[&str],
+        query: &str,
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<StoreItem>> {
+        let items = self.search(namespace, query, limit).await?;
+        Ok(filter_by_score(items, min_score))
+    }
+
+    /// 带相关度阈值的语义检索，过滤规则同 [`search_with_threshold`](Store::search_with_threshold)
+    async fn semantic_search_with_threshold(
+        &self,
+        namespace: &[&str],
+        query: &str,
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<StoreItem>> {
+        let items = self.semantic_search(namespace, query, limit).await?;
+        Ok(filter_by_score(items, min_score))
+    }
+
+    /// 导出指定命名空间下的全部条目（按 key 排序），用于备份或迁移
+    async fn export_namespace(&self, namespace: &[&str]) -> Result<Vec<StoreItem>> {
+        let _ = namespace;
+        Err(MemoryError::Unsupported("export_namespace".to_string()).into())
+    }
+
+    /// 按默认选项（冲突覆盖、保留原时间戳）导入条目，返回实际写入的条数
+    async fn import_items(&self, items: Vec<StoreItem>) -> Result<usize> {
+        self.import_items_with(items, ImportOptions::default())
+            .await
+    }
+
+    /// 按指定选项导入条目，返回实际写入的条数（被跳过的不计入）
+    ///
+    /// 条目写入其自身 `namespace` 字段所指的命名空间，`created_at` 保留原值。
+    async fn import_items_with(
+        &self,
+        items: Vec<StoreItem>,
+        options: ImportOptions,
+    ) -> Result<usize> {
+        let _ = (items, options);
+        Err(MemoryError::Unsupported("import_items".to_string()).into())
+    }
 }
 
 // ── InMemoryStore ─────────────────────────────────────────────────────────────
@@ -183,27 +331,7 @@ impl Store for InMemoryStore {
         let Some(bucket) = data.get(&ns_key) else {
             return Ok(vec![]);
         };
-        let keywords = tokenize(query);
-        let mut scored: Vec<(f32, StoreItem)> = bucket
-            .values()
-            .filter_map(|item| {
-                let score = value_relevance_score(&item.value, &keywords);
-                if score > 0.0 {
-                    Some((score, item.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-        Ok(scored
-            .into_iter()
-            .take(limit)
-            .map(|(s, mut item)| {
-                item.score = Some(s);
-                item
-            })
-            .collect())
+        Ok(rank_bucket(bucket, query, limit))
     }
 
     async fn delete(&self, namespace: &[&str], key: &str) -> Result<bool> {
@@ -215,24 +343,67 @@ impl Store for InMemoryStore {
             .unwrap_or(false))
     }
 
+    async fn compare_and_swap(
+        &self,
+        namespace: &[&str],
+        key: &str,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        let mut data = self.data.write().await;
+        Ok(swap_if(&mut data, namespace, key, expected.as_ref(), new).is_some())
+    }
+
     async fn list_namespaces(&self, prefix: Option<&[&str]>) -> Result<Vec<Vec<String>>> {
         let data = self.data.read().await;
-        let prefix_str = prefix.map(|p| p.join("/"));
         Ok(data
             .keys()
-            .filter(|k| {
-                prefix_str
-                    .as_deref()
-                    .map(|p| k.starts_with(p))
-                    .unwrap_or(true)
-            })
+            .filter(|k| prefix.is_none_or(|p| namespace_matches(k, p)))
             .map(|k| k.split('/').map(String::from).collect())
             .collect())
     }
+
+    async fn list_keys(
+        &self,
+        namespace: &[&str],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize)> {
+        let data = self.data.read().await;
+        Ok(page_keys(&data, namespace, offset, limit))
+    }
+
+    async fn export_namespace(&self, namespace: &[&str]) -> Result<Vec<StoreItem>> {
+        let data = self.data.read().await;
+        Ok(export_bucket(&data, namespace))
+    }
+
+    async fn import_items_with(
+        &self,
+        items: Vec<StoreItem>,
+        options: ImportOptions,
+    ) -> Result<usize> {
+        let mut data = self.data.write().await;
+        import_into(&mut data, items, &options)
+    }
 }
 
 // ── FileStore ─────────────────────────────────────────────────────────────────
 
+/// [`FileStore`] 条目变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// 新写入的 key
+    Created,
+    /// 已有 key 的值被覆盖
+    Updated,
+    /// key 被删除（回调收到的是删除前的条目）
+    Deleted,
+}
+
+/// [`FileStore::on_change`] 注册的变更回调
+pub type ChangeListener = Box<dyn Fn(&StoreItem, ChangeKind) + Send + Sync>;
+
 /// 基于 JSON 文件的持久化 Store
 ///
 /// 存储格式：
@@ -246,6 +417,7 @@ impl Store for InMemoryStore {
 pub struct FileStore {
     path: PathBuf,
     data: RwLock<HashMap<String, HashMap<String, StoreItem>>>,
+    listeners: std::sync::RwLock<Vec<ChangeListener>>,
 }
 
 impl FileStore {
@@ -275,9 +447,30 @@ impl FileStore {
         Ok(Self {
             path,
             data: RwLock::new(data),
+            listeners: std::sync::RwLock::new(Vec::new()),
         })
     }
 
+    /// 注册变更回调：`put` / `delete` 成功持久化到文件后按注册顺序同步调用。
+    ///
+    /// 可用于把本地变更同步到远端或更新外部索引。回调应尽快返回；
+    /// 回调内 panic 会被捕获并记录警告，不影响写入结果和其他回调。
+    pub fn on_change(&self, listener: ChangeListener) {
+        self.listeners
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(listener);
+    }
+
+    fn notify(&self, item: &StoreItem, kind: ChangeKind) {
+        let listeners = self.listeners.read().unwrap_or_else(|e| e.into_inner());
+        for listener in listeners.iter() {
+            if catch_unwind(AssertUnwindSafe(|| listener(item, kind))).is_err() {
+                warn!(key = %item.key, ?kind, "⚠️ Store 变更回调 panic，已忽略");
+            }
+        }
+    }
+
     async fn flush(&self) -> Result<()> {
         let data = self.data.read().await;
         let json = serde_json::to_string_pretty(&*data)
@@ -295,18 +488,26 @@ impl Store for FileStore {
     async fn put(&self, namespace: &[&str], key: &str, value: Value) -> Result<()> {
         let ns_key = namespace.join("/");
         let ns_vec: Vec<String> = namespace.iter().map(|s| s.to_string()).collect();
-        {
+        let (item, kind) = {
             let mut data = self.data.write().await;
             let bucket = data.entry(ns_key).or_default();
-            bucket
+            let kind = if bucket.contains_key(key) {
+                ChangeKind::Updated
+            } else {
+                ChangeKind::Created
+            };
+            let item = bucket
                 .entry(key.to_string())
                 .and_modify(|item| {
                     item.value = value.clone();
                     item.updated_at = now_secs();
                 })
                 .or_insert_with(|| StoreItem::new(ns_vec, key.to_string(), value));
-        }
-        self.flush().await
+            (item.clone(), kind)
+        };
+        self.flush().await?;
+        self.notify(&item, kind);
+        Ok(())
     }
 
     async fn get(&self, namespace: &[&str], key: &str) -> Result<Option<StoreItem>> {
@@ -326,62 +527,214 @@ impl Store for FileStore {
         let Some(bucket) = data.get(&ns_key) else {
             return Ok(vec![]);
         };
-        let keywords = tokenize(query);
-        let mut scored: Vec<(f32, StoreItem)> = bucket
-            .values()
-            .filter_map(|item| {
-                let score = value_relevance_score(&item.value, &keywords);
-                if score > 0.0 {
-                    Some((score, item.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-        debug!(namespace = %ns_key, query = %query, hits = scored.len(), "🔍 Store 检索");
-        Ok(scored
-            .into_iter()
-            .take(limit)
-            .map(|(s, mut item)| {
-                item.score = Some(s);
-                item
-            })
-            .collect())
+        let items = rank_bucket(bucket, query, limit);
+        debug!(namespace = %ns_key, query = %query, hits = items.len(), "🔍 Store 检索");
+        Ok(items)
     }
 
     async fn delete(&self, namespace: &[&str], key: &str) -> Result<bool> {
         let ns_key = namespace.join("/");
-        let found = {
+        let removed = {
             let mut data = self.data.write().await;
-            data.get_mut(&ns_key)
-                .map(|b| b.remove(key).is_some())
-                .unwrap_or(false)
+            data.get_mut(&ns_key).and_then(|b| b.remove(key))
         };
-        if found {
-            self.flush().await?;
-        }
-        Ok(found)
+        let Some(item) = removed else {
+            return Ok(false);
+        };
+        self.flush().await?;
+        self.notify(&item, ChangeKind::Deleted);
+        Ok(true)
+    }
+
+    async fn compare_and_swap(
+        &self,
+        namespace: &[&str],
+        key: &str,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        let swapped = {
+            let mut data = self.data.write().await;
+            swap_if(&mut data, namespace, key, expected.as_ref(), new)
+        };
+        let Some((item, kind)) = swapped else {
+            debug!(key = %key, "Store CAS 未命中期望值，未写入");
+            return Ok(false);
+        };
+        self.flush().await?;
+        self.notify(&item, kind);
+        Ok(true)
     }
 
     async fn list_namespaces(&self, prefix: Option<&[&str]>) -> Result<Vec<Vec<String>>> {
         let data = self.data.read().await;
-        let prefix_str = prefix.map(|p| p.join("/"));
         Ok(data
             .keys()
-            .filter(|k| {
-                prefix_str
-                    .as_deref()
-                    .map(|p| k.starts_with(p))
-                    .unwrap_or(true)
-            })
+            .filter(|k| prefix.is_none_or(|p| namespace_matches(k, p)))
             .map(|k| k.split('/').map(String::from).collect())
             .collect())
     }
+
+    async fn list_keys(
+        &self,
+        namespace: &[&str],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize)> {
+        let data = self.data.read().await;
+        Ok(page_keys(&data, namespace, offset, limit))
+    }
+
+    async fn export_namespace(&self, namespace: &[&str]) -> Result<Vec<StoreItem>> {
+        let data = self.data.read().await;
+        Ok(export_bucket(&data, namespace))
+    }
+
+    async fn import_items_with(
+        &self,
+        items: Vec<StoreItem>,
+        options: ImportOptions,
+    ) -> Result<usize> {
+        let imported = {
+            let mut data = self.data.write().await;
+            import_into(&mut data, items, &options)?
+        };
+        if imported > 0 {
+            self.flush().await?;
+        }
+        info!(path = %self.path.display(), imported, "📥 Store 导入完成");
+        Ok(imported)
+    }
 }
 
 // ── 私有工具函数 ──────────────────────────────────────────────────────────────
 
+type NamespaceMap = HashMap<String, HashMap<String, StoreItem>>;
+
+/// 关键词打分并排序：按分数降序，同分按 key 升序，保证分页结果稳定
+fn rank_bucket(bucket: &HashMap<String, StoreItem>, query: &str, limit: usize) -> Vec<StoreItem> {
+    let keywords = tokenize(query);
+    let mut scored: Vec<(f32, &StoreItem)> = bucket
+        .values()
+        .filter_map(|item| {
+            let score = value_relevance_score(&item.value, &keywords);
+            (score > 0.0).then_some((score, item))
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.key.cmp(&b.1.key))
+    });
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(s, item)| {
+            let mut item = item.clone();
+            item.score = Some(s);
+            item
+        })
+        .collect()
+}
+
+/// 按 key 升序取单个命名空间的一页 key，连同总条数返回
+fn page_keys(
+    data: &NamespaceMap,
+    namespace: &[&str],
+    offset: usize,
+    limit: usize,
+) -> (Vec<String>, usize) {
+    let Some(bucket) = data.get(&namespace.join("/")) else {
+        return (Vec::new(), 0);
+    };
+    let mut keys: Vec<&String> = bucket.keys().collect();
+    keys.sort();
+    let page = keys.into_iter().skip(offset).take(limit).cloned().collect();
+    (page, bucket.len())
+}
+
+/// 导出单个命名空间的全部条目，按 key 排序保证输出稳定
+fn export_bucket(data: &NamespaceMap, namespace: &[&str]) -> Vec<StoreItem> {
+    let mut items: Vec<StoreItem> = data
+        .get(&namespace.join("/"))
+        .map(|b| b.values().cloned().collect())
+        .unwrap_or_default();
+    items.sort_by(|a, b| a.key.cmp(&b.key));
+    items
+}
+
+/// 将条目按 `options` 写入内存表，返回实际写入条数
+///
+/// `ImportConflict::Error` 模式下先整体检查冲突（包括同批内重复的 key），
+/// 保证失败时不写入任何条目。
+fn import_into(
+    data: &mut NamespaceMap,
+    items: Vec<StoreItem>,
+    options: &ImportOptions,
+) -> Result<usize> {
+    let mut seen = HashSet::new();
+    if options.on_conflict == ImportConflict::Error
+        && let Some(item) = items.iter().find(|item| {
+            let ns_key = item.namespace.join("/");
+            data.get(&ns_key).is_some_and(|b| b.contains_key(&item.key))
+                || !seen.insert((ns_key, item.key.as_str()))
+        })
+    {
+        return Err(
+            MemoryError::Conflict(format!("{}/{}", item.namespace.join("/"), item.key)).into(),
+        );
+    }
+
+    let mut imported = 0;
+    for mut item in items {
+        let bucket = data.entry(item.namespace.join("/")).or_default();
+        if options.on_conflict == ImportConflict::Skip && bucket.contains_key(&item.key) {
+            continue;
+        }
+        if options.touch_updated_at {
+            item.updated_at = now_secs();
+        }
+        item.score = None;
+        bucket.insert(item.key.clone(), item);
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+/// 当前值等于 `expected`（`None` = 不存在）时写入 `new`，返回写入后的条目与变更类型
+///
+/// 调用方持有写锁，比较与写入之间不会被其他写入穿插。
+fn swap_if(
+    data: &mut NamespaceMap,
+    namespace: &[&str],
+    key: &str,
+    expected: Option<&Value>,
+    new: Value,
+) -> Option<(StoreItem, ChangeKind)> {
+    // 未命中时不创建空命名空间，避免它出现在 list_namespaces 中
+    let ns_key = namespace.join("/");
+    let current = data.get_mut(&ns_key).and_then(|bucket| bucket.get_mut(key));
+    match (current, expected) {
+        (Some(item), Some(expected)) if item.value == *expected => {
+            item.value = new;
+            item.updated_at = now_secs();
+            Some((item.clone(), ChangeKind::Updated))
+        }
+        (None, None) => {
+            let item = StoreItem::new(
+                namespace.iter().map(|s| s.to_string()).collect(),
+                key.to_string(),
+                new,
+            );
+            data.entry(ns_key)
+                .or_default()
+                .insert(key.to_string(), item.clone());
+            Some((item, ChangeKind::Created))
+        }
+        _ => None,
+    }
+}
+
 fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -399,6 +752,27 @@ fn tokenize(text: &str) -> Vec<String> {
         .collect()
 }
 
+/// 命名空间（`/` 连接的 key）是否匹配 `pattern`，规则见 [`Store::list_namespaces`]
+fn namespace_matches(namespace: &str, pattern: &[&str]) -> bool {
+    if !pattern.contains(&"*") {
+        return namespace.starts_with(&pattern.join("/"));
+    }
+    let segments: Vec<&str> = namespace.split('/').collect();
+    segments.len() >= pattern.len()
+        && pattern
+            .iter()
+            .zip(&segments)
+            .all(|(p, segment)| *p == "*" || p == segment)
+}
+
+/// 丢弃相关度低于阈值的条目
+fn filter_by_score(items: Vec<StoreItem>, min_score: f32) -> Vec<StoreItem> {
+    items
+        .into_iter()
+        .filter(|item| item.score.is_none_or(|score| score >= min_score))
+        .collect()
+}
+
 /// 计算 JSON Value 与关键词的匹配度（匹配关键词数 / 总关键词数）
 fn value_relevance_score(value: &Value, keywords: &[String]) -> f32 {
     if keywords.is_empty() {
@@ -453,6 +827,7 @@ fn expand_tilde(path: &Path) -> PathBuf {
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_in_memory_store_put_and_get() {
@@ -534,6 +909,155 @@ mod tests {
         assert!(results[0].score.is_some());
     }
 
+    /// 分页检查：key 升序、total 准确、offset 越界返回空；FileStore 与 InMemoryStore 行为一致
+    async fn assert_paginates(store: &dyn Store) {
+        let ns = &["user", "memories"];
+        for i in 0..5 {
+            store
+                .put(ns, &format!("k{i}"), json!({"content": "rust 笔记"}))
+                .await
+                .unwrap();
+        }
+
+        let (page, total) = store.list_keys(ns, 0, 2).await.unwrap();
+        assert_eq!((page, total), (vec!["k0".into(), "k1".into()], 5));
+        let (page, total) = store.list_keys(ns, 4, 2).await.unwrap();
+        assert_eq!((page, total), (vec!["k4".into()], 5));
+        let (page, total) = store.list_keys(ns, 9, 2).await.unwrap();
+        assert_eq!((page, total), (Vec::<String>::new(), 5));
+        assert_eq!(store.list_keys(&["none"], 0, 2).await.unwrap(), (vec![], 0));
+
+        // 同分结果按 key 排序，翻页不重不漏
+        let mut keys = Vec::new();
+        for offset in (0..6).step_by(2) {
+            let page = store.search_page(ns, "rust", offset, 2).await.unwrap();
+            keys.extend(page.into_iter().map(|item| item.key));
+        }
+        assert_eq!(keys, ["k0", "k1", "k2", "k3", "k4"]);
+        assert!(
+            store
+                .search_page(ns, "rust", 5, 2)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_keys_and_search_page() {
+        assert_paginates(&InMemoryStore::new()).await;
+
+        let path = std::env::temp_dir().join(format!("echo_store_{}.json", uuid::Uuid::new_v4()));
+        assert_paginates(&FileStore::new(&path).unwrap()).await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// 并发 CAS：同一期望值只有一个写入成功；expected = None 仅在 key 不存在时插入
+    async fn assert_compare_and_swap(store: Arc<dyn Store>) {
+        let ns = &["shared", "counter"];
+        assert!(
+            store
+                .compare_and_swap(ns, "n", None, json!(0))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .compare_and_swap(ns, "n", None, json!(100))
+                .await
+                .unwrap()
+        );
+
+        let tasks: Vec<_> = (1..=8)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    store
+                        .compare_and_swap(&["shared", "counter"], "n", Some(json!(0)), json!(i))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let results = futures::future::join_all(tasks).await;
+        let succeeded = results.into_iter().filter(|r| *r.as_ref().unwrap()).count();
+        assert_eq!(succeeded, 1);
+
+        let current = store.get(ns, "n").await.unwrap().unwrap().value;
+        assert_ne!(current, json!(0));
+        assert!(
+            store
+                .compare_and_swap(ns, "n", Some(current), json!("done"))
+                .await
+                .unwrap()
+        );
+        assert_eq!(store.get(ns, "n").await.unwrap().unwrap().value, "done");
+
+        // 未命中的 CAS 不创建空命名空间
+        assert!(
+            !store
+                .compare_and_swap(&["ghost"], "n", Some(json!(0)), json!(1))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            store.list_namespaces(None).await.unwrap(),
+            vec![vec!["shared".to_string(), "counter".to_string()]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_swap_only_one_concurrent_winner() {
+        assert_compare_and_swap(Arc::new(InMemoryStore::new())).await;
+
+        let path = std::env::temp_dir().join(format!("echo_store_{}.json", uuid::Uuid::new_v4()));
+        assert_compare_and_swap(Arc::new(FileStore::new(&path).unwrap())).await;
+        let reopened = FileStore::new(&path).unwrap();
+        assert_eq!(
+            reopened
+                .get(&["shared", "counter"], "n")
+                .await
+                .unwrap()
+                .unwrap()
+                .value,
+            "done"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_search_with_threshold_filters_low_scores() {
+        let store = InMemoryStore::new();
+        let ns = &["user", "memories"];
+        store
+            .put(ns, "k1", json!({"content": "rust async tokio runtime"}))
+            .await
+            .unwrap();
+        store
+            .put(ns, "k2", json!({"content": "python tokio"}))
+            .await
+            .unwrap();
+
+        // k2 只命中 1/3 个关键词，低于阈值被过滤，即使不足 limit 条
+        assert_eq!(
+            store.search(ns, "rust async tokio", 5).await.unwrap().len(),
+            2
+        );
+        let results = store
+            .search_with_threshold(ns, "rust async tokio", 5, 0.5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key, "k1");
+
+        // 全部低于阈值时返回空
+        let results = store
+            .search_with_threshold(ns, "python java go", 5, 0.5)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+    }
+
     #[tokio::test]
     async fn test_in_memory_store_list_namespaces() {
         let store = InMemoryStore::new();
@@ -606,9 +1130,224 @@ mod tests {
         assert_eq!(item.created_at, item.updated_at);
     }
 
+    #[tokio::test]
+    async fn test_export_then_import_roundtrip() {
+        let src = InMemoryStore::new();
+        let ns = &["alice", "memories"];
+        src.put(ns, "k1", json!({"content": "喜欢 Rust"}))
+            .await
+            .unwrap();
+        src.put(ns, "k2", json!({"content": "住在杭州"}))
+            .await
+            .unwrap();
+        src.put(&["bob", "memories"], "k3", json!({}))
+            .await
+            .unwrap();
+
+        let exported = src.export_namespace(ns).await.unwrap();
+        assert_eq!(exported.len(), 2);
+
+        let dst = InMemoryStore::new();
+        let imported = dst.import_items(exported.clone()).await.unwrap();
+        assert_eq!(imported, 2);
+
+        let reexported = dst.export_namespace(ns).await.unwrap();
+        assert_eq!(reexported.len(), exported.len());
+        for (a, b) in exported.iter().zip(reexported.iter()) {
+            assert_eq!(a.key, b.key);
+            assert_eq!(a.value, b.value);
+            assert_eq!(a.created_at, b.created_at);
+            assert_eq!(a.updated_at, b.updated_at);
+        }
+        assert!(dst.get(&["bob", "memories"], "k3").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_conflict_strategies() {
+        let ns = &["ns"];
+        let incoming = vec![StoreItem::new(
+            vec!["ns".to_string()],
+            "k1".to_string(),
+            json!({"v": "new"}),
+        )];
+
+        let store = InMemoryStore::new();
+        store.put(ns, "k1", json!({"v": "old"})).await.unwrap();
+
+        let skipped = store
+            .import_items_with(
+                incoming.clone(),
+                ImportOptions {
+                    on_conflict: ImportConflict::Skip,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            store.get(ns, "k1").await.unwrap().unwrap().value["v"],
+            "old"
+        );
+
+        let err = store
+            .import_items_with(
+                incoming.clone(),
+                ImportOptions {
+                    on_conflict: ImportConflict::Error,
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(err.is_err());
+
+        // 同批内重复的 key 同样视为冲突，整体不写入
+        let fresh = InMemoryStore::new();
+        let duplicated = vec![
+            StoreItem::new(vec!["ns".to_string()], "k2".to_string(), json!({"v": 1})),
+            StoreItem::new(vec!["ns".to_string()], "k2".to_string(), json!({"v": 2})),
+        ];
+        let err = fresh
+            .import_items_with(
+                duplicated,
+                ImportOptions {
+                    on_conflict: ImportConflict::Error,
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(err.is_err());
+        assert!(fresh.get(ns, "k2").await.unwrap().is_none());
+
+        let overwritten = store.import_items(incoming).await.unwrap();
+        assert_eq!(overwritten, 1);
+        assert_eq!(
+            store.get(ns, "k1").await.unwrap().unwrap().value["v"],
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_store_import_persists() {
+        let path = std::env::temp_dir().join(format!("echo_store_{}.json", uuid::Uuid::new_v4()));
+        let items = vec![StoreItem::new(
+            vec!["alice".to_string()],
+            "k1".to_string(),
+            json!({"content": "hello"}),
+        )];
+        {
+            let store = FileStore::new(&path).unwrap();
+            assert_eq!(store.import_items(items).await.unwrap(), 1);
+        }
+        let reopened = FileStore::new(&path).unwrap();
+        let exported = reopened.export_namespace(&["alice"]).await.unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].value["content"], "hello");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_change_listener() {
+        let path = std::env::temp_dir().join(format!("echo_store_{}.json", uuid::Uuid::new_v4()));
+        let store = FileStore::new(&path).unwrap();
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        store.on_change(Box::new(move |item, kind| {
+            sink.lock()
+                .unwrap()
+                .push((item.key.clone(), item.value.clone(), kind));
+        }));
+
+        store.put(&["ns"], "k1", json!("v1")).await.unwrap();
+        store.put(&["ns"], "k1", json!("v2")).await.unwrap();
+        assert!(store.delete(&["ns"], "k1").await.unwrap());
+        assert!(!store.delete(&["ns"], "k1").await.unwrap());
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                ("k1".to_string(), json!("v1"), ChangeKind::Created),
+                ("k1".to_string(), json!("v2"), ChangeKind::Updated),
+                ("k1".to_string(), json!("v2"), ChangeKind::Deleted),
+            ]
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_listener_panic_does_not_fail_put() {
+        let path = std::env::temp_dir().join(format!("echo_store_{}.json", uuid::Uuid::new_v4()));
+        let store = FileStore::new(&path).unwrap();
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        store.on_change(Box::new(|_, _| panic!("listener failure")));
+        store.on_change(Box::new(move |_, _| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }));
+
+        store.put(&["ns"], "k1", json!("v1")).await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let reopened = FileStore::new(&path).unwrap();
+        assert!(reopened.get(&["ns"], "k1").await.unwrap().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_store_supports_semantic_search_default() {
         let store = InMemoryStore::new();
         assert!(!store.supports_semantic_search());
     }
+
+    #[tokio::test]
+    async fn test_wildcard_namespaces_and_search_across() {
+        let store = InMemoryStore::new();
+        store
+            .put(
+                &["alice", "memories"],
+                "m1",
+                json!({"content": "喜欢 rust 编程"}),
+            )
+            .await
+            .unwrap();
+        store
+            .put(
+                &["bob", "memories"],
+                "m1",
+                json!({"content": "rust 和 go 都在用"}),
+            )
+            .await
+            .unwrap();
+        store
+            .put(&["bob", "notes"], "n1", json!({"content": "rust 笔记"}))
+            .await
+            .unwrap();
+        store
+            .put(&["memories"], "root", json!({"content": "rust"}))
+            .await
+            .unwrap();
+
+        // `*` 恰好匹配一层
+        let mut namespaces = store
+            .list_namespaces(Some(&["*", "memories"]))
+            .await
+            .unwrap();
+        namespaces.sort();
+        assert_eq!(
+            namespaces,
+            [vec!["alice", "memories"], vec!["bob", "memories"]]
+        );
+
+        let items = store
+            .search_across(&["*", "memories"], "rust", 10)
+            .await
+            .unwrap();
+        let sources: Vec<String> = items.iter().map(|i| i.namespace.join("/")).collect();
+        assert_eq!(sources, ["alice/memories", "bob/memories"]);
+        assert!(items.iter().all(|i| i.score.is_some()));
+
+        let items = store.search_across(&["bob", "*"], "rust", 1).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].namespace, ["bob", "memories"]);
+    }
 }
@@ -16,7 +16,7 @@ use crate::tools::others::math::{AddTool, DivideTool, MultiplyTool, SubtractTool
 ///
 /// let config = AgentConfig::new("qwen3-max", "calculator", "You are a calculator");
 /// let mut agent = ReactAgent::new(config);
-/// agent.add_skill(Box::new(CalculatorSkill));
+/// agent.add_skill(Box::new(CalculatorSkill)).unwrap();
 /// ```
 pub struct CalculatorSkill;
 
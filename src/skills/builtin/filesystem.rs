@@ -2,9 +2,10 @@ use std::path::PathBuf;
 
 use crate::skills::Skill;
 use crate::tools::Tool;
+pub use crate::tools::files::files::DEFAULT_MAX_BINARY_BYTES;
 use crate::tools::files::files::{
-    AppendFileTool, CreateFileTool, DeleteFileTool, ListDirTool, MoveFileTool, ReadFileTool,
-    UpdateFileTool, WriteFileTool,
+    AppendFileTool, CreateFileTool, DeleteFileTool, ListDirTool, MoveFileTool, ReadBinaryTool,
+    ReadFileTool, ReadGlobTool, UpdateFileTool, WriteBinaryTool, WriteFileTool,
 };
 
 /// 文件系统技能
@@ -13,7 +14,9 @@ use crate::tools::files::files::{
 /// - `create_file`：创建文件
 /// - `delete_file`：删除文件
 /// - `read_file`：读取文件内容
+/// - `read_glob`：按 glob 模式批量读取文件
 /// - `write_file`：覆盖写入文件
+/// - `read_binary` / `write_binary`：以 base64 读写图片、PDF 等二进制文件
 /// - `update_file`：更新文件
 /// - `append_file`：追加写入文件
 /// - `move_file`：移动文件
@@ -30,27 +33,49 @@ use crate::tools::files::files::{
 /// let config = AgentConfig::new("qwen3-max", "filesystem", "You are a file manager");
 /// let mut agent = ReactAgent::new(config);
 /// // 不限制路径（谨慎使用）
-/// agent.add_skill(Box::new(FileSystemSkill::new()));
+/// agent.add_skill(Box::new(FileSystemSkill::new())).unwrap();
 ///
 /// // 限制在 /workspace 目录下
-/// agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/workspace")));
+/// agent.add_skill(Box::new(FileSystemSkill::with_base_dir("/workspace"))).unwrap();
 /// ```
 pub struct FileSystemSkill {
     base_dir: Option<PathBuf>,
+    max_read_bytes: Option<usize>,
+    max_binary_bytes: usize,
 }
 
 impl FileSystemSkill {
     /// 创建不限制路径的文件系统 Skill
     pub fn new() -> Self {
-        Self { base_dir: None }
+        Self {
+            base_dir: None,
+            max_read_bytes: None,
+            max_binary_bytes: DEFAULT_MAX_BINARY_BYTES,
+        }
     }
 
     /// 创建限制在指定目录下的文件系统 Skill
     pub fn with_base_dir(base: impl Into<PathBuf>) -> Self {
         Self {
             base_dir: Some(base.into()),
+            max_read_bytes: None,
+            max_binary_bytes: DEFAULT_MAX_BINARY_BYTES,
         }
     }
+
+    /// 设置 `read_file` 的单文件最大读取字节数（默认不限制），
+    /// 超过时拒绝整文件读取，提示按行分页；`ToolQuota::max_read_bytes` 可再覆盖
+    pub fn max_read_bytes(mut self, limit: usize) -> Self {
+        self.max_read_bytes = Some(limit);
+        self
+    }
+
+    /// 设置 `read_binary` / `write_binary` 的单文件字节数上限（默认 [`DEFAULT_MAX_BINARY_BYTES`]），
+    /// 按编码前的原始大小计算，base64 编码后约膨胀 33%
+    pub fn max_binary_bytes(mut self, limit: usize) -> Self {
+        self.max_binary_bytes = limit;
+        self
+    }
 }
 
 impl Default for FileSystemSkill {
@@ -65,20 +90,44 @@ impl Skill for FileSystemSkill {
     }
 
     fn description(&self) -> &str {
-        "本地文件系统读写能力：创建文件、删除文件、移动文件路径、读取文件内容、写入文件内容、追加文件、修改文件内容，以及列出目录内容"
+        "本地文件系统读写能力：创建文件、删除文件、移动文件路径、读取文件内容、按 glob 批量读取、写入文件内容、追加文件、修改文件内容、以 base64 读写二进制文件，以及列出目录内容"
     }
 
     fn tools(&self) -> Vec<Box<dyn Tool>> {
         let base = self.base_dir.clone();
         vec![
+            Box::new({
+                let tool = match &base {
+                    Some(b) => ReadFileTool::with_base_dir(b),
+                    None => ReadFileTool::new(),
+                };
+                match self.max_read_bytes {
+                    Some(limit) => tool.with_max_read_bytes(limit),
+                    None => tool,
+                }
+            }),
             Box::new(match &base {
-                Some(b) => ReadFileTool::with_base_dir(b),
-                None => ReadFileTool::new(),
+                Some(b) => ReadGlobTool::with_base_dir(b),
+                None => ReadGlobTool::new(),
             }),
             Box::new(match &base {
                 Some(b) => WriteFileTool::with_base_dir(b),
                 None => WriteFileTool::new(),
             }),
+            Box::new(
+                match &base {
+                    Some(b) => ReadBinaryTool::with_base_dir(b),
+                    None => ReadBinaryTool::new(),
+                }
+                .with_max_bytes(self.max_binary_bytes),
+            ),
+            Box::new(
+                match &base {
+                    Some(b) => WriteBinaryTool::with_base_dir(b),
+                    None => WriteBinaryTool::new(),
+                }
+                .with_max_bytes(self.max_binary_bytes),
+            ),
             Box::new(match &base {
                 Some(b) => AppendFileTool::with_base_dir(b),
                 None => AppendFileTool::new(),
@@ -107,10 +156,16 @@ impl Skill for FileSystemSkill {
     }
 
     fn system_prompt_injection(&self) -> Option<String> {
-        let restriction = if let Some(base) = &self.base_dir {
-            format!("（操作范围限制在 '{}' 目录下）", base.display())
-        } else {
-            "（无路径限制，操作时请谨慎）".to_string()
+        let (restriction, path_rule) = match &self.base_dir {
+            Some(base) => (
+                format!("（操作范围限制在 '{}' 目录下）", base.display()),
+                format!(
+                    "\n**路径限制**：所有文件操作限定在 `{}` 目录内，请使用相对路径（相对于该目录），\
+                     越出该目录的绝对路径或 `..` 会被拒绝。",
+                    base.display()
+                ),
+            ),
+            None => ("（无路径限制，操作时请谨慎）".to_string(), String::new()),
         };
 
         Some(format!(
@@ -119,12 +174,34 @@ impl Skill for FileSystemSkill {
              - `create_file(path)`：创建文件，适合创建一个空文件等\n\
              - `delete_file(path)`：删除文件，适合删除 配置、日志、代码等不需要的旧文件\n\
              - `move_file(old_path, new_path)`：移动文件路径，需要移动文件路径等\n\
-             - `read_file(path)`：读取文件内容，适合查看配置、日志、代码等\n\
+             - `read_file(path, start_line?, max_lines?)`：读取文件内容，适合查看配置、日志、代码等；超大文件会被拒绝，请用 start_line / max_lines 分页读取\n\
+             - `read_glob(pattern, max_files?, max_bytes_per_file?)`：按 glob 模式（如 `src/**/*.rs`）一次读取多个文件，比逐个 read_file 更省轮次\n\
              - `write_file(path, content)`：覆盖写入文件，会清空原有内容\n\
              - `update_file(path, old_content, new_content)`：修改文件内容，用新内容替换旧内容（精确替换，首次匹配）\n\
              - `append_file(path, content)`：在文件末尾追加内容，不会清空原有内容\n\
              - `list_dir(path)`：列出目录下的文件和子目录\n\
-             **注意**：write_file 会覆盖原文件，如需保留原内容请先 read_file 再决定使用 write_file 还是 append_file。"
+             - `read_binary(path)` / `write_binary(path, base64)`：以 base64 读写图片、PDF 等二进制文件（read_file 只能读文本）\n\
+             **注意**：write_file 会覆盖原文件，如需保留原内容请先 read_file 再决定使用 write_file 还是 append_file。{path_rule}"
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_injection_includes_base_dir() {
+        let skill = FileSystemSkill::with_base_dir("/workspace/project");
+        let prompt = skill.system_prompt_injection().unwrap();
+        assert!(prompt.contains("所有文件操作限定在 `/workspace/project` 目录内"));
+        assert!(prompt.contains("请使用相对路径"));
+    }
+
+    #[test]
+    fn test_prompt_injection_without_base_dir_has_no_path_rule() {
+        let prompt = FileSystemSkill::new().system_prompt_injection().unwrap();
+        assert!(!prompt.contains("**路径限制**"));
+        assert!(prompt.contains("无路径限制"));
+    }
+}
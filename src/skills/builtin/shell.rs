@@ -2,6 +2,19 @@ use crate::skills::Skill;
 use crate::tools::Tool;
 use crate::tools::shell::ShellTool;
 
+/// ShellTool 用于执行命令的解释器
+#[cfg(target_os = "windows")]
+const SHELL_PROGRAM: &str = "cmd.exe";
+#[cfg(not(target_os = "windows"))]
+const SHELL_PROGRAM: &str = "sh";
+
+/// 在 PATH 中查找 shell 解释器（只检查文件是否存在，不启动进程）
+fn shell_available() -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(SHELL_PROGRAM).is_file())
+    })
+}
+
 /// Shell 命令执行技能
 ///
 /// 为 Agent 提供受控的 shell 命令执行能力，内置三级安全策略：
@@ -16,10 +29,10 @@ use crate::tools::shell::ShellTool;
 /// let config = AgentConfig::new("qwen3-max", "shell", "You are a shell assistant");
 /// let mut agent = ReactAgent::new(config);
 /// // 严格模式（默认）：只允许白名单命令
-/// agent.add_skill(Box::new(ShellSkill::new()));
+/// agent.add_skill(Box::new(ShellSkill::new())).unwrap();
 ///
 /// // 宽松模式：白名单之外的命令也可执行（不推荐用于生产）
-/// agent.add_skill(Box::new(ShellSkill::permissive()));
+/// agent.add_skill(Box::new(ShellSkill::permissive())).unwrap();
 /// ```
 pub struct ShellSkill {
     permissive: bool,
@@ -61,6 +74,14 @@ impl Skill for ShellSkill {
         vec![Box::new(tool)]
     }
 
+    fn validate(&self) -> Result<(), String> {
+        if shell_available() {
+            Ok(())
+        } else {
+            Err(format!("未在 PATH 中找到 shell 解释器 '{}'", SHELL_PROGRAM))
+        }
+    }
+
     fn system_prompt_injection(&self) -> Option<String> {
         Some(
             "\n\n## Shell 命令能力（Shell Skill）\n\
@@ -1,6 +1,6 @@
 use crate::skills::Skill;
 use crate::tools::Tool;
-use crate::tools::others::weather::WeatherTool;
+use crate::tools::others::weather::RandomWeatherTool;
 
 /// 天气查询技能
 ///
@@ -12,7 +12,7 @@ use crate::tools::others::weather::WeatherTool;
 ///
 /// let config = AgentConfig::new("qwen3-max", "weather", "You are a weather assistant");
 /// let mut agent = ReactAgent::new(config);
-/// agent.add_skill(Box::new(WeatherSkill));
+/// agent.add_skill(Box::new(WeatherSkill)).unwrap();
 /// ```
 pub struct WeatherSkill;
 
@@ -26,7 +26,7 @@ impl Skill for WeatherSkill {
     }
 
     fn tools(&self) -> Vec<Box<dyn Tool>> {
-        vec![Box::new(WeatherTool)]
+        vec![Box::new(RandomWeatherTool::new())]
     }
 
     fn system_prompt_injection(&self) -> Option<String> {
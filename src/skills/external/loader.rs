@@ -7,7 +7,7 @@ use crate::error::{ReactError, Result};
 
 use super::types::{LoadedSkill, ResourceRef, SkillMeta};
 
-const SKILL_FILE: &str = "SKILL.md";
+pub(crate) const SKILL_FILE: &str = "SKILL.md";
 
 // ── SkillLoader ───────────────────────────────────────────────────────────────
 
@@ -128,19 +128,54 @@ impl SkillLoader {
         Ok(loaded)
     }
 
+    /// 重新加载单个技能目录中的 SKILL.md
+    ///
+    /// 解析成功后才替换该目录下的旧版本（可能已改名）并清空其资源缓存；
+    /// 解析失败时返回错误，旧版本保持不变。
+    pub async fn reload(&mut self, skill_dir: &Path) -> Result<LoadedSkill> {
+        let meta = Self::read_meta(&skill_dir.join(SKILL_FILE)).await?;
+
+        let stale: Vec<String> = self
+            .skills
+            .values()
+            .filter(|s| s.skill_dir == skill_dir || s.meta.name == meta.name)
+            .map(|s| s.meta.name.clone())
+            .collect();
+        for name in &stale {
+            self.skills.remove(name);
+        }
+        self.resource_cache
+            .retain(|(skill, _), _| !stale.contains(skill) && *skill != meta.name);
+
+        let loaded = self.preload_resources(meta, skill_dir).await;
+        info!(
+            "已重新加载 Skill '{}' ({})",
+            loaded.meta.name,
+            skill_dir.display()
+        );
+        self.skills.insert(loaded.meta.name.clone(), loaded.clone());
+        Ok(loaded)
+    }
+
     /// 读取并解析单个 SKILL.md 文件
     async fn load_skill_file(
         &mut self,
         skill_dir: &Path,
         skill_file: &Path,
     ) -> Result<LoadedSkill> {
+        let meta = Self::read_meta(skill_file).await?;
+        Ok(self.preload_resources(meta, skill_dir).await)
+    }
+
+    async fn read_meta(skill_file: &Path) -> Result<SkillMeta> {
         let content = tokio::fs::read_to_string(skill_file)
             .await
             .map_err(|e| ReactError::Other(format!("读取 SKILL.md 失败: {}", e)))?;
+        Self::parse_frontmatter(&content)
+    }
 
-        let meta = Self::parse_frontmatter(&content)?;
-
-        // 立即加载 load_on_startup 的资源
+    /// 立即加载 load_on_startup 的资源
+    async fn preload_resources(&mut self, meta: SkillMeta, skill_dir: &Path) -> LoadedSkill {
         for res_ref in meta.startup_resources() {
             let res_path = skill_dir.join(&res_ref.path);
             match tokio::fs::read_to_string(&res_path).await {
@@ -160,10 +195,10 @@ impl SkillLoader {
             }
         }
 
-        Ok(LoadedSkill {
+        LoadedSkill {
             meta,
             skill_dir: skill_dir.to_path_buf(),
-        })
+        }
     }
 
     // ── Frontmatter 解析 ──────────────────────────────────────────────────────
@@ -276,6 +311,11 @@ impl SkillLoader {
         self.skills.get(name)
     }
 
+    /// 获取从指定目录加载的技能
+    pub fn skill_at(&self, skill_dir: &Path) -> Option<&LoadedSkill> {
+        self.skills.values().find(|s| s.skill_dir == skill_dir)
+    }
+
     /// 列出所有已加载的技能
     pub fn list_skills(&self) -> Vec<&LoadedSkill> {
         let mut skills: Vec<&LoadedSkill> = self.skills.values().collect();
@@ -2,6 +2,7 @@ mod loader;
 mod resource_tool;
 mod types;
 
+pub(crate) use loader::SKILL_FILE;
 pub use loader::SkillLoader;
 pub use resource_tool::LoadSkillResourceTool;
 pub use types::{LoadedSkill, ResourceRef, SkillMeta};
@@ -54,6 +54,7 @@ pub trait Skill: Send + Sync {
     /// 此 Skill 提供的工具集合
     ///
     /// 每次调用都应返回新的 Tool 实例（因为 Box<dyn Tool> 无法 Clone）。
+    /// 创建开销较大的工具可返回 [`LazyTool`](crate::tools::LazyTool)，首次被调用时才实例化。
     fn tools(&self) -> Vec<Box<dyn Tool>>;
 
     /// 注入到 Agent 系统提示词末尾的指引片段（可选）
@@ -63,6 +64,14 @@ pub trait Skill: Send + Sync {
     fn system_prompt_injection(&self) -> Option<String> {
         None
     }
+
+    /// 安装前校验运行依赖（可选实现，默认通过）
+    ///
+    /// 在 `agent.add_skill()` 安装前调用，返回 `Err(原因)` 时拒绝安装。
+    /// 实现应快速且无副作用（如检查命令是否存在、环境变量是否配置），不要发起网络请求。
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 // ── SkillInfo ─────────────────────────────────────────────────────────────────
@@ -103,6 +112,11 @@ impl SkillManager {
         self.skills.insert(info.name.clone(), info);
     }
 
+    /// 移除某 Skill 的记录（重载外部 Skill 时使用）
+    pub(crate) fn remove(&mut self, name: &str) -> Option<SkillInfo> {
+        self.skills.remove(name)
+    }
+
     /// 查询是否已安装某 Skill
     pub fn is_installed(&self, name: &str) -> bool {
         self.skills.contains_key(name)
@@ -1,30 +1,106 @@
 //! 任务管理器
 
-use crate::tasks::task::{Task, TaskStatus};
+use crate::tasks::task::{Task, TaskStatus, TaskStatusChange, TaskUpdate, now_secs};
 use std::collections::HashMap;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// 每个任务默认保留的状态变更记录条数
+const DEFAULT_HISTORY_LIMIT: usize = 50;
 
 /// DAG 任务集合管理器，负责任务的增删改查和依赖调度
 pub struct TaskManager {
     pub(crate) tasks: HashMap<String, Task>,
+    /// 单个任务状态历史的最大条数，超出时丢弃最早的记录
+    history_limit: usize,
+    /// 任务进度订阅端，任务创建或状态变更时推送 [`TaskUpdate`]
+    pub(crate) updates: Option<UnboundedSender<TaskUpdate>>,
 }
 
 impl TaskManager {
     pub(crate) fn new() -> Self {
         Self {
             tasks: HashMap::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            updates: None,
         }
     }
 
-    pub(crate) fn add_task(&mut self, task: Task) {
+    /// 清空全部任务，保留历史条数上限与进度订阅端
+    pub(crate) fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// 设置（或移除）任务进度订阅端
+    pub(crate) fn set_update_sender(&mut self, updates: Option<UnboundedSender<TaskUpdate>>) {
+        self.updates = updates;
+    }
+
+    /// 向订阅端推送任务的当前状态与整体进度；无订阅端或任务不存在时忽略
+    pub(crate) fn publish_update(&self, id: &str) {
+        let (Some(updates), Some(task)) = (&self.updates, self.tasks.get(id)) else {
+            return;
+        };
+        let _ = updates.send(TaskUpdate {
+            task_id: task.id.clone(),
+            status: task.status.clone(),
+            progress: self.get_progress(),
+        });
+    }
+
+    /// 设置单个任务状态历史的最大条数（至少保留 1 条）
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.set_history_limit(limit);
+        self
+    }
+
+    /// 运行时调整单个任务状态历史的最大条数（至少保留 1 条），在下次状态变更时生效
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit.max(1);
+    }
+
+    pub(crate) fn add_task(&mut self, mut task: Task) {
+        if task.status_history.is_empty() {
+            let at = if task.created_at > 0 {
+                task.created_at
+            } else {
+                now_secs()
+            };
+            task.status_history.push((at, task.status.clone(), None));
+        }
         self.tasks.insert(task.id.clone(), task);
     }
 
-    pub(crate) fn update_task(&mut self, id: &str, status: TaskStatus) {
+    /// 更新任务状态并在历史中追加一条记录
+    pub fn update_task(&mut self, id: &str, status: TaskStatus) {
+        self.update_task_with_reason(id, status, None);
+    }
+
+    /// 同 [`update_task`](Self::update_task)，附带变更原因
+    pub fn update_task_with_reason(
+        &mut self,
+        id: &str,
+        status: TaskStatus,
+        reason: Option<String>,
+    ) {
+        let limit = self.history_limit;
         if let Some(task) = self.tasks.get_mut(id) {
-            task.status = status;
+            let now = now_secs();
+            task.status = status.clone();
+            task.updated_at = now;
+            task.status_history.push((now, status, reason));
+            if task.status_history.len() > limit {
+                let overflow = task.status_history.len() - limit;
+                task.status_history.drain(..overflow);
+            }
+            self.publish_update(id);
         }
     }
 
+    /// 查询任务的状态变更时间线（按时间顺序）
+    pub fn get_task_timeline(&self, id: &str) -> Option<&[TaskStatusChange]> {
+        self.tasks.get(id).map(|t| t.status_history.as_slice())
+    }
+
     pub(crate) fn get_task_mut(&mut self, id: &str) -> Option<&mut Task> {
         self.tasks.get_mut(id)
     }
@@ -88,7 +164,7 @@ impl TaskManager {
     /// 获取下一个应该执行的任务
     pub fn get_next_task(&self) -> Option<&Task> {
         let mut ready = self.get_ready_tasks();
-        ready.sort_by(|a, b| b.priority.cmp(&a.priority));
+        ready.sort_by_key(|t| std::cmp::Reverse(t.priority));
         ready.first().copied()
     }
 
@@ -7,7 +7,8 @@ mod manager;
 mod task;
 
 pub use manager::TaskManager;
-pub use task::{Task, TaskStatus};
+pub(crate) use task::now_secs;
+pub use task::{Task, TaskStatus, TaskStatusChange, TaskUpdate};
 
 #[cfg(test)]
 mod tests {
@@ -26,6 +27,7 @@ mod tests {
             parent_id: None,
             created_at: 0,
             updated_at: 0,
+            status_history: Vec::new(),
         }
     }
 
@@ -200,8 +202,8 @@ mod tests {
         assert_eq!(chains.len(), 2, "应该有两条依赖链");
 
         // 验证两条链
-        let chain1 = vec!["task4", "task2", "task1"];
-        let chain2 = vec!["task4", "task3", "task1"];
+        let chain1 = ["task4", "task2", "task1"];
+        let chain2 = ["task4", "task3", "task1"];
         let chain1 = chain1.iter().map(|x| x.to_string()).collect();
         let chain2 = chain2.iter().map(|x| x.to_string()).collect();
 
@@ -260,6 +262,7 @@ mod tests {
             parent_id: None,
             created_at: 0,
             updated_at: 0,
+            status_history: Vec::new(),
         });
 
         manager.add_task(Task {
@@ -273,10 +276,60 @@ mod tests {
             parent_id: None,
             created_at: 0,
             updated_at: 0,
+            status_history: Vec::new(),
         });
 
         let next = manager.get_next_task();
         assert!(next.is_some(), "应该有下一个任务");
         assert_eq!(next.unwrap().id, "task2", "应该返回高优先级任务");
     }
+
+    #[test]
+    fn test_status_history_records_timeline() {
+        let mut manager = TaskManager::new();
+        manager.add_task(create_task("task1", "Task 1", vec![]));
+
+        manager.update_task("task1", TaskStatus::InProgress);
+        manager.update_task_with_reason(
+            "task1",
+            TaskStatus::Failed("超时".to_string()),
+            Some("超时".to_string()),
+        );
+        manager.update_task("task1", TaskStatus::Completed);
+
+        let timeline = manager.get_task_timeline("task1").unwrap();
+        let statuses: Vec<&TaskStatus> = timeline.iter().map(|(_, s, _)| s).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                &TaskStatus::Pending,
+                &TaskStatus::InProgress,
+                &TaskStatus::Failed("超时".to_string()),
+                &TaskStatus::Completed,
+            ]
+        );
+        assert!(timeline.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(timeline[2].2.as_deref(), Some("超时"));
+        assert!(manager.get_task_timeline("missing").is_none());
+    }
+
+    #[test]
+    fn test_status_history_limit_and_legacy_data() {
+        let mut manager = TaskManager::new().with_history_limit(2);
+        manager.add_task(create_task("task1", "Task 1", vec![]));
+        manager.update_task("task1", TaskStatus::InProgress);
+        manager.update_task("task1", TaskStatus::Completed);
+
+        let timeline = manager.get_task_timeline("task1").unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].1, TaskStatus::InProgress);
+        assert_eq!(timeline[1].1, TaskStatus::Completed);
+
+        // 旧数据没有 status_history 字段，反序列化后为空
+        let legacy = r#"{"id":"t","description":"d","status":"Pending","dependencies":[],
+            "priority":5,"result":null,"reasoning":null,"parent_id":null,
+            "created_at":0,"updated_at":0}"#;
+        let task: Task = serde_json::from_str(legacy).unwrap();
+        assert!(task.status_history.is_empty());
+    }
 }
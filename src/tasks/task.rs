@@ -19,6 +19,28 @@ pub enum TaskStatus {
     Blocked(String),
 }
 
+/// 一次状态变更记录：(时间戳秒, 新状态, 变更原因)
+pub type TaskStatusChange = (u64, TaskStatus, Option<String>);
+
+/// 任务被创建或状态变更时推送给订阅端的进度通知
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskUpdate {
+    /// 任务 ID
+    pub task_id: String,
+    /// 变更后的状态
+    pub status: TaskStatus,
+    /// 变更后的 `(已完成数, 任务总数)`
+    pub progress: (usize, usize),
+}
+
+/// 获取当前时间戳（秒），不会 panic
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Task {
     /// 任务 ID
@@ -38,6 +60,9 @@ pub struct Task {
     pub parent_id: Option<String>,
     pub created_at: u64,
     pub updated_at: u64,
+    /// 状态变更历史，按时间顺序追加（旧数据反序列化时为空）
+    #[serde(default)]
+    pub status_history: Vec<TaskStatusChange>,
 }
 
 impl Task {
@@ -53,6 +78,7 @@ impl Task {
             parent_id: None,
             created_at: 0,
             updated_at: 0,
+            status_history: Vec::new(),
         }
     }
 
@@ -0,0 +1,237 @@
+//! 端到端的 ReactAgent 测试脚手架
+//!
+//! [`AgentTestHarness`] 以声明式的方式描述每一轮 LLM 的行为（调用哪个工具、工具返回什么、
+//! 最终答案是什么），自动生成对应的 [`MockLlmClient`] 脚本并注册 [`MockTool`]，
+//! 跑完后提供断言助手检查工具调用、最终答案与推理轮数。
+//!
+//! # 示例
+//!
+//! ```rust
+//! use echo_agent::testing::AgentTestHarness;
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut harness = AgentTestHarness::builder()
+//!     .tool_call("weather", json!({ "city": "北京" }), "晴，25°C")
+//!     .final_answer("北京今天晴，25°C")
+//!     .build();
+//!
+//! harness.run("北京天气怎么样？").await.unwrap();
+//! harness
+//!     .assert_tool_called_with("weather", json!({ "city": "北京" }))
+//!     .assert_final_answer("北京今天晴，25°C")
+//!     .assert_iterations(2);
+//! # }
+//! ```
+
+use crate::agent::react_agent::{AgentConfig, ReactAgent};
+use crate::agent::{ExecutionResult, ToolCallRecord};
+use crate::error::Result;
+use crate::testing::{MockLlmClient, MockTool};
+use crate::tools::Tool;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// 脚本中某次工具调用的预设结果
+enum ScriptedOutput {
+    Success(String),
+    Failure(String),
+}
+
+/// [`AgentTestHarness`] 的构建器：按顺序声明每一轮 LLM 的响应
+pub struct AgentTestHarnessBuilder {
+    config: AgentConfig,
+    llm: MockLlmClient,
+    /// 按首次出现顺序记录的 Mock 工具及其依次返回的结果
+    mock_tools: Vec<(String, Vec<ScriptedOutput>)>,
+    tools: Vec<Box<dyn Tool>>,
+}
+
+impl AgentTestHarnessBuilder {
+    /// 替换默认的 Agent 配置（工具调用始终开启）
+    pub fn config(mut self, config: AgentConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 声明一轮：LLM 调用工具 `name`，工具返回 `output`
+    pub fn tool_call(self, name: &str, args: Value, output: &str) -> Self {
+        self.tool_calls([(name, args, output)])
+    }
+
+    /// 声明一轮：LLM 在同一条消息里调用多个工具（并行执行），各自返回对应的输出
+    pub fn tool_calls<'a>(
+        mut self,
+        calls: impl IntoIterator<Item = (&'a str, Value, &'a str)>,
+    ) -> Self {
+        let mut llm_calls = Vec::new();
+        for (name, args, output) in calls {
+            self.script(name, ScriptedOutput::Success(output.to_string()));
+            llm_calls.push((name, args));
+        }
+        self.llm = self.llm.with_tool_calls(llm_calls);
+        self
+    }
+
+    /// 声明一轮：LLM 调用工具 `name`，工具执行失败并返回 `error`
+    pub fn tool_failure(mut self, name: &str, args: Value, error: &str) -> Self {
+        self.script(name, ScriptedOutput::Failure(error.to_string()));
+        self.llm = self.llm.with_tool_calls([(name, args)]);
+        self
+    }
+
+    /// 声明一轮：LLM 调用 `final_answer` 给出最终答案
+    pub fn final_answer(mut self, answer: &str) -> Self {
+        self.llm = self
+            .llm
+            .with_tool_calls([("final_answer", json!({ "answer": answer }))]);
+        self
+    }
+
+    /// 注册自定义工具；与脚本中同名时使用该工具，不再自动生成 Mock
+    pub fn tool(mut self, tool: Box<dyn Tool>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    fn script(&mut self, name: &str, output: ScriptedOutput) {
+        match self.mock_tools.iter_mut().find(|(n, _)| n == name) {
+            Some((_, outputs)) => outputs.push(output),
+            None => self.mock_tools.push((name.to_string(), vec![output])),
+        }
+    }
+
+    pub fn build(self) -> AgentTestHarness {
+        let llm = Arc::new(self.llm);
+        let mut agent = ReactAgent::new(self.config.enable_tool(true));
+        agent.set_llm_client(llm.clone());
+
+        let custom: Vec<String> = self.tools.iter().map(|t| t.name().to_string()).collect();
+        agent.add_tools(self.tools);
+        for (name, outputs) in self.mock_tools {
+            if custom.contains(&name) {
+                continue;
+            }
+            let tool = outputs
+                .into_iter()
+                .fold(MockTool::new(name), |tool, output| match output {
+                    ScriptedOutput::Success(text) => tool.with_response(text),
+                    ScriptedOutput::Failure(msg) => tool.with_failure(msg),
+                });
+            agent.add_tool(Box::new(tool));
+        }
+
+        AgentTestHarness {
+            agent,
+            llm,
+            result: None,
+        }
+    }
+}
+
+/// 端到端的 ReactAgent 测试脚手架，详见[模块文档](self)
+pub struct AgentTestHarness {
+    agent: ReactAgent,
+    llm: Arc<MockLlmClient>,
+    result: Option<ExecutionResult>,
+}
+
+impl AgentTestHarness {
+    /// 创建构建器，默认配置为开启工具调用的 `AgentConfig::new("mock-model", "test_agent", ...)`
+    pub fn builder() -> AgentTestHarnessBuilder {
+        AgentTestHarnessBuilder {
+            config: AgentConfig::new("mock-model", "test_agent", "你是一个测试助手"),
+            llm: MockLlmClient::new(),
+            mock_tools: Vec::new(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// 执行一次任务并记录执行结果，返回最终答案
+    pub async fn run(&mut self, task: &str) -> Result<String> {
+        self.result = None;
+        let result = self.agent.execute_rich(task).await?;
+        let answer = result.final_answer.clone();
+        self.result = Some(result);
+        Ok(answer)
+    }
+
+    pub fn agent(&self) -> &ReactAgent {
+        &self.agent
+    }
+
+    pub fn agent_mut(&mut self) -> &mut ReactAgent {
+        &mut self.agent
+    }
+
+    /// 脚本化的 LLM 客户端，可检查每轮收到的消息
+    pub fn llm(&self) -> &MockLlmClient {
+        &self.llm
+    }
+
+    /// 最近一次 [`run`](Self::run) 的执行结果
+    ///
+    /// # Panics
+    /// 尚未成功执行过 `run` 时 panic。
+    #[track_caller]
+    pub fn result(&self) -> &ExecutionResult {
+        self.result
+            .as_ref()
+            .expect("AgentTestHarness: 尚未成功执行 run()")
+    }
+
+    /// 按执行顺序返回名为 `name` 的工具调用记录
+    pub fn tool_calls(&self, name: &str) -> Vec<&ToolCallRecord> {
+        self.result()
+            .tool_calls
+            .iter()
+            .filter(|r| r.name == name)
+            .collect()
+    }
+
+    /// 断言工具 `name` 至少被调用过一次
+    #[track_caller]
+    pub fn assert_tool_called(&self, name: &str) -> &Self {
+        assert!(
+            !self.tool_calls(name).is_empty(),
+            "工具 `{name}` 未被调用，实际调用：{:?}",
+            self.called_names()
+        );
+        self
+    }
+
+    /// 断言工具 `name` 以参数 `args` 被调用过（参数按执行时实际传入的值比较）
+    #[track_caller]
+    pub fn assert_tool_called_with(&self, name: &str, args: Value) -> &Self {
+        let calls = self.tool_calls(name);
+        assert!(
+            calls.iter().any(|r| r.args == args),
+            "工具 `{name}` 未以参数 {args} 被调用，实际参数：{:?}",
+            calls.iter().map(|r| &r.args).collect::<Vec<_>>()
+        );
+        self
+    }
+
+    /// 断言最终答案
+    #[track_caller]
+    pub fn assert_final_answer(&self, expected: &str) -> &Self {
+        assert_eq!(self.result().final_answer, expected, "最终答案不符");
+        self
+    }
+
+    /// 断言 LLM 推理轮数
+    #[track_caller]
+    pub fn assert_iterations(&self, expected: usize) -> &Self {
+        assert_eq!(self.result().iterations, expected, "推理轮数不符");
+        self
+    }
+
+    fn called_names(&self) -> Vec<&str> {
+        self.result()
+            .tool_calls
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect()
+    }
+}
@@ -4,6 +4,8 @@
 //! - 测试 [`SummaryCompressor`] 和 [`HybridCompressor`]（它们通过 `LlmClient` 调用 LLM）
 //! - 测试自定义 [`ContextCompressor`] 实现
 //! - 任何注入了 `Arc<dyn LlmClient>` 依赖的组件
+//! - 通过 [`with_tool_calls`](MockLlmClient::with_tool_calls) 脚本化 `ReactAgent` 的工具调用
+//!   （端到端场景推荐直接使用 [`AgentTestHarness`](crate::testing::AgentTestHarness)）
 //!
 //! # 示例
 //!
@@ -28,29 +30,46 @@
 //! ```
 
 use crate::error::{LlmError, ReactError, Result};
-use crate::llm::types::{DeltaMessage, Message};
+use crate::llm::types::{
+    ChatCompletionResponse, Choice, DeltaFunctionCall, DeltaMessage, DeltaToolCall, FunctionCall,
+    Message, ToolCall, Usage,
+};
 use crate::llm::{ChatChunk, ChatRequest, ChatResponse, LlmClient};
 use async_trait::async_trait;
 use futures::stream::BoxStream;
-use std::collections::VecDeque;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
-/// 预设响应的枚举（文本或错误）
+/// 预设响应的枚举（文本、完整消息、错误，或附带 token 用量的响应）
 enum MockLlmResponse {
     Content(String),
+    Message(Message),
+    /// 一次返回多个候选回复（对应请求的 `n > 1`）
+    Choices(Vec<Message>),
     Err(ReactError),
+    WithUsage(Box<MockLlmResponse>, Usage),
 }
 
 /// 可脚本化的 Mock LLM 客户端。
 ///
 /// 按顺序返回预设的响应；队列耗尽后返回 `EmptyResponse` 错误。
+/// 通过 `with_model_*` 为特定模型预设的响应优先于公共队列，便于按模型返回不同结果。
 /// 所有调用都被记录，可通过 [`call_count`](MockLlmClient::call_count) /
 /// [`last_messages`](MockLlmClient::last_messages) 等方法检查。
 pub struct MockLlmClient {
     model_name: String,
     responses: Arc<Mutex<VecDeque<MockLlmResponse>>>,
+    /// 按模型区分的预设响应，请求的模型有剩余响应时优先使用
+    model_responses: Arc<Mutex<HashMap<String, VecDeque<MockLlmResponse>>>>,
     /// 每次调用时收到的 messages 列表，按顺序记录
     calls: Arc<Mutex<Vec<Vec<Message>>>>,
+    /// 每次调用实际使用的模型（请求未指定时为 Mock 自身的模型名）
+    models: Arc<Mutex<Vec<String>>>,
+    /// 每次调用携带的工具名称（未携带工具定义时为空）
+    tools: Arc<Mutex<Vec<Vec<String>>>>,
+    /// 已生成的工具调用数，用于生成唯一的调用 ID
+    tool_call_seq: usize,
 }
 
 impl Default for MockLlmClient {
@@ -65,7 +84,11 @@ impl MockLlmClient {
         Self {
             model_name: "mock-model".to_string(),
             responses: Arc::new(Mutex::new(VecDeque::new())),
+            model_responses: Arc::new(Mutex::new(HashMap::new())),
             calls: Arc::new(Mutex::new(Vec::new())),
+            models: Arc::new(Mutex::new(Vec::new())),
+            tools: Arc::new(Mutex::new(Vec::new())),
+            tool_call_seq: 0,
         }
     }
 
@@ -95,6 +118,101 @@ impl MockLlmClient {
         self
     }
 
+    /// 追加一条包含多个候选回复（choices）的响应，模拟 `n > 1` 的请求
+    ///
+    /// `ChatResponse::message` 为第一个候选，全部候选在 `raw` 中；流式请求只产出第一个候选。
+    pub fn with_choices(self, choices: impl IntoIterator<Item = Message>) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(MockLlmResponse::Choices(choices.into_iter().collect()));
+        self
+    }
+
+    /// 追加一条完整的 assistant 消息（可携带 `tool_calls`）
+    pub fn with_message(self, message: Message) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(MockLlmResponse::Message(message));
+        self
+    }
+
+    /// 追加一条工具调用响应：同一条消息内依次调用 `(工具名, 参数)` 中的每个工具
+    ///
+    /// 调用 ID 按追加顺序生成为 `call_0`、`call_1`……
+    pub fn with_tool_calls(
+        mut self,
+        calls: impl IntoIterator<Item = (impl Into<String>, Value)>,
+    ) -> Self {
+        let message = self.tool_calls_message(calls);
+        self.with_message(message)
+    }
+
+    /// 为模型 `model` 追加一条成功响应文本
+    pub fn with_model_response(self, model: &str, text: impl Into<String>) -> Self {
+        self.push_model_response(model, MockLlmResponse::Content(text.into()));
+        self
+    }
+
+    /// 为模型 `model` 追加一条工具调用响应，调用 ID 与公共队列共用同一序列
+    pub fn with_model_tool_calls(
+        mut self,
+        model: &str,
+        calls: impl IntoIterator<Item = (impl Into<String>, Value)>,
+    ) -> Self {
+        let message = self.tool_calls_message(calls);
+        self.push_model_response(model, MockLlmResponse::Message(message));
+        self
+    }
+
+    fn push_model_response(&self, model: &str, response: MockLlmResponse) {
+        self.model_responses
+            .lock()
+            .unwrap()
+            .entry(model.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    fn tool_calls_message(
+        &mut self,
+        calls: impl IntoIterator<Item = (impl Into<String>, Value)>,
+    ) -> Message {
+        let tool_calls: Vec<ToolCall> = calls
+            .into_iter()
+            .map(|(name, args)| {
+                let id = format!("call_{}", self.tool_call_seq);
+                self.tool_call_seq += 1;
+                ToolCall {
+                    id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: name.into(),
+                        arguments: args.to_string(),
+                    },
+                }
+            })
+            .collect();
+        Message::assistant_with_tools(tool_calls)
+    }
+
+    /// 为公共队列中最近追加的一条响应附上 token 用量（用于测试用量统计与 token 预算）
+    pub fn with_usage(self, prompt_tokens: u32, completion_tokens: u32) -> Self {
+        {
+            let mut queue = self.responses.lock().unwrap();
+            if let Some(last) = queue.pop_back() {
+                let usage = Usage {
+                    prompt_tokens: Some(prompt_tokens),
+                    completion_tokens: Some(completion_tokens),
+                    total_tokens: Some(prompt_tokens + completion_tokens),
+                };
+                queue.push_back(MockLlmResponse::WithUsage(Box::new(last), usage));
+            }
+        }
+        self
+    }
+
     /// 追加一条错误响应（用于测试错误处理路径）
     pub fn with_error(self, err: ReactError) -> Self {
         self.responses
@@ -132,56 +250,156 @@ impl MockLlmClient {
         self.calls.lock().unwrap().clone()
     }
 
-    /// 剩余未消费的预设响应数量
+    /// 所有历史调用使用的模型（按时序排列）
+    pub fn all_models(&self) -> Vec<String> {
+        self.models.lock().unwrap().clone()
+    }
+
+    /// 所有历史调用携带的工具名称（按时序排列）
+    pub fn all_tool_names(&self) -> Vec<Vec<String>> {
+        self.tools.lock().unwrap().clone()
+    }
+
+    /// 剩余未消费的预设响应数量（含按模型预设的响应）
     pub fn remaining(&self) -> usize {
-        self.responses.lock().unwrap().len()
+        let by_model: usize = self
+            .model_responses
+            .lock()
+            .unwrap()
+            .values()
+            .map(VecDeque::len)
+            .sum();
+        self.responses.lock().unwrap().len() + by_model
     }
 
     /// 清空所有已记录的调用历史（响应队列不受影响）
     pub fn reset_calls(&self) {
         self.calls.lock().unwrap().clear();
+        self.models.lock().unwrap().clear();
+        self.tools.lock().unwrap().clear();
+    }
+
+    /// 记录本次调用，返回实际使用的模型
+    fn record_call(&self, request: ChatRequest) -> String {
+        let model = request
+            .options
+            .model
+            .unwrap_or_else(|| self.model_name.clone());
+        self.models.lock().unwrap().push(model.clone());
+        let tool_names = request
+            .tools
+            .iter()
+            .flatten()
+            .map(|def| def.function.name.clone())
+            .collect();
+        self.tools.lock().unwrap().push(tool_names);
+        self.calls.lock().unwrap().push(request.messages);
+        model
     }
 
-    /// 取出下一个响应
-    fn pop_response(&self) -> Result<String> {
-        match self.responses.lock().unwrap().pop_front() {
-            Some(MockLlmResponse::Content(text)) => Ok(text),
-            Some(MockLlmResponse::Err(e)) => Err(e),
-            None => Err(ReactError::Llm(LlmError::EmptyResponse)),
+    /// 取出下一个响应：该模型的预设响应优先，其次为公共队列
+    fn pop_response(&self, model: &str) -> Result<(Vec<Message>, Option<Usage>)> {
+        let response = self
+            .model_responses
+            .lock()
+            .unwrap()
+            .get_mut(model)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| self.responses.lock().unwrap().pop_front());
+        let Some(response) = response else {
+            return Err(ReactError::Llm(LlmError::EmptyResponse));
+        };
+        resolve_response(response)
+    }
+}
+
+/// 把预设响应转为候选消息（至少一条）与可选的 token 用量
+fn resolve_response(response: MockLlmResponse) -> Result<(Vec<Message>, Option<Usage>)> {
+    match response {
+        MockLlmResponse::Content(text) => Ok((vec![Message::assistant(text)], None)),
+        MockLlmResponse::Message(message) => Ok((vec![message], None)),
+        MockLlmResponse::Choices(choices) if choices.is_empty() => {
+            Err(ReactError::Llm(LlmError::EmptyResponse))
+        }
+        MockLlmResponse::Choices(choices) => Ok((choices, None)),
+        MockLlmResponse::Err(e) => Err(e),
+        MockLlmResponse::WithUsage(inner, usage) => {
+            resolve_response(*inner).map(|(messages, _)| (messages, Some(usage)))
         }
     }
 }
 
+fn finish_reason_of(message: &Message) -> String {
+    if message.tool_calls.is_some() {
+        "tool_calls"
+    } else {
+        "stop"
+    }
+    .to_string()
+}
+
 #[async_trait]
 impl LlmClient for MockLlmClient {
     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
         // 记录本次调用
-        self.calls.lock().unwrap().push(request.messages);
+        let model = self.record_call(request);
 
-        let text = self.pop_response()?;
+        let (messages, usage) = self.pop_response(&model)?;
+        let mut raw = ChatCompletionResponse::default().with_usage(usage);
+        if messages.len() > 1 {
+            raw.choices = messages
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    Choice::new(m.clone(), Some(finish_reason_of(m))).with_index(i as u32)
+                })
+                .collect();
+        }
+        let message = messages.into_iter().next().unwrap_or_default();
 
         Ok(ChatResponse {
-            message: Message::assistant(text),
-            finish_reason: Some("stop".to_string()),
-            raw: crate::llm::types::ChatCompletionResponse::default(),
+            finish_reason: Some(finish_reason_of(&message)),
+            message,
+            raw,
         })
     }
 
     async fn chat_stream(&self, request: ChatRequest) -> Result<BoxStream<'_, Result<ChatChunk>>> {
         // 记录本次调用
-        self.calls.lock().unwrap().push(request.messages);
+        let model = self.record_call(request);
 
-        let text = self.pop_response()?;
+        let (messages, _) = self.pop_response(&model)?;
+        let message = messages.into_iter().next().unwrap_or_default();
+        let tool_calls = message.tool_calls.map(|calls| {
+            calls
+                .into_iter()
+                .enumerate()
+                .map(|(index, call)| DeltaToolCall {
+                    index: index as u32,
+                    id: Some(call.id),
+                    call_type: Some(call.call_type),
+                    function: Some(DeltaFunctionCall {
+                        name: Some(call.function.name),
+                        arguments: Some(call.function.arguments),
+                    }),
+                })
+                .collect::<Vec<_>>()
+        });
+        let finish_reason = if tool_calls.is_some() {
+            "tool_calls"
+        } else {
+            "stop"
+        };
 
         // 创建一个简单的流，一次性返回整个内容
         let stream = futures::stream::once(async move {
             Ok(ChatChunk {
                 delta: DeltaMessage {
                     role: Some("assistant".to_string()),
-                    content: Some(text),
-                    tool_calls: None,
+                    content: message.content,
+                    tool_calls,
                 },
-                finish_reason: Some("stop".to_string()),
+                finish_reason: Some(finish_reason.to_string()),
             })
         });
 
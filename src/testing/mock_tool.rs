@@ -4,6 +4,8 @@
 //! - 测试工具参数解析逻辑
 //! - 在集成测试中替换真实工具（数据库、HTTP 等）
 //! - 测试工具执行失败时 Agent 的容错行为
+//! - 配合 `with_delay` 测试超时与并发限流，配合 `with_priority` 测试许可排队顺序
+//! - 配合 `with_streaming` 测试工具输出的逐行回传
 //!
 //! # 示例
 //!
@@ -26,16 +28,19 @@
 //! # }
 //! ```
 
-use crate::error::Result;
-use crate::tools::{Tool, ToolParameters, ToolResult};
+use crate::error::{Result, ToolError};
+use crate::tools::{TOOL_PRIORITY_NORMAL, Tool, ToolParameters, ToolResult};
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use serde_json::{Value, json};
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// 预设执行结果枚举
 enum MockToolResponse {
     Success(String),
+    Warning(String, Vec<String>),
     Failure(String),
 }
 
@@ -50,6 +55,16 @@ pub struct MockTool {
     responses: Arc<Mutex<VecDeque<MockToolResponse>>>,
     /// 每次调用时收到的参数，按顺序记录
     calls: Arc<Mutex<Vec<HashMap<String, Value>>>>,
+    /// 是否声明为副作用工具
+    side_effects: bool,
+    /// 每次执行返回前的模拟耗时
+    delay: Option<Duration>,
+    /// 是否声明为流式输出工具
+    streaming: bool,
+    /// 并发排队优先级
+    priority: u8,
+    /// 声明的输出 schema
+    output_schema: Option<Value>,
 }
 
 impl MockTool {
@@ -65,6 +80,11 @@ impl MockTool {
             }),
             responses: Arc::new(Mutex::new(VecDeque::new())),
             calls: Arc::new(Mutex::new(Vec::<HashMap<String, Value>>::new())),
+            side_effects: false,
+            delay: None,
+            streaming: false,
+            priority: TOOL_PRIORITY_NORMAL,
+            output_schema: None,
         }
     }
 
@@ -100,6 +120,22 @@ impl MockTool {
         self
     }
 
+    /// 追加一条带警告的成功响应（用于测试「成功但有隐患」时 Agent 的行为）
+    pub fn with_warnings(
+        self,
+        text: impl Into<String>,
+        warnings: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(MockToolResponse::Warning(
+                text.into(),
+                warnings.into_iter().map(Into::into).collect(),
+            ));
+        self
+    }
+
     /// 追加一条失败响应（用于测试工具失败时 Agent 的行为）
     pub fn with_failure(self, msg: impl Into<String>) -> Self {
         self.responses
@@ -109,6 +145,36 @@ impl MockTool {
         self
     }
 
+    /// 设置每次执行的模拟耗时：先等待 `delay` 再返回预设结果，可与 `with_response` / `with_failure` 组合
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// 声明为副作用工具（用于测试副作用操作预算）
+    pub fn with_side_effects(mut self) -> Self {
+        self.side_effects = true;
+        self
+    }
+
+    /// 声明为流式输出工具：`execute_streaming` 把响应文本按行（保留换行符）逐片返回
+    pub fn with_streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    /// 设置并发排队优先级（用于测试许可紧张时的执行顺序）
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 声明输出 schema（用于测试文本输出的结构化抽取）
+    pub fn with_output_schema(mut self, schema: Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
     /// 已执行的调用总次数
     pub fn call_count(&self) -> usize {
         self.calls.lock().unwrap().len()
@@ -148,12 +214,50 @@ impl Tool for MockTool {
         // 记录本次调用参数
         self.calls.lock().unwrap().push(params.clone());
 
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
         let response = self.responses.lock().unwrap().pop_front();
         match response {
             Some(MockToolResponse::Success(text)) => Ok(ToolResult::success(text)),
+            Some(MockToolResponse::Warning(text, warnings)) => {
+                Ok(ToolResult::success_with_warnings(text, warnings))
+            }
             Some(MockToolResponse::Failure(msg)) => Ok(ToolResult::error(msg)),
             // 队列耗尽时返回默认成功
             None => Ok(ToolResult::success("mock response".to_string())),
         }
     }
+
+    fn has_side_effects(&self) -> bool {
+        self.side_effects
+    }
+
+    fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.output_schema.clone()
+    }
+
+    fn streams_output(&self) -> bool {
+        self.streaming
+    }
+
+    fn execute_streaming(&self, params: ToolParameters) -> BoxStream<'_, Result<String>> {
+        Box::pin(async_stream::try_stream! {
+            let result = self.execute(params).await?;
+            if !result.success {
+                Err(ToolError::ExecutionFailed {
+                    tool: self.name.clone(),
+                    message: result.error.unwrap_or_default(),
+                })?;
+            }
+            for line in result.output.split_inclusive('\n') {
+                yield line.to_string();
+            }
+        })
+    }
 }
@@ -8,6 +8,7 @@
 //! | [`MockTool`] | 替代真实工具，用于测试 Agent 的工具调用 / 错误处理行为 |
 //! | [`MockAgent`] | 替代真实 SubAgent，用于测试多 Agent 编排逻辑 |
 //! | [`FailingMockAgent`] | 总是返回错误，用于测试编排的容错路径 |
+//! | [`AgentTestHarness`] | 声明式脚本化多轮工具调用，端到端测试 `ReactAgent` 并断言结果 |
 //!
 //! # 设计原则
 //!
@@ -93,11 +94,13 @@
 //! # }
 //! ```
 
+mod harness;
 mod mock_agent;
 mod mock_embedder;
 mod mock_llm;
 mod mock_tool;
 
+pub use harness::{AgentTestHarness, AgentTestHarnessBuilder};
 pub use mock_agent::{FailingMockAgent, MockAgent};
 pub use mock_embedder::MockEmbedder;
 pub use mock_llm::MockLlmClient;
@@ -1,5 +1,5 @@
 use crate::error::ToolError;
-use crate::tools::{Tool, ToolParameters, ToolResult};
+use crate::tools::{TOOL_PRIORITY_HIGH, Tool, ToolParameters, ToolResult};
 
 pub struct FinalAnswerTool;
 
@@ -26,6 +26,11 @@ impl Tool for FinalAnswerTool {
         })
     }
 
+    /// 给出最终答案不应排在其他工具之后
+    fn priority(&self) -> u8 {
+        TOOL_PRIORITY_HIGH
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let answer = parameters
             .get("answer")
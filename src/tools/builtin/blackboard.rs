@@ -0,0 +1,220 @@
+//! 共享黑板工具：blackboard_write / blackboard_read / blackboard_wait
+//!
+//! | 工具               | 对应 Blackboard 操作                      |
+//! |--------------------|-------------------------------------------|
+//! | `blackboard_write` | `board.write(agent, key, value)`          |
+//! | `blackboard_read`  | `board.read(key)` / `board.history(key)`  |
+//! | `blackboard_wait`  | `board.wait_for(key, timeout)`            |
+
+use crate::error::ToolError;
+use crate::memory::blackboard::{Blackboard, BlackboardEntry};
+use crate::tools::{Tool, ToolParameters, ToolResult};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+pub(crate) const TOOL_BLACKBOARD_WRITE: &str = "blackboard_write";
+pub(crate) const TOOL_BLACKBOARD_READ: &str = "blackboard_read";
+pub(crate) const TOOL_BLACKBOARD_WAIT: &str = "blackboard_wait";
+
+/// `blackboard_wait` 默认与最长等待时间（秒）
+const DEFAULT_WAIT_SECS: u64 = 30;
+const MAX_WAIT_SECS: u64 = 300;
+
+fn required_key(parameters: &ToolParameters) -> crate::error::Result<&str> {
+    parameters
+        .get("key")
+        .and_then(|v| v.as_str())
+        .filter(|k| !k.trim().is_empty())
+        .ok_or_else(|| ToolError::MissingParameter("key".to_string()).into())
+}
+
+fn format_entry(entry: &BlackboardEntry) -> String {
+    format!(
+        "{}（v{}，由 {} 写入）：{}",
+        entry.key, entry.version, entry.author, entry.value
+    )
+}
+
+// ── BlackboardWriteTool ──────────────────────────────────────────────────────
+
+/// 向黑板追加写入，写入者记为所属 Agent
+pub struct BlackboardWriteTool {
+    board: Arc<Blackboard>,
+    author: String,
+}
+
+impl BlackboardWriteTool {
+    pub fn new(board: Arc<Blackboard>, author: impl Into<String>) -> Self {
+        Self {
+            board,
+            author: author.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for BlackboardWriteTool {
+    fn name(&self) -> &str {
+        TOOL_BLACKBOARD_WRITE
+    }
+
+    fn description(&self) -> &str {
+        "把中间结果写到与其他 Agent 共享的黑板上。同一 key 每次写入产生新版本，不会覆盖他人的结果。"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "string",
+                    "description": "结果的名称，其他 Agent 按此读取，例如 \"research_notes\""
+                },
+                "value": {
+                    "description": "要共享的内容，可以是文本或任意 JSON"
+                }
+            },
+            "required": ["key", "value"]
+        })
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let key = required_key(&parameters)?;
+        let value = parameters
+            .get("value")
+            .cloned()
+            .ok_or_else(|| ToolError::MissingParameter("value".to_string()))?;
+
+        debug!(author = %self.author, key = %key, "📝 blackboard_write 写入黑板");
+        let entry = self.board.write(&self.author, key, value).await?;
+        Ok(ToolResult::success(format!(
+            "✅ 已写入黑板：{}（版本 v{}）",
+            entry.key, entry.version
+        )))
+    }
+}
+
+// ── BlackboardReadTool ───────────────────────────────────────────────────────
+
+/// 读取黑板上某个 key 的最新版本或全部历史
+pub struct BlackboardReadTool {
+    board: Arc<Blackboard>,
+}
+
+impl BlackboardReadTool {
+    pub fn new(board: Arc<Blackboard>) -> Self {
+        Self { board }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for BlackboardReadTool {
+    fn name(&self) -> &str {
+        TOOL_BLACKBOARD_READ
+    }
+
+    fn description(&self) -> &str {
+        "读取共享黑板上其他 Agent 写入的结果，默认返回最新版本。"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "key": { "type": "string", "description": "要读取的结果名称" },
+                "history": {
+                    "type": "boolean",
+                    "description": "是否返回全部历史版本（默认 false）"
+                }
+            },
+            "required": ["key"]
+        })
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let key = required_key(&parameters)?;
+        let history = parameters
+            .get("history")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let entries = if history {
+            self.board.history(key).await?
+        } else {
+            self.board.read(key).await?.into_iter().collect()
+        };
+        if entries.is_empty() {
+            return Ok(ToolResult::success(format!("黑板上还没有「{key}」。")));
+        }
+        Ok(ToolResult::success(
+            entries
+                .iter()
+                .map(format_entry)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ))
+    }
+}
+
+// ── BlackboardWaitTool ───────────────────────────────────────────────────────
+
+/// 等待黑板上出现某个 key（其他 Agent 尚未写入时阻塞）
+pub struct BlackboardWaitTool {
+    board: Arc<Blackboard>,
+}
+
+impl BlackboardWaitTool {
+    pub fn new(board: Arc<Blackboard>) -> Self {
+        Self { board }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for BlackboardWaitTool {
+    fn name(&self) -> &str {
+        TOOL_BLACKBOARD_WAIT
+    }
+
+    fn description(&self) -> &str {
+        "等待其他 Agent 把某个结果写到共享黑板上，出现后返回最新版本；超时则返回未找到。"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "key": { "type": "string", "description": "要等待的结果名称" },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_WAIT_SECS,
+                    "description": "最长等待秒数（默认 30）"
+                }
+            },
+            "required": ["key"]
+        })
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let key = required_key(&parameters)?;
+        let secs = parameters
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map(|n| n.clamp(1, MAX_WAIT_SECS))
+            .unwrap_or(DEFAULT_WAIT_SECS);
+
+        debug!(key = %key, secs, "⏳ blackboard_wait 等待黑板写入");
+        match self.board.wait_for(key, Duration::from_secs(secs)).await? {
+            Some(entry) => Ok(ToolResult::success(format_entry(&entry))),
+            None => Ok(ToolResult::success(format!(
+                "等待 {secs} 秒后黑板上仍没有「{key}」。"
+            ))),
+        }
+    }
+}
@@ -76,6 +76,9 @@ impl Tool for HumanInLoop {
         let result_text = match self.provider.request(req).await? {
             HumanLoopResponse::Text(text) => text,
             HumanLoopResponse::Approved => "用户已确认".to_string(),
+            HumanLoopResponse::ApprovedWithModification { args } => {
+                format!("用户已确认，并修改参数为：{args}")
+            }
             HumanLoopResponse::Rejected { reason } => {
                 format!(
                     "用户已拒绝{}",
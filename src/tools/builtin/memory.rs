@@ -251,6 +251,15 @@ impl Tool for ForgetTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let id = params.get("id")?.as_str()?;
+        Some(format!("将删除 ID 为「{}」的长期记忆条目", id))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let id_prefix = parameters
             .get("id")
@@ -1,5 +1,6 @@
 pub(crate) mod agent_dispatch;
 pub(crate) mod answer;
+pub(crate) mod blackboard;
 pub(crate) mod human_in_loop;
 pub(crate) mod memory;
 pub(crate) mod plan;
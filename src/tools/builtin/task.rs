@@ -1,18 +1,10 @@
 use crate::error::{ReactError, ToolError};
-use crate::tasks::{Task, TaskManager, TaskStatus};
+use crate::tasks::{Task, TaskManager, TaskStatus, now_secs};
 use crate::tools::{Tool, ToolParameters, ToolResult};
 use serde_json::{Value, json};
 use std::sync::{Arc, RwLock};
 use tracing::{debug, info};
 
-/// 获取当前时间戳（秒），不会 panic
-fn now_secs() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs()
-}
-
 /// 安全读取 RwLock，将 poisoned lock 转为 ReactError
 fn read_lock<T>(lock: &RwLock<T>) -> crate::error::Result<std::sync::RwLockReadGuard<'_, T>> {
     lock.read()
@@ -120,6 +112,7 @@ impl Tool for CreateTaskTool {
             parent_id: None,
             created_at: now,
             updated_at: now,
+            status_history: Vec::new(),
         };
 
         let mut manager = write_lock(&self.task_manager)?;
@@ -150,6 +143,7 @@ impl Tool for CreateTaskTool {
             return Ok(ToolResult::error(error_msg));
         }
 
+        manager.publish_update(task_id);
         info!(
             "Task [{}] created successfully, no circular dependencies.",
             task_id
@@ -244,7 +238,7 @@ impl Tool for UpdateTaskTool {
             "in_progress" => TaskStatus::InProgress,
             "completed" => TaskStatus::Completed,
             "cancelled" => TaskStatus::Cancelled,
-            "failed" => TaskStatus::Failed(reason.unwrap_or_default()),
+            "failed" => TaskStatus::Failed(reason.clone().unwrap_or_default()),
             _ => {
                 return Err(ToolError::InvalidParameter {
                     name: "status".to_string(),
@@ -255,12 +249,11 @@ impl Tool for UpdateTaskTool {
         };
 
         let mut manager = write_lock(&self.task_manager)?;
-        manager.update_task(task_id, new_status.clone());
+        manager.update_task_with_reason(task_id, new_status.clone(), reason);
 
         // 更新结果
         if let Some(task) = manager.get_task_mut(task_id) {
             task.result = result;
-            task.updated_at = now_secs();
         }
 
         let update = format!("✓ 任务 [{}] 状态已更新为: {:?}", task_id, new_status);
@@ -0,0 +1,294 @@
+//! 工具并发限流器：支持运行时调整许可数、按工具优先级排队，以及遇到上游限流时自动收紧
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{AcquireError, Notify, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// 连续成功多少次后尝试放宽一档并发
+const RECOVER_AFTER_SUCCESSES: usize = 5;
+
+/// 排队每满该时长，等待者的有效优先级提升一级，避免低优先级工具饥饿
+const AGING_STEP: Duration = Duration::from_millis(50);
+
+/// 错误信息中表示上游限流的关键字
+const RATE_LIMIT_KEYWORDS: &[&str] = &["rate limit", "too many requests", "retry-after", "限流"];
+
+/// 紧邻在 429 之前、表明它是 HTTP 状态码的词
+const STATUS_WORDS: &[&str] = &["http", "status", "code", "状态码"];
+
+/// 判断错误信息是否为上游限流信号
+pub(crate) fn is_rate_limited(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    RATE_LIMIT_KEYWORDS.iter().any(|k| lower.contains(k)) || has_429_status(&lower)
+}
+
+/// 是否带有 HTTP 429 状态码（`HTTP/1.1 429`、`status: 429`、`状态码 429` 等）
+///
+/// 只认前面紧跟状态码标识的 429，响应正文、ID 等处恰好出现的数字不算限流。
+fn has_429_status(lower: &str) -> bool {
+    let tokens: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tokens.iter().enumerate().any(|(i, token)| {
+        // 跳过 `HTTP/1.1` 中的版本号
+        *token == "429"
+            && tokens[..i]
+                .iter()
+                .rev()
+                .find(|t| !t.chars().all(|c| c.is_ascii_digit()))
+                .is_some_and(|t| STATUS_WORDS.iter().any(|w| t.ends_with(w)))
+    })
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// 当前生效的并发上限（`None` = 不限制）
+    limit: Option<usize>,
+    /// 显式设置的上限，自动恢复时不超过该值
+    ceiling: Option<usize>,
+    /// 缩容时仍被占用、归还后需要作废的许可数
+    debt: usize,
+    /// 上次收紧后的连续成功次数
+    success_streak: usize,
+    /// 等待许可的排队者
+    waiters: Vec<Waiter>,
+    next_ticket: u64,
+}
+
+/// 一个等待许可的排队者
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    priority: u8,
+    since: Instant,
+}
+
+impl Waiter {
+    /// 有效优先级：基础优先级 + 等待时长补偿
+    fn effective_priority(&self, now: Instant) -> u128 {
+        self.priority as u128 + now.duration_since(self.since).as_millis() / AGING_STEP.as_millis()
+    }
+}
+
+/// 可动态调整许可数的并发限流器
+///
+/// - 扩容：先抵消未偿还的 `debt`，剩余部分通过 `add_permits` 追加
+/// - 缩容：立即作废空闲许可；已被占用的部分记入 `debt`，在执行结束归还时作废，
+///   因此不会中断正在执行的工具
+/// - 上限为 0 表示暂停：新的执行会一直等待，直到上限被调大
+/// - 许可紧张时按有效优先级排队：只有队首（优先级最高，同级先到先得）去争抢许可，
+///   等待越久有效优先级越高
+#[derive(Debug)]
+pub(crate) struct ConcurrencyLimiter {
+    semaphore: Semaphore,
+    state: Mutex<LimiterState>,
+    /// 队首离开时唤醒其余排队者
+    queue_changed: Notify,
+}
+
+/// 排队凭证，drop 时（含等待被取消）离开队列
+struct QueueTicket<'a> {
+    limiter: &'a ConcurrencyLimiter,
+    ticket: u64,
+}
+
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        self.limiter
+            .lock()
+            .waiters
+            .retain(|w| w.ticket != self.ticket);
+        self.limiter.queue_changed.notify_waiters();
+    }
+}
+
+/// 限流许可，drop 时若存在 `debt` 则作废而非归还
+pub(crate) struct LimiterPermit<'a> {
+    permit: Option<SemaphorePermit<'a>>,
+    limiter: &'a ConcurrencyLimiter,
+}
+
+impl Drop for LimiterPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            let mut state = self.limiter.lock();
+            if state.debt > 0 {
+                state.debt -= 1;
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl ConcurrencyLimiter {
+    /// `limit` 为 `None` 时不限制；构造时的上限至少为 1（与旧行为一致）
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        let limit = limit.map(|n| n.max(1));
+        Self {
+            semaphore: Semaphore::new(limit.unwrap_or(0)),
+            state: Mutex::new(LimiterState {
+                limit,
+                ceiling: limit,
+                debt: 0,
+                success_streak: 0,
+                waiters: Vec::new(),
+                next_ticket: 0,
+            }),
+            queue_changed: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 当前生效的并发上限
+    pub(crate) fn limit(&self) -> Option<usize> {
+        self.lock().limit
+    }
+
+    /// 显式设置的并发上限
+    pub(crate) fn ceiling(&self) -> Option<usize> {
+        self.lock().ceiling
+    }
+
+    /// 显式设置并发上限（同时作为自动恢复的上界）
+    pub(crate) fn set_limit(&self, n: usize) {
+        let mut state = self.lock();
+        state.ceiling = Some(n);
+        state.success_streak = 0;
+        self.resize(&mut state, n);
+    }
+
+    fn resize(&self, state: &mut LimiterState, n: usize) {
+        match state.limit {
+            None => self.semaphore.add_permits(n),
+            Some(current) if n > current => {
+                let grow = n - current;
+                let repaid = grow.min(state.debt);
+                state.debt -= repaid;
+                self.semaphore.add_permits(grow - repaid);
+            }
+            Some(current) => {
+                let shrink = current - n;
+                let forgotten = self.semaphore.forget_permits(shrink);
+                state.debt += shrink - forgotten;
+            }
+        }
+        state.limit = Some(n);
+    }
+
+    /// 获取执行许可；不限制时立即返回
+    ///
+    /// 有空闲许可且无人排队时直接获得；否则按 `priority`（越大越优先）排队。
+    pub(crate) async fn acquire(&self, priority: u8) -> Result<LimiterPermit<'_>, AcquireError> {
+        if self.limit().is_none() {
+            return Ok(LimiterPermit {
+                permit: None,
+                limiter: self,
+            });
+        }
+        let ticket = {
+            let mut state = self.lock();
+            if state.waiters.is_empty()
+                && let Ok(permit) = self.semaphore.try_acquire()
+            {
+                return Ok(LimiterPermit {
+                    permit: Some(permit),
+                    limiter: self,
+                });
+            }
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiters.push(Waiter {
+                ticket,
+                priority,
+                since: Instant::now(),
+            });
+            QueueTicket {
+                limiter: self,
+                ticket,
+            }
+        };
+        // 新来者可能优先级更高，让当前队首重新比较
+        self.queue_changed.notify_waiters();
+
+        loop {
+            // 先登记唤醒再检查，避免检查与等待之间的队列变化被错过
+            let notified = self.queue_changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            // 有效优先级随时间变化，除队列变化外还需定期重新比较
+            let recheck = tokio::time::sleep(AGING_STEP);
+            if !self.is_head(ticket.ticket) {
+                tokio::select! {
+                    _ = notified => {}
+                    _ = recheck => {}
+                }
+                continue;
+            }
+            // 只有队首争抢许可；被插队时放弃本次等待（信号量等待可安全取消）
+            tokio::select! {
+                permit = self.semaphore.acquire() => {
+                    let permit = permit?;
+                    // 被插队后尚未撤回的等待仍可能分到许可，交还给新的队首
+                    if !self.is_head(ticket.ticket) {
+                        drop(permit);
+                        continue;
+                    }
+                    drop(ticket);
+                    return Ok(LimiterPermit {
+                        permit: Some(permit),
+                        limiter: self,
+                    });
+                }
+                _ = notified => {}
+                _ = recheck => {}
+            }
+        }
+    }
+
+    /// `ticket` 是否为当前有效优先级最高的排队者（同级时先到先得）
+    fn is_head(&self, ticket: u64) -> bool {
+        let now = Instant::now();
+        self.lock()
+            .waiters
+            .iter()
+            .max_by(|a, b| {
+                a.effective_priority(now)
+                    .cmp(&b.effective_priority(now))
+                    .then(b.ticket.cmp(&a.ticket))
+            })
+            .is_some_and(|head| head.ticket == ticket)
+    }
+
+    /// 收到限流信号：并发上限减半（最低 1），返回调整后的上限；暂停中（上限为 0）保持暂停
+    pub(crate) fn backoff(&self) -> Option<usize> {
+        let mut state = self.lock();
+        state.success_streak = 0;
+        let current = state.limit?;
+        let target = (current / 2).max(1).min(current);
+        if target < current {
+            self.resize(&mut state, target);
+        }
+        Some(target)
+    }
+
+    /// 记录一次成功执行；连续成功足够多次后放宽一档，直到恢复到显式设置的上限
+    pub(crate) fn record_success(&self) {
+        let mut state = self.lock();
+        let (Some(current), Some(ceiling)) = (state.limit, state.ceiling) else {
+            return;
+        };
+        if current >= ceiling || current == 0 {
+            return;
+        }
+        state.success_streak += 1;
+        if state.success_streak >= RECOVER_AFTER_SUCCESSES {
+            state.success_streak = 0;
+            self.resize(&mut state, current + 1);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! 工具输出的编码检测与转换
+//!
+//! 子进程输出或文件内容不一定是 UTF-8（如 Windows 中文环境下的 GBK）。
+//! [`decode_output`] 先按 UTF-8 解析，失败时检测实际编码并转换为 UTF-8；
+//! 逐行处理时用 [`detect_encoding`] 按开头样本确定一次编码；
+//! 无法确定编码时回退到有损解码，并在文本末尾标注，避免静默产生乱码。
+//!
+//! ```rust
+//! use echo_agent::tools::encoding::{Encoding, decode_output};
+//!
+//! let gbk = Encoding::for_label(b"gbk").unwrap();
+//! // "你好" 的 GBK 字节
+//! assert_eq!(decode_output(&[0xC4, 0xE3, 0xBA, 0xC3], Some(gbk)), "你好");
+//! ```
+
+pub use encoding_rs::Encoding;
+
+use chardetng::EncodingDetector;
+use encoding_rs::UTF_8;
+use std::borrow::Cow;
+use tracing::debug;
+
+/// 把输出字节解码为 UTF-8 文本
+///
+/// `encoding` 为 `Some` 时按指定编码解码；为 `None` 时先尝试 UTF-8，失败再自动检测编码。
+/// 解码中出现无法识别的字节时，结果末尾附带提示。
+pub fn decode_output<'a>(bytes: &'a [u8], encoding: Option<&'static Encoding>) -> Cow<'a, str> {
+    if let Some(encoding) = encoding {
+        return decode_marked(bytes, encoding);
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    let guessed = detect(bytes);
+    if guessed != UTF_8 {
+        let (text, had_errors) = guessed.decode_without_bom_handling(bytes);
+        if !had_errors {
+            debug!(
+                encoding = guessed.name(),
+                "🔤 工具输出已从非 UTF-8 编码转换"
+            );
+            return text;
+        }
+    }
+    // 检测结果也无法完整解码：回退到 UTF-8 有损解码
+    decode_marked(bytes, UTF_8)
+}
+
+/// 由样本字节确定整段内容的编码
+///
+/// 样本是合法 UTF-8（允许末尾被截断的多字节字符）时返回 UTF-8，否则按字节分布检测。
+/// 逐行解码文件或流式输出时先用开头的样本确定一次编码，再以
+/// `decode_output(line, Some(encoding))` 解码各行；短行单独检测容易误判。
+pub fn detect_encoding(sample: &[u8]) -> &'static Encoding {
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => detect(sample),
+    }
+}
+
+/// 按指定编码解码，出现无法解码的字节时替换为 � 并在末尾标注
+fn decode_marked<'a>(bytes: &'a [u8], encoding: &'static Encoding) -> Cow<'a, str> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if !had_errors {
+        return text;
+    }
+    Cow::Owned(format!(
+        "{text}\n[⚠️ 输出含有无法按 {} 解码的字节，已替换为 �]",
+        encoding.name()
+    ))
+}
+
+/// 按字节分布猜测编码（不会返回 UTF-8 以外的 Unicode 编码）
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_output_gbk() {
+        // "编码测试：中文输出" 的 GBK 字节
+        let gbk: &[u8] = &[
+            0xB1, 0xE0, 0xC2, 0xEB, 0xB2, 0xE2, 0xCA, 0xD4, 0xA3, 0xBA, 0xD6, 0xD0, 0xCE, 0xC4,
+            0xCA, 0xE4, 0xB3, 0xF6,
+        ];
+        let explicit = decode_output(gbk, Encoding::for_label(b"gbk"));
+        assert_eq!(explicit, "编码测试：中文输出");
+        assert_eq!(decode_output(gbk, None), "编码测试：中文输出");
+
+        assert!(matches!(
+            decode_output("纯文本".as_bytes(), None),
+            Cow::Borrowed("纯文本")
+        ));
+
+        // 样本检测：截断在多字节字符中间的 UTF-8 仍视为 UTF-8
+        assert_eq!(detect_encoding(&"中文".as_bytes()[..4]), UTF_8);
+        assert_eq!(detect_encoding(gbk).name(), "GBK");
+
+        // 无法按指定编码解码的字节：替换并标注
+        let lossy = decode_output(&[0x61, 0xFF, 0x62], Some(UTF_8));
+        assert!(lossy.starts_with("a\u{FFFD}b"), "{lossy}");
+        assert!(lossy.contains("无法按 UTF-8 解码"), "{lossy}");
+    }
+}
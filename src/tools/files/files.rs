@@ -1,10 +1,28 @@
 use crate::error::ToolError;
 use crate::prelude::{Tool, ToolParameters, ToolResult};
+use crate::tools::ToolQuota;
+use crate::tools::encoding::{decode_output, detect_encoding};
 use crate::tools::files::resolve_path;
 use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde_json::{Value, json};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
+/// 生成操作预览用的路径：越界路径原样展示并标注（执行时仍会被拒绝）
+fn preview_path(
+    tool: &str,
+    params: &ToolParameters,
+    key: &str,
+    base_dir: &Option<PathBuf>,
+) -> Option<String> {
+    let raw = params.get(key)?.as_str()?;
+    Some(match resolve_path(tool, raw, base_dir) {
+        Ok(path) => path.display().to_string(),
+        Err(_) => format!("{}（超出允许的目录范围，将被拒绝）", raw),
+    })
+}
+
 // ── CreateFileTool ────────────────────────────────────────────────────────────
 pub struct CreateFileTool {
     base_dir: Option<PathBuf>,
@@ -45,6 +63,15 @@ impl Tool for CreateFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("create_file", params, "path", &self.base_dir)?;
+        Some(format!("将创建空文件 {}", path))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let path_str = parameters
             .get("path")
@@ -121,6 +148,15 @@ impl Tool for DeleteFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("delete_file", params, "path", &self.base_dir)?;
+        Some(format!("将删除文件 {}（不可恢复）", path))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let path_str = parameters
             .get("path")
@@ -151,21 +187,79 @@ impl Tool for DeleteFileTool {
 }
 
 // ── ReadFileTool ──────────────────────────────────────────────────────────────
-/// 读取文件内容
+
+/// 分页读取时未指定 `max_lines` 的默认行数
+const READ_DEFAULT_PAGE_LINES: usize = 200;
+/// 分页读取时用于确定文件编码的开头样本字节数
+const ENCODING_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// 读取文件内容；设置了单文件读取上限时，超大文件需按行分页读取
 pub struct ReadFileTool {
     base_dir: Option<PathBuf>,
+    max_read_bytes: Option<usize>,
 }
 
 impl ReadFileTool {
     pub fn new() -> Self {
-        Self { base_dir: None }
+        Self {
+            base_dir: None,
+            max_read_bytes: None,
+        }
     }
 
     pub fn with_base_dir(base: impl Into<PathBuf>) -> Self {
         Self {
             base_dir: Some(base.into()),
+            max_read_bytes: None,
         }
     }
+
+    /// 单文件最大读取字节数：整文件读取超过时拒绝，分页读取时单页不超过该值
+    pub fn with_max_read_bytes(mut self, limit: usize) -> Self {
+        self.max_read_bytes = Some(limit);
+        self
+    }
+
+    /// 从第 `start_line` 行（从 1 计数）起最多读取 `max_lines` 行，单页不超过读取上限
+    async fn read_page(
+        &self,
+        path: &Path,
+        start_line: usize,
+        max_lines: usize,
+    ) -> std::io::Result<String> {
+        use tokio::io::AsyncBufReadExt;
+
+        let file = fs::File::open(path).await?;
+        let mut reader = tokio::io::BufReader::with_capacity(ENCODING_SAMPLE_BYTES, file);
+        // 按文件开头的样本确定一次编码，各行沿用，避免短行单独检测误判
+        let encoding = detect_encoding(reader.fill_buf().await?);
+        let mut lines = reader.split(b'\n');
+        let mut line_no = 0;
+        let mut page = String::new();
+        let mut read = 0;
+        while let Some(line) = lines.next_segment().await? {
+            line_no += 1;
+            if line_no < start_line {
+                continue;
+            }
+            let line = line.strip_suffix(b"\r").unwrap_or(&line);
+            let line = decode_output(line, Some(encoding));
+            let exceeds = self
+                .max_read_bytes
+                .is_some_and(|limit| read > 0 && page.len() + line.len() + 1 > limit);
+            if read == max_lines || exceeds {
+                page.push_str(&format!(
+                    "…[第 {start_line}-{} 行，文件未读完，可用 start_line={line_no} 继续读取]",
+                    line_no - 1
+                ));
+                return Ok(page);
+            }
+            page.push_str(&line);
+            page.push('\n');
+            read += 1;
+        }
+        Ok(page)
+    }
 }
 
 #[async_trait]
@@ -185,12 +279,38 @@ impl Tool for ReadFileTool {
                 "path": {
                     "type": "string",
                     "description": "要读取的文件路径（相对路径或绝对路径）"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "分页读取的起始行（从 1 计数），用于读取超大文件"
+                },
+                "max_lines": {
+                    "type": "integer",
+                    "description": format!("分页读取的最大行数，默认 {READ_DEFAULT_PAGE_LINES}")
                 }
             },
             "required": ["path"]
         })
     }
 
+    fn apply_quota(&mut self, quota: &ToolQuota) {
+        if let Some(limit) = quota.max_read_bytes {
+            self.max_read_bytes = Some(limit);
+        }
+    }
+
+    fn supports_batch(&self) -> bool {
+        true
+    }
+
+    /// 同一轮的多个读文件请求并发读取
+    async fn batch_execute(
+        &self,
+        params_list: Vec<ToolParameters>,
+    ) -> Vec<crate::error::Result<ToolResult>> {
+        futures::future::join_all(params_list.into_iter().map(|params| self.execute(params))).await
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let path_str = parameters
             .get("path")
@@ -206,15 +326,42 @@ impl Tool for ReadFileTool {
             return Ok(ToolResult::error(format!("'{}' 不是文件", path.display())));
         }
 
-        let content =
-            tokio::fs::read_to_string(&path)
+        let start_line = parameters.get("start_line").and_then(|v| v.as_u64());
+        let max_lines = parameters.get("max_lines").and_then(|v| v.as_u64());
+        if start_line.is_some() || max_lines.is_some() {
+            let start_line = start_line.unwrap_or(1).max(1) as usize;
+            let max_lines = max_lines.map_or(READ_DEFAULT_PAGE_LINES, |n| n.max(1) as usize);
+            let page = self
+                .read_page(&path, start_line, max_lines)
                 .await
                 .map_err(|e| ToolError::ExecutionFailed {
                     tool: "read_file".to_string(),
                     message: format!("读取失败: {}", e),
                 })?;
+            return Ok(ToolResult::success(page));
+        }
+
+        if let Some(limit) = self.max_read_bytes {
+            let size = fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+            if size > limit as u64 {
+                return Ok(ToolResult::error(format!(
+                    "文件 {} 大小为 {size} 字节，超过单文件读取上限 {limit} 字节，已拒绝读取。\
+                     请使用 start_line / max_lines 参数分页读取",
+                    path.display()
+                )));
+            }
+        }
+
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed {
+                tool: "read_file".to_string(),
+                message: format!("读取失败: {}", e),
+            })?;
 
-        Ok(ToolResult::success(content))
+        Ok(ToolResult::success(
+            decode_output(&bytes, None).into_owned(),
+        ))
     }
 }
 
@@ -264,6 +411,27 @@ impl Tool for WriteFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("write_file", params, "path", &self.base_dir)?;
+        let content = params.get("content")?.as_str()?;
+        let effect = if Path::new(&path).exists() {
+            "覆盖现有内容"
+        } else {
+            "新建文件"
+        };
+        Some(format!(
+            "将向 {} 写入 {} 字节（{} 行），{}",
+            path,
+            content.len(),
+            content.lines().count(),
+            effect
+        ))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let path_str = parameters
             .get("path")
@@ -288,6 +456,7 @@ impl Tool for WriteFileTool {
         }
 
         let bytes = content.len();
+        let overwritten = tokio::fs::try_exists(&path).await.unwrap_or(false);
         tokio::fs::write(&path, content)
             .await
             .map_err(|e| ToolError::ExecutionFailed {
@@ -295,11 +464,18 @@ impl Tool for WriteFileTool {
                 message: format!("写入失败: {}", e),
             })?;
 
-        Ok(ToolResult::success(format!(
-            "已成功写入 {} 字节到 '{}'",
-            bytes,
-            path.display()
-        )))
+        let output = format!("已成功写入 {} 字节到 '{}'", bytes, path.display());
+        if overwritten {
+            Ok(ToolResult::success_with_warnings(
+                output,
+                [format!(
+                    "已覆盖已存在的文件 '{}'，原内容丢失",
+                    path.display()
+                )],
+            ))
+        } else {
+            Ok(ToolResult::success(output))
+        }
     }
 }
 
@@ -349,6 +525,16 @@ impl Tool for AppendFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("append_file", params, "path", &self.base_dir)?;
+        let content = params.get("content")?.as_str()?;
+        Some(format!("将在 {} 末尾追加 {} 字节", path, content.len()))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         use tokio::io::AsyncWriteExt;
 
@@ -448,6 +634,22 @@ impl Tool for UpdateFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("update_file", params, "path", &self.base_dir)?;
+        let old_content = params.get("old_content")?.as_str()?;
+        let new_content = params.get("new_content")?.as_str()?;
+        Some(format!(
+            "将修改 {}：把首个匹配的 {} 字节片段替换为 {} 字节新内容",
+            path,
+            old_content.len(),
+            new_content.len()
+        ))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let path_str = parameters
             .get("path")
@@ -543,6 +745,16 @@ impl Tool for MoveFileTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let old_path = preview_path("move_file", params, "old_path", &self.base_dir)?;
+        let new_path = preview_path("move_file", params, "new_path", &self.base_dir)?;
+        Some(format!("将把文件 {} 移动到 {}", old_path, new_path))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
         let old_path_str = parameters
             .get("old_path")
@@ -723,3 +935,812 @@ impl Tool for ListDirTool {
         Ok(ToolResult::success(output))
     }
 }
+
+// ── ReadGlobTool ──────────────────────────────────────────────────────────────
+
+/// 默认最多读取的文件数
+const GLOB_DEFAULT_MAX_FILES: usize = 20;
+/// 默认单文件最多返回的字节数
+const GLOB_DEFAULT_MAX_BYTES_PER_FILE: usize = 32 * 1024;
+/// 单次调用返回内容的总字节上限
+const GLOB_MAX_TOTAL_BYTES: usize = 256 * 1024;
+
+/// 按 glob 模式批量读取文件内容（如 `src/**/*.rs`），每个匹配文件都必须位于 base_dir 内
+///
+/// - base_dir 按字面匹配，其中的 `[`、`*`、`?` 不会被当作通配符
+/// - 匹配到 base_dir 之外的文件（`..`、指向外部的软链接）时整体报错，不返回部分内容
+/// - 边遍历边读取，达到文件数或总字节上限即停止，之后的匹配不再遍历
+pub struct ReadGlobTool {
+    base_dir: Option<PathBuf>,
+}
+
+impl ReadGlobTool {
+    pub fn new() -> Self {
+        Self { base_dir: None }
+    }
+
+    pub fn with_base_dir(base: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: Some(base.into()),
+        }
+    }
+}
+
+impl Default for ReadGlobTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 按字节截断字符串，保证不切断 UTF-8 字符
+fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[async_trait]
+impl Tool for ReadGlobTool {
+    fn name(&self) -> &str {
+        "read_glob"
+    }
+
+    fn description(&self) -> &str {
+        "按 glob 模式（如 src/**/*.rs）一次读取所有匹配文件的内容，每个文件以文件名分隔"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "glob 模式，支持 *、?、** 和 [...]，如 src/**/*.rs"
+                },
+                "max_files": {
+                    "type": "integer",
+                    "description": "最多读取的文件数，默认 20"
+                },
+                "max_bytes_per_file": {
+                    "type": "integer",
+                    "description": "单个文件最多返回的字节数，默认 32768"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let pattern = parameters
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::MissingParameter("pattern".to_string()))?;
+        let max_files = parameters
+            .get("max_files")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(GLOB_DEFAULT_MAX_FILES);
+        let max_bytes_per_file = parameters
+            .get("max_bytes_per_file")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(GLOB_DEFAULT_MAX_BYTES_PER_FILE);
+
+        // base_dir 中的 `[`、`*`、`?` 按字面匹配，只有调用方给出的 pattern 部分参与通配
+        let full_pattern = match &self.base_dir {
+            Some(base) if !std::path::Path::new(pattern).is_absolute() => {
+                Path::new(&glob::Pattern::escape(&base.to_string_lossy()))
+                    .join(pattern)
+                    .to_string_lossy()
+                    .to_string()
+            }
+            _ => pattern.to_string(),
+        };
+
+        let entries = glob::glob(&full_pattern).map_err(|e| ToolError::InvalidParameter {
+            name: "pattern".to_string(),
+            message: e.to_string(),
+        })?;
+
+        // 边遍历边读取，达到文件数或总字节上限即停止，不会先遍历整棵目录树
+        let mut output = String::new();
+        let mut read_count = 0;
+        let mut notice = None;
+        for entry in entries.flatten().filter(|entry| entry.is_file()) {
+            if read_count == max_files {
+                notice = Some(format!(
+                    "匹配文件超过 {max_files} 个，仅读取前 {max_files} 个"
+                ));
+                break;
+            }
+            if output.len() >= GLOB_MAX_TOTAL_BYTES {
+                notice = Some(format!(
+                    "总内容已达 {} 字节上限，已读取 {} 个文件，其余匹配文件未读取",
+                    GLOB_MAX_TOTAL_BYTES, read_count
+                ));
+                break;
+            }
+            // 每个匹配项都经 resolve_path 校验：落在 base_dir 之外（`..`、指向外部的软链接）即整体报错
+            let path = resolve_path("read_glob", &entry.to_string_lossy(), &self.base_dir)?;
+            read_count += 1;
+
+            output.push_str(&format!("===== {} =====\n", path.display()));
+
+            let content = match fs::read(&path).await {
+                Ok(bytes) => decode_output(&bytes, None).into_owned(),
+                Err(e) => {
+                    output.push_str(&format!("[读取失败: {}]\n\n", e));
+                    continue;
+                }
+            };
+
+            let budget = max_bytes_per_file.min(GLOB_MAX_TOTAL_BYTES.saturating_sub(output.len()));
+            let shown = truncate_utf8(&content, budget);
+            output.push_str(shown);
+            if shown.len() < content.len() {
+                output.push_str(&format!(
+                    "\n[已截断：显示 {} / {} 字节]",
+                    shown.len(),
+                    content.len()
+                ));
+            }
+            output.push_str("\n\n");
+        }
+
+        if read_count == 0 {
+            return Ok(ToolResult::success(format!(
+                "没有文件匹配模式 '{}'",
+                pattern
+            )));
+        }
+        if let Some(notice) = notice {
+            output.push_str(&format!("⚠️ {}\n", notice));
+        }
+
+        Ok(ToolResult::success(output.trim_end().to_string()))
+    }
+}
+
+// ── ReadBinaryTool ────────────────────────────────────────────────────────────
+
+/// 二进制读写工具默认的单文件大小上限（5 MiB，base64 编码后约 6.7 MiB）
+pub const DEFAULT_MAX_BINARY_BYTES: usize = 5 * 1024 * 1024;
+
+/// 按文件头魔数推测 MIME 类型，无法识别时按扩展名推测
+fn guess_mime(path: &Path, bytes: &[u8]) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return mime;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "json" => "application/json",
+        "txt" | "md" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 读取二进制文件（图片、PDF 等），返回 base64 编码的内容、字节数与推测的 MIME 类型
+pub struct ReadBinaryTool {
+    base_dir: Option<PathBuf>,
+    max_bytes: usize,
+}
+
+impl ReadBinaryTool {
+    pub fn new() -> Self {
+        Self {
+            base_dir: None,
+            max_bytes: DEFAULT_MAX_BINARY_BYTES,
+        }
+    }
+
+    pub fn with_base_dir(base: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: Some(base.into()),
+            max_bytes: DEFAULT_MAX_BINARY_BYTES,
+        }
+    }
+
+    /// 单文件最大读取字节数（编码前），超过时拒绝读取
+    ///
+    /// 与文本读取配额 [`ToolQuota::max_read_bytes`] 相互独立，默认 [`DEFAULT_MAX_BINARY_BYTES`]。
+    pub fn with_max_bytes(mut self, limit: usize) -> Self {
+        self.max_bytes = limit;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for ReadBinaryTool {
+    fn name(&self) -> &str {
+        "read_binary"
+    }
+
+    fn description(&self) -> &str {
+        "读取二进制文件（图片、PDF 等），返回 JSON：base64 编码的内容、字节数 size 与推测的 MIME 类型 mime"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "要读取的文件路径（相对路径或绝对路径）"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let path_str = parameters
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::MissingParameter("path".to_string()))?;
+
+        let path = resolve_path("read_binary", path_str, &self.base_dir)?;
+
+        if !path.is_file() {
+            return Ok(ToolResult::error(format!(
+                "文件不存在或不是文件: {}",
+                path.display()
+            )));
+        }
+        let size = fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+        if size > self.max_bytes as u64 {
+            return Ok(ToolResult::error(format!(
+                "文件 {} 大小为 {size} 字节，超过二进制读取上限 {} 字节，已拒绝读取",
+                path.display(),
+                self.max_bytes
+            )));
+        }
+
+        let bytes = fs::read(&path)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed {
+                tool: "read_binary".to_string(),
+                message: format!("读取失败: {}", e),
+            })?;
+
+        Ok(ToolResult::success(
+            json!({
+                "path": path.display().to_string(),
+                "size": bytes.len(),
+                "mime": guess_mime(&path, &bytes),
+                "base64": BASE64.encode(&bytes),
+            })
+            .to_string(),
+        ))
+    }
+}
+
+// ── WriteBinaryTool ───────────────────────────────────────────────────────────
+
+/// 把 base64 编码的内容解码后写入（覆盖）文件，若目录不存在则自动创建
+pub struct WriteBinaryTool {
+    base_dir: Option<PathBuf>,
+    max_bytes: usize,
+}
+
+impl WriteBinaryTool {
+    pub fn new() -> Self {
+        Self {
+            base_dir: None,
+            max_bytes: DEFAULT_MAX_BINARY_BYTES,
+        }
+    }
+
+    pub fn with_base_dir(base: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: Some(base.into()),
+            max_bytes: DEFAULT_MAX_BINARY_BYTES,
+        }
+    }
+
+    /// 单文件最大写入字节数（解码后），超过时拒绝写入
+    pub fn with_max_bytes(mut self, limit: usize) -> Self {
+        self.max_bytes = limit;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for WriteBinaryTool {
+    fn name(&self) -> &str {
+        "write_binary"
+    }
+
+    fn description(&self) -> &str {
+        "把 base64 编码的二进制内容解码后写入指定路径的文件（覆盖写），若目录不存在则自动创建"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "要写入的文件路径"
+                },
+                "base64": {
+                    "type": "string",
+                    "description": "标准 base64 编码的文件内容"
+                }
+            },
+            "required": ["path", "base64"]
+        })
+    }
+
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let path = preview_path("write_binary", params, "path", &self.base_dir)?;
+        let encoded = params.get("base64")?.as_str()?;
+        let effect = if Path::new(&path).exists() {
+            "覆盖现有内容"
+        } else {
+            "新建文件"
+        };
+        Some(format!(
+            "将向 {} 写入约 {} 字节的二进制内容，{}",
+            path,
+            encoded.trim().len() / 4 * 3,
+            effect
+        ))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> crate::error::Result<ToolResult> {
+        let path_str = parameters
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::MissingParameter("path".to_string()))?;
+        let encoded = parameters
+            .get("base64")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::MissingParameter("base64".to_string()))?
+            .trim();
+
+        let path = resolve_path("write_binary", path_str, &self.base_dir)?;
+
+        // 解码前按编码长度粗判，避免为超大输入分配内存
+        if encoded.len() / 4 * 3 > self.max_bytes + 2 {
+            return Ok(ToolResult::error(format!(
+                "内容解码后超过二进制写入上限 {} 字节，已拒绝写入",
+                self.max_bytes
+            )));
+        }
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|e| ToolError::InvalidParameter {
+                name: "base64".to_string(),
+                message: format!("base64 解码失败: {}", e),
+            })?;
+        if bytes.len() > self.max_bytes {
+            return Ok(ToolResult::error(format!(
+                "内容为 {} 字节，超过二进制写入上限 {} 字节，已拒绝写入",
+                bytes.len(),
+                self.max_bytes
+            )));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed {
+                    tool: "write_binary".to_string(),
+                    message: format!("创建目录失败: {}", e),
+                })?;
+        }
+        fs::write(&path, &bytes)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed {
+                tool: "write_binary".to_string(),
+                message: format!("写入失败: {}", e),
+            })?;
+
+        Ok(ToolResult::success(format!(
+            "已成功写入 {} 字节到 '{}'",
+            bytes.len(),
+            path.display()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn params(pairs: &[(&str, Value)]) -> ToolParameters {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<HashMap<_, _>>()
+    }
+
+    fn temp_tree() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("echo_glob_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub mod nested;").unwrap();
+        std::fs::write(root.join("src/nested/mod.rs"), "pub fn hello() {}").unwrap();
+        std::fs::write(root.join("src/notes.txt"), "not rust").unwrap();
+        std::fs::write(root.join("secret.txt"), "outside").unwrap();
+        root
+    }
+
+    #[tokio::test]
+    async fn test_read_file_rejects_file_over_quota() {
+        let root = temp_tree();
+        let big: String = (1..=100).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(root.join("big.log"), &big).unwrap();
+
+        let mut tool = ReadFileTool::with_base_dir(&root);
+        tool.apply_quota(&ToolQuota {
+            max_read_bytes: Some(64),
+            ..Default::default()
+        });
+
+        let result = tool
+            .execute(params(&[("path", json!("big.log"))]))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("start_line / max_lines"));
+
+        // 小文件不受影响
+        let ok = tool
+            .execute(params(&[("path", json!("src/lib.rs"))]))
+            .await
+            .unwrap();
+        assert_eq!(ok.output, "pub mod nested;");
+
+        // 分页读取：按行数截止，提示下一页起点
+        let page = tool
+            .execute(params(&[
+                ("path", json!("big.log")),
+                ("start_line", json!(10)),
+                ("max_lines", json!(3)),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            page.output,
+            "line 10\nline 11\nline 12\n…[第 10-12 行，文件未读完，可用 start_line=13 继续读取]"
+        );
+
+        // 单页也不超过读取上限
+        let page = tool
+            .execute(params(&[
+                ("path", json!("big.log")),
+                ("start_line", json!(1)),
+            ]))
+            .await
+            .unwrap();
+        assert!(page.output.len() < 64 + 80);
+        assert!(page.output.ends_with("继续读取]"));
+    }
+
+    #[tokio::test]
+    async fn test_read_page_detects_encoding_once_per_file() {
+        let root = temp_tree();
+        let gbk = crate::tools::encoding::Encoding::for_label(b"gbk").unwrap();
+        let text = "第一行：编码测试中文输出\n是\n好\n";
+        std::fs::write(root.join("gbk.txt"), gbk.encode(text).0).unwrap();
+
+        // 单独检测时极短的行容易被误判，按整个文件确定的编码逐行解码
+        let tool = ReadFileTool::with_base_dir(&root);
+        let page = tool
+            .execute(params(&[
+                ("path", json!("gbk.txt")),
+                ("start_line", json!(2)),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(page.output, "是\n好\n");
+    }
+
+    #[tokio::test]
+    async fn test_write_binary_then_read_back() {
+        let root = temp_tree();
+        let bytes: Vec<u8> = b"\x89PNG\r\n\x1a\n"
+            .iter()
+            .copied()
+            .chain(0..=255)
+            .collect();
+        let encoded = BASE64.encode(&bytes);
+
+        let written = WriteBinaryTool::with_base_dir(&root)
+            .execute(params(&[
+                ("path", json!("img/logo.png")),
+                ("base64", json!(encoded)),
+            ]))
+            .await
+            .unwrap();
+        assert!(written.success, "{:?}", written.error);
+        assert_eq!(std::fs::read(root.join("img/logo.png")).unwrap(), bytes);
+
+        let read = ReadBinaryTool::with_base_dir(&root)
+            .execute(params(&[("path", json!("img/logo.png"))]))
+            .await
+            .unwrap();
+        let read: Value = serde_json::from_str(&read.output).unwrap();
+        assert_eq!(read["base64"], encoded);
+        assert_eq!(read["size"], bytes.len());
+        assert_eq!(read["mime"], "image/png");
+
+        // 解码失败、越界与超限均明确拒绝
+        let bad = WriteBinaryTool::with_base_dir(&root)
+            .execute(params(&[
+                ("path", json!("x.bin")),
+                ("base64", json!("不是base64")),
+            ]))
+            .await;
+        assert!(bad.unwrap_err().to_string().contains("base64 解码失败"));
+        let escape = ReadBinaryTool::with_base_dir(&root)
+            .execute(params(&[("path", json!("../etc/passwd"))]))
+            .await;
+        assert!(escape.is_err());
+        let too_big = ReadBinaryTool::with_base_dir(&root)
+            .with_max_bytes(16)
+            .execute(params(&[("path", json!("img/logo.png"))]))
+            .await
+            .unwrap();
+        assert!(!too_big.success);
+
+        // 文本读取配额不影响二进制读取
+        let mut quota_limited = ReadBinaryTool::with_base_dir(&root);
+        quota_limited.apply_quota(&ToolQuota {
+            max_read_bytes: Some(16),
+            ..Default::default()
+        });
+        let read = quota_limited
+            .execute(params(&[("path", json!("img/logo.png"))]))
+            .await
+            .unwrap();
+        assert!(read.success, "{:?}", read.error);
+    }
+
+    #[test]
+    fn test_write_file_preview() {
+        let root = temp_tree();
+        let tool = WriteFileTool::with_base_dir(&root);
+
+        let preview = tool
+            .preview(&params(&[
+                ("path", json!("secret.txt")),
+                ("content", json!("line1\nline2\n")),
+            ]))
+            .unwrap();
+        assert_eq!(
+            preview,
+            format!(
+                "将向 {} 写入 12 字节（2 行），覆盖现有内容",
+                root.join("secret.txt").display()
+            )
+        );
+
+        let preview = tool
+            .preview(&params(&[
+                ("path", json!("new.txt")),
+                ("content", json!("x")),
+            ]))
+            .unwrap();
+        assert!(preview.ends_with("写入 1 字节（1 行），新建文件"));
+
+        let escaped = tool
+            .preview(&params(&[
+                ("path", json!("../a.txt")),
+                ("content", json!("")),
+            ]))
+            .unwrap();
+        assert!(escaped.contains("将被拒绝"));
+
+        // 缺少参数时无法生成预览，回退到 JSON 展示
+        assert!(tool.preview(&params(&[("path", json!("a.txt"))])).is_none());
+
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_escape_is_rejected() {
+        use std::os::unix::fs::symlink;
+
+        let root = temp_tree();
+        let outside = std::env::temp_dir().join(format!("echo_outside_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("passwd"), "root:x:0:0").unwrap();
+        symlink(&outside, root.join("escape")).unwrap();
+        symlink(outside.join("missing.txt"), root.join("dangling")).unwrap();
+        symlink(root.join("src"), root.join("src_link")).unwrap();
+
+        let read = ReadFileTool::with_base_dir(&root);
+        let err = read
+            .execute(params(&[("path", json!("escape/passwd"))]))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("经符号链接解析后超出允许的目录范围")
+        );
+
+        // 尚不存在的路径按最深的已存在祖先校验
+        let write = WriteFileTool::with_base_dir(&root);
+        assert!(
+            write
+                .execute(params(&[
+                    ("path", json!("escape/new/evil.txt")),
+                    ("content", json!("x")),
+                ]))
+                .await
+                .is_err()
+        );
+        // 悬空软链接：写入会落到外部目标，同样拒绝
+        assert!(
+            write
+                .execute(params(&[
+                    ("path", json!("dangling")),
+                    ("content", json!("x"))
+                ]))
+                .await
+                .is_err()
+        );
+        assert!(!outside.join("missing.txt").exists());
+        assert!(!outside.join("new").exists());
+
+        // 指向 base_dir 内部的软链接照常可用
+        let inside = read
+            .execute(params(&[("path", json!("src_link/lib.rs"))]))
+            .await
+            .unwrap();
+        assert!(inside.success);
+
+        std::fs::remove_dir_all(root).ok();
+        std::fs::remove_dir_all(outside).ok();
+    }
+
+    #[tokio::test]
+    async fn test_write_file_warns_on_overwrite() {
+        let root = temp_tree();
+        let tool = WriteFileTool::with_base_dir(&root);
+
+        let created = tool
+            .execute(params(&[
+                ("path", json!("new.txt")),
+                ("content", json!("x")),
+            ]))
+            .await
+            .unwrap();
+        assert!(created.success && created.warnings.is_empty());
+
+        let overwritten = tool
+            .execute(params(&[
+                ("path", json!("secret.txt")),
+                ("content", json!("y")),
+            ]))
+            .await
+            .unwrap();
+        assert!(overwritten.success);
+        assert_eq!(overwritten.warnings.len(), 1);
+        assert!(overwritten.warnings[0].contains("已覆盖已存在的文件"));
+
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_read_glob_returns_all_matches() {
+        let root = temp_tree();
+        let tool = ReadGlobTool::with_base_dir(&root);
+
+        let result = tool
+            .execute(params(&[("pattern", json!("src/**/*.rs"))]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("src/lib.rs ====="));
+        assert!(result.output.contains("pub mod nested;"));
+        assert!(result.output.contains("src/nested/mod.rs ====="));
+        assert!(result.output.contains("pub fn hello() {}"));
+        assert!(!result.output.contains("not rust"));
+
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_read_glob_truncates_and_rejects_escape() {
+        let root = temp_tree();
+        let tool = ReadGlobTool::with_base_dir(root.join("src"));
+
+        let result = tool
+            .execute(params(&[
+                ("pattern", json!("**/*.rs")),
+                ("max_files", json!(1)),
+                ("max_bytes_per_file", json!(3)),
+            ]))
+            .await
+            .unwrap();
+        assert!(result.output.contains("pub\n[已截断：显示 3 / 15 字节]"));
+        assert!(result.output.contains("匹配文件超过 1 个，仅读取前 1 个"));
+
+        let escaped = tool
+            .execute(params(&[("pattern", json!("../*.txt"))]))
+            .await;
+        assert!(escaped.is_err());
+
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_read_glob_escapes_base_dir_and_stops_at_cap() {
+        let root = temp_tree();
+        // base_dir 名中的 `[1]` 按字面匹配，不会匹配到兄弟目录 dir1
+        std::fs::create_dir_all(root.join("dir[1]")).unwrap();
+        std::fs::create_dir_all(root.join("dir1")).unwrap();
+        std::fs::write(root.join("dir[1]/a.txt"), "inside").unwrap();
+        std::fs::write(root.join("dir[1]/b.txt"), "inside too").unwrap();
+        std::fs::write(root.join("dir1/a.txt"), "sibling").unwrap();
+        let tool = ReadGlobTool::with_base_dir(root.join("dir[1]"));
+
+        let result = tool
+            .execute(params(&[("pattern", json!("*.txt"))]))
+            .await
+            .unwrap();
+        assert!(result.output.contains("inside"));
+        assert!(!result.output.contains("sibling"));
+
+        // 位于 base_dir 之外的匹配（指向外部的软链接）整体报错
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret.txt"), root.join("dir[1]/z.txt")).unwrap();
+            let escaped = tool.execute(params(&[("pattern", json!("*.txt"))])).await;
+            assert!(escaped.is_err());
+
+            // 达到文件数上限后停止遍历，后面的匹配不再读取也不再校验
+            let capped = tool
+                .execute(params(&[
+                    ("pattern", json!("*.txt")),
+                    ("max_files", json!(2)),
+                ]))
+                .await
+                .unwrap();
+            assert!(capped.output.contains("匹配文件超过 2 个，仅读取前 2 个"));
+            assert!(!capped.output.contains("outside"));
+        }
+
+        std::fs::remove_dir_all(root).ok();
+    }
+}
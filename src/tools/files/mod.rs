@@ -10,6 +10,7 @@ use crate::error::{Result, ToolError};
 ///
 /// - 绝对路径：规范化后直接校验是否在 base_dir 内
 /// - 相对路径：以 base_dir 为根展开后校验
+/// - 字符串校验通过后再解析符号链接，见 [`check_real_path`]
 fn resolve_path(tool: &str, path_str: &str, base_dir: &Option<PathBuf>) -> Result<PathBuf> {
     let requested = Path::new(path_str);
 
@@ -30,6 +31,7 @@ fn resolve_path(tool: &str, path_str: &str, base_dir: &Option<PathBuf>) -> Resul
             }
             .into());
         }
+        check_real_path(tool, path_str, &normalized_base, &normalized)?;
         normalized
     } else {
         normalize_path(requested)
@@ -38,6 +40,32 @@ fn resolve_path(tool: &str, path_str: &str, base_dir: &Option<PathBuf>) -> Resul
     Ok(resolved)
 }
 
+/// 解析符号链接后再次校验路径仍在 base_dir 内，防止借助 base_dir 中指向外部的软链接逃逸
+///
+/// - 路径已存在：`canonicalize` 后与 canonicalize 后的 base_dir 比较
+/// - 路径尚不存在（写入 / 创建）：校验其已存在的最深一级祖先，其余部分在字符串校验时已消除 `..`
+/// - 已存在但无法解析（如指向不存在目标的悬空软链接，写入会落到链接目标）：一律拒绝
+/// - base_dir 本身不存在时其中不可能有软链接，只依赖字符串校验
+fn check_real_path(tool: &str, path_str: &str, base: &Path, path: &Path) -> Result<()> {
+    let Ok(real_base) = base.canonicalize() else {
+        return Ok(());
+    };
+    let Some(existing) = path
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_ok())
+    else {
+        return Ok(());
+    };
+    match existing.canonicalize() {
+        Ok(real) if real.starts_with(&real_base) => Ok(()),
+        _ => Err(ToolError::ExecutionFailed {
+            tool: tool.to_string(),
+            message: format!("路径 '{}' 经符号链接解析后超出允许的目录范围", path_str),
+        }
+        .into()),
+    }
+}
+
 /// 不依赖文件系统的路径规范化（消除 `.` 和 `..`）
 fn normalize_path(path: &Path) -> PathBuf {
     let mut components = Vec::new();
@@ -0,0 +1,58 @@
+//! 工具调用拦截器
+//!
+//! 通过 [`ToolManager::add_interceptor`](super::ToolManager::add_interceptor) 注册的
+//! [`ToolInterceptor`] 在工具真正执行前按注册顺序依次调用，可以统一改写参数（相对路径转绝对路径、
+//! 补充统一的请求头、把危险命令换成安全的等价命令等），也可以直接给出结果，跳过后续拦截器与真实执行。
+
+use super::{ToolParameters, ToolResult};
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// 拦截器的处理结果
+#[derive(Debug, Clone)]
+pub enum InterceptAction {
+    /// 继续执行，携带（可能已改写的）参数交给下一个拦截器或工具
+    Continue(ToolParameters),
+    /// 短路：直接以该结果作为本次调用的结果，不再调用后续拦截器，也不执行工具
+    Respond(ToolResult),
+}
+
+/// 工具调用拦截器
+///
+/// # 示例
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use echo_agent::error::Result;
+/// use echo_agent::tools::{InterceptAction, ToolInterceptor, ToolParameters, ToolResult};
+///
+/// /// 拒绝所有 `rm` 命令，其余命令统一加上超时
+/// struct ShellGuard;
+///
+/// #[async_trait]
+/// impl ToolInterceptor for ShellGuard {
+///     async fn intercept(&self, tool_name: &str, mut parameters: ToolParameters) -> Result<InterceptAction> {
+///         if tool_name != "shell" {
+///             return Ok(InterceptAction::Continue(parameters));
+///         }
+///         let command = parameters.get("command").and_then(|v| v.as_str()).unwrap_or_default();
+///         if command.starts_with("rm ") {
+///             return Ok(InterceptAction::Respond(ToolResult::error("禁止删除文件".to_string())));
+///         }
+///         let wrapped = format!("timeout 30 {command}");
+///         parameters.insert("command".to_string(), wrapped.into());
+///         Ok(InterceptAction::Continue(parameters))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ToolInterceptor: Send + Sync {
+    /// 处理一次工具调用，`tool_name` 为别名解析后的真实工具名
+    ///
+    /// 返回 `Err` 时本次调用以该错误结束，后续拦截器与工具都不会执行。
+    async fn intercept(
+        &self,
+        tool_name: &str,
+        parameters: ToolParameters,
+    ) -> Result<InterceptAction>;
+}
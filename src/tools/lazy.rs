@@ -0,0 +1,219 @@
+//! 延迟实例化的工具
+//!
+//! 需要建立连接、加载模型等开销较大的工具，可以包装为 [`LazyTool`]：注册时只提供名称、描述与参数
+//! schema，工具定义照常发给 LLM；直到 LLM 第一次真正调用时才通过工厂闭包创建真实工具并缓存复用。
+
+use super::{Randomness, Tool, ToolParameters, ToolQuota, ToolResult};
+use crate::error::{Result, ToolError};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 创建真实工具的工厂闭包
+pub type ToolFactory = Box<dyn Fn() -> Result<Box<dyn Tool>> + Send + Sync>;
+
+/// 首次执行时才通过工厂创建真实工具的代理工具
+///
+/// - 工厂只在首次使用时调用一次，之后复用缓存的实例；并发的首次调用也只创建一次。
+///   执行、参数校验（[`Tool::validate_parameters`]）与审批预览（[`Tool::preview`]）都算使用，
+///   因此需要审批的调用会在弹出审批前创建实例
+/// - 工厂是同步闭包，可以直接做阻塞的连接、加载：`execute` 中的首次创建放到
+///   [`tokio::task::spawn_blocking`] 的阻塞线程上，不占用异步 worker；同步的校验与预览在调用线程上创建
+/// - 工厂失败时本次调用返回 [`ToolError::ExecutionFailed`]，不缓存失败，下次调用会重新尝试
+/// - 注册时下发的配额与随机源在实例创建后转交给真实工具
+/// - 真实工具的 `supports_batch`、`streams_output` 等能力声明在实例化前不可知，因此不生效；
+///   副作用需通过 [`with_side_effects`](Self::with_side_effects) 预先声明
+/// - 执行环境（[`Tool::sandbox`]）在实例化后取自真实工具；首次调用前的日志与审批请求
+///   使用 [`with_sandbox`](Self::with_sandbox) 预先声明的值
+///
+/// # 示例
+///
+/// ```rust
+/// use echo_agent::tools::LazyTool;
+/// use echo_agent::tools::others::math::AddTool;
+/// use serde_json::json;
+///
+/// let tool = LazyTool::new(
+///     "add",
+///     "计算两个数的和",
+///     json!({ "type": "object", "properties": { "a": { "type": "number" }, "b": { "type": "number" } } }),
+///     || Ok(Box::new(AddTool)),
+/// );
+/// assert!(!tool.is_instantiated());
+/// ```
+pub struct LazyTool {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+    side_effects: bool,
+    sandbox: Option<String>,
+    /// 实例化状态，阻塞线程上的首次创建持有其引用
+    slot: Arc<LazySlot>,
+}
+
+/// 工厂与缓存的真实工具
+struct LazySlot {
+    name: String,
+    factory: ToolFactory,
+    instance: OnceLock<Box<dyn Tool>>,
+    /// 串行化首次创建，保证并发的首次调用也只调用一次工厂
+    init: Mutex<()>,
+    quota: Mutex<Option<ToolQuota>>,
+    randomness: Mutex<Option<Arc<dyn Randomness>>>,
+}
+
+impl LazySlot {
+    /// 取得真实工具，尚未创建时调用工厂（会阻塞当前线程直到工厂返回）
+    fn instance(&self) -> Result<&dyn Tool> {
+        if let Some(tool) = self.instance.get() {
+            return Ok(tool.as_ref());
+        }
+        let _guard = self.init.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tool) = self.instance.get() {
+            return Ok(tool.as_ref());
+        }
+        let mut tool = (self.factory)().map_err(|e| self.failure(e))?;
+        if let Some(quota) = self
+            .quota
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            tool.apply_quota(quota);
+        }
+        if let Some(rng) = self
+            .randomness
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        {
+            tool.apply_randomness(rng);
+        }
+        tracing::info!(tool = %self.name, "🔌 延迟工具已实例化");
+        Ok(self.instance.get_or_init(|| tool).as_ref())
+    }
+
+    fn failure(&self, e: impl std::fmt::Display) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.name.clone(),
+            message: format!("工具实例化失败: {e}"),
+        }
+    }
+}
+
+impl LazyTool {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+        factory: impl Fn() -> Result<Box<dyn Tool>> + Send + Sync + 'static,
+    ) -> Self {
+        let name = name.into();
+        Self {
+            slot: Arc::new(LazySlot {
+                name: name.clone(),
+                factory: Box::new(factory),
+                instance: OnceLock::new(),
+                init: Mutex::new(()),
+                quota: Mutex::new(None),
+                randomness: Mutex::new(None),
+            }),
+            name,
+            description: description.into(),
+            parameters,
+            side_effects: false,
+            sandbox: None,
+        }
+    }
+
+    /// 声明真实工具会修改外部状态，见 [`Tool::has_side_effects`]
+    pub fn with_side_effects(mut self) -> Self {
+        self.side_effects = true;
+        self
+    }
+
+    /// 声明真实工具的执行环境，实例化前的日志与审批请求使用该值，见 [`Tool::sandbox`]
+    pub fn with_sandbox(mut self, sandbox: impl Into<String>) -> Self {
+        self.sandbox = Some(sandbox.into());
+        self
+    }
+
+    /// 真实工具是否已创建
+    pub fn is_instantiated(&self) -> bool {
+        self.slot.instance.get().is_some()
+    }
+
+    /// 在阻塞线程上取得真实工具，等待工厂期间不占用异步 worker
+    async fn instance_async(&self) -> Result<&dyn Tool> {
+        if let Some(tool) = self.slot.instance.get() {
+            return Ok(tool.as_ref());
+        }
+        let slot = self.slot.clone();
+        tokio::task::spawn_blocking(move || slot.instance().map(|_| ()))
+            .await
+            .map_err(|e| self.slot.failure(e))??;
+        self.slot.instance()
+    }
+}
+
+#[async_trait]
+impl Tool for LazyTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        self.parameters.clone()
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
+        self.instance_async().await?.execute(parameters).await
+    }
+
+    /// 先创建真实工具再校验；工厂失败时返回实例化错误
+    fn validate_parameters(&self, params: &ToolParameters) -> Result<()> {
+        self.slot.instance()?.validate_parameters(params)
+    }
+
+    /// 先创建真实工具再生成预览；工厂失败时没有预览
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        self.slot.instance().ok()?.preview(params)
+    }
+
+    fn has_side_effects(&self) -> bool {
+        self.side_effects
+    }
+
+    /// 已实例化时取真实工具的执行环境，否则取 [`with_sandbox`](Self::with_sandbox) 声明的值
+    fn sandbox(&self) -> Option<String> {
+        match self.slot.instance.get() {
+            Some(tool) => tool.sandbox(),
+            None => self.sandbox.clone(),
+        }
+    }
+
+    fn apply_quota(&mut self, quota: &ToolQuota) {
+        match Arc::get_mut(&mut self.slot).and_then(|slot| slot.instance.get_mut()) {
+            Some(tool) => tool.apply_quota(quota),
+            None => {
+                *self.slot.quota.lock().unwrap_or_else(|e| e.into_inner()) = Some(quota.clone())
+            }
+        }
+    }
+
+    fn apply_randomness(&mut self, rng: Arc<dyn Randomness>) {
+        match Arc::get_mut(&mut self.slot).and_then(|slot| slot.instance.get_mut()) {
+            Some(tool) => tool.apply_randomness(rng),
+            None => {
+                *self
+                    .slot
+                    .randomness
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()) = Some(rng)
+            }
+        }
+    }
+}
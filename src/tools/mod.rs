@@ -4,10 +4,12 @@
 //!
 //! # 核心类型
 //!
-//! - [`Tool`]: 工具接口 trait，所有工具必须实现
-//! - [`ToolManager`]: 工具管理器，负责注册和执行
-//! - [`ToolResult`]: 工具执行结果
-//! - [`ToolExecutionConfig`]: 执行配置（超时、重试、并发）
+//! - [`Tool`]：工具接口 trait，所有工具必须实现
+//! - [`ToolManager`]：工具管理器，负责注册和执行
+//! - [`ToolResult`]：工具执行结果
+//! - [`ToolExecutionConfig`]：执行配置（超时、重试、并发）
+//! - [`Randomness`]：下发给工具的共享随机源（可设种复现）
+//! - [`ToolInterceptor`]：工具执行前的拦截器，可改写参数或短路返回
 //!
 //! # 快速开始
 //!
@@ -86,24 +88,46 @@
 //! ```
 
 pub mod builtin;
+mod concurrency;
+pub mod encoding;
 pub mod files;
+mod interceptor;
+mod lazy;
 pub mod others;
+mod random;
 pub mod shell;
 
 use crate::error::{Result, ToolError};
 use crate::llm::types::ToolDefinition;
+use concurrency::{ConcurrencyLimiter, is_rate_limited};
+use futures::StreamExt;
+use futures::stream::BoxStream;
+pub use interceptor::{InterceptAction, ToolInterceptor};
+pub use lazy::{LazyTool, ToolFactory};
+pub use random::{Randomness, SeededRng};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::Semaphore;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// 工具并发排队优先级，见 [`Tool::priority`]
+pub const TOOL_PRIORITY_LOW: u8 = 64;
+pub const TOOL_PRIORITY_NORMAL: u8 = 128;
+pub const TOOL_PRIORITY_HIGH: u8 = 192;
+
+/// 流式工具输出片段的接收端，见 [`Tool::execute_streaming`]
+pub(crate) type ToolChunkSender = UnboundedSender<String>;
 
 /// 工具执行结果
 ///
+/// 标记为 `#[non_exhaustive]`：请通过 [`success`](Self::success) / [`error`](Self::error)
+/// 与 `with_*` 方法构造，新增字段不会破坏已有代码。
+///
 /// # 示例
 ///
 /// ```
-/// use echo_agent::tools::ToolResult;
+/// use echo_agent::tools::{Source, ToolResult};
 ///
 /// let success = ToolResult::success("执行成功".to_string());
 /// assert!(success.success);
@@ -112,8 +136,14 @@ use tokio::sync::Semaphore;
 /// let error = ToolResult::error("执行失败".to_string());
 /// assert!(!error.success);
 /// assert_eq!(error.error, Some("执行失败".to_string()));
+///
+/// // 搜索、读文件等工具可附带信息来源，供最终答案标注引用
+/// let found = ToolResult::success("Rust 1.85 发布了 2024 edition".to_string())
+///     .with_source(Source::url("https://blog.rust-lang.org").with_snippet("Rust 2024"));
+/// assert_eq!(found.sources.len(), 1);
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ToolResult {
     /// 是否执行成功
     pub success: bool,
@@ -121,6 +151,63 @@ pub struct ToolResult {
     pub output: String,
     /// 错误信息（失败时）
     pub error: Option<String>,
+    /// 输出内容的信息来源（URL、文件路径等）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<Source>,
+    /// 结构化输出：工具原生提供，或开启 [`AgentConfig::auto_extract`](crate::agent::AgentConfig::auto_extract) 后按
+    /// [`Tool::output_schema`] 从文本输出抽取
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    /// 成功但有隐患时的警告（如覆盖了已存在的文件），Agent 会在上下文中单独标注给 LLM
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// 信息来源的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// 网页
+    Url,
+    /// 本地文件
+    File,
+    /// 其他（数据库记录、API 等）
+    Other,
+}
+
+/// 工具结果携带的信息来源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    pub kind: SourceKind,
+    /// 来源标识：URL、文件路径等
+    #[serde(rename = "ref")]
+    pub reference: String,
+    /// 相关片段（可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl Source {
+    pub fn new(kind: SourceKind, reference: impl Into<String>) -> Self {
+        Self {
+            kind,
+            reference: reference.into(),
+            snippet: None,
+        }
+    }
+
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::new(SourceKind::Url, url)
+    }
+
+    pub fn file(path: impl Into<String>) -> Self {
+        Self::new(SourceKind::File, path)
+    }
+
+    pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
+        self.snippet = Some(snippet.into());
+        self
+    }
 }
 
 /// 工具执行配置：超时、重试、并发度
@@ -138,7 +225,8 @@ pub struct ToolResult {
 ///     max_concurrency: Some(4), // 最多4个并发
 /// };
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ToolExecutionConfig {
     /// 单次工具执行超时（毫秒）。0 = 不限制。默认 30_000（30 秒）
     pub timeout_ms: u64,
@@ -164,6 +252,30 @@ impl Default for ToolExecutionConfig {
     }
 }
 
+/// 单个工具的粗粒度资源配额，`None` 表示不限制
+///
+/// 通过 [`ToolManager::set_quota`] / [`ToolManager::set_tool_quota`]（或 `AgentConfig` 的同名构建方法）设置。
+/// `max_output_bytes` 由 [`ToolManager`] 统一执行（超出部分截断）；
+/// `max_read_bytes` 通过 [`Tool::apply_quota`] 下发，由读文件的工具自行遵守。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolQuota {
+    /// 单次执行的最大输出字节数，超出部分截断
+    pub max_output_bytes: Option<usize>,
+    /// 单个文件的最大读取字节数，超出时拒绝读取（`read_file` 等）
+    pub max_read_bytes: Option<usize>,
+}
+
+impl ToolQuota {
+    /// 逐项合并：本配额设置了的项优先，未设置的项取 `fallback`
+    pub fn or(&self, fallback: &ToolQuota) -> ToolQuota {
+        ToolQuota {
+            max_output_bytes: self.max_output_bytes.or(fallback.max_output_bytes),
+            max_read_bytes: self.max_read_bytes.or(fallback.max_read_bytes),
+        }
+    }
+}
+
 impl ToolResult {
     /// 创建成功结果
     pub fn success(output: String) -> Self {
@@ -171,6 +283,20 @@ impl ToolResult {
             success: true,
             output,
             error: None,
+            sources: Vec::new(),
+            data: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// 创建带警告的成功结果：执行成功，但有需要 LLM 留意的隐患
+    pub fn success_with_warnings(
+        output: String,
+        warnings: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            warnings: warnings.into_iter().map(Into::into).collect(),
+            ..Self::success(output)
         }
     }
 
@@ -180,13 +306,63 @@ impl ToolResult {
             success: false,
             output: String::new(),
             error: Some(error),
+            sources: Vec::new(),
+            data: None,
+            warnings: Vec::new(),
         }
     }
+
+    /// 追加一条信息来源
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// 追加多条信息来源
+    pub fn with_sources(mut self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.sources.extend(sources);
+        self
+    }
+
+    /// 附带结构化输出
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// 追加一条警告，见 [`success_with_warnings`](Self::success_with_warnings)
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
 }
 
 /// 工具参数类型
 pub type ToolParameters = HashMap<String, serde_json::Value>;
 
+/// 副作用调用的幂等键：(`tool_call_id`, 真实工具名, 规范化的参数 JSON)
+type CallKey = (String, String, String);
+
+/// 判断工具当前是否可用时的上下文摘要，见 [`Tool::is_available`]
+///
+/// 只包含计数与工具名，不含任何消息内容或工具参数。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextHint {
+    /// 上下文中的消息条数（含 system）
+    pub message_count: usize,
+    /// 当前执行已进行的推理轮数
+    pub iteration: usize,
+    /// 上下文中最近调用过的工具名（按时间顺序，最近的在末尾，最多 10 个）
+    pub recent_tools: Vec<String>,
+}
+
+impl ContextHint {
+    /// 最近的工具调用中是否出现过 `name`
+    pub fn called_recently(&self, name: &str) -> bool {
+        self.recent_tools.iter().any(|t| t == name)
+    }
+}
+
 /// 工具接口
 ///
 /// 所有工具都必须实现此 trait。工具可以是：
@@ -236,10 +412,110 @@ pub trait Tool: Send + Sync {
     /// 执行工具
     async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult>;
 
+    /// 输出内容的 JSON Schema（可选实现），默认 `None`
+    ///
+    /// 返回纯文本的工具声明后，开启 [`AgentConfig::auto_extract`](crate::agent::AgentConfig::auto_extract) 时
+    /// 由 LLM 把文本输出抽取为符合该 schema 的 `ToolResult.data`。
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
     /// 验证参数（可选实现，默认不验证）
     fn validate_parameters(&self, _params: &ToolParameters) -> Result<()> {
         Ok(())
     }
+
+    /// 人类可读的操作预览（可选实现），用于人工审批时替代原始参数 JSON
+    ///
+    /// 例如 "将向 /tmp/a.txt 写入 512 字节，覆盖现有内容"。返回 `None` 时回退到 JSON 展示。
+    fn preview(&self, _params: &ToolParameters) -> Option<String> {
+        None
+    }
+
+    /// 当前上下文下是否可用，默认始终可用
+    ///
+    /// 返回 `false` 时本轮发给 LLM 的工具列表中不包含该工具，LLM 仍调用时 Agent 拒绝执行，
+    /// 例如"提交"工具只在最近调用过"暂存"之后才出现。
+    fn is_available(&self, _hint: &ContextHint) -> bool {
+        true
+    }
+
+    /// 并发受限时获取执行许可的优先级，越大越先执行，默认 [`TOOL_PRIORITY_NORMAL`]
+    ///
+    /// 许可紧张时高优先级工具插队；排队越久的工具有效优先级越高，低优先级工具不会饿死。
+    /// 可被 [`ToolManager::set_tool_priority`] 覆盖。
+    fn priority(&self) -> u8 {
+        TOOL_PRIORITY_NORMAL
+    }
+
+    /// 是否会修改外部状态（写/删/移动文件、执行命令等），默认 `false`
+    ///
+    /// 计入 `AgentConfig::destructive_op_threshold` 的副作用操作预算。
+    fn has_side_effects(&self) -> bool {
+        false
+    }
+
+    /// 执行环境（沙箱 / 容器）的名称，如 `docker:python:3.12`，默认 `None`（宿主机直接执行）
+    ///
+    /// Agent 在执行日志和人工审批请求中展示「此工具将在沙箱 X 中执行」。
+    fn sandbox(&self) -> Option<String> {
+        None
+    }
+
+    /// 是否逐步产生输出（长时间运行的命令等），默认 `false`
+    ///
+    /// 返回 `true` 时，流式执行（`execute_stream` / `chat_stream`）改用
+    /// [`execute_streaming`](Tool::execute_streaming)，每个片段以
+    /// `AgentEvent::ToolResultChunk` 实时回传，结束后聚合为完整输出写入上下文。
+    fn streams_output(&self) -> bool {
+        false
+    }
+
+    /// 是否支持批量执行，默认 `false`
+    ///
+    /// 返回 `true` 时，同一轮里对该工具的多个调用会合并为一次
+    /// [`batch_execute`](Tool::batch_execute)，适合批量读文件、批量查询等可摊薄开销的场景。
+    fn supports_batch(&self) -> bool {
+        false
+    }
+
+    /// 批量执行，结果与 `params_list` 按下标一一对应；默认逐个调用 [`execute`](Tool::execute)
+    async fn batch_execute(&self, params_list: Vec<ToolParameters>) -> Vec<Result<ToolResult>> {
+        let mut results = Vec::with_capacity(params_list.len());
+        for params in params_list {
+            results.push(self.execute(params).await);
+        }
+        results
+    }
+
+    /// 接收注册时生效的资源配额（已合并 per-tool 覆盖），默认忽略
+    ///
+    /// 读文件、创建临时文件等工具可据此收紧自身限制。
+    fn apply_quota(&mut self, _quota: &ToolQuota) {}
+
+    /// 接收 Agent 统一下发的随机源，默认忽略
+    ///
+    /// 含随机行为的工具（mock 数据、采样等）实现此方法并只从该随机源取随机数，
+    /// 设置 `AgentConfig::tool_seed` 后输出即可复现。未设置随机源时不会调用。
+    fn apply_randomness(&mut self, _rng: Arc<dyn Randomness>) {}
+
+    /// 流式执行，逐片返回输出；默认把 [`execute`](Tool::execute) 的结果包装成单元素流
+    ///
+    /// 片段按原样拼接即为完整输出；执行失败时产出 `Err` 并结束。
+    fn execute_streaming(&self, parameters: ToolParameters) -> BoxStream<'_, Result<String>> {
+        Box::pin(futures::stream::once(async move {
+            let result = self.execute(parameters).await?;
+            if result.success {
+                Ok(result.output)
+            } else {
+                Err(ToolError::ExecutionFailed {
+                    tool: self.name().to_string(),
+                    message: result.error.unwrap_or(result.output),
+                }
+                .into())
+            }
+        }))
+    }
 }
 
 /// 工具管理器
@@ -260,12 +536,37 @@ pub trait Tool: Send + Sync {
 pub struct ToolManager {
     tools: HashMap<String, Box<dyn Tool>>,
     config: ToolExecutionConfig,
-    /// 并发限流器
-    semaphore: Option<Arc<Semaphore>>,
+    /// 并发限流器（支持运行时调整）
+    limiter: ConcurrencyLimiter,
     /// 缓存的工具定义
     cached_definitions: Option<Vec<ToolDefinition>>,
+    /// 运行时覆盖的工具描述（工具名 → 新描述），仅影响发给 LLM 的定义
+    description_overrides: HashMap<String, String>,
+    /// 工具别名（别名 → 真实工具名），查找时真名优先，不出现在工具定义中
+    aliases: HashMap<String, String>,
+    /// 工具降级链（主工具名 → 备用工具名列表），主工具失败时按顺序尝试
+    fallback_chains: HashMap<String, Vec<String>>,
+    /// 本执行周期内副作用工具已完成的调用（调用键 → 结果），用于幂等去重
+    executed_calls: Mutex<HashMap<CallKey, ToolResult>>,
+    /// 下发给工具的共享随机源（未设置时不下发）
+    randomness: Option<Arc<dyn Randomness>>,
+    /// 工具执行前按注册顺序调用的拦截器
+    interceptors: Vec<Box<dyn ToolInterceptor>>,
+    /// 软超时占 `timeout_ms` 的比例（`None` = 关闭），见 [`set_soft_timeout_ratio`](Self::set_soft_timeout_ratio)
+    soft_timeout_ratio: Option<f32>,
+    /// 工具执行达到软超时时调用的钩子
+    soft_timeout_hook: Option<SoftTimeoutHook>,
+    /// 所有工具默认的资源配额
+    quota: ToolQuota,
+    /// 按工具名覆盖的配额，逐项覆盖 `quota` 中的同名限制
+    tool_quotas: HashMap<String, ToolQuota>,
+    /// 按工具名覆盖的并发排队优先级，优先于 [`Tool::priority`]
+    tool_priorities: HashMap<String, u8>,
 }
 
+/// 软超时钩子：参数为工具名与软超时时长
+pub type SoftTimeoutHook = Arc<dyn Fn(&str, Duration) + Send + Sync>;
+
 impl ToolManager {
     /// 获取 OpenAI 格式的工具定义列表（带缓存）
     ///
@@ -275,15 +576,40 @@ impl ToolManager {
         if let Some(ref cached) = self.cached_definitions {
             return cached.clone();
         }
-        let definitions: Vec<ToolDefinition> = self
-            .tools
-            .values()
-            .map(|tool| ToolDefinition::from_tool(&**tool))
-            .collect();
+        let definitions = self.get_tool_definitions();
         self.cached_definitions = Some(definitions.clone());
         definitions
     }
 
+    /// 按 [`Tool::is_available`] 过滤后的工具定义列表，用于组装本轮 LLM 请求
+    pub(crate) fn available_tools(&mut self, hint: &ContextHint) -> Vec<ToolDefinition> {
+        let mut definitions = self.get_openai_tools();
+        definitions.retain(|def| {
+            self.tools
+                .get(&def.function.name)
+                .is_none_or(|tool| tool.is_available(hint))
+        });
+        definitions
+    }
+
+    /// [`Tool::is_available`] 返回 `false` 的工具名
+    pub(crate) fn unavailable_tools(&self, hint: &ContextHint) -> HashSet<String> {
+        self.tools
+            .values()
+            .filter(|tool| !tool.is_available(hint))
+            .map(|tool| tool.name().to_string())
+            .collect()
+    }
+
+    /// 生成单个工具的定义，若存在描述覆盖则替换 description
+    fn definition_for(&self, tool: &dyn Tool) -> ToolDefinition {
+        let mut definition = ToolDefinition::from_tool(tool);
+        if let Some(desc) = self.description_overrides.get(tool.name()) {
+            definition.function.description = desc.clone();
+        }
+        definition
+    }
+
     /// 使缓存失效（注册/注销工具时调用）
     fn invalidate_cache(&mut self) {
         self.cached_definitions = None;
@@ -300,180 +626,826 @@ impl ToolManager {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
-            semaphore: None,
+            limiter: ConcurrencyLimiter::new(None),
             config: ToolExecutionConfig::default(),
             cached_definitions: None,
+            description_overrides: HashMap::new(),
+            aliases: HashMap::new(),
+            fallback_chains: HashMap::new(),
+            executed_calls: Mutex::new(HashMap::new()),
+            randomness: None,
+            interceptors: Vec::new(),
+            soft_timeout_ratio: None,
+            soft_timeout_hook: None,
+            quota: ToolQuota::default(),
+            tool_quotas: HashMap::new(),
+            tool_priorities: HashMap::new(),
         }
     }
 
     pub fn new_with_config(config: ToolExecutionConfig) -> Self {
-        let semaphore = config
-            .max_concurrency
-            .map(|n| Arc::new(Semaphore::new(n.max(1))));
         Self {
             tools: HashMap::new(),
-            semaphore,
+            limiter: ConcurrencyLimiter::new(config.max_concurrency),
             config,
             cached_definitions: None,
+            description_overrides: HashMap::new(),
+            aliases: HashMap::new(),
+            fallback_chains: HashMap::new(),
+            executed_calls: Mutex::new(HashMap::new()),
+            randomness: None,
+            interceptors: Vec::new(),
+            soft_timeout_ratio: None,
+            soft_timeout_hook: None,
+            quota: ToolQuota::default(),
+            tool_quotas: HashMap::new(),
+            tool_priorities: HashMap::new(),
         }
     }
 
-    /// 返回并发度限制（`None` = 不限制）
+    /// 返回当前生效的并发度限制（`None` = 不限制）
+    ///
+    /// 可能因 [`set_max_concurrency`](Self::set_max_concurrency) 或限流自动收紧而与初始配置不同。
     pub fn max_concurrency(&self) -> Option<usize> {
-        self.config.max_concurrency
+        self.limiter.limit()
+    }
+
+    /// 显式配置的并发度上限（初始配置或最近一次 `set_max_concurrency`），不受限流自动收紧影响
+    pub(crate) fn concurrency_ceiling(&self) -> Option<usize> {
+        self.limiter.ceiling()
+    }
+
+    /// 运行时调整工具并发度
+    ///
+    /// - 调大：立即放出新增许可，等待中的执行会被唤醒
+    /// - 调小：已持有许可的执行不受影响，归还许可时才会收回多出的部分
+    /// - 调到 `0`：暂停，新的工具执行会一直等待，直到并发度被调大
+    ///
+    /// 设置值同时作为限流自动收紧后恢复的上限。
+    pub fn set_max_concurrency(&self, n: usize) {
+        tracing::info!("工具并发度调整为 {}", n);
+        self.limiter.set_limit(n);
+    }
+
+    /// 根据工具执行结果自动调整并发度：遇到 429 / 限流信号时减半，连续成功后逐步恢复
+    fn observe_rate_limit(&self, tool_name: &str, result: &Result<ToolResult>) {
+        let message = match result {
+            Ok(r) if r.success => None,
+            Ok(r) => r.error.as_deref(),
+            Err(e) => return self.observe_error_message(tool_name, &e.to_string()),
+        };
+        match message {
+            Some(msg) => self.observe_error_message(tool_name, msg),
+            None => self.limiter.record_success(),
+        }
+    }
+
+    fn observe_error_message(&self, tool_name: &str, message: &str) {
+        if is_rate_limited(message)
+            && let Some(limit) = self.limiter.backoff()
+        {
+            tracing::warn!("工具 '{}' 触发限流，并发度自动下调为 {}", tool_name, limit);
+        }
     }
 
-    /// 注册单个工具
-    pub fn register(&mut self, tool: Box<dyn Tool>) {
+    /// 替换共享随机源，并重新下发给已注册的工具
+    pub fn set_randomness(&mut self, rng: Arc<dyn Randomness>) {
+        for tool in self.tools.values_mut() {
+            tool.apply_randomness(rng.clone());
+        }
+        self.randomness = Some(rng);
+    }
+
+    /// 注册单个工具（同时下发该工具生效的资源配额与共享随机源）
+    pub fn register(&mut self, mut tool: Box<dyn Tool>) {
+        tool.apply_quota(&self.quota_for(tool.name()));
+        if let Some(rng) = &self.randomness {
+            tool.apply_randomness(rng.clone());
+        }
         self.tools.insert(tool.name().to_string(), tool);
         self.invalidate_cache();
     }
 
     /// 批量注册工具
     pub fn register_tools(&mut self, tools: Vec<Box<dyn Tool>>) {
-        for tool in tools {
+        for mut tool in tools {
+            tool.apply_quota(&self.quota_for(tool.name()));
+            if let Some(rng) = &self.randomness {
+                tool.apply_randomness(rng.clone());
+            }
             self.tools.insert(tool.name().to_string(), tool);
         }
         self.invalidate_cache();
     }
 
+    /// 设置所有工具默认的资源配额（默认不限制），并重新下发给已注册的工具
+    pub fn set_quota(&mut self, quota: ToolQuota) {
+        self.quota = quota;
+        self.reapply_quotas();
+    }
+
+    /// 为指定工具设置配额覆盖，设置了的项优先于默认配额
+    pub fn set_tool_quota(&mut self, tool_name: impl Into<String>, quota: ToolQuota) {
+        self.tool_quotas.insert(tool_name.into(), quota);
+        self.reapply_quotas();
+    }
+
+    /// 指定工具生效的配额：per-tool 覆盖中设置了的项优先，其余取默认配额
+    pub fn quota_for(&self, tool_name: &str) -> ToolQuota {
+        match self.tool_quotas.get(tool_name) {
+            Some(o) => o.or(&self.quota),
+            None => self.quota.clone(),
+        }
+    }
+
+    fn reapply_quotas(&mut self) {
+        let quotas: Vec<(String, ToolQuota)> = self
+            .tools
+            .keys()
+            .map(|name| (name.clone(), self.quota_for(name)))
+            .collect();
+        for (name, quota) in quotas {
+            if let Some(tool) = self.tools.get_mut(&name) {
+                tool.apply_quota(&quota);
+            }
+        }
+    }
+
+    /// 为指定工具设置并发排队优先级（如把用户点名要求的工具提到最前），优先于 [`Tool::priority`]
+    pub fn set_tool_priority(&mut self, tool_name: impl Into<String>, priority: u8) {
+        self.tool_priorities.insert(tool_name.into(), priority);
+    }
+
+    /// 按 `max_output_bytes` 配额截断输出（在字符边界处截断并附加提示）
+    fn enforce_output_quota(&self, tool_name: &str, mut result: ToolResult) -> ToolResult {
+        let Some(limit) = self.quota_for(tool_name).max_output_bytes else {
+            return result;
+        };
+        if result.output.len() > limit {
+            let total = result.output.len();
+            let mut end = limit;
+            while !result.output.is_char_boundary(end) {
+                end -= 1;
+            }
+            result.output.truncate(end);
+            result.output.push_str(&format!(
+                "\n…[输出共 {total} 字节，超过配额 {limit} 字节，已截断]"
+            ));
+            tracing::warn!(tool = %tool_name, total, limit, "工具输出超过配额，已截断");
+        }
+        result
+    }
+
     /// 注销工具
     pub fn unregister(&mut self, tool_name: &str) -> Option<Box<dyn Tool>> {
         let tool = self.tools.remove(tool_name);
         if tool.is_some() {
+            self.description_overrides.remove(tool_name);
             self.invalidate_cache();
         }
         tool
     }
 
-    /// 列出所有已注册的工具名称
+    /// 运行时覆盖工具描述
+    ///
+    /// 只替换发给 LLM 的 definition 中的 description，工具名、参数 schema 与工具实例均不变。
+    /// 工具未注册时打印警告并返回 `false`。
+    pub fn override_tool_description(
+        &mut self,
+        tool_name: &str,
+        description: impl Into<String>,
+    ) -> bool {
+        if !self.tools.contains_key(tool_name) {
+            tracing::warn!(tool = %tool_name, "⚠️ 覆盖描述失败：工具未注册");
+            return false;
+        }
+        self.description_overrides
+            .insert(tool_name.to_string(), description.into());
+        self.invalidate_cache();
+        true
+    }
+
+    /// 移除工具描述覆盖，恢复工具自身的 `description()`
+    pub fn clear_tool_description_override(&mut self, tool_name: &str) -> bool {
+        let removed = self.description_overrides.remove(tool_name).is_some();
+        if removed {
+            self.invalidate_cache();
+        }
+        removed
+    }
+
+    /// 注册工具别名，使 LLM 以 `alias`（如 `readFile`）调用时落到真实工具 `real_name`
+    ///
+    /// 查找时先查真名再查别名，别名不出现在发给 LLM 的工具列表中。
+    /// 同一别名重复注册时后者覆盖并打印警告；与已注册工具同名的别名不会生效。
+    pub fn register_alias(&mut self, alias: impl Into<String>, real_name: impl Into<String>) {
+        let (alias, real_name) = (alias.into(), real_name.into());
+        if self.tools.contains_key(&alias) {
+            tracing::warn!(alias = %alias, "⚠️ 别名与已注册工具同名，调用时将使用真实工具");
+        }
+        if let Some(previous) = self.aliases.insert(alias.clone(), real_name.clone())
+            && previous != real_name
+        {
+            tracing::warn!(alias = %alias, previous = %previous, now = %real_name, "⚠️ 工具别名冲突，覆盖旧映射");
+        }
+    }
+
+    /// 注册工具降级链：`primary` 执行失败时，Agent 按顺序尝试 `fallbacks` 中的备用工具，
+    /// 直到某个成功；全部失败才把主工具的错误回传 LLM
+    ///
+    /// 备用工具沿用主工具的调用参数，只有参数能通过备用工具 schema 校验时才会尝试，
+    /// 因此链上应放参数兼容的工具。重复注册时覆盖旧链。
+    pub fn register_fallback_chain(
+        &mut self,
+        primary: impl Into<String>,
+        fallbacks: impl IntoIterator<Item = impl Into<String>>,
+    ) {
+        let chain: Vec<String> = fallbacks.into_iter().map(Into::into).collect();
+        self.fallback_chains.insert(primary.into(), chain);
+    }
+
+    /// 主工具的降级链（未注册时为空）
+    pub fn fallback_chain(&self, primary: &str) -> &[String] {
+        self.fallback_chains
+            .get(self.resolve_name(primary))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// 移除工具别名，存在时返回原来指向的真实工具名
+    pub fn remove_alias(&mut self, alias: &str) -> Option<String> {
+        self.aliases.remove(alias)
+    }
+
+    /// 把别名解析为真实工具名；已注册的真名或未知名称原样返回
+    pub fn resolve_name<'a>(&'a self, tool_name: &'a str) -> &'a str {
+        if self.tools.contains_key(tool_name) {
+            return tool_name;
+        }
+        self.aliases
+            .get(tool_name)
+            .map_or(tool_name, |real| real.as_str())
+    }
+
+    /// 列出所有已注册的工具名称（不含别名）
     pub fn list_tools(&self) -> Vec<&str> {
         self.tools.keys().map(|name| name.as_str()).collect()
     }
 
-    /// 获取工具引用
+    /// 获取工具引用，支持以别名查找
     pub fn get_tool(&self, tool_name: &str) -> Option<&dyn Tool> {
-        self.tools.get(tool_name).map(|tool| &**tool)
+        self.tools
+            .get(self.resolve_name(tool_name))
+            .map(|tool| &**tool)
     }
 
-    /// 获取工具定义列表（用于展示或调试）
+    /// 获取工具定义列表（用于展示或调试），已应用描述覆盖
     pub fn get_tool_definitions(&self) -> Vec<ToolDefinition> {
         self.tools
             .values()
-            .map(|tool| ToolDefinition::from_tool(&**tool))
+            .map(|tool| self.definition_for(&**tool))
             .collect()
     }
 
-    /// 执行工具
+    /// 以 `tool_call_id`、工具名与参数作为幂等键执行工具
     ///
-    /// 支持并发控制、超时和重试。
-    pub async fn execute_tool(
+    /// 仅对声明了 [`Tool::has_side_effects`] 的工具生效：同一执行周期内重复的调用
+    /// （如流式断线重连、重试）直接返回首次的结果，不会再次执行；ID 相同但工具或参数不同
+    /// 时视为新的调用。只读工具照常执行。执行返回 `Err` 时不记录，允许重试。
+    pub async fn execute_tool_call(
         &self,
+        tool_call_id: &str,
         tool_name: &str,
         parameters: ToolParameters,
     ) -> Result<ToolResult> {
-        let tool = self
-            .get_tool(tool_name)
-            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
+        let parameters = match self
+            .intercept(self.resolve_name(tool_name), parameters)
+            .await?
+        {
+            InterceptAction::Continue(parameters) => parameters,
+            InterceptAction::Respond(result) => return Ok(result),
+        };
+        self.execute_tool_call_with(tool_call_id, tool_name, parameters, None)
+            .await
+    }
 
-        // 并发控制：获取信号量许可
-        let _permit = if let Some(sem) = &self.semaphore {
-            match sem.acquire().await {
-                Ok(permit) => Some(permit),
-                Err(e) => {
-                    tracing::warn!("Failed to acquire semaphore permit: {}", e);
-                    return Err(ToolError::ExecutionFailed {
-                        tool: tool_name.to_string(),
-                        message: format!("Concurrency limit error: {}", e),
-                    }
-                    .into());
+    /// 同 [`execute_tool_call`](Self::execute_tool_call)，但不再经过拦截器（调用方已先调用
+    /// [`intercept`](Self::intercept)）；提供 `chunks` 且工具 [`streams_output`](Tool::streams_output)
+    /// 时改为流式执行，片段实时发送到 `chunks`
+    pub(crate) async fn execute_tool_call_with(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        parameters: ToolParameters,
+        chunks: Option<&ToolChunkSender>,
+    ) -> Result<ToolResult> {
+        let tool = self.get_tool(tool_name);
+        let chunks = chunks.filter(|_| tool.is_some_and(|t| t.streams_output()));
+        let side_effects = tool.is_some_and(|tool| tool.has_side_effects());
+        let key = side_effects.then(|| self.call_key(tool_call_id, tool_name, &parameters));
+        let run = async {
+            match chunks {
+                Some(chunks) => {
+                    self.execute_tool_streaming(tool_name, parameters, chunks)
+                        .await
                 }
+                None => self.run_tool(tool_name, parameters).await,
             }
-        } else {
-            None
         };
-
-        let max_retries = if self.config.retry_on_fail {
-            self.config.max_retries
-        } else {
-            0
+        let Some(key) = key else {
+            return run.await;
         };
+        if let Some(result) = self.cached_call(&key) {
+            tracing::info!(tool = %tool_name, tool_call_id, "♻️ 重复的工具调用，返回首次执行结果");
+            return Ok(result);
+        }
+        let result = run.await?;
+        self.executed_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, result.clone());
+        Ok(result)
+    }
 
-        let mut last_err: Option<crate::error::ReactError> = None;
+    /// 批量执行同一工具的多个调用，结果与 `calls` 按下标一一对应
+    ///
+    /// 工具 [`supports_batch`](Tool::supports_batch) 时合并为一次
+    /// [`batch_execute`](Tool::batch_execute)：整批共享一个并发许可与超时（`timeout_ms`），
+    /// 其中失败或超时的调用再按 `retry_on_fail` 各自重试，每次重试单独计时；
+    /// 每个调用先逐个经过拦截器链，被短路的调用不进入批量；工具不支持批量时逐个执行。
+    /// 副作用工具同样按 `tool_call_id` 去重。
+    pub async fn execute_tool_call_batch(
+        &self,
+        tool_name: &str,
+        calls: Vec<(String, ToolParameters)>,
+    ) -> Vec<Result<ToolResult>> {
+        let mut results: Vec<Option<Result<ToolResult>>> = Vec::with_capacity(calls.len());
+        let mut pending = Vec::new();
+        for (tool_call_id, parameters) in calls {
+            match self
+                .intercept(self.resolve_name(tool_name), parameters)
+                .await
+            {
+                Ok(InterceptAction::Continue(parameters)) => {
+                    pending.push((results.len(), (tool_call_id, parameters)));
+                    results.push(None);
+                }
+                Ok(InterceptAction::Respond(result)) => results.push(Some(Ok(result))),
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+        let (indices, batch): (Vec<usize>, Vec<_>) = pending.into_iter().unzip();
+        let outcomes = self.execute_intercepted_batch(tool_name, batch).await;
+        for (i, outcome) in indices.into_iter().zip(outcomes) {
+            results[i] = Some(outcome);
+        }
+        results.into_iter().flatten().collect()
+    }
 
-        for attempt in 0..=max_retries {
-            if attempt > 0 {
-                let delay_ms = self.config.retry_delay_ms * (1u64 << (attempt as u64 - 1).min(5));
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+    /// 同 [`execute_tool_call_batch`](Self::execute_tool_call_batch)，但参数已经过拦截器链
+    pub(crate) async fn execute_intercepted_batch(
+        &self,
+        tool_name: &str,
+        calls: Vec<(String, ToolParameters)>,
+    ) -> Vec<Result<ToolResult>> {
+        let Some(tool) = self.get_tool(tool_name).filter(|t| t.supports_batch()) else {
+            let mut results = Vec::with_capacity(calls.len());
+            for (tool_call_id, parameters) in calls {
+                results.push(
+                    self.execute_tool_call_with(&tool_call_id, tool_name, parameters, None)
+                        .await,
+                );
             }
+            return results;
+        };
+        let tool_name = self.resolve_name(tool_name);
+        let side_effects = tool.has_side_effects();
+
+        let keys: Vec<Option<CallKey>> = calls
+            .iter()
+            .map(|(tool_call_id, parameters)| {
+                side_effects.then(|| self.call_key(tool_call_id, tool_name, parameters))
+            })
+            .collect();
+        let mut results: Vec<Option<Result<ToolResult>>> = keys
+            .iter()
+            .map(|key| key.as_ref().and_then(|key| self.cached_call(key)).map(Ok))
+            .collect();
+        let pending: Vec<usize> = (0..calls.len()).filter(|&i| results[i].is_none()).collect();
+        let params_list: Vec<ToolParameters> =
+            pending.iter().map(|&i| calls[i].1.clone()).collect();
 
-            let result = if self.config.timeout_ms > 0 {
-                match tokio::time::timeout(
-                    Duration::from_millis(self.config.timeout_ms),
-                    tool.execute(parameters.clone()),
-                )
+        let outcomes = match self.acquire_permit(tool_name, tool.priority()).await {
+            Ok(_permit) => self
+                .run_with_timeout(tool_name, tool.batch_execute(params_list))
                 .await
-                {
-                    Ok(r) => r,
-                    Err(_) => Err(ToolError::Timeout(tool_name.to_string()).into()),
-                }
-            } else {
-                tool.execute(parameters.clone()).await
-            };
+                .unwrap_or_else(|| {
+                    pending
+                        .iter()
+                        .map(|_| Err(ToolError::Timeout(tool_name.to_string()).into()))
+                        .collect()
+                }),
+            Err(e) => pending
+                .iter()
+                .map(|_| {
+                    Err(ToolError::ExecutionFailed {
+                        tool: tool_name.to_string(),
+                        message: e.to_string(),
+                    }
+                    .into())
+                })
+                .collect(),
+        };
 
-            match result {
-                Ok(r) => return Ok(r),
-                Err(e) if attempt < max_retries => {
-                    last_err = Some(e);
+        let retry = self.config.retry_on_fail && self.config.max_retries > 0;
+        let mut outcomes = outcomes.into_iter();
+        let settled = futures::future::join_all(pending.iter().map(|&i| {
+            let first = outcomes.next().unwrap_or_else(|| {
+                Err(ToolError::ExecutionFailed {
+                    tool: tool_name.to_string(),
+                    message: "批量执行未返回该调用的结果".to_string(),
                 }
-                Err(e) => return Err(e),
+                .into())
+            });
+            let parameters = calls[i].1.clone();
+            async move {
+                // 批量中失败的调用各自重试，与逐个执行的重试语义一致
+                let _permit = if first.is_err() && retry {
+                    match self.acquire_permit(tool_name, tool.priority()).await {
+                        Ok(permit) => Some(permit),
+                        Err(_) => {
+                            self.observe_rate_limit(tool_name, &first);
+                            return first;
+                        }
+                    }
+                } else {
+                    None
+                };
+                self.execute_attempts(tool_name, tool, parameters, Some(first))
+                    .await
             }
-        }
+        }))
+        .await;
 
-        Err(last_err.unwrap_or_else(|| ToolError::NotFound(tool_name.to_string()).into()))
+        for (i, result) in pending.into_iter().zip(settled) {
+            if let (Some(key), Ok(done)) = (&keys[i], &result) {
+                self.executed_calls
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(key.clone(), done.clone());
+            }
+            results[i] = Some(result);
+        }
+        results.into_iter().flatten().collect()
     }
 
-    /// 验证工具参数
-    pub fn validate_tool_parameters(
+    /// 本执行周期内同一调用（`tool_call_id`、工具名与参数均相同）已完成的副作用结果
+    pub fn executed_call(
         &self,
+        tool_call_id: &str,
         tool_name: &str,
         parameters: &ToolParameters,
-    ) -> Result<()> {
-        let tool = self
-            .get_tool(tool_name)
-            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
-        tool.validate_parameters(parameters)
+    ) -> Option<ToolResult> {
+        self.cached_call(&self.call_key(tool_call_id, tool_name, parameters))
     }
-}
 
-// ── 单元测试 ──────────────────────────────────────────────────────────────────────
+    fn cached_call(&self, key: &CallKey) -> Option<ToolResult> {
+        self.executed_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::testing::MockTool;
+    /// 幂等键：ID、真实工具名与规范化（键有序）的参数 JSON
+    fn call_key(
+        &self,
+        tool_call_id: &str,
+        tool_name: &str,
+        parameters: &ToolParameters,
+    ) -> CallKey {
+        let args: serde_json::Map<String, serde_json::Value> = parameters
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        (
+            tool_call_id.to_string(),
+            self.resolve_name(tool_name).to_string(),
+            serde_json::Value::Object(args).to_string(),
+        )
+    }
 
-    #[test]
-    fn test_tool_manager_new() {
-        let manager = ToolManager::new();
-        assert!(manager.list_tools().is_empty());
-        assert!(manager.max_concurrency().is_none());
+    /// 清空幂等记录，开始新的执行周期
+    pub fn clear_executed_calls(&self) {
+        self.executed_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
     }
 
-    #[test]
-    fn test_tool_manager_with_config() {
-        let config = ToolExecutionConfig {
-            timeout_ms: 5000,
-            retry_on_fail: true,
-            max_retries: 3,
-            retry_delay_ms: 100,
-            max_concurrency: Some(4),
-        };
-        let manager = ToolManager::new_with_config(config);
-        assert_eq!(manager.max_concurrency(), Some(4));
+    /// 注册工具拦截器，追加到拦截器链末尾
+    ///
+    /// 每次执行工具前按注册顺序调用：前一个拦截器改写后的参数交给下一个；
+    /// 任一拦截器返回 [`InterceptAction::Respond`] 时跳过后续拦截器与真实执行。
+    /// 拦截器在重试之前只调用一次；批量执行时每个调用各自经过拦截器链。
+    /// 由 Agent 执行的调用在人工审批之前经过拦截器，审批者看到的是改写后的参数。
+    pub fn add_interceptor(&mut self, interceptor: Box<dyn ToolInterceptor>) {
+        self.interceptors.push(interceptor);
     }
 
-    #[test]
+    /// 按注册顺序调用拦截器，遇到短路结果立即返回
+    pub(crate) async fn intercept(
+        &self,
+        tool_name: &str,
+        mut parameters: ToolParameters,
+    ) -> Result<InterceptAction> {
+        for interceptor in &self.interceptors {
+            match interceptor.intercept(tool_name, parameters).await? {
+                InterceptAction::Continue(next) => parameters = next,
+                respond => {
+                    tracing::info!(tool = %tool_name, "🛑 工具调用被拦截器短路");
+                    return Ok(respond);
+                }
+            }
+        }
+        Ok(InterceptAction::Continue(parameters))
+    }
+
+    /// 执行工具
+    ///
+    /// 先经过拦截器链（见 [`add_interceptor`](Self::add_interceptor)），再做并发控制、超时和重试。
+    pub async fn execute_tool(
+        &self,
+        tool_name: &str,
+        parameters: ToolParameters,
+    ) -> Result<ToolResult> {
+        let tool_name = self.resolve_name(tool_name);
+        if self.get_tool(tool_name).is_none() {
+            return Err(ToolError::NotFound(tool_name.to_string()).into());
+        }
+        let parameters = match self.intercept(tool_name, parameters).await? {
+            InterceptAction::Continue(parameters) => parameters,
+            InterceptAction::Respond(result) => return Ok(result),
+        };
+        self.run_tool(tool_name, parameters).await
+    }
+
+    /// 不经过拦截器执行工具：并发控制、超时和重试
+    async fn run_tool(&self, tool_name: &str, parameters: ToolParameters) -> Result<ToolResult> {
+        let tool_name = self.resolve_name(tool_name);
+        let tool = self
+            .get_tool(tool_name)
+            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
+
+        // 并发控制：获取信号量许可（并发度为 0 时在此等待）
+        let _permit = self.acquire_permit(tool_name, tool.priority()).await?;
+        self.execute_attempts(tool_name, tool, parameters, None)
+            .await
+    }
+
+    /// 在超时限制内执行，失败时按 `retry_on_fail` 指数退避重试；调用方需已持有并发许可
+    ///
+    /// `first` 为已经完成的首次尝试（批量执行中该调用的结果），提供时从第一次重试开始。
+    async fn execute_attempts(
+        &self,
+        tool_name: &str,
+        tool: &dyn Tool,
+        parameters: ToolParameters,
+        mut first: Option<Result<ToolResult>>,
+    ) -> Result<ToolResult> {
+        let max_retries = if self.config.retry_on_fail {
+            self.config.max_retries
+        } else {
+            0
+        };
+
+        let mut last_err: Option<crate::error::ReactError> = None;
+
+        for attempt in 0..=max_retries {
+            if attempt > 0 {
+                let delay_ms = self.config.retry_delay_ms * (1u64 << (attempt as u64 - 1).min(5));
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            }
+
+            let result = match first.take() {
+                Some(result) => result,
+                None => self
+                    .run_with_timeout(tool_name, tool.execute(parameters.clone()))
+                    .await
+                    .unwrap_or_else(|| Err(ToolError::Timeout(tool_name.to_string()).into())),
+            };
+            self.observe_rate_limit(tool_name, &result);
+
+            match result {
+                Ok(r) => return Ok(self.enforce_output_quota(tool_name, r)),
+                Err(e) if attempt < max_retries => {
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| ToolError::NotFound(tool_name.to_string()).into()))
+    }
+
+    /// 流式执行工具：片段实时发送到 `chunks`，结束后聚合为完整结果
+    ///
+    /// 与 [`execute_tool`](Self::execute_tool) 共享并发与超时限制；已发出的片段无法撤回，因此不重试。
+    /// 中途失败时结果为失败，`output` 保留已产生的部分输出。参数需已经过拦截器链。
+    pub(crate) async fn execute_tool_streaming(
+        &self,
+        tool_name: &str,
+        parameters: ToolParameters,
+        chunks: &ToolChunkSender,
+    ) -> Result<ToolResult> {
+        let tool_name = self.resolve_name(tool_name);
+        let tool = self
+            .get_tool(tool_name)
+            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
+        let _permit = self.acquire_permit(tool_name, tool.priority()).await?;
+
+        let run = async {
+            let mut stream = tool.execute_streaming(parameters);
+            let mut output = String::new();
+            while let Some(chunk) = stream.next().await {
+                match chunk {
+                    Ok(chunk) => {
+                        // 接收端已关闭（调用方不再关心进度）时仍继续聚合
+                        let _ = chunks.send(chunk.clone());
+                        output.push_str(&chunk);
+                    }
+                    Err(e) => {
+                        return ToolResult {
+                            output,
+                            ..ToolResult::error(e.to_string())
+                        };
+                    }
+                }
+            }
+            ToolResult::success(output)
+        };
+        let result = self
+            .run_with_timeout(tool_name, run)
+            .await
+            .ok_or_else(|| ToolError::Timeout(tool_name.to_string()))?;
+        Ok(self.enforce_output_quota(tool_name, result))
+    }
+
+    /// 开启软超时：执行时间达到 `timeout_ms × ratio` 仍未完成时记录告警并触发
+    /// [`on_soft_timeout`](Self::on_soft_timeout) 钩子，不影响执行结果；达到 `timeout_ms`
+    /// 才中止执行并返回 [`ToolError::Timeout`]。默认关闭，`ratio` 不在 (0, 1) 内时同样关闭
+    pub fn set_soft_timeout_ratio(&mut self, ratio: f32) {
+        self.soft_timeout_ratio = Some(ratio);
+    }
+
+    /// 软超时时长；未开启软超时、未设置硬超时或比例不在 (0, 1) 内时为 `None`
+    pub fn soft_timeout(&self) -> Option<Duration> {
+        let ratio = self.soft_timeout_ratio?;
+        let timeout_ms = self.config.timeout_ms;
+        (timeout_ms > 0 && ratio > 0.0 && ratio < 1.0)
+            .then(|| Duration::from_millis((timeout_ms as f64 * ratio as f64).round() as u64))
+    }
+
+    /// 注册软超时钩子，替换已有钩子
+    ///
+    /// 工具执行时间超过 [`soft_timeout`](Self::soft_timeout) 仍未完成时同步调用一次（每次尝试各自计时），
+    /// 执行继续进行，结果不受影响。适合上报监控指标，发现"越来越慢"的工具；钩子应尽快返回。
+    pub fn on_soft_timeout(&mut self, hook: impl Fn(&str, Duration) + Send + Sync + 'static) {
+        self.soft_timeout_hook = Some(Arc::new(hook));
+    }
+
+    /// 在硬超时内执行 `run`，超时返回 `None`；超过软超时时告警后继续等待
+    async fn run_with_timeout<T>(
+        &self,
+        tool_name: &str,
+        run: impl std::future::Future<Output = T>,
+    ) -> Option<T> {
+        if self.config.timeout_ms == 0 {
+            return Some(run.await);
+        }
+        let hard = Duration::from_millis(self.config.timeout_ms);
+        let run = async {
+            let mut run = std::pin::pin!(run);
+            if let Some(soft) = self.soft_timeout() {
+                match tokio::time::timeout(soft, &mut run).await {
+                    Ok(output) => return output,
+                    Err(_) => {
+                        tracing::warn!(
+                            tool = %tool_name,
+                            soft_timeout_ms = soft.as_millis() as u64,
+                            timeout_ms = self.config.timeout_ms,
+                            "🐢 工具执行已超过软超时，继续等待"
+                        );
+                        if let Some(hook) = &self.soft_timeout_hook {
+                            hook(tool_name, soft);
+                        }
+                    }
+                }
+            }
+            run.await
+        };
+        tokio::time::timeout(hard, run).await.ok()
+    }
+
+    async fn acquire_permit(
+        &self,
+        tool_name: &str,
+        priority: u8,
+    ) -> Result<concurrency::LimiterPermit<'_>> {
+        let priority = self
+            .tool_priorities
+            .get(tool_name)
+            .copied()
+            .unwrap_or(priority);
+        self.limiter.acquire(priority).await.map_err(|e| {
+            tracing::warn!("Failed to acquire semaphore permit: {}", e);
+            ToolError::ExecutionFailed {
+                tool: tool_name.to_string(),
+                message: format!("Concurrency limit error: {}", e),
+            }
+            .into()
+        })
+    }
+
+    /// 验证工具参数
+    pub fn validate_tool_parameters(
+        &self,
+        tool_name: &str,
+        parameters: &ToolParameters,
+    ) -> Result<()> {
+        let tool = self
+            .get_tool(tool_name)
+            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
+        tool.validate_parameters(parameters)
+    }
+}
+
+// ── 单元测试 ──────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockTool;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_tool_result_sources_serde_compat() {
+        let old: ToolResult =
+            serde_json::from_str(r#"{"success":true,"output":"ok","error":null}"#).unwrap();
+        assert!(old.sources.is_empty());
+        assert!(!serde_json::to_string(&old).unwrap().contains("sources"));
+
+        let result = ToolResult::success("ok".to_string())
+            .with_sources([Source::url("https://a.com").with_snippet("A")]);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json["sources"],
+            serde_json::json!([{ "kind": "url", "ref": "https://a.com", "snippet": "A" }])
+        );
+        let back: ToolResult = serde_json::from_value(json).unwrap();
+        assert_eq!(back.sources, result.sources);
+    }
+
+    #[test]
+    fn test_tool_result_warnings_serde_compat() {
+        let old: ToolResult =
+            serde_json::from_str(r#"{"success":true,"output":"ok","error":null}"#).unwrap();
+        assert!(old.warnings.is_empty());
+        assert!(!serde_json::to_string(&old).unwrap().contains("warnings"));
+
+        let result = ToolResult::success_with_warnings("ok".to_string(), ["有未跟踪文件"]);
+        assert!(result.success);
+        let back: ToolResult =
+            serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+        assert_eq!(back.warnings, ["有未跟踪文件"]);
+
+        let built = ToolResult::success("ok".to_string())
+            .with_warning("有未跟踪文件")
+            .with_warning("工作区有未提交改动");
+        assert_eq!(built.warnings, ["有未跟踪文件", "工作区有未提交改动"]);
+    }
+
+    #[test]
+    fn test_tool_manager_new() {
+        let manager = ToolManager::new();
+        assert!(manager.list_tools().is_empty());
+        assert!(manager.max_concurrency().is_none());
+    }
+
+    #[test]
+    fn test_tool_manager_with_config() {
+        let config = ToolExecutionConfig {
+            timeout_ms: 5000,
+            retry_on_fail: true,
+            max_retries: 3,
+            retry_delay_ms: 100,
+            max_concurrency: Some(4),
+        };
+        let manager = ToolManager::new_with_config(config);
+        assert_eq!(manager.max_concurrency(), Some(4));
+    }
+
+    #[test]
     fn test_register_single_tool() {
         let mut manager = ToolManager::new();
         let tool = Box::new(MockTool::new("test_tool"));
@@ -537,6 +1509,39 @@ mod tests {
         assert_eq!(definitions.len(), 2);
     }
 
+    #[test]
+    fn test_override_tool_description() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(MockTool::new("search")));
+        let original = manager.get_tool_definitions()[0].clone();
+
+        assert!(manager.override_tool_description("search", "学术来源优先"));
+        let definitions = manager.get_openai_tools();
+        assert_eq!(definitions[0].function.description, "学术来源优先");
+        assert_eq!(definitions[0].function.name, original.function.name);
+        assert_eq!(
+            definitions[0].function.parameters,
+            original.function.parameters
+        );
+        // 工具实例本身的描述不变
+        assert_eq!(
+            manager.get_tool("search").unwrap().description(),
+            original.function.description
+        );
+
+        assert!(manager.clear_tool_description_override("search"));
+        assert_eq!(
+            manager.get_openai_tools()[0].function.description,
+            original.function.description
+        );
+    }
+
+    #[test]
+    fn test_override_missing_tool_description() {
+        let mut manager = ToolManager::new();
+        assert!(!manager.override_tool_description("missing", "desc"));
+    }
+
     #[test]
     fn test_tool_result_success() {
         let result = ToolResult::success("output".to_string());
@@ -563,6 +1568,48 @@ mod tests {
         assert!(config.max_concurrency.is_none());
     }
 
+    #[tokio::test]
+    async fn test_output_quota_with_per_tool_override() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(
+            MockTool::new("quiet").with_response("数据很长很长"),
+        ));
+        manager.register(Box::new(
+            MockTool::new("verbose").with_response("0123456789"),
+        ));
+        manager.set_quota(ToolQuota {
+            max_output_bytes: Some(4),
+            max_read_bytes: Some(1024),
+        });
+        manager.set_tool_quota(
+            "verbose",
+            ToolQuota {
+                max_output_bytes: Some(8),
+                ..Default::default()
+            },
+        );
+        // 覆盖项优先，未覆盖的项沿用默认配额
+        assert_eq!(
+            manager.quota_for("verbose"),
+            ToolQuota {
+                max_output_bytes: Some(8),
+                max_read_bytes: Some(1024),
+            }
+        );
+
+        let quiet = manager.execute_tool("quiet", HashMap::new()).await.unwrap();
+        assert!(
+            quiet
+                .output
+                .starts_with("数\n…[输出共 18 字节，超过配额 4 字节")
+        );
+        let verbose = manager
+            .execute_tool("verbose", HashMap::new())
+            .await
+            .unwrap();
+        assert!(verbose.output.starts_with("01234567\n…["));
+    }
+
     #[tokio::test]
     async fn test_execute_tool_not_found() {
         let manager = ToolManager::new();
@@ -580,4 +1627,779 @@ mod tests {
         let tool_result = result.unwrap();
         assert!(tool_result.success);
     }
+
+    #[tokio::test]
+    async fn test_execute_tool_by_alias() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(
+            MockTool::new("read_file")
+                .with_response("内容")
+                .with_response("内容"),
+        ));
+        manager.register(Box::new(MockTool::new("write_file")));
+        manager.register_alias("readFile", "read_file");
+        manager.register_alias("file_read", "write_file");
+        // 冲突时后注册的覆盖
+        manager.register_alias("file_read", "read_file");
+
+        for name in ["readFile", "file_read"] {
+            let result = manager.execute_tool(name, HashMap::new()).await.unwrap();
+            assert_eq!(result.output, "内容");
+        }
+        assert_eq!(manager.get_tool("readFile").unwrap().name(), "read_file");
+
+        // 别名不出现在发给 LLM 的工具列表里
+        let mut names: Vec<_> = manager
+            .get_openai_tools()
+            .into_iter()
+            .map(|d| d.function.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["read_file", "write_file"]);
+
+        assert_eq!(
+            manager.remove_alias("readFile").as_deref(),
+            Some("read_file")
+        );
+        assert!(
+            manager
+                .execute_tool("readFile", HashMap::new())
+                .await
+                .is_err()
+        );
+    }
+
+    /// 记录同时在执行的最大数量的慢工具
+    #[derive(Default)]
+    struct SlowTool {
+        running: AtomicUsize,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for SlowTool {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "sleeps briefly"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+
+        async fn execute(&self, _parameters: ToolParameters) -> Result<ToolResult> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(ToolResult::success("done".to_string()))
+        }
+    }
+
+    /// 返回注册了 SlowTool 的管理器，以及用于读取并重置峰值并发数的计数器
+    fn slow_manager(max_concurrency: usize) -> (ToolManager, Arc<AtomicUsize>) {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            max_concurrency: Some(max_concurrency),
+            ..Default::default()
+        });
+        let tool = SlowTool::default();
+        let peak = tool.peak.clone();
+        manager.register(Box::new(tool));
+        (manager, peak)
+    }
+
+    fn take_peak(peak: &AtomicUsize) -> usize {
+        peak.swap(0, Ordering::SeqCst)
+    }
+
+    async fn run_slow_batch(manager: &ToolManager, n: usize) {
+        let calls = (0..n).map(|_| manager.execute_tool("slow", HashMap::new()));
+        for result in futures::future::join_all(calls).await {
+            assert!(result.unwrap().success);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_max_concurrency_at_runtime() {
+        let (manager, peak) = slow_manager(1);
+        run_slow_batch(&manager, 4).await;
+        assert_eq!(take_peak(&peak), 1);
+
+        manager.set_max_concurrency(3);
+        assert_eq!(manager.max_concurrency(), Some(3));
+        run_slow_batch(&manager, 6).await;
+        assert_eq!(take_peak(&peak), 3);
+
+        manager.set_max_concurrency(2);
+        run_slow_batch(&manager, 6).await;
+        assert_eq!(take_peak(&peak), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shrink_concurrency_does_not_interrupt_running() {
+        let (manager, peak) = slow_manager(3);
+        let batch = run_slow_batch(&manager, 3);
+        let shrink = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            manager.set_max_concurrency(1);
+        };
+        tokio::join!(batch, shrink);
+        assert_eq!(take_peak(&peak), 3);
+
+        // 收回多出的许可后，新的执行遵循新上限
+        run_slow_batch(&manager, 3).await;
+        assert_eq!(take_peak(&peak), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_zero_concurrency_pauses_execution() {
+        let (manager, _) = slow_manager(2);
+        manager.set_max_concurrency(0);
+        let paused = tokio::time::timeout(
+            Duration::from_millis(80),
+            manager.execute_tool("slow", HashMap::new()),
+        )
+        .await;
+        assert!(paused.is_err(), "并发度为 0 时工具不应执行");
+
+        let resume = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            manager.set_max_concurrency(1);
+        };
+        let (result, _) = tokio::join!(manager.execute_tool("slow", HashMap::new()), resume);
+        assert!(result.unwrap().success);
+    }
+
+    /// 收到放行信号前一直占着并发许可的工具
+    struct GateTool {
+        gate: Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for GateTool {
+        fn name(&self) -> &str {
+            "busy"
+        }
+
+        fn description(&self) -> &str {
+            "waits for the gate"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+
+        async fn execute(&self, _parameters: ToolParameters) -> Result<ToolResult> {
+            self.gate.notified().await;
+            Ok(ToolResult::success("done".to_string()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_high_priority_tool_jumps_permit_queue() {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            max_concurrency: Some(1),
+            ..Default::default()
+        });
+        manager.set_tool_priority("urgent", TOOL_PRIORITY_HIGH);
+        let gate = Arc::new(tokio::sync::Notify::new());
+        manager.register(Box::new(GateTool { gate: gate.clone() }));
+        manager.register(Box::new(
+            MockTool::new("low").with_priority(TOOL_PRIORITY_LOW),
+        ));
+        manager.register(Box::new(MockTool::new("normal")));
+        manager.register(Box::new(MockTool::new("urgent")));
+
+        // 并发度为 1：busy 占住许可，其余按到达顺序排队；时钟暂停，排队时长不会触发老化提级
+        let order = Mutex::new(Vec::new());
+        let run = |name: &'static str| {
+            let (manager, order) = (&manager, &order);
+            async move {
+                assert!(
+                    manager
+                        .execute_tool(name, HashMap::new())
+                        .await
+                        .unwrap()
+                        .success
+                );
+                order.lock().unwrap().push(name);
+            }
+        };
+        // join_all 首轮按顺序轮询，放行时所有调用都已在排队
+        let calls = futures::future::join_all(["busy", "low", "normal", "urgent", "low"].map(run));
+        let release = async {
+            tokio::task::yield_now().await;
+            gate.notify_one();
+        };
+        tokio::join!(calls, release);
+        assert_eq!(
+            order.into_inner().unwrap(),
+            ["busy", "urgent", "normal", "low", "low"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_tool_call_id_returns_cached_result() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(
+            MockTool::new("write")
+                .with_side_effects()
+                .with_responses(["first", "second", "third", "fourth"]),
+        ));
+        manager.register(Box::new(MockTool::new("read").with_responses(["r1", "r2"])));
+
+        let run = |id: &'static str, name: &'static str| {
+            let manager = &manager;
+            async move {
+                manager
+                    .execute_tool_call(id, name, HashMap::new())
+                    .await
+                    .unwrap()
+                    .output
+            }
+        };
+
+        // 相同 tool_call_id 第二次不真正执行，返回首次结果
+        assert_eq!(run("call_1", "write").await, "first");
+        assert_eq!(run("call_1", "write").await, "first");
+        assert_eq!(run("call_2", "write").await, "second");
+        // ID 重复但参数不同：视为新的调用，不返回旧结果
+        let other_args = HashMap::from([("path".to_string(), serde_json::json!("b.txt"))]);
+        let output = manager
+            .execute_tool_call("call_2", "write", other_args)
+            .await
+            .unwrap()
+            .output;
+        assert_eq!(output, "third");
+        // 只读工具不做去重
+        assert_eq!(run("call_3", "read").await, "r1");
+        assert_eq!(run("call_3", "read").await, "r2");
+
+        // 新周期清空记录后重新执行
+        manager.clear_executed_calls();
+        assert_eq!(run("call_1", "write").await, "fourth");
+    }
+
+    #[tokio::test]
+    async fn test_streaming_tool_sends_chunks_line_by_line() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(
+            MockTool::new("build")
+                .with_streaming()
+                .with_response("compiling a\ncompiling b\nfinished\n"),
+        ));
+        manager.register(Box::new(MockTool::new("plain").with_response("whole")));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = manager
+            .execute_tool_call_with("call_1", "build", HashMap::new(), Some(&tx))
+            .await
+            .unwrap();
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = rx.try_recv() {
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks, ["compiling a\n", "compiling b\n", "finished\n"]);
+        // 完整输出由片段聚合而成
+        assert_eq!(result.output, chunks.concat());
+
+        // 未声明流式输出的工具走普通执行，不发送片段
+        let result = manager
+            .execute_tool_call_with("call_2", "plain", HashMap::new(), Some(&tx))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "whole");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_signal_lowers_concurrency() {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            max_concurrency: Some(4),
+            ..Default::default()
+        });
+        manager.register(Box::new(
+            MockTool::new("api")
+                .with_failure("HTTP 429 Too Many Requests")
+                .with_failure("rate limit exceeded"),
+        ));
+        manager.register(Box::new(MockTool::new("other").with_failure("boom")));
+
+        let _ = manager.execute_tool("other", HashMap::new()).await;
+        assert_eq!(manager.max_concurrency(), Some(4));
+
+        let _ = manager.execute_tool("api", HashMap::new()).await;
+        assert_eq!(manager.max_concurrency(), Some(2));
+        let _ = manager.execute_tool("api", HashMap::new()).await;
+        assert_eq!(manager.max_concurrency(), Some(1));
+    }
+
+    #[test]
+    fn test_rate_limit_detection_matches_status_code() {
+        assert!(is_rate_limited("HTTP 429 Too Many Requests"));
+        assert!(is_rate_limited("HTTP/1.1 429"));
+        assert!(is_rate_limited("request failed, status: 429"));
+        assert!(is_rate_limited("上游返回状态码 429"));
+        assert!(!is_rate_limited("HTTP 500: order 429 not found"));
+        assert!(!is_rate_limited("read 4290 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_signal_keeps_paused_limiter_paused() {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            max_concurrency: Some(2),
+            ..Default::default()
+        });
+        manager.register(Box::new(MockTool::new("api")));
+        manager.set_max_concurrency(0);
+        assert_eq!(manager.limiter.backoff(), Some(0));
+        assert_eq!(manager.max_concurrency(), Some(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrency_recovers_after_successes() {
+        let (manager, _) = slow_manager(2);
+        manager.limiter.backoff();
+        assert_eq!(manager.max_concurrency(), Some(1));
+        run_slow_batch(&manager, 5).await;
+        assert_eq!(manager.max_concurrency(), Some(2));
+        run_slow_batch(&manager, 5).await;
+        assert_eq!(manager.max_concurrency(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_mock_tool_delay_triggers_timeout() {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            timeout_ms: 20,
+            ..Default::default()
+        });
+        manager.register(Box::new(
+            MockTool::new("slow_api")
+                .with_delay(Duration::from_millis(200))
+                .with_response("too late"),
+        ));
+        manager.register(Box::new(
+            MockTool::new("quick_fail")
+                .with_delay(Duration::from_millis(1))
+                .with_failure("boom"),
+        ));
+
+        let err = manager
+            .execute_tool("slow_api", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::ReactError::Tool(ToolError::Timeout(ref name)) if name == "slow_api"
+        ));
+
+        // 延迟短于超时时照常返回预设结果
+        let result = manager
+            .execute_tool("quick_fail", HashMap::new())
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_tool_instantiated_on_first_call() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let counter = created.clone();
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(LazyTool::new(
+            "db_query",
+            "查询数据库",
+            serde_json::json!({ "type": "object" }),
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(
+                    MockTool::new("db_query").with_responses(["3 rows", "5 rows"]),
+                ))
+            },
+        )));
+
+        // 注册与生成工具定义都不会实例化
+        assert_eq!(manager.get_tool_definitions().len(), 1);
+        assert_eq!(created.load(Ordering::SeqCst), 0);
+
+        // 第二次调用复用同一个实例，依次取出其预设响应
+        for expected in ["3 rows", "5 rows"] {
+            let result = manager
+                .execute_tool("db_query", HashMap::new())
+                .await
+                .unwrap();
+            assert_eq!(result.output, expected);
+        }
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+    }
+
+    /// 校验 `sql` 参数并给出审批预览的工具
+    struct SqlTool;
+
+    #[async_trait::async_trait]
+    impl Tool for SqlTool {
+        fn name(&self) -> &str {
+            "db_query"
+        }
+
+        fn description(&self) -> &str {
+            "查询数据库"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({ "type": "object" })
+        }
+
+        fn validate_parameters(&self, params: &ToolParameters) -> Result<()> {
+            if params.contains_key("sql") {
+                Ok(())
+            } else {
+                Err(ToolError::MissingParameter("sql".to_string()).into())
+            }
+        }
+
+        fn preview(&self, params: &ToolParameters) -> Option<String> {
+            Some(format!("将执行 SQL: {}", params.get("sql")?.as_str()?))
+        }
+
+        async fn execute(&self, _parameters: ToolParameters) -> Result<ToolResult> {
+            Ok(ToolResult::success("3 rows".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_lazy_tool_validates_and_previews_before_first_call() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let counter = created.clone();
+        let tool = LazyTool::new(
+            "db_query",
+            "查询数据库",
+            serde_json::json!({ "type": "object" }),
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(SqlTool))
+            },
+        );
+
+        assert!(tool.validate_parameters(&HashMap::new()).is_err());
+        let mut params = HashMap::new();
+        params.insert("sql".to_string(), serde_json::json!("SELECT 1"));
+        assert!(tool.validate_parameters(&params).is_ok());
+        assert_eq!(
+            tool.preview(&params).as_deref(),
+            Some("将执行 SQL: SELECT 1")
+        );
+        assert!(tool.is_instantiated());
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+    }
+
+    /// 首次执行时工厂在阻塞线程上运行，单线程运行时里的其他任务照常推进
+    #[tokio::test]
+    async fn test_lazy_tool_factory_does_not_block_async_worker() {
+        let tool = LazyTool::new(
+            "db_query",
+            "查询数据库",
+            serde_json::json!({ "type": "object" }),
+            || {
+                std::thread::sleep(Duration::from_millis(200));
+                Ok(Box::new(MockTool::new("db_query").with_response("3 rows")))
+            },
+        );
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+
+        let result = tool.execute(HashMap::new()).await.unwrap();
+        ticker.abort();
+        assert_eq!(result.output, "3 rows");
+        assert!(ticks.load(Ordering::SeqCst) > 0);
+    }
+
+    /// 实例化前使用预先声明的执行环境，实例化后取真实工具的
+    #[test]
+    fn test_lazy_tool_forwards_sandbox_after_instantiation() {
+        use crate::tools::shell::SandboxedShellTool;
+
+        let tool = LazyTool::new(
+            "shell",
+            "执行命令",
+            serde_json::json!({ "type": "object" }),
+            || Ok(Box::new(SandboxedShellTool::new("real-box", ["echo"]))),
+        )
+        .with_sandbox("declared-box");
+        assert_eq!(tool.sandbox().as_deref(), Some("declared-box"));
+
+        let mut params = HashMap::new();
+        params.insert("command".to_string(), serde_json::json!("ls"));
+        assert!(tool.preview(&params).is_some());
+        assert!(tool.is_instantiated());
+        assert_eq!(tool.sandbox().as_deref(), Some("real-box"));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_tool_factory_failure_is_reported() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(LazyTool::new(
+            "db_query",
+            "查询数据库",
+            serde_json::json!({ "type": "object" }),
+            || Err(crate::error::ReactError::Other("连接被拒绝".to_string())),
+        )));
+
+        let err = manager
+            .execute_tool("db_query", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::ReactError::Tool(ToolError::ExecutionFailed { ref tool, ref message })
+                if tool == "db_query" && message.contains("连接被拒绝")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_soft_timeout_warns_without_failing() {
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            timeout_ms: 200,
+            ..Default::default()
+        });
+        manager.set_soft_timeout_ratio(0.1);
+        let warned = Arc::new(Mutex::new(Vec::new()));
+        let sink = warned.clone();
+        manager.on_soft_timeout(move |tool, soft| {
+            sink.lock().unwrap().push((tool.to_string(), soft));
+        });
+        manager.register(Box::new(
+            MockTool::new("slow_api")
+                .with_delay(Duration::from_millis(60))
+                .with_response("done"),
+        ));
+        manager.register(Box::new(MockTool::new("fast_api").with_response("done")));
+
+        // 介于软、硬超时之间：告警但照常成功
+        let result = manager
+            .execute_tool("slow_api", HashMap::new())
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "done");
+        assert_eq!(
+            *warned.lock().unwrap(),
+            [("slow_api".to_string(), Duration::from_millis(20))]
+        );
+
+        manager
+            .execute_tool("fast_api", HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(warned.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_soft_timeout_off_by_default_and_outside_unit_interval() {
+        let mut manager = ToolManager::new();
+        assert_eq!(manager.soft_timeout(), None);
+        manager.set_soft_timeout_ratio(0.8);
+        assert_eq!(manager.soft_timeout(), Some(Duration::from_secs(24)));
+        manager.set_soft_timeout_ratio(1.0);
+        assert_eq!(manager.soft_timeout(), None);
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            timeout_ms: 0,
+            ..Default::default()
+        });
+        manager.set_soft_timeout_ratio(0.5);
+        assert_eq!(manager.soft_timeout(), None);
+    }
+
+    /// 批量执行中 `flaky` 总是失败，单独执行时成功
+    struct FlakyBatchTool {
+        singles: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for FlakyBatchTool {
+        fn name(&self) -> &str {
+            "read_file"
+        }
+
+        fn description(&self) -> &str {
+            "读取文件"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({ "type": "object" })
+        }
+
+        fn supports_batch(&self) -> bool {
+            true
+        }
+
+        async fn execute(&self, params: ToolParameters) -> Result<ToolResult> {
+            self.singles.fetch_add(1, Ordering::SeqCst);
+            Ok(ToolResult::success(format!("单独:{}", params["path"])))
+        }
+
+        async fn batch_execute(&self, params_list: Vec<ToolParameters>) -> Vec<Result<ToolResult>> {
+            params_list
+                .iter()
+                .map(|params| match params["path"].as_str() {
+                    Some("flaky") => Err(ToolError::ExecutionFailed {
+                        tool: "read_file".to_string(),
+                        message: "暂时不可用".to_string(),
+                    }
+                    .into()),
+                    _ => Ok(ToolResult::success(format!("批量:{}", params["path"]))),
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_retries_failed_items_individually() {
+        let calls = || {
+            ["ok", "flaky"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let mut params = ToolParameters::new();
+                    params.insert("path".to_string(), serde_json::json!(path));
+                    (format!("c{i}"), params)
+                })
+                .collect::<Vec<_>>()
+        };
+        let manager_with = |retry_on_fail: bool, singles: &Arc<AtomicUsize>| {
+            let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+                retry_on_fail,
+                retry_delay_ms: 1,
+                ..Default::default()
+            });
+            manager.register(Box::new(FlakyBatchTool {
+                singles: singles.clone(),
+            }));
+            manager
+        };
+
+        let singles = Arc::new(AtomicUsize::new(0));
+        let results = manager_with(true, &singles)
+            .execute_tool_call_batch("read_file", calls())
+            .await;
+        assert_eq!(results[0].as_ref().unwrap().output, "批量:\"ok\"");
+        assert_eq!(results[1].as_ref().unwrap().output, "单独:\"flaky\"");
+        assert_eq!(singles.load(Ordering::SeqCst), 1);
+
+        // 未开启重试：失败的调用保持失败，与逐个执行一致
+        let singles = Arc::new(AtomicUsize::new(0));
+        let results = manager_with(false, &singles)
+            .execute_tool_call_batch("read_file", calls())
+            .await;
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert_eq!(singles.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_tool_delay_respects_concurrency_limit() {
+        let delay = Duration::from_millis(40);
+        let mut manager = ToolManager::new_with_config(ToolExecutionConfig {
+            max_concurrency: Some(2),
+            ..Default::default()
+        });
+        manager.register(Box::new(MockTool::new("slow").with_delay(delay)));
+
+        // 4 个调用、并发上限 2：至少分两批执行，总耗时不少于 2 个 delay
+        let start = std::time::Instant::now();
+        let calls = (0..4).map(|_| manager.execute_tool("slow", HashMap::new()));
+        for result in futures::future::join_all(calls).await {
+            assert!(result.unwrap().success);
+        }
+        assert!(start.elapsed() >= delay * 2);
+    }
+
+    /// 原样返回 `path` 参数的工具
+    struct EchoPathTool;
+
+    #[async_trait::async_trait]
+    impl Tool for EchoPathTool {
+        fn name(&self) -> &str {
+            "read_file"
+        }
+
+        fn description(&self) -> &str {
+            "echoes the path"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"path": {"type": "string"}}})
+        }
+
+        async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
+            let path = parameters.get("path").and_then(|v| v.as_str());
+            Ok(ToolResult::success(path.unwrap_or_default().to_string()))
+        }
+    }
+
+    /// 把 `path` 参数改写为 `prefix` + 原值；原值以 `deny` 开头时短路返回
+    struct PrefixPath {
+        prefix: &'static str,
+        deny: Option<&'static str>,
+    }
+
+    #[async_trait::async_trait]
+    impl ToolInterceptor for PrefixPath {
+        async fn intercept(
+            &self,
+            _tool_name: &str,
+            mut parameters: ToolParameters,
+        ) -> Result<InterceptAction> {
+            let path = parameters["path"].as_str().unwrap_or_default().to_string();
+            if self.deny.is_some_and(|deny| path.starts_with(deny)) {
+                return Ok(InterceptAction::Respond(ToolResult::error(format!(
+                    "{}: 拒绝访问",
+                    self.prefix
+                ))));
+            }
+            parameters.insert("path".to_string(), format!("{}{path}", self.prefix).into());
+            Ok(InterceptAction::Continue(parameters))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_rewrite_parameters_in_order() {
+        let mut manager = ToolManager::new();
+        manager.register(Box::new(EchoPathTool));
+        manager.add_interceptor(Box::new(PrefixPath {
+            prefix: "/workspace/",
+            deny: Some("secret"),
+        }));
+        manager.add_interceptor(Box::new(PrefixPath {
+            prefix: "/mnt",
+            deny: None,
+        }));
+
+        let path = |p: &str| HashMap::from([("path".to_string(), serde_json::json!(p))]);
+        let result = manager
+            .execute_tool("read_file", path("src/main.rs"))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "/mnt/workspace/src/main.rs");
+
+        // 第一个拦截器短路：第二个拦截器与工具都不执行
+        let result = manager
+            .execute_tool("read_file", path("secret.txt"))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("/workspace/: 拒绝访问"));
+    }
 }
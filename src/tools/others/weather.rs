@@ -1,9 +1,35 @@
 use crate::error::{Result, ToolError};
-use crate::tools::{Tool, ToolParameters, ToolResult};
+use crate::tools::{Randomness, Tool, ToolParameters, ToolResult};
 use serde_json::{Value, json};
+use std::sync::Arc;
 
+const CONDITIONS: [&str; 5] = ["晴", "多云", "阴", "小雨", "暴雨"];
+
+/// 模拟天气查询工具，固定返回暴雨、30 摄氏度
+#[derive(Default)]
 pub struct WeatherTool;
 
+impl WeatherTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// 随机生成天气的模拟查询工具（工具名同为 `query_weather`）
+///
+/// 收到共享随机源（设置了 `AgentConfig::tool_seed`）后从中生成天气与温度，相同种子可复现；
+/// 未收到时与 [`WeatherTool`] 输出相同。
+#[derive(Default)]
+pub struct RandomWeatherTool {
+    rng: Option<Arc<dyn Randomness>>,
+}
+
+impl RandomWeatherTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[async_trait::async_trait]
 impl Tool for WeatherTool {
     fn name(&self) -> &str {
@@ -32,18 +58,55 @@ impl Tool for WeatherTool {
     }
 
     async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
-        let city = parameters
-            .get("city")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::MissingParameter("city".to_string()))?;
+        weather_report(&parameters, None)
+    }
+}
 
-        let date = parameters
-            .get("date")
-            .and_then(|city| city.as_str())
-            .ok_or_else(|| ToolError::MissingParameter("date".to_string()))?;
+#[async_trait::async_trait]
+impl Tool for RandomWeatherTool {
+    fn name(&self) -> &str {
+        WeatherTool.name()
+    }
 
-        let result = format!("{} 的 {} 天气是暴雨，温度 30摄氏度。", city, date);
+    fn description(&self) -> &str {
+        WeatherTool.description()
+    }
 
-        Ok(ToolResult::success(result))
+    fn parameters(&self) -> Value {
+        WeatherTool.parameters()
     }
+
+    async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
+        weather_report(&parameters, self.rng.as_deref())
+    }
+
+    fn apply_randomness(&mut self, rng: Arc<dyn Randomness>) {
+        self.rng = Some(rng);
+    }
+}
+
+fn weather_report(parameters: &ToolParameters, rng: Option<&dyn Randomness>) -> Result<ToolResult> {
+    let city = parameters
+        .get("city")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::MissingParameter("city".to_string()))?;
+
+    let date = parameters
+        .get("date")
+        .and_then(|city| city.as_str())
+        .ok_or_else(|| ToolError::MissingParameter("date".to_string()))?;
+
+    let (condition, temperature) = match rng {
+        Some(rng) => (
+            CONDITIONS[rng.below(CONDITIONS.len() as u64) as usize],
+            rng.below(41) as i64 - 5,
+        ),
+        None => ("暴雨", 30),
+    };
+    let result = format!(
+        "{} 的 {} 天气是{}，温度 {}摄氏度。",
+        city, date, condition, temperature
+    );
+
+    Ok(ToolResult::success(result))
 }
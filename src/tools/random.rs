@@ -0,0 +1,84 @@
+//! 工具侧随机源
+//!
+//! 含随机行为的工具（mock 数据、采样等）通过 [`Tool::apply_randomness`] 接收 Agent
+//! 统一下发的 [`Randomness`]。设置 `AgentConfig::tool_seed` 后所有工具共享同一个
+//! [`SeededRng`]，相同种子、相同调用顺序下产生相同的随机序列，与 LLM 的 `seed`
+//! 配合可复现整条执行链。
+//!
+//! [`Tool::apply_randomness`]: super::Tool::apply_randomness
+
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 工具可用的随机源
+pub trait Randomness: Send + Sync {
+    /// 下一个均匀分布的 `u64`
+    fn next_u64(&self) -> u64;
+
+    /// `[0, n)` 内的均匀整数；`n == 0` 时返回 0
+    fn below(&self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        self.next_u64() % n
+    }
+
+    /// `[0, 1)` 内的均匀浮点数
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// 基于 SplitMix64 的可设种随机数生成器（非密码学用途）
+pub struct SeededRng {
+    state: Mutex<u64>,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Mutex::new(seed),
+        }
+    }
+
+    /// 以当前时间为种子创建（未设置种子时的默认随机源）
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+}
+
+impl Randomness for SeededRng {
+    fn next_u64(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let (a, b) = (SeededRng::new(7), SeededRng::new(7));
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs[0], SeededRng::new(8).next_u64());
+
+        assert!((0..100).all(|_| a.below(6) < 6));
+        assert!(
+            (0..100)
+                .map(|_| a.next_f64())
+                .all(|x| (0.0..1.0).contains(&x))
+        );
+    }
+}
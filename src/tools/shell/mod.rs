@@ -2,14 +2,27 @@
 //!
 //! ⚠️ 安全策略：仅允许白名单中的安全命令执行
 
+use super::encoding::{Encoding, decode_output, detect_encoding};
 use super::{Tool, ToolParameters, ToolResult};
 use crate::error::{Result, ToolError};
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use serde_json::Value;
 use std::collections::HashSet;
+use std::process::Stdio;
 use std::sync::LazyLock;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
+mod sandbox;
+
+pub use sandbox::SandboxedShellTool;
+
+#[cfg(target_os = "windows")]
+const SHELL: (&str, &str) = ("cmd", "/C");
+#[cfg(not(target_os = "windows"))]
+const SHELL: (&str, &str) = ("sh", "-c");
+
 static ALLOWED_COMMANDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
     HashSet::from([
         // ===== 文件查看 =====
@@ -81,6 +94,8 @@ pub enum CommandSafety {
 pub struct ShellTool {
     /// 是否启用严格模式（默认 true）
     strict_mode: bool,
+    /// 子进程输出的编码；`None` 时自动检测
+    output_encoding: Option<&'static Encoding>,
 }
 
 impl Default for ShellTool {
@@ -92,12 +107,28 @@ impl Default for ShellTool {
 impl ShellTool {
     /// 创建新的 Shell 工具（默认严格模式）
     pub fn new() -> Self {
-        Self { strict_mode: true }
+        Self {
+            strict_mode: true,
+            output_encoding: None,
+        }
     }
 
     /// 创建非严格模式的 Shell 工具（不推荐！）
     pub fn new_permissive() -> Self {
-        Self { strict_mode: false }
+        Self {
+            strict_mode: false,
+            output_encoding: None,
+        }
+    }
+
+    /// 显式指定子进程输出的编码（如 `Encoding::for_label(b"gbk")`），不再自动检测
+    pub fn with_output_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.output_encoding = Some(encoding);
+        self
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        decode_output(bytes, self.output_encoding).into_owned()
     }
 
     /// 检查命令是否安全
@@ -148,6 +179,21 @@ impl ShellTool {
         }
     }
 
+    /// 命令未通过安全检查时返回回传给 LLM 的拒绝说明
+    fn blocked_reason(&self, command: &str) -> Option<String> {
+        match self.check_command_safety(command) {
+            CommandSafety::Safe => None,
+            CommandSafety::RequiresApproval(reason) => Some(format!(
+                "⚠️  需要人工确认：{}\n命令：{}\n\n请使用 human_loop 模块进行确认后再执行。",
+                reason, command
+            )),
+            CommandSafety::Dangerous(reason) => Some(format!(
+                "🚫 安全拒绝：{}\n命令：{}\n\n如需执行此类操作，请手动在终端中执行。",
+                reason, command
+            )),
+        }
+    }
+
     /// 检查 git 子命令
     fn check_git_command(&self, parts: &[&str]) -> CommandSafety {
         if parts.len() < 2 {
@@ -251,6 +297,15 @@ impl Tool for ShellTool {
         })
     }
 
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let command = params.get("command")?.as_str()?;
+        Some(format!("将在 shell 中执行命令: {}", command))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
         let command = parameters
             .get("command")
@@ -258,29 +313,11 @@ impl Tool for ShellTool {
             .ok_or_else(|| ToolError::MissingParameter("command".to_string()))?;
 
         // 安全检查
-        match self.check_command_safety(command) {
-            CommandSafety::Safe => {
-                // 继续执行
-            }
-            CommandSafety::RequiresApproval(reason) => {
-                return Ok(ToolResult::error(format!(
-                    "⚠️  需要人工确认：{}\n命令：{}\n\n请使用 human_loop 模块进行确认后再执行。",
-                    reason, command
-                )));
-            }
-            CommandSafety::Dangerous(reason) => {
-                return Ok(ToolResult::error(format!(
-                    "🚫 安全拒绝：{}\n命令：{}\n\n如需执行此类操作，请手动在终端中执行。",
-                    reason, command
-                )));
-            }
+        if let Some(reason) = self.blocked_reason(command) {
+            return Ok(ToolResult::error(reason));
         }
 
-        #[cfg(target_os = "windows")]
-        let (shell, shell_arg) = ("cmd", "/C");
-        #[cfg(not(target_os = "windows"))]
-        let (shell, shell_arg) = ("sh", "-c");
-
+        let (shell, shell_arg) = SHELL;
         match Command::new(shell)
             .arg(shell_arg)
             .arg(command)
@@ -288,8 +325,8 @@ impl Tool for ShellTool {
             .await
         {
             Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                let stdout = self.decode(&output.stdout);
+                let stderr = self.decode(&output.stderr);
 
                 if output.status.success() {
                     Ok(ToolResult::success(stdout))
@@ -305,6 +342,67 @@ impl Tool for ShellTool {
             Err(e) => Ok(ToolResult::error(format!("无法执行命令: {}", e))),
         }
     }
+
+    fn streams_output(&self) -> bool {
+        true
+    }
+
+    /// 逐行产出子进程的标准输出与错误输出（按到达顺序交织），退出码非 0 时以错误结束
+    fn execute_streaming(&self, parameters: ToolParameters) -> BoxStream<'_, Result<String>> {
+        let failed = |message: String| ToolError::ExecutionFailed {
+            tool: "shell".to_string(),
+            message,
+        };
+        let command = parameters
+            .get("command")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        Box::pin(async_stream::try_stream! {
+            let command = command.ok_or_else(|| ToolError::MissingParameter("command".to_string()))?;
+            if let Some(reason) = self.blocked_reason(&command) {
+                Err(failed(reason))?;
+            }
+
+            let (shell, shell_arg) = SHELL;
+            let mut child = Command::new(shell)
+                .arg(shell_arg)
+                .arg(&command)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| failed(format!("无法执行命令: {e}")))?;
+            // 按字节切行再解码，非 UTF-8 输出不会中断读取
+            let mut stdout = child.stdout.take().map(|s| BufReader::new(s).split(b'\n'));
+            let mut stderr = child.stderr.take().map(|s| BufReader::new(s).split(b'\n'));
+            // 首个非 UTF-8 行确定编码后沿用到后续各行，不再逐行检测
+            let mut encoding = self.output_encoding;
+
+            while stdout.is_some() || stderr.is_some() {
+                let (from_stdout, line) = tokio::select! {
+                    line = async { stdout.as_mut()?.next_segment().await.transpose() }, if stdout.is_some() => (true, line),
+                    line = async { stderr.as_mut()?.next_segment().await.transpose() }, if stderr.is_some() => (false, line),
+                };
+                match line {
+                    Some(line) => {
+                        let line = line?;
+                        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+                        if encoding.is_none() && std::str::from_utf8(line).is_err() {
+                            encoding = Some(detect_encoding(line));
+                        }
+                        yield format!("{}\n", decode_output(line, encoding));
+                    }
+                    None if from_stdout => stdout = None,
+                    None => stderr = None,
+                }
+            }
+
+            let status = child.wait().await.map_err(|e| failed(format!("无法执行命令: {e}")))?;
+            if !status.success() {
+                Err(failed(format!("命令执行失败，退出码: {:?}", status.code())))?;
+            }
+        })
+    }
 }
 
 #[cfg(test)]
@@ -490,4 +588,42 @@ mod tests {
         assert!(!result.success);
         assert!(result.error.unwrap().contains("拒绝"));
     }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_shell_tool_output_encoding() {
+        // "你好" 的 GBK 字节
+        let mut params = HashMap::new();
+        params.insert(
+            "command".to_string(),
+            serde_json::json!("printf '\\304\\343\\272\\303'"),
+        );
+        let tool = ShellTool::new().with_output_encoding(Encoding::for_label(b"gbk").unwrap());
+        let result = tool.execute(params).await.unwrap();
+        assert_eq!(result.output, "你好");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_shell_tool_streams_lines() {
+        use futures::StreamExt;
+
+        let tool = ShellTool::new();
+        let mut params = HashMap::new();
+        params.insert(
+            "command".to_string(),
+            serde_json::json!("printf 'a\\nb\\n'"),
+        );
+        let chunks: Vec<String> = tool
+            .execute_streaming(params)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks, ["a\n", "b\n"]);
+
+        let mut params = HashMap::new();
+        params.insert("command".to_string(), serde_json::json!("sudo reboot"));
+        let results: Vec<_> = tool.execute_streaming(params).collect().await;
+        assert!(results[0].is_err());
+    }
 }
@@ -0,0 +1,302 @@
+//! 沙箱中的 Shell 命令执行
+//!
+//! 通过可配置的包装命令（`docker run`、`firejail` 等）把命令放进隔离环境执行。
+//! 沙箱不可用时直接拒绝，不会回退到宿主机执行。
+
+use super::super::encoding::{Encoding, decode_output};
+use super::super::{Tool, ToolParameters, ToolResult};
+use crate::error::{Result, ToolError};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 模板中代表待执行命令的占位符
+const COMMAND_PLACEHOLDER: &str = "{command}";
+
+/// 默认执行超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 在沙箱中执行命令的 Shell 工具
+///
+/// 命令模板是一组 argv，其中的 `{command}` 替换为 LLM 给出的命令；模板中没有占位符时
+/// 命令追加为最后一个参数。隔离由沙箱负责，因此不做 [`ShellTool`](super::ShellTool) 的白名单检查。
+///
+/// # 示例
+/// ```rust
+/// use echo_agent::tools::shell::SandboxedShellTool;
+///
+/// let docker = SandboxedShellTool::docker("python:3.12-slim");
+/// assert_eq!(docker.wrap("ls")[..3], ["docker", "run", "--rm"]);
+///
+/// let custom = SandboxedShellTool::new("bwrap", ["bwrap", "--ro-bind", "/", "/", "sh", "-c", "{command}"]);
+/// assert_eq!(custom.wrap("ls").last().map(String::as_str), Some("ls"));
+/// ```
+pub struct SandboxedShellTool {
+    /// 沙箱名称，展示在审批请求与执行日志中
+    sandbox: String,
+    /// 包装命令模板（argv）
+    template: Vec<String>,
+    /// 子进程输出的编码；`None` 时自动检测
+    output_encoding: Option<&'static Encoding>,
+    /// 执行超时，超时后终止包装进程
+    timeout: Duration,
+}
+
+impl SandboxedShellTool {
+    /// 使用自定义包装命令模板，`sandbox` 为展示用的沙箱名称
+    pub fn new(sandbox: &str, template: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            sandbox: sandbox.to_string(),
+            template: template.into_iter().map(Into::into).collect(),
+            output_encoding: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// 在一次性 docker 容器中执行（`docker run --rm --network none <image> sh -c <command>`）
+    pub fn docker(image: &str) -> Self {
+        Self::new(
+            &format!("docker:{image}"),
+            [
+                "docker",
+                "run",
+                "--rm",
+                "--network",
+                "none",
+                image,
+                "sh",
+                "-c",
+                COMMAND_PLACEHOLDER,
+            ],
+        )
+    }
+
+    /// 在 firejail 中执行（私有 home 目录、无网络）
+    pub fn firejail() -> Self {
+        Self::new(
+            "firejail",
+            [
+                "firejail",
+                "--quiet",
+                "--private",
+                "--net=none",
+                "sh",
+                "-c",
+                COMMAND_PLACEHOLDER,
+            ],
+        )
+    }
+
+    /// 显式指定子进程输出的编码，不再自动检测
+    pub fn with_output_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.output_encoding = Some(encoding);
+        self
+    }
+
+    /// 设置执行超时（默认 60 秒），超时后终止包装进程并返回错误
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 把命令代入模板，得到实际执行的 argv
+    pub fn wrap(&self, command: &str) -> Vec<String> {
+        let mut argv: Vec<String> = self
+            .template
+            .iter()
+            .map(|arg| arg.replace(COMMAND_PLACEHOLDER, command))
+            .collect();
+        if !self
+            .template
+            .iter()
+            .any(|arg| arg.contains(COMMAND_PLACEHOLDER))
+        {
+            argv.push(command.to_string());
+        }
+        argv
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        decode_output(bytes, self.output_encoding).into_owned()
+    }
+
+    /// 沙箱不可用时的拒绝说明：模板为空或包装程序不存在
+    fn unavailable_reason(&self) -> Option<String> {
+        let reason = match self.template.first() {
+            None => "包装命令模板为空".to_string(),
+            Some(program) if !program_available(program) => {
+                format!("未找到包装命令 '{program}'")
+            }
+            Some(_) => return None,
+        };
+        Some(format!(
+            "🚫 沙箱 {} 不可用：{reason}，已拒绝执行（不会在宿主机直接执行）",
+            self.sandbox
+        ))
+    }
+}
+
+/// 包装程序是否存在：含路径分隔符时检查该文件，否则在 PATH 中查找（不启动进程）
+fn program_available(program: &str) -> bool {
+    let is_file = |path: &Path| {
+        path.is_file() || (cfg!(target_os = "windows") && path.with_extension("exe").is_file())
+    };
+    if program.contains(['/', '\\']) {
+        return is_file(Path::new(program));
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| is_file(&dir.join(program))))
+}
+
+#[async_trait]
+impl Tool for SandboxedShellTool {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn description(&self) -> &str {
+        "在隔离的沙箱环境中执行 shell 命令。参数：command - 要执行的命令"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "要在沙箱中执行的 shell 命令"
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    fn preview(&self, params: &ToolParameters) -> Option<String> {
+        let command = params.get("command")?.as_str()?;
+        Some(format!("将在 shell 中执行命令: {}", command))
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
+    fn sandbox(&self) -> Option<String> {
+        Some(self.sandbox.clone())
+    }
+
+    async fn execute(&self, parameters: ToolParameters) -> Result<ToolResult> {
+        let command = parameters
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::MissingParameter("command".to_string()))?;
+
+        if let Some(reason) = self.unavailable_reason() {
+            return Ok(ToolResult::error(reason));
+        }
+        let argv = self.wrap(command);
+
+        let output = Command::new(&argv[0])
+            .args(&argv[1..])
+            .kill_on_drop(true)
+            .output();
+        let Ok(output) = tokio::time::timeout(self.timeout, output).await else {
+            return Ok(ToolResult::error(format!(
+                "⏰ 沙箱 {} 中的命令执行超时（{}ms），已终止",
+                self.sandbox,
+                self.timeout.as_millis()
+            )));
+        };
+        match output {
+            Ok(output) => {
+                let stdout = self.decode(&output.stdout);
+                let stderr = self.decode(&output.stderr);
+
+                if output.status.success() {
+                    Ok(ToolResult::success(stdout))
+                } else {
+                    Ok(ToolResult::error(format!(
+                        "命令执行失败，退出码: {:?}\n标准输出: {}\n错误输出: {}",
+                        output.status.code(),
+                        stdout,
+                        stderr
+                    )))
+                }
+            }
+            Err(e) => Ok(ToolResult::error(format!(
+                "🚫 无法启动沙箱 {}: {}，已拒绝执行（不会在宿主机直接执行）",
+                self.sandbox, e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn command(command: &str) -> ToolParameters {
+        HashMap::from([("command".to_string(), serde_json::json!(command))])
+    }
+
+    #[test]
+    fn test_wrap_substitutes_placeholder() {
+        let tool = SandboxedShellTool::docker("alpine");
+        assert_eq!(
+            tool.wrap("ls -la"),
+            [
+                "docker",
+                "run",
+                "--rm",
+                "--network",
+                "none",
+                "alpine",
+                "sh",
+                "-c",
+                "ls -la"
+            ]
+        );
+
+        // 占位符可以嵌在参数中；没有占位符时追加为最后一个参数
+        let tool = SandboxedShellTool::new("box", ["runner", "--cmd={command}"]);
+        assert_eq!(tool.wrap("pwd"), ["runner", "--cmd=pwd"]);
+        let tool = SandboxedShellTool::new("box", ["runner", "--"]);
+        assert_eq!(tool.wrap("pwd"), ["runner", "--", "pwd"]);
+    }
+
+    /// 包装命令 mock 成 echo：输出即为实际执行的 argv
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_executes_through_wrapper() {
+        let tool = SandboxedShellTool::new("mock", ["echo", "[mock]", "sh", "-c", "{command}"]);
+        assert_eq!(tool.sandbox().as_deref(), Some("mock"));
+        let result = tool.execute(command("rm -rf /tmp/x")).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "[mock] sh -c rm -rf /tmp/x\n");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_times_out_long_running_command() {
+        let tool = SandboxedShellTool::new("mock", ["sh", "-c", "{command}"])
+            .with_timeout(Duration::from_millis(100));
+        let result = tool.execute(command("sleep 5")).await.unwrap();
+        assert!(result.error.unwrap().contains("执行超时"));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_when_sandbox_unavailable() {
+        let tool = SandboxedShellTool::new("ghost", ["echo-agent-missing-sandbox", "{command}"]);
+        let result = tool.execute(command("echo hello")).await.unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.contains("沙箱 ghost 不可用"), "{error}");
+        assert!(!error.contains("hello\n"));
+
+        let tool = SandboxedShellTool::new("empty", Vec::<String>::new());
+        let result = tool.execute(command("echo hello")).await.unwrap();
+        assert!(result.error.unwrap().contains("模板为空"));
+    }
+}
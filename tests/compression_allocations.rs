@@ -0,0 +1,74 @@
+//! 压缩路径的堆分配基准
+//!
+//! 替换全局分配器统计分配次数，因此放在独立的集成测试二进制中，不影响库自身的测试。
+
+use echo_agent::compression::compressor::SlidingWindowCompressor;
+use echo_agent::compression::{CompressionInput, ContextCompressor, ContextManager};
+use echo_agent::error::Result;
+use echo_agent::llm::types::Message;
+
+/// 统计当前线程的堆分配次数，用于对比压缩路径的开销
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|n| n.get())
+}
+
+/// 基准：长会话（4000 条消息）超限压缩时，借用路径与“克隆整个历史再压缩”的分配次数对比
+#[tokio::test]
+async fn bench_prepare_allocations_on_large_context() -> Result<()> {
+    let history: Vec<Message> = (0..2000)
+        .flat_map(|i| {
+            [
+                Message::user(format!("第 {i} 个问题：请解释一下这段代码的作用")),
+                Message::assistant(format!("第 {i} 个回答：这段代码负责读取配置并初始化连接池")),
+            ]
+        })
+        .collect();
+
+    // 优化前：每次压缩都克隆整个历史交给 compress
+    let compressor = SlidingWindowCompressor::new(20);
+    let start = allocations();
+    let output = compressor
+        .compress(CompressionInput {
+            messages: history.clone(),
+            token_limit: 100,
+            current_query: None,
+        })
+        .await?;
+    let cloned = allocations() - start;
+    assert_eq!(output.messages.len(), 20);
+    drop(output);
+
+    // 优化后：compress_ref 借用历史，按计划移动保留与裁剪的消息
+    let mut ctx = ContextManager::builder(100)
+        .compressor(SlidingWindowCompressor::new(20))
+        .build();
+    ctx.push_many(history);
+    let start = allocations();
+    let messages = ctx.prepare(None).await?;
+    let borrowed = allocations() - start;
+    assert_eq!(messages.len(), 20);
+
+    println!("克隆整个历史：{cloned} 次分配；借用压缩：{borrowed} 次分配");
+    assert!(borrowed * 10 < cloned, "{borrowed} vs {cloned}");
+    Ok(())
+}